@@ -0,0 +1,191 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use io_tubes::{
+    bench::{haystack, lines, replay},
+    utils::{AnyMatcher, Matcher},
+};
+use log::{LevelFilter, Log, Metadata, Record};
+use tokio::runtime::Runtime;
+
+const LEN: usize = 64 * 1024;
+
+fn recv_until(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let data = haystack(LEN, b"flag{");
+    let mut group = c.benchmark_group("recv_until");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    for chunk_size in [16, 1024, 8192] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(chunk_size),
+            &chunk_size,
+            |b, &chunk_size| {
+                b.to_async(&rt).iter(|| async {
+                    let mut p = replay(data.clone(), chunk_size);
+                    p.recv_until("flag{").await.unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn recv_until_delims_len(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("recv_until_delims_len");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    for delims_len in [1, 5, 64, 4096] {
+        let delims: Vec<u8> = b"flag{".iter().copied().cycle().take(delims_len).collect();
+        let data = haystack(LEN, &delims);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(delims_len),
+            &delims,
+            |b, delims| {
+                b.to_async(&rt).iter(|| async {
+                    let mut p = replay(data.clone(), 1024);
+                    p.recv_until(delims).await.unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// A long stream where the first byte of the delimiter never appears before it, like the output
+/// of exfiltrating a file, so that the scan for the first byte dominates.
+fn recv_until_exfil(c: &mut Criterion) {
+    const LEN: usize = 16 * 1024 * 1024;
+    let rt = Runtime::new().unwrap();
+    let data = haystack(LEN, b"\0END");
+    let mut group = c.benchmark_group("recv_until_exfil");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    group.sample_size(10);
+    group.bench_function("65536", |b| {
+        b.to_async(&rt).iter(|| async {
+            let mut p = replay(data.clone(), 65536);
+            p.recv_until("\0END").await.unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn recv_until_any(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let data = haystack(LEN, b"flag{");
+    let mut group = c.benchmark_group("recv_until_any");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    // Up to 3 first bytes are scanned for with memchr, and more run the automaton on every byte.
+    let all: [&[u8]; 5] = [b"flag{", b"> ", b"Invalid", b"\n$ ", b"CTF{"];
+    for count in [2, 5] {
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.to_async(&rt).iter(|| async {
+                let mut p = replay(data.clone(), 8192);
+                p.recv_until_any(&all[..count]).await.unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// The matchers alone, without the tube.
+fn matcher(c: &mut Criterion) {
+    let data = haystack(LEN, b"flag{");
+    let mut group = c.benchmark_group("matcher");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    group.bench_function("Matcher", |b| {
+        b.iter(|| Matcher::new(b"flag{").push_bytes(&data).unwrap())
+    });
+    group.bench_function("AnyMatcher", |b| {
+        b.iter(|| {
+            AnyMatcher::new(&[b"flag{", b"CTF{"])
+                .push_bytes(&data)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn recv_line(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let data = lines(LEN / 64, 64);
+    let mut group = c.benchmark_group("recv_line");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    group.bench_function("64", |b| {
+        b.to_async(&rt).iter(|| async {
+            let mut p = replay(data.clone(), 8192);
+            for _ in 0..LEN / 64 {
+                p.recv_line().await.unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+/// A logger that formats the records and throws them away, to measure the logging overhead.
+struct FormatLogger;
+
+impl Log for FormatLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        std::hint::black_box(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+fn logging(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let data = haystack(LEN, b"flag{");
+    log::set_logger(&FormatLogger).unwrap();
+    let mut group = c.benchmark_group("logging");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    for level in [LevelFilter::Off, LevelFilter::Debug] {
+        group.bench_with_input(BenchmarkId::from_parameter(level), &level, |b, &level| {
+            log::set_max_level(level);
+            b.to_async(&rt).iter(|| async {
+                let mut p = replay(data.clone(), 1024);
+                p.recv_until("flag{").await.unwrap()
+            })
+        });
+    }
+    log::set_max_level(LevelFilter::Off);
+    group.finish();
+}
+
+fn copy(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let data = haystack(LEN, b"flag{");
+    log::set_max_level(LevelFilter::Debug);
+    let mut group = c.benchmark_group("copy");
+    group.throughput(Throughput::Bytes(LEN as u64));
+    group.bench_function("tokio", |b| {
+        b.to_async(&rt).iter(|| async {
+            let mut p = replay(data.clone(), 8192);
+            tokio::io::copy(&mut p, &mut tokio::io::sink())
+                .await
+                .unwrap()
+        })
+    });
+    group.bench_function("copy_to", |b| {
+        b.to_async(&rt).iter(|| async {
+            let mut p = replay(data.clone(), 8192);
+            p.copy_to(&mut tokio::io::sink()).await.unwrap()
+        })
+    });
+    log::set_max_level(LevelFilter::Off);
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    recv_until,
+    recv_until_delims_len,
+    recv_until_exfil,
+    recv_until_any,
+    matcher,
+    recv_line,
+    logging,
+    copy
+);
+criterion_main!(benches);
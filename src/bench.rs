@@ -0,0 +1,133 @@
+//! Deterministic synthetic tubes for benchmarking, enabled by the `bench-support` feature.
+//!
+//! The data is generated up front, so the benchmarks measure the matching, framing and logging
+//! done by the tube instead of the IO. The size of each read is controlled to exercise matching
+//! across several reads.
+//! ```rust
+//! use io_tubes::bench::{haystack, replay};
+//! use std::io;
+//!
+//! #[tokio::main]
+//! async fn bench() -> io::Result<()> {
+//!     let data = haystack(4096, b"> ");
+//!
+//!     // Every iteration receives the same data in reads of 16 bytes.
+//!     for _ in 0..3 {
+//!         let mut p = replay(data.clone(), 16);
+//!         assert_eq!(p.recv_until("> ").await?.len(), 4096);
+//!     }
+//!
+//!     Ok(())
+//! }
+//!
+//! bench();
+//! ```
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, BufReader, ReadBuf};
+
+use crate::tubes::{random_byte, Tube};
+
+/// A stream that replays pre-seeded data in reads of at most `chunk_size` bytes, then reaches
+/// EOF. Everything written to it is counted and discarded.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    data: Arc<[u8]>,
+    pos: usize,
+    chunk_size: usize,
+    written: u64,
+}
+
+impl Replay {
+    /// Create a stream that replays the data, which is shared between clones.
+    pub fn new(data: impl Into<Arc<[u8]>>, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must not be 0");
+        Self {
+            data: data.into(),
+            pos: 0,
+            chunk_size,
+            written: 0,
+        }
+    }
+
+    /// Replay the data from the start again.
+    pub fn rewind(&mut self) {
+        self.pos = 0;
+    }
+
+    /// The number of bytes written to the stream.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+impl AsyncRead for Replay {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let len = (this.data.len() - this.pos)
+            .min(this.chunk_size)
+            .min(buf.remaining());
+        buf.put_slice(&this.data[this.pos..this.pos + len]);
+        this.pos += len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Replay {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.written += buf.len() as u64;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Create a tube that replays the data in reads of at most `chunk_size` bytes.
+pub fn replay(data: impl Into<Arc<[u8]>>, chunk_size: usize) -> Tube<BufReader<Replay>> {
+    Tube::new(Replay::new(data, chunk_size))
+}
+
+/// Generate `len` bytes of pseudo-random lowercase letters ending with the needle, which is the
+/// worst case for [`Tube::recv_until`] if the needle has other characters. The data is always the
+/// same for the same arguments.
+pub fn haystack(len: usize, needle: &[u8]) -> Arc<[u8]> {
+    let noise = len.saturating_sub(needle.len());
+    (0..noise as u64)
+        .map(|pos| b'a' + random_byte(0, pos) % 26)
+        .chain(needle.iter().copied())
+        .collect()
+}
+
+/// Generate `count` lines of `len` bytes each including the newline, made of pseudo-random
+/// lowercase letters. The data is always the same for the same arguments.
+pub fn lines(count: usize, len: usize) -> Arc<[u8]> {
+    assert!(len > 0, "a line has at least the newline");
+    (0..(count * len) as u64)
+        .map(|pos| {
+            if pos % len as u64 == len as u64 - 1 {
+                b'\n'
+            } else {
+                b'a' + random_byte(1, pos) % 26
+            }
+        })
+        .collect()
+}
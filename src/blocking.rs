@@ -0,0 +1,210 @@
+//! A synchronous facade over [`Tube`] for quick scripts that don't want to be async.
+//!
+//! [`BlockingTube`] owns a current-thread runtime and blocks on it for every call, so the methods
+//! have the same names and behave the same as the async ones, including the timeouts, logging
+//! and recording of the tube. Don't use it inside an async runtime, where blocking panics.
+//! ```rust
+//! use io_tubes::{blocking::BlockingTube, tubes::Tube};
+//! use std::io;
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut p = BlockingTube::new(Tube::echo())?;
+//!
+//!     p.send_line("Hello")?;
+//!     assert_eq!(p.recv_line()?, b"Hello\n");
+//!     p.send("name: ")?;
+//!     assert_eq!(p.send_line_after("name: ", "admin")?, b"name: ");
+//!     assert_eq!(p.recv_until('\n')?, b"admin\n");
+//!
+//!     Ok(())
+//! }
+//! ```
+
+#[cfg(feature = "process")]
+use std::ffi::OsStr;
+use std::io;
+
+#[cfg(any(feature = "process", feature = "net"))]
+use tokio::io::BufReader;
+#[cfg(feature = "net")]
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::{
+    io::{AsyncBufRead, AsyncWrite},
+    runtime::{Builder, Runtime},
+};
+
+#[cfg(not(target_family = "wasm"))]
+use crate::tubes::InteractiveEnd;
+#[cfg(feature = "process")]
+use crate::tubes::ProcessTube;
+use crate::{tubes::Tube, utils::Needle};
+
+/// A tube with blocking methods, see the [module docs](self).
+#[derive(Debug)]
+pub struct BlockingTube<T> {
+    runtime: Runtime,
+    tube: Tube<T>,
+}
+
+fn runtime() -> io::Result<Runtime> {
+    Builder::new_current_thread().enable_all().build()
+}
+
+impl<T> BlockingTube<T> {
+    /// Wrap a tube that doesn't depend on a runtime, e.g. [`Tube::echo`] or [`Tube::pair`]. Use
+    /// [`BlockingTube::from_async`] for tubes that need one to be created, like sockets.
+    pub fn new(tube: Tube<T>) -> io::Result<Self> {
+        Ok(Self {
+            runtime: runtime()?,
+            tube,
+        })
+    }
+
+    /// Create the tube with `f` inside the runtime of the blocking tube, for tubes without a
+    /// blocking constructor.
+    /// ```rust
+    /// use io_tubes::{blocking::BlockingTube, tubes::{RemoteOptions, Tube}};
+    /// use std::{io, net::TcpListener, thread, time::Duration};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0")?;
+    ///     let addr = listener.local_addr()?;
+    ///     let server = thread::spawn(move || listener.accept());
+    ///
+    ///     let options = RemoteOptions {
+    ///         connect_timeout: Some(Duration::from_secs(1)),
+    ///         ..RemoteOptions::default()
+    ///     };
+    ///     let mut p = BlockingTube::from_async(async || Tube::remote_with(addr, &options).await)?;
+    ///     p.send_line("hi")?;
+    ///     server.join().unwrap()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_async(f: impl AsyncFnOnce() -> io::Result<Tube<T>>) -> io::Result<Self> {
+        let runtime = runtime()?;
+        let tube = runtime.block_on(f())?;
+        Ok(Self { runtime, tube })
+    }
+
+    /// Run async code on the tube, e.g. for methods without a blocking version.
+    /// ```rust
+    /// use io_tubes::{blocking::BlockingTube, tubes::Tube};
+    /// use std::io;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut p = BlockingTube::new(Tube::echo())?;
+    ///     p.send("Hello")?;
+    ///     assert_eq!(p.block_on(async |p| p.peek(5).await)?, b"Hello");
+    ///     assert_eq!(p.recv(5)?, b"Hello");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn block_on<R>(&mut self, f: impl AsyncFnOnce(&mut Tube<T>) -> R) -> R {
+        self.runtime.block_on(f(&mut self.tube))
+    }
+
+    /// The async tube, e.g. to change its settings.
+    pub fn get_ref(&self) -> &Tube<T> {
+        &self.tube
+    }
+
+    /// The async tube, e.g. to change its settings.
+    pub fn get_mut(&mut self) -> &mut Tube<T> {
+        &mut self.tube
+    }
+
+    /// Take the async tube out, dropping the runtime. Tubes that depend on the runtime, like
+    /// sockets and processes, can only be used in another runtime if they don't need a reactor.
+    pub fn into_inner(self) -> Tube<T> {
+        self.tube
+    }
+}
+
+#[cfg(feature = "process")]
+impl BlockingTube<BufReader<ProcessTube>> {
+    /// Create a process with supplied path to program, see [`Tube::process`].
+    pub fn process<S: AsRef<OsStr>>(program: S) -> io::Result<Self> {
+        let runtime = runtime()?;
+        let tube = runtime.block_on(async { Tube::process(program) })?;
+        Ok(Self { runtime, tube })
+    }
+}
+
+#[cfg(feature = "net")]
+impl BlockingTube<BufReader<TcpStream>> {
+    /// Connect to a remote address, see [`Tube::remote`].
+    pub fn remote(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let runtime = runtime()?;
+        let tube = runtime.block_on(Tube::remote(addr))?;
+        Ok(Self { runtime, tube })
+    }
+}
+
+impl<T> BlockingTube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// See [`Tube::recv`].
+    pub fn recv(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        self.runtime.block_on(self.tube.recv(len))
+    }
+
+    /// See [`Tube::recv_line`].
+    pub fn recv_line(&mut self) -> io::Result<Vec<u8>> {
+        self.runtime.block_on(self.tube.recv_line())
+    }
+
+    /// See [`Tube::recv_lines`].
+    pub fn recv_lines(&mut self, n: usize) -> io::Result<Vec<Vec<u8>>> {
+        self.runtime.block_on(self.tube.recv_lines(n))
+    }
+
+    /// See [`Tube::recv_until`].
+    pub fn recv_until(&mut self, delims: impl Needle) -> io::Result<Vec<u8>> {
+        self.runtime.block_on(self.tube.recv_until(delims))
+    }
+
+    /// See [`Tube::recv_until_drop`].
+    pub fn recv_until_drop(&mut self, delims: impl Needle) -> io::Result<Vec<u8>> {
+        self.runtime.block_on(self.tube.recv_until_drop(delims))
+    }
+
+    /// See [`Tube::recv_all`].
+    pub fn recv_all(&mut self) -> io::Result<Vec<u8>> {
+        self.runtime.block_on(self.tube.recv_all())
+    }
+
+    /// See [`Tube::send`].
+    pub fn send(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
+        self.runtime.block_on(self.tube.send(data))
+    }
+
+    /// See [`Tube::send_line`].
+    pub fn send_line(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
+        self.runtime.block_on(self.tube.send_line(data))
+    }
+
+    /// See [`Tube::send_line_after`].
+    pub fn send_line_after(
+        &mut self,
+        pattern: impl Needle,
+        data: impl AsRef<[u8]>,
+    ) -> io::Result<Vec<u8>> {
+        self.runtime
+            .block_on(self.tube.send_line_after(pattern, data))
+    }
+
+    /// See [`Tube::close_send`].
+    pub fn close_send(&mut self) -> io::Result<()> {
+        self.runtime.block_on(self.tube.close_send())
+    }
+
+    /// See [`Tube::interactive`].
+    #[cfg(not(target_family = "wasm"))]
+    pub fn interactive(&mut self) -> io::Result<InteractiveEnd> {
+        self.runtime.block_on(self.tube.interactive())
+    }
+}
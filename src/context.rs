@@ -0,0 +1,289 @@
+//! The architecture of the target, the defaults of new tubes and the random seed, like `context`
+//! in pwntools.
+//!
+//! The context is global and set once at the start of the exploit, so that the same exploit
+//! ports to another architecture by changing one line. It is used by
+//! [`Tube::recv_uint`](crate::tubes::Tube::recv_uint) and
+//! [`Tube::send_uint`](crate::tubes::Tube::send_uint). The tubes constructed afterwards start
+//! with the [`TubeDefaults`], which can still be changed on each tube. The random data of the
+//! exploit comes from the seed, see [`rng`].
+//! ```rust
+//! use io_tubes::{context::{self, Context}, tubes::Tube};
+//! use std::io;
+//!
+//! #[tokio::main]
+//! async fn context() -> io::Result<()> {
+//!     context::set(Context::MIPS);
+//!
+//!     let mut p = Tube::echo();
+//!     p.send_uint(0x400800).await?;
+//!     assert_eq!(p.recv(4).await?, b"\x00\x40\x08\x00");
+//!     p.send(b"\x7f\xff\x12\x34").await?;
+//!     assert_eq!(p.recv_uint().await?, 0x7fff1234);
+//!
+//!     context::set(Context::default());
+//!     Ok(())
+//! }
+//!
+//! context();
+//! ```
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{Mutex, RwLock},
+    time::Duration,
+};
+
+use log::{info, LevelFilter};
+
+use crate::{packing::Endian, tubes::LogOptions};
+
+static CONTEXT: RwLock<Context> = RwLock::new(Context::AMD64);
+
+/// The defaults of new tubes, which are [`TubeDefaults::default`] if `None`.
+static TUBE_DEFAULTS: RwLock<Option<TubeDefaults>> = RwLock::new(None);
+
+/// The seed, once chosen, and the number of generators derived from it.
+static SEED: Mutex<Option<(u64, u64)>> = Mutex::new(None);
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The word size and byte order of the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Context {
+    /// The size of a word in bytes, which is at most 8.
+    pub word_size: usize,
+    /// The byte order of integers.
+    pub endian: Endian,
+}
+
+impl Context {
+    /// 32-bit x86.
+    pub const I386: Self = Self::new(4, Endian::Little);
+    /// 64-bit x86, which is the default.
+    pub const AMD64: Self = Self::new(8, Endian::Little);
+    /// 32-bit ARM in little endian.
+    pub const ARM: Self = Self::new(4, Endian::Little);
+    /// 64-bit ARM in little endian.
+    pub const AARCH64: Self = Self::new(8, Endian::Little);
+    /// 32-bit MIPS in big endian.
+    pub const MIPS: Self = Self::new(4, Endian::Big);
+    /// 32-bit MIPS in little endian.
+    pub const MIPSEL: Self = Self::new(4, Endian::Little);
+    /// 32-bit PowerPC in big endian.
+    pub const POWERPC: Self = Self::new(4, Endian::Big);
+    /// 64-bit PowerPC in big endian.
+    pub const POWERPC64: Self = Self::new(8, Endian::Big);
+
+    /// A context with words of `word_size` bytes, which must be at most 8.
+    pub const fn new(word_size: usize, endian: Endian) -> Self {
+        assert!(word_size <= 8, "word size must be at most 8 bytes");
+        Self { word_size, endian }
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::AMD64
+    }
+}
+
+/// Set the global context. Panics if the word size is more than 8 bytes.
+pub fn set(context: Context) {
+    *CONTEXT.write().unwrap_or_else(|err| err.into_inner()) =
+        Context::new(context.word_size, context.endian);
+}
+
+/// Returns the global context, which is [`Context::AMD64`] unless set.
+pub fn get() -> Context {
+    *CONTEXT.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// The settings that tubes start with, see [`set_tube_defaults`].
+/// ```rust
+/// use io_tubes::{context::{self, TubeDefaults}, tubes::Tube};
+/// use std::{io, time::Duration};
+///
+/// #[tokio::main]
+/// async fn tube_defaults() -> io::Result<()> {
+///     context::set_tube_defaults(TubeDefaults {
+///         timeout: Duration::from_millis(500),
+///         newline: b"\r\n".to_vec(),
+///         ..TubeDefaults::default()
+///     });
+///
+///     let mut p = Tube::echo();
+///     assert_eq!(p.timeout, Duration::from_millis(500));
+///     p.send_line("USER anonymous").await?;
+///     assert_eq!(p.recv_line().await?, b"USER anonymous\r\n");
+///
+///     // Each tube can still be changed.
+///     p.newline = b"\n".to_vec();
+///
+///     context::set_tube_defaults(TubeDefaults::default());
+///     Ok(())
+/// }
+///
+/// tube_defaults();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TubeDefaults {
+    /// The [`timeout`](crate::tubes::Tube::timeout) of receiving and sending, which is
+    /// unlimited by default.
+    pub timeout: Duration,
+    /// The [`newline`](crate::tubes::Tube::newline) sent by the line methods, which is `\n` by
+    /// default.
+    pub newline: Vec<u8>,
+    /// How the traffic is logged, see
+    /// [`Tube::set_log_options`](crate::tubes::Tube::set_log_options).
+    pub log: LogOptions,
+}
+
+impl Default for TubeDefaults {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::MAX,
+            newline: b"\n".to_vec(),
+            log: LogOptions::default(),
+        }
+    }
+}
+
+/// Set the defaults of the tubes constructed from now on. The existing tubes are unchanged.
+pub fn set_tube_defaults(defaults: TubeDefaults) {
+    *TUBE_DEFAULTS.write().unwrap_or_else(|err| err.into_inner()) = Some(defaults);
+}
+
+/// Returns the defaults of new tubes.
+pub fn tube_defaults() -> TubeDefaults {
+    TUBE_DEFAULTS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Set how verbose the log of the whole program is, e.g. [`LevelFilter::Debug`] to see the
+/// traffic of the tubes. This is a shortcut for [`log::set_max_level`], and the logger still has
+/// to be installed.
+pub fn set_log_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// A pseudo-random generator, which is splitmix64 so that the same seed always generates the same
+/// data on every platform. It is not suitable for cryptography.
+/// ```rust
+/// use io_tubes::context::Rng;
+///
+/// let mut rng = Rng::new(1337);
+/// let marker = rng.alphanumeric(16);
+/// assert!(marker.iter().all(u8::is_ascii_alphanumeric));
+/// assert_eq!(Rng::new(1337).alphanumeric(16), marker);
+///
+/// let mut alphabet = b"abcdefgh".to_vec();
+/// rng.shuffle(&mut alphabet);
+/// alphabet.sort();
+/// assert_eq!(alphabet, b"abcdefgh");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// A generator of the sequence of the seed.
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// The next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A random number below `bound`, which must not be 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must not be 0");
+        self.next_u64() % bound
+    }
+
+    /// Fill the buffer with random bytes, e.g. for padding.
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let len = chunk.len();
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..len]);
+        }
+    }
+
+    /// `len` random bytes.
+    pub fn bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut buf = vec![0; len];
+        self.fill(&mut buf);
+        buf
+    }
+
+    /// `len` random ASCII letters and digits, e.g. for a marker to find in the output of a shell.
+    pub fn alphanumeric(&mut self, len: usize) -> Vec<u8> {
+        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+        (0..len)
+            .map(|_| CHARSET[self.below(CHARSET.len() as u64) as usize])
+            .collect()
+    }
+
+    /// Shuffle the items, e.g. the alphabet of a cyclic pattern.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            items.swap(idx, self.below(idx as u64 + 1) as usize);
+        }
+    }
+}
+
+/// Set the global seed, e.g. to the seed logged by a failing run to reproduce it. The generators
+/// returned by [`rng`] start over from the first one.
+pub fn set_seed(seed: u64) {
+    *SEED.lock().unwrap_or_else(|err| err.into_inner()) = Some((seed, 0));
+}
+
+/// Returns the global seed. Unless set, a seed is chosen at random and logged the first time it
+/// is used.
+pub fn seed() -> u64 {
+    with_seed(|seed, _| *seed)
+}
+
+/// Returns a new generator derived from the global seed. Every call returns a different
+/// generator, and the generators are the same across runs with the same seed as long as they are
+/// created in the same order. This is where [`Tube::rng`](crate::tubes::Tube::rng) comes from.
+/// ```rust
+/// use io_tubes::context;
+///
+/// context::set_seed(42);
+/// let first = context::rng().next_u64();
+/// assert_ne!(context::rng().next_u64(), first);
+///
+/// context::set_seed(42);
+/// assert_eq!(context::rng().next_u64(), first);
+/// ```
+pub fn rng() -> Rng {
+    with_seed(|seed, streams| {
+        *streams += 1;
+        Rng::new(Rng::new(*seed ^ streams.wrapping_mul(GOLDEN_GAMMA)).next_u64())
+    })
+}
+
+fn with_seed<R>(f: impl FnOnce(&mut u64, &mut u64) -> R) -> R {
+    let mut guard = SEED.lock().unwrap_or_else(|err| err.into_inner());
+    let (seed, streams) = guard.get_or_insert_with(|| {
+        let seed = RandomState::new().build_hasher().finish();
+        info!(
+            target: "context",
+            "Random seed is {:#x}, set it with context::set_seed to reproduce the run",
+            seed
+        );
+        (seed, 0)
+    });
+    f(seed, streams)
+}
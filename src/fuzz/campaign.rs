@@ -0,0 +1,387 @@
+#[cfg(feature = "process")]
+use std::any::Any;
+use std::{fs, future::Future, io, ops::Range, path::Path, time::Duration};
+
+#[cfg(feature = "process")]
+use tokio::io::BufReader;
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+#[cfg(feature = "process")]
+use crate::tubes::ProcessTube;
+use crate::tubes::{parse_transcript, random_byte, Direction, TranscriptEvent, Tube};
+
+/// Values that often break the parsing of numbers.
+const NUMBERS: &[&[u8]] = &[
+    b"0",
+    b"-1",
+    b"127",
+    b"255",
+    b"65536",
+    b"2147483647",
+    b"2147483648",
+    b"4294967295",
+    b"-2147483649",
+    b"99999999999999999999",
+];
+
+/// Bytes that often break the parsing of binary fields.
+const BYTES: &[u8] = &[0x00, 0x01, 0x7f, 0x80, 0xff, b'%', b'\n', b'"', b'\''];
+
+/// A recorded interaction with fields marked for mutation, replayed by [`Campaign`].
+///
+/// In the transcript of [`Tube::record`](crate::tubes::Tube::record), a field is marked by a
+/// comment of the form `# fuzz <start>..<end>` before the sent event it belongs to, where the
+/// range is the offsets of the bytes in the data of the event.
+#[derive(Debug, Clone)]
+pub struct Session {
+    events: Vec<TranscriptEvent>,
+    /// The index of the event and the range of each field.
+    fields: Vec<(usize, Range<usize>)>,
+}
+
+impl Session {
+    /// Read a transcript with marked fields from the file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse a transcript with marked fields.
+    pub fn parse(transcript: &str) -> io::Result<Self> {
+        let events = parse_transcript(transcript)?;
+        let mut fields = Vec::new();
+        let mut index = 0;
+        for (line_number, line) in transcript.lines().enumerate() {
+            if let Some(range) = line.strip_prefix("# fuzz ") {
+                let range = range
+                    .split_once("..")
+                    .and_then(|(start, end)| Some(start.parse().ok()?..end.parse().ok()?))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("invalid fuzz field on line {}", line_number + 1),
+                        )
+                    })?;
+                fields.push((index, range));
+            } else if !line.is_empty() && !line.starts_with('#') {
+                index += 1;
+            }
+        }
+        Ok(Self { events, fields })
+    }
+
+    /// Use the events without marked fields, e.g. of a
+    /// [`MemoryTranscript`](crate::tubes::MemoryTranscript).
+    pub fn from_events(events: Vec<TranscriptEvent>) -> Self {
+        Self {
+            events,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Mark the bytes in the range of the data of the event as a field. Fields of received
+    /// events and bytes past the end of the data are ignored.
+    pub fn field(mut self, event: usize, range: Range<usize>) -> Self {
+        self.fields.push((event, range));
+        self
+    }
+
+    /// The events of the interaction.
+    pub fn events(&self) -> &[TranscriptEvent] {
+        &self.events
+    }
+
+    /// The data sent in the iteration, where one of the fields is mutated.
+    fn mutate(&self, rng: &mut Rng) -> Vec<TranscriptEvent> {
+        let mut events = self.events.clone();
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .filter(|(event, _)| {
+                events
+                    .get(*event)
+                    .is_some_and(|event| event.direction == Direction::Send)
+            })
+            .collect();
+        if fields.is_empty() {
+            return events;
+        }
+        let (event, range) = fields[rng.below(fields.len())];
+        let data = &mut events[*event].data;
+        let end = range.end.min(data.len());
+        let start = range.start.min(end);
+        let value = mutate_field(&data[start..end], rng);
+        data.splice(start..end, value);
+        events
+    }
+}
+
+/// The mutated value of the field.
+fn mutate_field(field: &[u8], rng: &mut Rng) -> Vec<u8> {
+    let mut value = field.to_vec();
+    match rng.below(6) {
+        0 if !value.is_empty() => {
+            let pos = rng.below(value.len());
+            value[pos] ^= 1 << rng.below(8);
+        }
+        1 if !value.is_empty() => {
+            let pos = rng.below(value.len());
+            value[pos] = BYTES[rng.below(BYTES.len())];
+        }
+        2 => value.extend((0..16 << rng.below(8)).map(|_| b'A')),
+        3 if !value.is_empty() => value.truncate(rng.below(value.len())),
+        4 if value.iter().all(u8::is_ascii_digit) => {
+            value = NUMBERS[rng.below(NUMBERS.len())].to_vec();
+        }
+        _ => {
+            let len = rng.below(value.len() * 2 + 2);
+            value = (0..len).map(|_| rng.byte()).collect();
+        }
+    }
+    value
+}
+
+/// The pseudo-random numbers of an iteration.
+struct Rng {
+    seed: u64,
+    pos: u64,
+}
+
+impl Rng {
+    fn new(seed: u64, iteration: u64) -> Self {
+        let seed = u64::from_le_bytes(std::array::from_fn(|i| {
+            random_byte(seed, iteration.wrapping_mul(8) + i as u64)
+        }));
+        Self { seed, pos: 0 }
+    }
+
+    fn byte(&mut self) -> u8 {
+        self.pos += 1;
+        random_byte(self.seed, self.pos - 1)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        let value = u64::from_le_bytes(std::array::from_fn(|_| self.byte()));
+        (value % n as u64) as usize
+    }
+}
+
+/// How an iteration of a [`Campaign`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The whole interaction went through.
+    Completed,
+    /// The target closed the connection or failed at the event.
+    Closed {
+        /// The index of the event.
+        event: usize,
+    },
+    /// The process of the target was killed by the signal at the event, which is the number of
+    /// events if it happened after the interaction.
+    Crashed {
+        /// The index of the event.
+        event: usize,
+        /// The signal number, e.g. 11 for `SIGSEGV`.
+        signal: i32,
+    },
+}
+
+/// An iteration of a [`Campaign`] that did not complete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The iteration, which reproduces the finding with the same seed.
+    pub iteration: u64,
+    /// How the iteration ended.
+    pub outcome: Outcome,
+    /// The events replayed, with the mutated field.
+    pub events: Vec<TranscriptEvent>,
+}
+
+/// Replays a [`Session`] against fresh connections to the target, mutating one of the marked
+/// fields in every iteration, and reports the iterations where the target closes the connection
+/// or crashes. Crashes are detected for processes spawned with [`Tube::process`] or
+/// [`ProcessTube`](crate::tubes::ProcessTube) on unix.
+///
+/// The data received is not compared with the recording, since the replies change with the
+/// mutations. Each received event waits for as many bytes as recorded, or until the
+/// [`recv_timeout`](Campaign::recv_timeout).
+/// ```rust
+/// use io_tubes::{
+///     fuzz::{Campaign, Outcome, Session},
+///     tubes::{ProcessTube, Tube},
+/// };
+/// use std::{io, time::Duration};
+/// use tokio::process::Command;
+///
+/// #[tokio::main]
+/// async fn campaign() -> io::Result<()> {
+///     let session = Session::parse(
+///         "# io-tubes transcript\n\
+///          # fuzz 5..8\n\
+///          1 0 send 4e414d452062656e0a\n\
+///          2 100 recv 6f6b0a\n",
+///     )?;
+///     let mut campaign = Campaign::new(session, || async {
+///         // Crashes on names longer than 8 bytes.
+///         let mut command = Command::new("/bin/sh");
+///         command.arg("-c").arg(r#"read x; [ ${#x} -gt 13 ] && kill -SEGV $$; echo ok"#);
+///         Ok(Tube::new(ProcessTube::from_command(command)?))
+///     })
+///     .seed(1337)
+///     .recv_timeout(Duration::from_millis(500));
+///
+///     let findings = campaign.run(20).await?;
+///     let crash = findings
+///         .iter()
+///         .find(|finding| matches!(finding.outcome, Outcome::Crashed { signal: 11, .. }))
+///         .expect("no crash found");
+///     assert!(crash.events[0].data.len() > 14);
+///     assert_eq!(campaign.run_once(crash.iteration).await?.outcome, crash.outcome);
+///
+///     Ok(())
+/// }
+///
+/// campaign();
+/// ```
+#[derive(Debug)]
+pub struct Campaign<F> {
+    session: Session,
+    connect: F,
+    seed: u64,
+    recv_timeout: Duration,
+}
+
+impl<F, Fut, T> Campaign<F>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<Tube<T>>>,
+    T: AsyncBufRead + AsyncWrite + Unpin + 'static,
+{
+    /// Replay the session against the tubes returned by `connect`.
+    pub fn new(session: Session, connect: F) -> Self {
+        Self {
+            session,
+            connect,
+            seed: 0,
+            recv_timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Derive the mutations from the seed, which is 0 by default.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Wait at most this long for each received event, which is 1 second by default.
+    pub fn recv_timeout(mut self, timeout: Duration) -> Self {
+        self.recv_timeout = timeout;
+        self
+    }
+
+    /// Run the iterations from 0, and return those that did not complete. Fails if `connect`
+    /// fails, e.g. when a crashed server is down.
+    pub async fn run(&mut self, iterations: u64) -> io::Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+        for iteration in 0..iterations {
+            let finding = self.run_once(iteration).await?;
+            if finding.outcome != Outcome::Completed {
+                findings.push(finding);
+            }
+        }
+        Ok(findings)
+    }
+
+    /// Run a single iteration, e.g. to reproduce a finding.
+    pub async fn run_once(&mut self, iteration: u64) -> io::Result<Finding> {
+        let events = self.session.mutate(&mut Rng::new(self.seed, iteration));
+        let mut tube = (self.connect)().await?;
+        tube.timeout = self.recv_timeout;
+        let outcome = match replay(&mut tube, &events).await {
+            Some(event) => match exit_signal(&mut tube, self.recv_timeout).await {
+                Some(signal) => Outcome::Crashed { event, signal },
+                None => Outcome::Closed { event },
+            },
+            None => match tube.recv_checked(1).await {
+                Err(err) if err.is_eof() => match exit_signal(&mut tube, self.recv_timeout).await {
+                    Some(signal) => Outcome::Crashed {
+                        event: events.len(),
+                        signal,
+                    },
+                    None => Outcome::Completed,
+                },
+                _ => Outcome::Completed,
+            },
+        };
+        stop(&mut tube).await;
+        Ok(Finding {
+            iteration,
+            outcome,
+            events,
+        })
+    }
+}
+
+/// Replay the events, and return the index of the event where the target closed the connection.
+async fn replay<T>(tube: &mut Tube<T>, events: &[TranscriptEvent]) -> Option<usize>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    for (index, event) in events.iter().enumerate() {
+        match event.direction {
+            Direction::Send => {
+                if tube.send(&event.data).await.is_err() {
+                    return Some(index);
+                }
+            }
+            Direction::Recv => {
+                let mut remaining = event.data.len();
+                while remaining > 0 {
+                    match tube.recv_checked(remaining).await {
+                        Ok(data) => remaining -= data.len(),
+                        Err(err) if err.is_timeout() => break,
+                        Err(_) => return Some(index),
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The signal that killed the process of the tube, waiting for it to exit at most `timeout`.
+#[cfg(all(unix, feature = "process"))]
+async fn exit_signal<T: 'static>(tube: &mut Tube<T>, timeout: Duration) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let process = (&mut tube.inner as &mut dyn Any).downcast_mut::<BufReader<ProcessTube>>()?;
+    let process = process.get_mut();
+    let status = tokio::time::timeout(timeout, async {
+        loop {
+            match process.try_wait() {
+                Ok(Some(status)) => return Some(status),
+                Ok(None) => tokio::time::sleep(Duration::from_millis(5)).await,
+                Err(_) => return None,
+            }
+        }
+    })
+    .await
+    .ok()??;
+    status.signal()
+}
+
+#[cfg(not(all(unix, feature = "process")))]
+async fn exit_signal<T>(_tube: &mut Tube<T>, _timeout: Duration) -> Option<i32> {
+    None
+}
+
+/// Kill the process of the tube, if it is one, so that it does not outlive the iteration.
+async fn stop<T: 'static>(tube: &mut Tube<T>) {
+    #[cfg(feature = "process")]
+    if let Some(process) =
+        (&mut tube.inner as &mut dyn Any).downcast_mut::<BufReader<ProcessTube>>()
+    {
+        let _ = process.get_mut().kill().await;
+    }
+    #[cfg(not(feature = "process"))]
+    let _ = tube;
+}
@@ -0,0 +1,151 @@
+//! Tubes driven by fuzzer input, and a protocol fuzzer replaying recorded interactions, enabled
+//! by the `fuzz` feature.
+//!
+//! The input decides both the data received and how it is split into reads, with injected
+//! errors and spurious wake ups in between, so that a parser written on top of [`Tube`] can be
+//! fuzzed with AFL or libFuzzer. The input is a sequence of frames, each starting with a control
+//! byte:
+//!
+//! - `0xff`: the read fails with [`ConnectionReset`](io::ErrorKind::ConnectionReset).
+//! - `0xfe`: the read is pending once and wakes up immediately.
+//! - any other byte `n`: the next `n + 1` bytes are received in one read.
+//!
+//! EOF is reached at the end of the input.
+//! ```rust
+//! use io_tubes::fuzz;
+//!
+//! // e.g. the body of `fuzz_target!(|data: &[u8]| { ... })`
+//! let data = b"\x04Hello\xfe\x00\n";
+//! let line = fuzz::run(data, |mut p| async move { p.recv_line().await });
+//! assert_eq!(line.unwrap(), b"Hello\n");
+//! ```
+//!
+//! To fuzz a live target instead, mark the fields of a recorded interaction and replay it with
+//! [`Campaign`].
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, BufReader, ReadBuf},
+    runtime,
+};
+
+use crate::tubes::Tube;
+
+mod campaign;
+pub use campaign::*;
+
+const ERROR: u8 = 0xff;
+const PENDING: u8 = 0xfe;
+
+/// A stream that receives the framed fuzzer input, see the [module documentation](self).
+/// Everything written to it is counted and discarded.
+#[derive(Debug, Clone)]
+pub struct FuzzStream {
+    input: Vec<u8>,
+    pos: usize,
+    /// The bytes left in the current frame.
+    frame: usize,
+    written: u64,
+}
+
+impl FuzzStream {
+    /// Create a stream that receives the framed input.
+    pub fn new(input: impl Into<Vec<u8>>) -> Self {
+        Self {
+            input: input.into(),
+            pos: 0,
+            frame: 0,
+            written: 0,
+        }
+    }
+
+    /// The number of bytes written to the stream.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+impl AsyncRead for FuzzStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.frame == 0 {
+            let Some(&control) = this.input.get(this.pos) else {
+                return Poll::Ready(Ok(()));
+            };
+            this.pos += 1;
+            match control {
+                ERROR => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "error injected by the fuzzer",
+                    )))
+                }
+                PENDING => {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                len => this.frame = len as usize + 1,
+            }
+        }
+        let len = this
+            .frame
+            .min(this.input.len() - this.pos)
+            .min(buf.remaining());
+        buf.put_slice(&this.input[this.pos..this.pos + len]);
+        this.pos += len;
+        this.frame = if this.pos == this.input.len() {
+            0
+        } else {
+            this.frame - len
+        };
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for FuzzStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.written += buf.len() as u64;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Create a tube that receives the framed input.
+pub fn tube(input: impl Into<Vec<u8>>) -> Tube<BufReader<FuzzStream>> {
+    Tube::new(FuzzStream::new(input))
+}
+
+/// Run the protocol handler against a tube that receives the framed input on a new
+/// single-threaded runtime, and return its output. Fuzz targets are synchronous, so this is
+/// meant to be called from one.
+pub fn run<F, Fut>(input: &[u8], handler: F) -> Fut::Output
+where
+    F: FnOnce(Tube<BufReader<FuzzStream>>) -> Fut,
+    Fut: Future,
+{
+    runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("failed to build the runtime")
+        .block_on(handler(tube(input)))
+}
@@ -0,0 +1,40 @@
+//! The futures behind the receive methods of [`Tube`](crate::tubes::Tube), for embedding them in
+//! custom futures and state machines.
+//!
+//! Unlike the methods of the tube, these futures have no timeout and work on any
+//! [`AsyncBufRead`](tokio::io::AsyncBufRead). They append what they receive to a buffer owned by
+//! the caller and only consume data from the reader once it is appended, so they can be dropped
+//! at any point, e.g. in [`tokio::select!`], without losing data. The matchers they use are in
+//! [`utils`](crate::utils).
+//! ```rust
+//! use io_tubes::{io::RecvUntilAny, tubes::Tube};
+//! use std::{io, time::Duration};
+//! use tokio::time;
+//!
+//! #[tokio::main]
+//! async fn select() -> io::Result<()> {
+//!     let mut p = Tube::echo();
+//!     p.send("Login failed\n").await?;
+//!
+//!     let mut buf = Vec::new();
+//!     let found = tokio::select! {
+//!         found = RecvUntilAny::new(&mut p, &[b"Welcome", b"failed"], &mut buf) => found?,
+//!         _ = time::sleep(Duration::from_secs(1)) => None,
+//!     };
+//!     assert_eq!(found, Some(1));
+//!     assert_eq!(buf, b"Login failed");
+//!
+//!     Ok(())
+//! }
+//!
+//! select();
+//! ```
+//!
+//! [`TubeExt`] provides the common helpers of the tube on the same streams, with a timeout per
+//! call through [`TubeExt::timeout`].
+
+pub use crate::utils::ext::{TubeExt, WithTimeout};
+pub use crate::utils::interactive::Interactive;
+pub use crate::utils::needle::RecvNeedle;
+pub use crate::utils::recv_regex::RecvRegex;
+pub use crate::utils::recv_until::{RecvUntil, RecvUntilAny, RecvUntilFuzzy};
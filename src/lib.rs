@@ -2,12 +2,13 @@
 //!
 //! Provides tube functionality like the python library [pwntools](https://github.com/Gallopsled/pwntools).
 //!
-//! The methods are provided in the struct [`Tube`](tubes::Tube)
+//! The methods are provided in the struct [`Tube`](tubes::Tube), and the [`prelude`] exports
+//! it along with the packing and payload helpers for scripts.
 //!
 //! ## Example
 //!
-//! ```rust
-//! use io_tubes::tubes::Tube;
+//! ```rust,no_run
+//! use io_tubes::prelude::*;
 //! use std::io;
 //!
 //! #[tokio::main]
@@ -55,6 +56,64 @@
 //! ## Logging
 //! This crate provides logging of sent and received bytes through the [`log`](https://docs.rs/log) crate.
 //! You can use [any logger implementation](https://docs.rs/log#available-logging-implementations) with the
-//! log level at `DEBUG` or lower to capture the output.
+//! log level at `DEBUG` or lower to capture the output. Each message starts with
+//! `[+<time>s <name>(<id>):<seq> <direction>]`, the time since the first tube was created, the
+//! [name](tubes::Tube::name) and [id](tubes::Tube::id) of the tube and the sequence number of the
+//! event in the tube. The id and the sequence number are also stored in transcripts.
+//!
+//! With the `tracing` feature, the traffic is also emitted as structured `tracing` events in a
+//! span per tube, so that the traffic of concurrent tubes can be told apart. See `Tube::span`.
+//!
+//! ## WebAssembly
+//! The crate compiles for `wasm32` targets with the default features turned off, which gate the
+//! transports that need the operating system:
+//!
+//! - `process`: [`ProcessTube`](tubes::ProcessTube) and [`Tube::process`](tubes::Tube::process).
+//! - `net`: [`Tube::remote`](tubes::Tube::remote), [`Listener`](tubes::Listener), unix sockets
+//!   and port forwarding.
+//!
+//! The interactive methods that use stdin and stdout are not available on `wasm32` either. The
+//! in-memory [`Tube::pair`](tubes::Tube::pair), [`Tube::echo`](tubes::Tube::echo) and
+//! [`ReplayTube`](tubes::ReplayTube) are available, and any other stream like a WebSocket still works with
+//! [`Tube::new`](tubes::Tube::new).
+//!
+//! There is no clock on `wasm32-unknown-unknown`, unlike `wasm32-wasip1`. Tubes still send and
+//! receive there, but the timeouts are not applied, the times in [`TubeStats`](tubes::TubeStats)
+//! stay zero and the log lines have no timestamp. The methods that take a duration, like
+//! [`Tube::can_recv`](tubes::Tube::can_recv), still need a clock.
+//!
+//! ## Other runtimes
+//! The tubes are built on tokio. With the `futures-io` feature, streams of other runtimes like
+//! async-std or smol can be wrapped with `Tube::from_futures_io`, and every tube implements the
+//! `futures-io` traits. The timeouts still use tokio timers, so a tokio runtime has to be
+//! entered.
+extern crate alloc;
+
+#[cfg(feature = "bench-support")]
+pub mod bench;
+pub mod blocking;
+pub mod context;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod io;
+pub mod packing;
+pub mod prelude;
+pub mod report;
 pub mod tubes;
-mod utils;
+pub mod utils;
+
+#[cfg(feature = "stream")]
+pub use bytes;
+pub use regex;
+#[cfg(feature = "sqlite")]
+pub use rusqlite;
+#[cfg(feature = "tls")]
+pub use tokio_rustls;
+#[cfg(feature = "codec")]
+pub use tokio_util::codec;
+#[cfg(feature = "cancel")]
+pub use tokio_util::sync::CancellationToken;
+#[cfg(feature = "tracing")]
+pub use tracing;
+#[cfg(feature = "screen")]
+pub use vt100;
@@ -0,0 +1,138 @@
+//! Conversion between integers and bytes, like `p64` and `u64` in pwntools.
+//!
+//! The short functions use little endian, which is what most targets use. The `_be` variants use
+//! big endian and [`pack`] and [`unpack`] work with any size and endianness.
+//! ```rust
+//! use io_tubes::packing::{p32, p64, u64, u16_be};
+//!
+//! assert_eq!(p64(0x401136), *b"\x36\x11\x40\x00\x00\x00\x00\x00");
+//! assert_eq!(p32(0xdeadbeef), *b"\xef\xbe\xad\xde");
+//! // Leaked pointers are usually shorter than 8 bytes, and they are zero extended.
+//! assert_eq!(u64(b"\x10\xe0\xff\xf7\xff\x7f"), 0x7ffff7ffe010);
+//! assert_eq!(u16_be(b"\x13\x37"), 0x1337);
+//! ```
+
+/// The byte order of integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    /// Least significant byte first, used by x86 and most targets.
+    #[default]
+    Little,
+    /// Most significant byte first, also known as network byte order.
+    Big,
+}
+
+/// Pack the lowest `size` bytes of the value, which must be at most 8.
+pub fn pack(value: u64, size: usize, endian: Endian) -> Vec<u8> {
+    assert!(size <= 8, "cannot pack {} bytes into u64", size);
+    match endian {
+        Endian::Little => value.to_le_bytes()[..size].to_vec(),
+        Endian::Big => value.to_be_bytes()[8 - size..].to_vec(),
+    }
+}
+
+/// Unpack at most 8 bytes into an integer, zero extending shorter input.
+pub fn unpack(bytes: &[u8], endian: Endian) -> u64 {
+    assert!(
+        bytes.len() <= 8,
+        "cannot unpack {} bytes into u64",
+        bytes.len()
+    );
+    let mut buf = [0; 8];
+    match endian {
+        Endian::Little => {
+            buf[..bytes.len()].copy_from_slice(bytes);
+            u64::from_le_bytes(buf)
+        }
+        Endian::Big => {
+            buf[8 - bytes.len()..].copy_from_slice(bytes);
+            u64::from_be_bytes(buf)
+        }
+    }
+}
+
+/// Pack a `u8`, for symmetry with the other sizes.
+pub fn p8(value: u8) -> [u8; 1] {
+    [value]
+}
+
+/// Pack a `u16` in little endian.
+pub fn p16(value: u16) -> [u8; 2] {
+    value.to_le_bytes()
+}
+
+/// Pack a `u32` in little endian.
+pub fn p32(value: u32) -> [u8; 4] {
+    value.to_le_bytes()
+}
+
+/// Pack a `u64` in little endian.
+pub fn p64(value: u64) -> [u8; 8] {
+    value.to_le_bytes()
+}
+
+/// Pack a `u16` in big endian.
+pub fn p16_be(value: u16) -> [u8; 2] {
+    value.to_be_bytes()
+}
+
+/// Pack a `u32` in big endian.
+pub fn p32_be(value: u32) -> [u8; 4] {
+    value.to_be_bytes()
+}
+
+/// Pack a `u64` in big endian.
+pub fn p64_be(value: u64) -> [u8; 8] {
+    value.to_be_bytes()
+}
+
+/// Unpack a `u8`. Panics if the input is empty or longer than 1 byte.
+pub fn u8(bytes: &[u8]) -> u8 {
+    assert_eq!(
+        bytes.len(),
+        1,
+        "cannot unpack {} bytes into u8",
+        bytes.len()
+    );
+    bytes[0]
+}
+
+/// Unpack a little endian `u16`, zero extending shorter input. Panics if it is too long.
+pub fn u16(bytes: &[u8]) -> u16 {
+    unpack_sized(bytes, 2, Endian::Little) as u16
+}
+
+/// Unpack a little endian `u32`, zero extending shorter input. Panics if it is too long.
+pub fn u32(bytes: &[u8]) -> u32 {
+    unpack_sized(bytes, 4, Endian::Little) as u32
+}
+
+/// Unpack a little endian `u64`, zero extending shorter input. Panics if it is too long.
+pub fn u64(bytes: &[u8]) -> u64 {
+    unpack_sized(bytes, 8, Endian::Little)
+}
+
+/// Unpack a big endian `u16`, zero extending shorter input. Panics if it is too long.
+pub fn u16_be(bytes: &[u8]) -> u16 {
+    unpack_sized(bytes, 2, Endian::Big) as u16
+}
+
+/// Unpack a big endian `u32`, zero extending shorter input. Panics if it is too long.
+pub fn u32_be(bytes: &[u8]) -> u32 {
+    unpack_sized(bytes, 4, Endian::Big) as u32
+}
+
+/// Unpack a big endian `u64`, zero extending shorter input. Panics if it is too long.
+pub fn u64_be(bytes: &[u8]) -> u64 {
+    unpack_sized(bytes, 8, Endian::Big)
+}
+
+fn unpack_sized(bytes: &[u8], size: usize, endian: Endian) -> u64 {
+    assert!(
+        bytes.len() <= size,
+        "cannot unpack {} bytes into {} bytes",
+        bytes.len(),
+        size
+    );
+    unpack(bytes, endian)
+}
@@ -0,0 +1,41 @@
+//! The items most scripts need, like `from pwn import *` in pwntools.
+//!
+//! The extension traits of tokio are included, so that the methods of
+//! [`tokio::io::AsyncReadExt`] and friends can be called on tubes too, and so is [`TubeExt`] to
+//! use the helpers of a tube on any other stream.
+//! ```rust
+//! use io_tubes::prelude::*;
+//! use std::io;
+//!
+//! #[tokio::main]
+//! async fn prelude() -> io::Result<()> {
+//!     let mut p = Tube::echo();
+//!
+//!     p.send_line(flat([FlatValue::from(0x401136u64), cyclic(8).into()])).await?;
+//!     let leak = p.recv_until(Regex::new("aaaa").unwrap()).await?;
+//!     assert_eq!(u64(&leak[..8]), 0x401136);
+//!     assert_eq!(p.recv_line().await?, b"baaa\n");
+//!
+//!     p.write_all(b"raw\n").await?;
+//!     p.flush().await?;
+//!     assert_eq!(p.recv_line().await?, b"raw\n");
+//!
+//!     Ok(())
+//! }
+//!
+//! prelude();
+//! ```
+
+pub use regex::bytes::Regex;
+pub use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+pub use crate::blocking::BlockingTube;
+pub use crate::context;
+pub use crate::io::{TubeExt, WithTimeout};
+pub use crate::packing::*;
+pub use crate::tubes::{CloseOptions, EofPolicy, RecvUntilOptions, Tube, TubeError};
+#[cfg(feature = "net")]
+pub use crate::tubes::{Listener, RemoteOptions};
+#[cfg(feature = "process")]
+pub use crate::tubes::{ProcessOptions, ProcessTube};
+pub use crate::utils::{cyclic, cyclic_find, fit, flat, AnyMatcher, FlatValue, Matcher, Needle};
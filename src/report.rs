@@ -0,0 +1,237 @@
+//! A session report collected across all tubes, to help tune flaky multi-stage exploits.
+//!
+//! Nothing is collected until [`enable`] is called. The report counts the bytes and timeouts of
+//! every tube operation, split into the stages named by [`stage`].
+//! ```rust
+//! use io_tubes::{report, tubes::Tube};
+//! use std::{io, time::Duration};
+//!
+//! #[tokio::main]
+//! async fn report() -> io::Result<()> {
+//!     // The report is printed to stderr when the guard is dropped at the end.
+//!     let _report = report::enable();
+//!
+//!     let mut p = Tube::echo();
+//!     p.timeout = Duration::from_millis(50);
+//!
+//!     report::stage("leak");
+//!     p.send("0x1337\n").await?;
+//!     p.recv_line().await?;
+//!
+//!     report::stage("overwrite");
+//!     p.recv_line().await?;
+//!
+//!     let summary = report::snapshot();
+//!     assert_eq!(summary.stages[0].name, "leak");
+//!     assert_eq!(summary.stages[0].bytes_sent, 7);
+//!     assert_eq!(summary.stages[1].timeouts, 1);
+//!
+//!     Ok(())
+//! }
+//!
+//! report();
+//! ```
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+#[derive(Debug)]
+struct State {
+    stages: Vec<StageReport>,
+    current: usize,
+    stage_started: Instant,
+}
+
+/// The statistics of a stage.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StageReport {
+    /// The name given to [`stage`], or empty before any stage is named.
+    pub name: String,
+    /// The total time spent in the stage.
+    pub duration: Duration,
+    /// The number of bytes sent by all tubes.
+    pub bytes_sent: u64,
+    /// The number of bytes received by all tubes.
+    pub bytes_received: u64,
+    /// The number of operations that timed out.
+    pub timeouts: u64,
+    /// The number of retries recorded by [`record_retry`].
+    pub retries: u64,
+    /// The number of reconnects recorded by [`record_reconnect`].
+    pub reconnects: u64,
+}
+
+/// A snapshot of the session report, which is printed as a table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The stages in the order they are first entered.
+    pub stages: Vec<StageReport>,
+}
+
+/// Prints the report to stderr when dropped, returned by [`enable`].
+#[must_use = "the report is printed when the guard is dropped"]
+#[derive(Debug)]
+pub struct ReportGuard {
+    _private: (),
+}
+
+impl Drop for ReportGuard {
+    fn drop(&mut self) {
+        print();
+    }
+}
+
+/// Start collecting the report, clearing anything collected before.
+pub fn enable() -> ReportGuard {
+    *lock() = Some(State {
+        stages: vec![StageReport::default()],
+        current: 0,
+        stage_started: Instant::now(),
+    });
+    ENABLED.store(true, Ordering::Relaxed);
+    ReportGuard { _private: () }
+}
+
+/// Stop collecting the report. The report collected so far is kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+    if let Some(state) = lock().as_mut() {
+        state.close_stage();
+    }
+}
+
+/// Enter the stage with the name. Entering a stage again adds to its previous statistics.
+pub fn stage(name: impl Into<String>) {
+    let name = name.into();
+    update(|state| {
+        state.close_stage();
+        state.current = match state.stages.iter().position(|stage| stage.name == name) {
+            Some(index) => index,
+            None => {
+                state.stages.push(StageReport {
+                    name,
+                    ..Default::default()
+                });
+                state.stages.len() - 1
+            }
+        };
+    });
+}
+
+/// Record a retry of an operation in the current stage.
+pub fn record_retry() {
+    update(|state| state.current().retries += 1);
+}
+
+/// Record a reconnect in the current stage.
+pub fn record_reconnect() {
+    update(|state| state.current().reconnects += 1);
+}
+
+/// Get the report collected so far. The unnamed stage is omitted if nothing happened in it.
+pub fn snapshot() -> Report {
+    let guard = lock();
+    let Some(state) = guard.as_ref() else {
+        return Report::default();
+    };
+    let mut stages = state.stages.clone();
+    if ENABLED.load(Ordering::Relaxed) {
+        stages[state.current].duration += state.stage_started.elapsed();
+    }
+    if stages.len() > 1 && stages[0].is_idle() {
+        stages.remove(0);
+    }
+    Report { stages }
+}
+
+/// Print the report collected so far to stderr.
+pub fn print() {
+    eprint!("{}", snapshot());
+}
+
+pub(crate) fn record_timeout() {
+    update(|state| state.current().timeouts += 1);
+}
+
+pub(crate) fn record_sent(len: usize) {
+    update(|state| state.current().bytes_sent += len as u64);
+}
+
+pub(crate) fn record_received(len: usize) {
+    update(|state| state.current().bytes_received += len as u64);
+}
+
+fn lock() -> MutexGuard<'static, Option<State>> {
+    STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn update(f: impl FnOnce(&mut State)) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(state) = lock().as_mut() {
+        f(state);
+    }
+}
+
+impl State {
+    fn current(&mut self) -> &mut StageReport {
+        &mut self.stages[self.current]
+    }
+
+    fn close_stage(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.stage_started;
+        self.current().duration += elapsed;
+        self.stage_started = now;
+    }
+}
+
+impl StageReport {
+    fn is_idle(&self) -> bool {
+        self.bytes_sent == 0
+            && self.bytes_received == 0
+            && self.timeouts == 0
+            && self.retries == 0
+            && self.reconnects == 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>12} {:>10} {:>10} {:>8} {:>8} {:>10}",
+            "stage", "duration", "sent", "received", "timeouts", "retries", "reconnects"
+        )?;
+        for stage in &self.stages {
+            let name = if stage.name.is_empty() {
+                "(unnamed)"
+            } else {
+                &stage.name
+            };
+            writeln!(
+                f,
+                "{:<20} {:>12} {:>10} {:>10} {:>8} {:>8} {:>10}",
+                name,
+                format!("{:.3?}", stage.duration),
+                stage.bytes_sent,
+                stage.bytes_received,
+                stage.timeouts,
+                stage.retries,
+                stage.reconnects
+            )?;
+        }
+        Ok(())
+    }
+}
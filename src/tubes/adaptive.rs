@@ -0,0 +1,130 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::utils::clock;
+
+use super::Tube;
+
+/// Estimates the response time of the other side like the retransmission timeout of TCP (RFC
+/// 6298), see [`Tube::adaptive_timeout`].
+#[derive(Debug, Clone)]
+pub(super) struct AdaptiveTimeout {
+    min: Duration,
+    max: Duration,
+    smoothed: Option<Duration>,
+    variation: Duration,
+    /// When the oldest data that is not responded yet is sent.
+    request_sent: Option<Instant>,
+}
+
+/// Lock the estimate, which is shared by the halves of a split tube.
+pub(super) fn lock(latency: &Mutex<AdaptiveTimeout>) -> MutexGuard<'_, AdaptiveTimeout> {
+    latency.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+impl AdaptiveTimeout {
+    fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            smoothed: None,
+            variation: Duration::ZERO,
+            request_sent: None,
+        }
+    }
+
+    pub(super) fn sent(&mut self) {
+        if let Some(now) = clock::now() {
+            self.request_sent.get_or_insert(now);
+        }
+    }
+
+    pub(super) fn received(&mut self) {
+        if let Some(request_sent) = self.request_sent.take() {
+            self.sample(request_sent.elapsed());
+        }
+    }
+
+    fn sample(&mut self, latency: Duration) {
+        match self.smoothed {
+            None => {
+                self.smoothed = Some(latency);
+                self.variation = latency / 2;
+            }
+            Some(smoothed) => {
+                self.variation = (self.variation * 3 + smoothed.abs_diff(latency)) / 4;
+                self.smoothed = Some((smoothed * 7 + latency) / 8);
+            }
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        match self.smoothed {
+            Some(smoothed) => smoothed
+                .saturating_add(self.variation.saturating_mul(4))
+                .clamp(self.min, self.max),
+            None => self.max,
+        }
+    }
+}
+
+impl<T> Tube<T> {
+    /// Adapt the timeout for receiving to the measured response time of the other side, bounded
+    /// by `min` and `max`. [`Tube::timeout`] is not used for receiving until
+    /// [`Tube::clear_adaptive_timeout`] is called.
+    ///
+    /// The response time is measured from sending data to receiving the next data, and the
+    /// timeout is estimated from its average and variation like TCP does. The timeout is `max`
+    /// until the first response is measured.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{
+    ///     io,
+    ///     time::{Duration, Instant},
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn adaptive_timeout() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.adaptive_timeout(Duration::from_millis(50), Duration::from_secs(5));
+    ///     assert_eq!(p.estimated_timeout(), Some(Duration::from_secs(5)));
+    ///
+    ///     p.send("ping\n").await?;
+    ///     assert_eq!(p.recv_line().await?, b"ping\n");
+    ///     assert_eq!(p.estimated_timeout(), Some(Duration::from_millis(50)));
+    ///
+    ///     // cat answers quickly, so waiting for a line that never comes fails fast.
+    ///     let start = Instant::now();
+    ///     assert_eq!(p.recv_line().await?, b"");
+    ///     assert!(start.elapsed() < Duration::from_secs(1));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// adaptive_timeout();
+    /// ```
+    pub fn adaptive_timeout(&mut self, min: Duration, max: Duration) {
+        assert!(
+            min <= max,
+            "the minimum timeout must not exceed the maximum"
+        );
+        self.traffic.latency = Some(Arc::new(Mutex::new(AdaptiveTimeout::new(min, max))));
+    }
+
+    /// Stop adapting the timeout set by [`Tube::adaptive_timeout`] and use [`Tube::timeout`]
+    /// again.
+    pub fn clear_adaptive_timeout(&mut self) {
+        self.traffic.latency = None;
+    }
+
+    /// The timeout for receiving estimated by [`Tube::adaptive_timeout`], `None` if it is not
+    /// enabled.
+    pub fn estimated_timeout(&self) -> Option<Duration> {
+        self.traffic
+            .latency
+            .as_ref()
+            .map(|latency| lock(latency).timeout())
+    }
+}
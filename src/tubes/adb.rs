@@ -0,0 +1,128 @@
+use std::io;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use super::Tube;
+
+/// The default address of the adb server started by `adb start-server`.
+const DEFAULT_SERVER: &str = "127.0.0.1:5037";
+
+/// A handle to an Android device through the adb server, which opens services on the device as
+/// tubes.
+/// ```rust
+/// use io_tubes::tubes::Adb;
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn adb() -> io::Result<()> {
+///     let adb = Adb::new().serial("emulator-5554");
+///
+///     let mut shell = adb.shell("/data/local/tmp/chall").await?;
+///     shell.send_line("hello").await?;
+///
+///     // Connect to a TCP port on the device, like `adb forward`.
+///     let mut service = adb.remote(1337).await?;
+///     service.recv_line().await?;
+///
+///     Ok(())
+/// }
+///
+/// adb();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Adb {
+    /// The address of the adb server.
+    pub server: String,
+    /// The serial of the device to use, or any device if it is `None`.
+    pub serial: Option<String>,
+}
+
+impl Adb {
+    /// Use the local adb server with any connected device.
+    pub fn new() -> Self {
+        Self {
+            server: DEFAULT_SERVER.to_string(),
+            serial: None,
+        }
+    }
+
+    /// Use the adb server at the address instead, e.g. `adb -H`.
+    pub fn server(mut self, server: impl Into<String>) -> Self {
+        self.server = server.into();
+        self
+    }
+
+    /// Use the device with the serial, e.g. `adb -s`.
+    pub fn serial(mut self, serial: impl Into<String>) -> Self {
+        self.serial = Some(serial.into());
+        self
+    }
+
+    /// Run the command on the device with `adb shell` and return the tube to it.
+    pub async fn shell(&self, command: impl AsRef<str>) -> io::Result<Tube<BufReader<TcpStream>>> {
+        self.open(&format!("shell:{}", command.as_ref())).await
+    }
+
+    /// Connect to the TCP port on the device.
+    pub async fn remote(&self, port: u16) -> io::Result<Tube<BufReader<TcpStream>>> {
+        self.open(&format!("tcp:{}", port)).await
+    }
+
+    /// Open any service on the device, e.g. `localabstract:<name>` for abstract unix sockets.
+    pub async fn open(&self, service: &str) -> io::Result<Tube<BufReader<TcpStream>>> {
+        let mut stream = TcpStream::connect(&self.server).await?;
+        let transport = match &self.serial {
+            Some(serial) => format!("host:transport:{}", serial),
+            None => "host:transport-any".to_string(),
+        };
+        request(&mut stream, &transport).await?;
+        request(&mut stream, service).await?;
+        Ok(Tube::new(stream))
+    }
+}
+
+impl Default for Adb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Send a request in the adb host protocol and check the status.
+async fn request(stream: &mut TcpStream, request: &str) -> io::Result<()> {
+    stream
+        .write_all(format!("{:04x}{}", request.len(), request).as_bytes())
+        .await?;
+    let mut status = [0; 4];
+    stream.read_exact(&mut status).await?;
+    match &status {
+        b"OKAY" => Ok(()),
+        b"FAIL" => {
+            let mut len = [0; 4];
+            stream.read_exact(&mut len).await?;
+            let len = std::str::from_utf8(&len)
+                .ok()
+                .and_then(|len| usize::from_str_radix(len, 16).ok())
+                .ok_or_else(|| invalid_response(&len))?;
+            let mut message = vec![0; len];
+            stream.read_exact(&mut message).await?;
+            Err(io::Error::other(format!(
+                "adb: {}",
+                String::from_utf8_lossy(&message)
+            )))
+        }
+        _ => Err(invalid_response(&status)),
+    }
+}
+
+fn invalid_response(response: &[u8]) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "unexpected adb response {:?}",
+            String::from_utf8_lossy(response)
+        ),
+    )
+}
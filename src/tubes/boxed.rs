@@ -0,0 +1,67 @@
+use std::fmt;
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+
+use super::Tube;
+
+/// A tube over any buffered stream, so that helpers can take and return "some tube" without
+/// being generic over the stream. Made by [`Tube::boxed`].
+pub type BoxTube = Tube<Box<dyn BufTubeIo>>;
+
+/// A stream of any transport, which is boxed in the tubes made by
+/// [`Tube::connect`](Tube#method.connect).
+pub trait TubeIo: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> TubeIo for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl fmt::Debug for dyn TubeIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TubeIo").finish_non_exhaustive()
+    }
+}
+
+/// A buffered stream of any transport, which is boxed in a [`BoxTube`].
+pub trait BufTubeIo: AsyncBufRead + AsyncWrite + Unpin + Send {}
+
+impl<T> BufTubeIo for T where T: AsyncBufRead + AsyncWrite + Unpin + Send {}
+
+impl fmt::Debug for dyn BufTubeIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufTubeIo").finish_non_exhaustive()
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Erase the type of the stream. The settings, the data already received and the logging of
+    /// the tube are kept.
+    /// ```rust
+    /// use io_tubes::tubes::{BoxTube, LineEcho, Tube};
+    /// use std::io;
+    ///
+    /// async fn is_echo(p: &mut BoxTube) -> io::Result<bool> {
+    ///     p.send_line("ping").await?;
+    ///     Ok(p.recv_line().await? == b"ping\n")
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn boxed() -> io::Result<()> {
+    ///     let mut targets = vec![
+    ///         Tube::echo().boxed(),
+    ///         Tube::new(LineEcho::new()).boxed(),
+    ///     ];
+    ///     for p in &mut targets {
+    ///         assert!(is_echo(p).await?);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// boxed();
+    /// ```
+    pub fn boxed(self) -> BoxTube {
+        self.map_buffered(|inner| Box::new(inner) as Box<dyn BufTubeIo>)
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    error::Error,
+    fmt,
+    future::{self, Future},
+    io,
+    task::Poll,
+};
+
+use tokio::io::AsyncWrite;
+
+use super::Tube;
+
+/// Sends the same data to many tubes at once, e.g. to spray a payload at several instances of a
+/// service for a race condition. The sends run concurrently, and every tube gets the data even
+/// if some of them fail.
+///
+/// The members can be whole tubes or the write halves of [`Tube::split`], so that the responses
+/// can still be received separately.
+/// ```rust
+/// use io_tubes::tubes::{Broadcast, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn broadcast() -> io::Result<()> {
+///     let (mut receivers, mut senders) = (Vec::new(), Vec::new());
+///     for _ in 0..3 {
+///         let (rx, tx) = Tube::echo().split();
+///         receivers.push(rx);
+///         senders.push(tx);
+///     }
+///
+///     let mut broadcast = Broadcast::new(senders);
+///     broadcast.send_line("race").await?;
+///     for rx in &mut receivers {
+///         assert_eq!(rx.recv_line().await?, b"race\n");
+///     }
+///
+///     Ok(())
+/// }
+///
+/// broadcast();
+/// ```
+#[derive(Debug)]
+pub struct Broadcast<T> {
+    /// The tubes that the data is sent to.
+    pub tubes: Vec<Tube<T>>,
+}
+
+impl<T> Broadcast<T> {
+    /// Send to all the tubes.
+    pub fn new(tubes: impl IntoIterator<Item = Tube<T>>) -> Self {
+        Self {
+            tubes: tubes.into_iter().collect(),
+        }
+    }
+
+    /// Get back the tubes.
+    pub fn into_inner(self) -> Vec<Tube<T>> {
+        self.tubes
+    }
+}
+
+impl<T> Broadcast<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Send the data to all the tubes, see [`Tube::send`].
+    pub async fn send(&mut self, data: impl AsRef<[u8]>) -> Result<(), BroadcastError> {
+        let data = data.as_ref();
+        self.each(async |tube| tube.send(data).await).await
+    }
+
+    /// Send the data and a newline to all the tubes, see [`Tube::send_line`].
+    pub async fn send_line(&mut self, data: impl AsRef<[u8]>) -> Result<(), BroadcastError> {
+        let data = data.as_ref();
+        self.each(async |tube| tube.send_line(data).await).await
+    }
+
+    /// Close the sending direction of all the tubes, see [`Tube::close_send`].
+    pub async fn close_send(&mut self) -> Result<(), BroadcastError> {
+        self.each(async |tube| tube.close_send().await).await
+    }
+
+    /// Run `f` on all the tubes concurrently, collecting the errors.
+    async fn each(
+        &mut self,
+        f: impl AsyncFn(&mut Tube<T>) -> io::Result<()>,
+    ) -> Result<(), BroadcastError> {
+        let f = &f;
+        let total = self.tubes.len();
+        let mut sends: Vec<_> = self
+            .tubes
+            .iter_mut()
+            .map(|tube| Some(Box::pin(f(tube))))
+            .collect();
+        let mut errors = Vec::new();
+        future::poll_fn(|cx| {
+            let mut pending = false;
+            for (index, slot) in sends.iter_mut().enumerate() {
+                let Some(send) = slot else {
+                    continue;
+                };
+                match send.as_mut().poll(cx) {
+                    Poll::Ready(result) => {
+                        *slot = None;
+                        if let Err(error) = result {
+                            errors.push((index, error));
+                        }
+                    }
+                    Poll::Pending => pending = true,
+                }
+            }
+            match pending {
+                true => Poll::Pending,
+                false => Poll::Ready(()),
+            }
+        })
+        .await;
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+        errors.sort_by_key(|(index, _)| *index);
+        Err(BroadcastError { total, errors })
+    }
+}
+
+/// The error returned by [`Broadcast`] when sending to some of the tubes fails. The data is still
+/// sent to the other tubes.
+#[derive(Debug)]
+pub struct BroadcastError {
+    /// The number of tubes that the data is sent to.
+    pub total: usize,
+    /// The index of each tube that failed and its error, in the order of the tubes.
+    pub errors: Vec<(usize, io::Error)>,
+}
+
+impl fmt::Display for BroadcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} tubes failed", self.errors.len(), self.total)?;
+        if let Some((index, error)) = self.errors.first() {
+            write!(f, ", tube {}: {}", index, error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for BroadcastError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.errors.first().map(|(_, error)| error as _)
+    }
+}
+
+impl From<BroadcastError> for io::Error {
+    fn from(err: BroadcastError) -> Self {
+        let kind = err
+            .errors
+            .first()
+            .map_or(io::ErrorKind::Other, |(_, error)| error.kind());
+        io::Error::new(kind, err)
+    }
+}
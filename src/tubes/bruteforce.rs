@@ -0,0 +1,247 @@
+use std::{
+    cell::Cell,
+    error::Error,
+    fmt,
+    future::{self, Future},
+    io,
+    task::Poll,
+    time::Duration,
+};
+
+use log::debug;
+
+use crate::utils::timeout;
+
+use super::Tube;
+
+/// What [`bruteforce`] does when an attempt fails with an error instead of returning whether it
+/// succeeded, e.g. because the connection cannot be made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryPolicy {
+    /// Stop with the error.
+    Abort,
+    /// Count the attempt as failed and go on with the next ones.
+    Skip,
+    /// Run the attempt again with a new tube up to this many times, then stop with the error.
+    Retry(u32),
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::Retry(3)
+    }
+}
+
+/// Options for [`bruteforce`].
+#[derive(Debug, Clone)]
+pub struct BruteforceOptions {
+    /// The number of attempts running at the same time, 1 by default.
+    pub concurrency: usize,
+    /// The number of attempts to make, e.g. 256 to guess a byte. There is no limit if it is
+    /// `None`, which is the default.
+    pub max_attempts: Option<u64>,
+    /// What to do when an attempt fails with an error, retrying it 3 times by default.
+    pub on_error: RetryPolicy,
+    /// The time that the whole brute force must finish in. There is no timeout if it is `None`,
+    /// which is the default.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for BruteforceOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 1,
+            max_attempts: None,
+            on_error: RetryPolicy::default(),
+            timeout: None,
+        }
+    }
+}
+
+/// The error returned by [`bruteforce`] when no attempt succeeds.
+#[derive(Debug)]
+pub enum BruteforceError {
+    /// Every attempt failed.
+    Exhausted {
+        /// The number of attempts made.
+        attempts: u64,
+    },
+    /// The timeout of the options is reached.
+    Timeout {
+        /// The number of attempts started.
+        attempts: u64,
+    },
+    /// An attempt failed with an error, see [`BruteforceOptions::on_error`].
+    Failed {
+        /// The index of the attempt.
+        attempt: u64,
+        /// The error of its last run.
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for BruteforceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exhausted { attempts } => write!(f, "all {} attempts failed", attempts),
+            Self::Timeout { attempts } => {
+                write!(f, "timed out after starting {} attempts", attempts)
+            }
+            Self::Failed { attempt, error } => write!(f, "attempt {} failed: {}", attempt, error),
+        }
+    }
+}
+
+impl Error for BruteforceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Failed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<BruteforceError> for io::Error {
+    fn from(err: BruteforceError) -> Self {
+        let kind = match &err {
+            BruteforceError::Exhausted { .. } => io::ErrorKind::NotFound,
+            BruteforceError::Timeout { .. } => io::ErrorKind::TimedOut,
+            BruteforceError::Failed { error, .. } => error.kind(),
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// Run attempts with a fresh tube each until one succeeds, e.g. to guess a stack canary byte by
+/// byte or to win a race, returning what the successful attempt returns.
+///
+/// `connect` creates the tube of an attempt, and `attempt` gets it together with the index of
+/// the attempt, counting from 0. It returns `Some` on success, or `None` if the attempt failed
+/// and the next ones should be tried. The attempts run concurrently in the current task up to
+/// [`BruteforceOptions::concurrency`], and the ones still running are dropped once one succeeds.
+/// ```rust
+/// use io_tubes::tubes::{bruteforce, BruteforceOptions, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn bruteforce_canary() -> io::Result<()> {
+///     // A service that crashes unless the byte sent is the canary.
+///     let connect = async || {
+///         let (p, mut server) = Tube::pair();
+///         tokio::spawn(async move {
+///             let byte = server.recv_u8().await?;
+///             match byte {
+///                 0x42 => server.send("ok\n").await,
+///                 _ => server.send("*** stack smashing detected ***\n").await,
+///             }
+///         });
+///         Ok(p)
+///     };
+///     let options = BruteforceOptions {
+///         concurrency: 16,
+///         max_attempts: Some(256),
+///         ..Default::default()
+///     };
+///
+///     let canary = bruteforce(
+///         connect,
+///         async |mut p: Tube<_>, attempt| {
+///             p.send([attempt as u8]).await?;
+///             Ok((p.recv_line().await? == b"ok\n").then_some(attempt as u8))
+///         },
+///         &options,
+///     )
+///     .await?;
+///     assert_eq!(canary, 0x42);
+///
+///     Ok(())
+/// }
+///
+/// bruteforce_canary();
+/// ```
+pub async fn bruteforce<T, R>(
+    connect: impl AsyncFn() -> io::Result<Tube<T>>,
+    attempt: impl AsyncFn(Tube<T>, u64) -> io::Result<Option<R>>,
+    options: &BruteforceOptions,
+) -> Result<R, BruteforceError> {
+    let started = Cell::new(0);
+    let search = search(&connect, &attempt, options, &started);
+    match options.timeout {
+        Some(duration) => timeout(duration, search).await.unwrap_or_else(|_| {
+            Err(BruteforceError::Timeout {
+                attempts: started.get(),
+            })
+        }),
+        None => search.await,
+    }
+}
+
+/// Run the attempts, counting the ones started in `started`.
+async fn search<T, R>(
+    connect: &impl AsyncFn() -> io::Result<Tube<T>>,
+    attempt: &impl AsyncFn(Tube<T>, u64) -> io::Result<Option<R>>,
+    options: &BruteforceOptions,
+    started: &Cell<u64>,
+) -> Result<R, BruteforceError> {
+    let run = async |index: u64| {
+        let mut retries = 0;
+        loop {
+            let result = match connect().await {
+                Ok(tube) => attempt(tube, index).await,
+                Err(err) => Err(err),
+            };
+            match (result, options.on_error) {
+                (Err(err), RetryPolicy::Retry(max)) if retries < max => {
+                    debug!(target: "bruteforce", "Attempt {} failed, retrying: {}", index, err);
+                    retries += 1;
+                }
+                (result, _) => return (index, result),
+            }
+        }
+    };
+    let mut running: Vec<_> = (0..options.concurrency.max(1)).map(|_| None).collect();
+    future::poll_fn(|cx| loop {
+        for slot in &mut running {
+            let next = started.get();
+            if slot.is_none() && options.max_attempts.is_none_or(|max| next < max) {
+                *slot = Some(Box::pin(run(next)));
+                started.set(next + 1);
+            }
+        }
+        let mut finished = false;
+        let mut pending = false;
+        for slot in &mut running {
+            let Some(future) = slot else {
+                continue;
+            };
+            let Poll::Ready((index, result)) = future.as_mut().poll(cx) else {
+                pending = true;
+                continue;
+            };
+            *slot = None;
+            finished = true;
+            match result {
+                Ok(Some(value)) => return Poll::Ready(Ok(value)),
+                Ok(None) => {}
+                Err(err) if options.on_error == RetryPolicy::Skip => {
+                    debug!(target: "bruteforce", "Attempt {} failed, skipping: {}", index, err);
+                }
+                Err(error) => {
+                    return Poll::Ready(Err(BruteforceError::Failed {
+                        attempt: index,
+                        error,
+                    }))
+                }
+            }
+        }
+        if !finished {
+            return match pending {
+                true => Poll::Pending,
+                false => Poll::Ready(Err(BruteforceError::Exhausted {
+                    attempts: started.get(),
+                })),
+            };
+        }
+    })
+    .await
+}
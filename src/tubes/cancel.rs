@@ -0,0 +1,158 @@
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, BufReader, ReadBuf};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+use super::Tube;
+
+/// The error inside the [`io::Error`] returned once the token of a [`CancelTube`] is cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Cancelled {
+    /// Returns true if the error is caused by a cancelled token.
+    pub fn is(err: &io::Error) -> bool {
+        err.get_ref().is_some_and(|err| err.is::<Cancelled>())
+    }
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+impl From<Cancelled> for io::Error {
+    fn from(err: Cancelled) -> Self {
+        io::Error::other(err)
+    }
+}
+
+/// Fails the reads and writes of the inner stream with [`Cancelled`] as soon as the token is
+/// cancelled, including the ones already waiting. Created by [`Tube::with_cancel`].
+///
+/// Shutting down is still passed through, so that the tube can be closed after cancelling.
+#[derive(Debug)]
+pub struct CancelTube<T> {
+    inner: T,
+    token: CancellationToken,
+    cancelled: Pin<Box<WaitForCancellationFutureOwned>>,
+}
+
+impl<T> CancelTube<T> {
+    /// Cancel the reads and writes of `inner` when `token` is cancelled.
+    pub fn new(inner: T, token: CancellationToken) -> Self {
+        Self {
+            inner,
+            cancelled: Box::pin(token.clone().cancelled_owned()),
+            token,
+        }
+    }
+
+    /// The token that cancels the stream.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Get back the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Ready with the error once the token is cancelled, and wakes the task when it is.
+    fn poll_cancelled(&mut self, cx: &mut Context<'_>) -> Poll<io::Error> {
+        self.cancelled.as_mut().poll(cx).map(|()| Cancelled.into())
+    }
+}
+
+impl<T> Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Make every operation of the tube, like [`Tube::recv_until`] or [`Tube::interactive`],
+    /// fail promptly with [`Cancelled`] once the token is cancelled, even while waiting. Cancel a
+    /// parent token to shut down many tubes at once, e.g. the workers of a brute force.
+    /// ```rust
+    /// use io_tubes::{
+    ///     tubes::{Cancelled, Tube},
+    ///     CancellationToken,
+    /// };
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn with_cancel() -> io::Result<()> {
+    ///     let token = CancellationToken::new();
+    ///     let mut workers = Vec::new();
+    ///     for _ in 0..4 {
+    ///         let (p, server) = Tube::pair();
+    ///         let mut p = p.with_cancel(token.child_token());
+    ///         workers.push(tokio::spawn(async move {
+    ///             let result = p.recv_until("flag{").await;
+    ///             drop(server);
+    ///             result
+    ///         }));
+    ///     }
+    ///
+    ///     token.cancel();
+    ///     for worker in workers {
+    ///         assert!(Cancelled::is(&worker.await?.unwrap_err()));
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// with_cancel();
+    /// ```
+    pub fn with_cancel(self, token: CancellationToken) -> Tube<BufReader<CancelTube<T>>> {
+        self.map_inner(|inner| CancelTube::new(inner, token))
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for CancelTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if let Poll::Ready(err) = this.poll_cancelled(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for CancelTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if let Poll::Ready(err) = this.poll_cancelled(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Pin::new(&mut this.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if let Poll::Ready(err) = this.poll_cancelled(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(feature = "process")]
+use std::any::Any;
+use std::{io, time::Duration};
+
+#[cfg(feature = "process")]
+use tokio::io::BufReader;
+use tokio::{
+    io::{AsyncBufRead, AsyncReadExt, AsyncWrite},
+    time,
+};
+
+#[cfg(feature = "process")]
+use super::ProcessTube;
+use super::Tube;
+
+/// Options for [`Tube::close_with`].
+#[derive(Debug, Clone)]
+pub struct CloseOptions {
+    /// Receive what the other side still sends until EOF, for at most this long, and return it.
+    /// Nothing is received if `None`, which is the default.
+    pub drain: Option<Duration>,
+    /// How long a process is given to exit after its stdin is closed before it is killed. 1
+    /// second by default.
+    pub grace: Duration,
+}
+
+impl Default for CloseOptions {
+    fn default() -> Self {
+        Self {
+            drain: None,
+            grace: Duration::from_secs(1),
+        }
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin + 'static,
+{
+    /// Close the tube the same way for every transport: write the queued data, shut down the
+    /// sending direction, and for process tubes wait for the process to exit, killing it if it
+    /// doesn't within a second. See [`Tube::close_with`] to receive the remaining data.
+    ///
+    /// It is fine if the other side is already gone.
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.close_with(&CloseOptions::default()).await.map(drop)
+    }
+
+    /// Same as [`Tube::close`] with options, returning the data drained if
+    /// [`CloseOptions::drain`] is set.
+    /// ```rust
+    /// use io_tubes::tubes::{CloseOptions, Tube};
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn close_with() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.send("goodbye").await?;
+    ///
+    ///     let options = CloseOptions {
+    ///         drain: Some(Duration::from_secs(1)),
+    ///         ..CloseOptions::default()
+    ///     };
+    ///     assert_eq!(p.close_with(&options).await?, b"goodbye");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// close_with();
+    /// ```
+    pub async fn close_with(&mut self, options: &CloseOptions) -> io::Result<Vec<u8>> {
+        match self.close_send().await {
+            Err(err)
+                if !matches!(
+                    err.kind(),
+                    io::ErrorKind::BrokenPipe | io::ErrorKind::NotConnected
+                ) =>
+            {
+                return Err(err)
+            }
+            _ => {}
+        }
+
+        let mut drained = Vec::new();
+        if let Some(drain) = options.drain {
+            let duration = self.limit_to_deadline(drain);
+            if let Ok(result) = time::timeout(duration, self.read_to_end(&mut drained)).await {
+                result?;
+            }
+        }
+
+        #[cfg(feature = "process")]
+        if let Some(process) =
+            (&mut self.inner as &mut dyn Any).downcast_mut::<BufReader<ProcessTube>>()
+        {
+            let process = process.get_mut();
+            if time::timeout(options.grace, process.wait()).await.is_err() {
+                process.kill().await?;
+            }
+        }
+        Ok(drained)
+    }
+}
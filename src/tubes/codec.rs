@@ -0,0 +1,56 @@
+use tokio::io::{AsyncBufRead, AsyncWrite};
+use tokio_util::codec::{Framed, FramedParts};
+
+use super::Tube;
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Use the codec to receive and send frames on top of the tube.
+    ///
+    /// The data already buffered in the tube is decoded first, so it is fine to receive some
+    /// data with the tube before switching to frames. Use [`Tube::from_framed`] to switch back.
+    /// ```rust
+    /// use futures::{SinkExt, StreamExt};
+    /// use io_tubes::{codec::LinesCodec, tubes::Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn framed() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.send("Banner\nfirst\n").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Banner\n");
+    ///
+    ///     let mut lines = p.framed(LinesCodec::new());
+    ///     lines.send("second").await.unwrap();
+    ///     assert_eq!(lines.next().await.unwrap().unwrap(), "first");
+    ///     assert_eq!(lines.next().await.unwrap().unwrap(), "second");
+    ///
+    ///     let mut p = Tube::from_framed(lines);
+    ///     p.send("third\n").await?;
+    ///     assert_eq!(p.recv_line().await?, b"third\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// framed();
+    /// ```
+    pub fn framed<C>(self, codec: C) -> Framed<Self, C> {
+        Framed::new(self, codec)
+    }
+
+    /// Get back the tube from [`Tube::framed`]. The data received but not yet decoded can be
+    /// received again, and the encoded frames not yet written are sent before any other data.
+    pub fn from_framed<C>(framed: Framed<Self, C>) -> Self {
+        let FramedParts {
+            io: mut tube,
+            read_buf,
+            write_buf,
+            ..
+        } = framed.into_parts();
+        tube.unrecv(&read_buf);
+        tube.send_queue.push(&write_buf);
+        tube
+    }
+}
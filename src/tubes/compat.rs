@@ -0,0 +1,101 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, BufReader, ReadBuf};
+use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt};
+
+use super::Tube;
+
+/// A tube over a [`futures_io`] stream, created by [`Tube::from_futures_io`].
+pub type FuturesIoTube<T> = Tube<BufReader<Compat<T>>>;
+
+impl<T> Tube<BufReader<Compat<T>>>
+where
+    T: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+{
+    /// Construct a tube from a stream of the [`futures_io`] traits, e.g. a socket of async-std or
+    /// smol. The tube itself also implements the [`futures_io`] traits, so it can be passed to
+    /// code written for them.
+    ///
+    /// The timeouts of the tube are tokio timers, so a tokio runtime must be entered and keep
+    /// running, e.g. a multi-thread runtime, while the tube is used from another executor.
+    /// ```rust
+    /// use futures::{
+    ///     executor::block_on,
+    ///     io::{AsyncBufReadExt, Cursor},
+    /// };
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let runtime = tokio::runtime::Runtime::new()?;
+    ///     let _guard = runtime.enter();
+    ///
+    ///     block_on(async {
+    ///         let stream = Cursor::new(b"Hello\nWorld\n".to_vec());
+    ///         let mut p = Tube::from_futures_io(stream);
+    ///         assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///
+    ///         let mut line = String::new();
+    ///         p.read_line(&mut line).await?;
+    ///         assert_eq!(line, "World\n");
+    ///         Ok(())
+    ///     })
+    /// }
+    /// ```
+    pub fn from_futures_io(stream: T) -> Self {
+        Tube::new(stream.compat())
+    }
+}
+
+impl<T> futures_io::AsyncRead for Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = ReadBuf::new(buf);
+        ready!(AsyncRead::poll_read(self, cx, &mut buf))?;
+        Poll::Ready(Ok(buf.filled().len()))
+    }
+}
+
+impl<T> futures_io::AsyncBufRead for Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        AsyncBufRead::poll_fill_buf(self, cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        AsyncBufRead::consume(self, amt)
+    }
+}
+
+impl<T> futures_io::AsyncWrite for Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(self, cx)
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_compression::tokio::{bufread, write};
+use tokio::io::{split, AsyncRead, AsyncWrite, BufReader, ReadBuf, ReadHalf, WriteHalf};
+
+/// The compression formats of [`CompressTube`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Raw deflate streams without any header, as in RFC 1951.
+    Deflate,
+    /// Deflate streams with the zlib header and checksum, as in RFC 1950.
+    Zlib,
+    /// Deflate streams with the gzip header and checksum, as in RFC 1952.
+    Gzip,
+    /// Zstandard frames.
+    Zstd,
+}
+
+macro_rules! dispatch {
+    ($enum:ident, $value:expr, $inner:ident => $body:expr) => {
+        match $value {
+            $enum::Deflate($inner) => $body,
+            $enum::Zlib($inner) => $body,
+            $enum::Gzip($inner) => $body,
+            $enum::Zstd($inner) => $body,
+        }
+    };
+}
+
+#[derive(Debug)]
+enum Decoder<R> {
+    Deflate(bufread::DeflateDecoder<R>),
+    Zlib(bufread::ZlibDecoder<R>),
+    Gzip(bufread::GzipDecoder<R>),
+    Zstd(bufread::ZstdDecoder<R>),
+}
+
+#[derive(Debug)]
+enum Encoder<W> {
+    Deflate(write::DeflateEncoder<W>),
+    Zlib(write::ZlibEncoder<W>),
+    Gzip(write::GzipEncoder<W>),
+    Zstd(write::ZstdEncoder<W>),
+}
+
+/// Compresses everything written to the inner stream and decompresses everything read from it,
+/// so that a protocol wrapped in compression can be driven with the helpers of
+/// [`Tube`](super::Tube).
+///
+/// Every flush, which happens after each send of a tube, emits the data written so far, so the
+/// peer can decompress it without waiting for the end of the stream. Shutting down finishes the
+/// compressed stream. Reads continue past the end of a compressed stream into the next one, so a
+/// service compressing each message separately is read as one stream.
+/// ```rust
+/// use io_tubes::tubes::{Compression, CompressTube, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn compress() -> io::Result<()> {
+///     // The compressed stream is echoed back and decompressed again
+///     let mut p = Tube::echo().map_inner(|inner| CompressTube::new(inner, Compression::Zlib));
+///
+///     p.send_line("Hello").await?;
+///     assert_eq!(p.recv_line().await?, b"Hello\n");
+///     p.send("World! ").await?;
+///     assert_eq!(p.recv_until("! ").await?, b"World! ");
+///
+///     Ok(())
+/// }
+///
+/// compress();
+/// ```
+#[derive(Debug)]
+pub struct CompressTube<T> {
+    reader: Decoder<BufReader<ReadHalf<T>>>,
+    writer: Encoder<WriteHalf<T>>,
+}
+
+impl<T: AsyncRead + AsyncWrite> CompressTube<T> {
+    /// Compress the inner stream in both directions with the format.
+    pub fn new(inner: T, compression: Compression) -> Self {
+        let (read, write) = split(inner);
+        let read = BufReader::new(read);
+        let (reader, writer) = match compression {
+            Compression::Deflate => (
+                Decoder::Deflate(bufread::DeflateDecoder::new(read)),
+                Encoder::Deflate(write::DeflateEncoder::new(write)),
+            ),
+            Compression::Zlib => (
+                Decoder::Zlib(bufread::ZlibDecoder::new(read)),
+                Encoder::Zlib(write::ZlibEncoder::new(write)),
+            ),
+            Compression::Gzip => (
+                Decoder::Gzip(bufread::GzipDecoder::new(read)),
+                Encoder::Gzip(write::GzipEncoder::new(write)),
+            ),
+            Compression::Zstd => (
+                Decoder::Zstd(bufread::ZstdDecoder::new(read)),
+                Encoder::Zstd(write::ZstdEncoder::new(write)),
+            ),
+        };
+        let mut tube = Self { reader, writer };
+        dispatch!(Decoder, &mut tube.reader, decoder => decoder.multiple_members(true));
+        tube
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> CompressTube<T> {
+    /// Consumes the adapter, returning the inner stream. Compressed data received but not
+    /// decompressed yet is lost, and the compressed stream sent is not finished.
+    pub fn into_inner(self) -> T {
+        let read = dispatch!(Decoder, self.reader, decoder => decoder.into_inner().into_inner());
+        let write = dispatch!(Encoder, self.writer, encoder => encoder.into_inner());
+        read.unsplit(write)
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for CompressTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        dispatch!(Decoder, &mut self.get_mut().reader, decoder => {
+            Pin::new(decoder).poll_read(cx, buf)
+        })
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for CompressTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        dispatch!(Encoder, &mut self.get_mut().writer, encoder => {
+            Pin::new(encoder).poll_write(cx, buf)
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch!(Encoder, &mut self.get_mut().writer, encoder => {
+            Pin::new(encoder).poll_flush(cx)
+        })
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch!(Encoder, &mut self.get_mut().writer, encoder => {
+            Pin::new(encoder).poll_shutdown(cx)
+        })
+    }
+}
@@ -0,0 +1,113 @@
+use std::io;
+
+use tokio::io::BufReader;
+
+use super::{Tube, TubeInfo, TubeIo};
+
+impl Tube<BufReader<Box<dyn TubeIo>>> {
+    /// Create a tube from a URI, so that the exploit can switch between the local binary and the
+    /// remote service with an environment variable. The schemes are:
+    ///
+    /// - `tcp://host:port`, see [`Tube::remote`].
+    /// - `tls://host:port`, where any certificate of the server is accepted. See
+    ///   `Tube::remote_tls` to verify it. Needs the `tls` feature.
+    /// - `udp://host:port`, see `Tube::udp`.
+    /// - `unix:///path/to/socket`, see `Tube::unix`.
+    /// - `process://path/to/program`, where the path is relative unless it starts with `/`, e.g.
+    ///   `process://./chall` or `process:///usr/bin/cat`. See [`Tube::process`].
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if the scheme is unknown or its
+    /// feature is disabled.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::{fmt, io};
+    ///
+    /// #[tokio::main]
+    /// async fn connect() -> io::Result<()> {
+    ///     let target = std::env::var("TARGET").unwrap_or("process:///usr/bin/cat".to_string());
+    ///     let mut p = Tube::connect(&target).await?;
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///
+    ///     let l = Listener::bind("127.0.0.1:0").await?;
+    ///     let mut p = Tube::connect(&format!("tcp://127.0.0.1:{}", l.port()?)).await?;
+    ///     let mut server = l.accept().await?;
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(server.recv_line().await?, b"Hello\n");
+    ///
+    ///     let err = Tube::connect("gopher://127.0.0.1:70").await.unwrap_err();
+    ///     assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// connect();
+    /// ```
+    pub async fn connect(uri: &str) -> io::Result<Self> {
+        let (scheme, rest) = uri.split_once("://").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("missing scheme in {}", uri),
+            )
+        })?;
+        let (stream, info): (Box<dyn TubeIo>, TubeInfo) = match scheme {
+            #[cfg(feature = "net")]
+            "tcp" => {
+                let stream = tokio::net::TcpStream::connect(rest).await?;
+                let info = TubeInfo {
+                    peer_addr: stream.peer_addr().ok(),
+                    local_addr: stream.local_addr().ok(),
+                    ..TubeInfo::default()
+                };
+                (Box::new(stream), info)
+            }
+            #[cfg(feature = "tls")]
+            "tls" => {
+                let host = match rest.rsplit_once(':') {
+                    Some((host, _port)) => host.trim_start_matches('[').trim_end_matches(']'),
+                    None => rest,
+                };
+                let config = super::tls::any_server_cert_config();
+                let tube = Tube::remote_tls(rest, host, config).await?;
+                let info = tube.info().clone();
+                (Box::new(tube.into_inner().into_inner()), info)
+            }
+            #[cfg(feature = "net")]
+            "udp" => {
+                let stream = super::UdpTube::connect(rest).await?;
+                let info = TubeInfo {
+                    peer_addr: stream.get_ref().peer_addr().ok(),
+                    local_addr: stream.get_ref().local_addr().ok(),
+                    ..TubeInfo::default()
+                };
+                (Box::new(stream), info)
+            }
+            #[cfg(all(unix, feature = "net"))]
+            "unix" => (
+                Box::new(tokio::net::UnixStream::connect(rest).await?),
+                TubeInfo::default(),
+            ),
+            #[cfg(feature = "process")]
+            "process" => {
+                let stream = super::ProcessTube::new(rest)?;
+                let info = TubeInfo {
+                    pid: stream.id(),
+                    program: Some(rest.into()),
+                    ..TubeInfo::default()
+                };
+                (Box::new(stream), info)
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unsupported scheme {} in {}", scheme, uri),
+                ))
+            }
+        };
+        let mut tube = Tube::new(stream);
+        tube.traffic.info = info;
+        tube.traffic.describe("peer", uri);
+        tube.traffic.set_name(uri.to_string());
+        Ok(tube)
+    }
+}
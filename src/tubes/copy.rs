@@ -0,0 +1,180 @@
+use std::{io, mem};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::Tube;
+
+/// The buffer size of [`CopyOptions::default`].
+const DEFAULT_COPY_BUFFER: usize = 64 * 1024;
+
+/// Options for [`Tube::copy_to_with`] and [`Tube::copy_from_with`].
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    /// The size of the buffer used for each read, 64 KiB by default. The buffer is kept by the
+    /// tube and reused by later copies.
+    pub buffer_size: usize,
+    /// Log the copied data like other traffic. It is off by default since dumping every chunk of
+    /// a large transfer is much slower than the transfer itself. The statistics and the
+    /// transcript still see the data either way.
+    pub log: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            buffer_size: DEFAULT_COPY_BUFFER,
+            log: false,
+        }
+    }
+}
+
+impl<T> Tube<T> {
+    /// Run the copy with the reusable buffer of at least `buffer_size` bytes and the log
+    /// suppressed unless requested.
+    async fn with_copy_buffer<F>(&mut self, options: &CopyOptions, f: F) -> io::Result<u64>
+    where
+        F: AsyncFnOnce(&mut Self, &mut [u8]) -> io::Result<u64>,
+    {
+        let mut buf = mem::take(&mut self.copy_buf);
+        let size = options.buffer_size.max(1);
+        if buf.len() < size {
+            buf.resize(size, 0);
+        }
+        let enabled = self.traffic.log_options.enabled;
+        self.traffic.log_options.enabled = enabled && options.log;
+
+        let result = f(self, &mut buf[..size]).await;
+
+        self.traffic.log_options.enabled = enabled;
+        self.copy_buf = buf;
+        result
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Copy everything received until EOF into the writer, returning the number of bytes copied.
+    /// Unlike [`tokio::io::copy`] over the tube, the traffic is not logged and the buffer is
+    /// reused, see [`CopyOptions`]. The timeout of the tube doesn't apply.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn copy_to() -> io::Result<()> {
+    ///     let (mut a, mut b) = Tube::pair();
+    ///     // The other side is closed when the task ends.
+    ///     let sender = tokio::spawn(async move { a.send(vec![b'A'; 1 << 20]).await });
+    ///
+    ///     let mut out = Vec::new();
+    ///     assert_eq!(b.copy_to(&mut out).await?, 1 << 20);
+    ///     assert_eq!(out, vec![b'A'; 1 << 20]);
+    ///     sender.await??;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// copy_to();
+    /// ```
+    pub async fn copy_to<W>(&mut self, writer: &mut W) -> io::Result<u64>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        self.copy_to_with(writer, &CopyOptions::default(), |_| {})
+            .await
+    }
+
+    /// Same as [`Tube::copy_to`] with options, calling `progress` with the total number of bytes
+    /// copied so far after every chunk.
+    pub async fn copy_to_with<W>(
+        &mut self,
+        writer: &mut W,
+        options: &CopyOptions,
+        mut progress: impl FnMut(u64),
+    ) -> io::Result<u64>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        self.with_copy_buffer(options, async |tube, buf| {
+            let mut total = 0;
+            loop {
+                let numb = tube.read(buf).await?;
+                if numb == 0 {
+                    break;
+                }
+                writer.write_all(&buf[..numb]).await?;
+                total += numb as u64;
+                progress(total);
+            }
+            writer.flush().await?;
+            Ok(total)
+        })
+        .await
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Send everything read from the reader until EOF, returning the number of bytes copied,
+    /// e.g. to upload a file. The traffic is not logged and the timeout of the tube doesn't
+    /// apply, see [`Tube::copy_to`].
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn copy_from() -> io::Result<()> {
+    ///     let (mut a, mut b) = Tube::pair();
+    ///     let data = b"file contents\n".repeat(1024);
+    ///
+    ///     assert_eq!(a.copy_from(&mut &data[..]).await?, data.len() as u64);
+    ///     drop(a);
+    ///     let mut out = Vec::new();
+    ///     b.copy_to(&mut out).await?;
+    ///     assert_eq!(out, data);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// copy_from();
+    /// ```
+    pub async fn copy_from<R>(&mut self, reader: &mut R) -> io::Result<u64>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        self.copy_from_with(reader, &CopyOptions::default(), |_| {})
+            .await
+    }
+
+    /// Same as [`Tube::copy_from`] with options, calling `progress` with the total number of
+    /// bytes copied so far after every chunk.
+    pub async fn copy_from_with<R>(
+        &mut self,
+        reader: &mut R,
+        options: &CopyOptions,
+        mut progress: impl FnMut(u64),
+    ) -> io::Result<u64>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        self.with_copy_buffer(options, async |tube, buf| {
+            let mut total = 0;
+            loop {
+                let numb = reader.read(buf).await?;
+                if numb == 0 {
+                    break;
+                }
+                tube.write_all(&buf[..numb]).await?;
+                total += numb as u64;
+                progress(total);
+            }
+            tube.flush().await?;
+            Ok(total)
+        })
+        .await
+    }
+}
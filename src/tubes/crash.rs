@@ -0,0 +1,284 @@
+use std::{error::Error, fmt, io, process::ExitStatus, time::Duration};
+
+use tokio::{
+    io::{AsyncWriteExt, BufReader},
+    time,
+};
+
+use super::{ProcessTube, Tube};
+
+/// How long to wait for stderr to reach EOF after the process exits, in case a child of the
+/// process still holds it open.
+const STDERR_GRACE: Duration = Duration::from_millis(100);
+
+/// Messages of the heap checks in glibc before it aborts.
+const HEAP_MESSAGES: &[&str] = &[
+    "free(): ",
+    "malloc(): ",
+    "realloc(): ",
+    "munmap_chunk(): ",
+    "malloc_consolidate(): ",
+    "double free or corruption",
+    "corrupted size vs. prev_size",
+    "corrupted double-linked list",
+];
+
+/// What went wrong in a process, see [`ProcessTube::wait_exit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CrashKind {
+    /// AddressSanitizer reported an error, e.g. `heap-buffer-overflow` or `SEGV`.
+    Asan {
+        /// The type of the error.
+        kind: String,
+        /// The address accessed, if reported.
+        address: Option<u64>,
+    },
+    /// UndefinedBehaviorSanitizer reported a runtime error.
+    Ubsan {
+        /// The message after `runtime error: `.
+        message: String,
+    },
+    /// The stack protector found the canary overwritten.
+    StackSmashing,
+    /// The heap checks of glibc found the heap corrupted, e.g. `free(): double free detected in
+    /// tcache 2`.
+    HeapCorruption {
+        /// The message printed before aborting.
+        message: String,
+    },
+    /// The process was killed by the signal without a report, e.g. 11 for `SIGSEGV`.
+    Signal(i32),
+}
+
+impl CrashKind {
+    /// Find the report of a sanitizer, the stack protector or the heap checks of glibc in the
+    /// output to stderr. The first report found is returned.
+    /// ```rust
+    /// use io_tubes::tubes::CrashKind;
+    ///
+    /// let report = b"==4242==ERROR: AddressSanitizer: heap-use-after-free on address \
+    ///     0x602000000010 at pc 0x55d0c5 bp 0x7ffd sp 0x7ffd\\nREAD of size 4";
+    /// assert_eq!(
+    ///     CrashKind::from_stderr(report),
+    ///     Some(CrashKind::Asan {
+    ///         kind: "heap-use-after-free".to_string(),
+    ///         address: Some(0x602000000010),
+    ///     })
+    /// );
+    ///
+    /// let report = b"main.c:5:10: runtime error: signed integer overflow";
+    /// assert!(matches!(CrashKind::from_stderr(report), Some(CrashKind::Ubsan { .. })));
+    /// assert_eq!(CrashKind::from_stderr(b"Hello\\n"), None);
+    /// ```
+    pub fn from_stderr(stderr: &[u8]) -> Option<Self> {
+        let stderr = String::from_utf8_lossy(stderr);
+        stderr.lines().find_map(|line| {
+            if let Some((_, report)) = line.split_once("ERROR: AddressSanitizer: ") {
+                let kind = report.split_whitespace().next().unwrap_or_default();
+                let address = report.split_once("address 0x").and_then(|(_, address)| {
+                    let end = address
+                        .find(|c: char| !c.is_ascii_hexdigit())
+                        .unwrap_or(address.len());
+                    u64::from_str_radix(&address[..end], 16).ok()
+                });
+                return Some(CrashKind::Asan {
+                    kind: kind.to_string(),
+                    address,
+                });
+            }
+            if let Some((_, message)) = line.split_once("runtime error: ") {
+                return Some(CrashKind::Ubsan {
+                    message: message.to_string(),
+                });
+            }
+            if line.contains("*** stack smashing detected ***") {
+                return Some(CrashKind::StackSmashing);
+            }
+            if HEAP_MESSAGES
+                .iter()
+                .any(|message| line.starts_with(message))
+            {
+                return Some(CrashKind::HeapCorruption {
+                    message: line.to_string(),
+                });
+            }
+            None
+        })
+    }
+}
+
+/// How a process exited, returned by [`ProcessTube::wait_exit`].
+#[derive(Debug, Clone)]
+pub struct ExitInfo {
+    /// The exit status of the process.
+    pub status: ExitStatus,
+    /// What went wrong, if the process crashed or a sanitizer reported an error.
+    pub crash: Option<CrashKind>,
+    /// The output to stderr, if it is piped in the command.
+    pub stderr: Option<Vec<u8>>,
+}
+
+impl ExitInfo {
+    /// Whether the process crashed or a sanitizer reported an error.
+    pub fn is_crash(&self) -> bool {
+        self.crash.is_some()
+    }
+}
+
+impl ProcessTube {
+    /// Close stdin, wait for the process to exit, and classify the crash from the reports in
+    /// stderr and the signal that killed it, so that brute force loops can triage the attempts.
+    /// Reports are only found if stderr is piped in the command.
+    /// ```rust
+    /// use io_tubes::tubes::{CrashKind, ProcessTube};
+    /// use std::{io, process::Stdio};
+    /// use tokio::process::Command;
+    ///
+    /// #[tokio::main]
+    /// async fn wait_exit() -> io::Result<()> {
+    ///     let mut command = Command::new("/bin/sh");
+    ///     command
+    ///         .arg("-c")
+    ///         .arg("echo '*** stack smashing detected ***: terminated' >&2; kill -ABRT $$")
+    ///         .stderr(Stdio::piped());
+    ///     let mut p = ProcessTube::from_command(command)?;
+    ///
+    ///     let exit = p.wait_exit().await?;
+    ///     assert_eq!(exit.crash, Some(CrashKind::StackSmashing));
+    ///
+    ///     let mut p = ProcessTube::new("/usr/bin/true")?;
+    ///     assert!(!p.wait_exit().await?.is_crash());
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// wait_exit();
+    /// ```
+    pub async fn wait_exit(&mut self) -> io::Result<ExitInfo> {
+        let status = self.wait().await?;
+        if let Some(task) = self.stderr.as_mut().and_then(|capture| capture.task.take()) {
+            let _ = time::timeout(STDERR_GRACE, task).await;
+        }
+        let stderr = self.stderr();
+        let crash = stderr
+            .as_deref()
+            .and_then(CrashKind::from_stderr)
+            .or_else(|| signal(status).map(CrashKind::Signal));
+        Ok(ExitInfo {
+            status,
+            crash,
+            stderr,
+        })
+    }
+}
+
+/// The error returned by [`Tube::finish`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FinishError {
+    /// The process exited with a non-zero code or was killed by a signal.
+    Failed {
+        /// How the process exited.
+        exit: ExitInfo,
+        /// The output received after stdin is closed.
+        output: Vec<u8>,
+    },
+    /// An IO error occurred.
+    Io(io::Error),
+}
+
+impl fmt::Display for FinishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FinishError::Failed { exit, .. } => match &exit.crash {
+                Some(crash) => write!(f, "process failed with {}: {:?}", exit.status, crash),
+                None => write!(f, "process failed with {}", exit.status),
+            },
+            FinishError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for FinishError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FinishError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FinishError {
+    fn from(err: io::Error) -> Self {
+        FinishError::Io(err)
+    }
+}
+
+impl From<FinishError> for io::Error {
+    fn from(err: FinishError) -> Self {
+        match err {
+            FinishError::Io(err) => err,
+            err => io::Error::other(err),
+        }
+    }
+}
+
+impl Tube<BufReader<ProcessTube>> {
+    /// End a local run: close stdin, receive the remaining output, and wait for the process to
+    /// exit. Returns the output if the process exits successfully, and [`FinishError::Failed`]
+    /// with the output otherwise. The receive timeout applies to receiving the output.
+    /// ```rust
+    /// use io_tubes::tubes::{FinishError, ProcessTube, Tube};
+    /// use std::io;
+    /// use tokio::process::Command;
+    ///
+    /// #[tokio::main]
+    /// async fn finish() -> io::Result<()> {
+    ///     let mut p = Tube::process("/usr/bin/cat")?;
+    ///     p.send("last words").await?;
+    ///     assert_eq!(p.finish().await?, b"last words");
+    ///
+    ///     let mut command = Command::new("/bin/sh");
+    ///     command.arg("-c").arg("cat; exit 3");
+    ///     let mut p = Tube::new(ProcessTube::from_command(command)?);
+    ///     p.send("input").await?;
+    ///     match p.finish().await {
+    ///         Err(FinishError::Failed { exit, output }) => {
+    ///             assert_eq!(exit.status.code(), Some(3));
+    ///             assert_eq!(output, b"input");
+    ///         }
+    ///         other => panic!("unexpected {:?}", other),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// finish();
+    /// ```
+    pub async fn finish(&mut self) -> Result<Vec<u8>, FinishError> {
+        match self.shutdown().await {
+            // The process may exit before reading everything.
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => return Err(err.into()),
+            _ => {}
+        }
+        let output = self.recv_all().await?;
+        let exit = self.inner.get_mut().wait_exit().await?;
+        if !exit.status.success() {
+            return Err(FinishError::Failed { exit, output });
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_status: ExitStatus) -> Option<i32> {
+    None
+}
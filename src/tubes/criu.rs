@@ -0,0 +1,144 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use tokio::process::Command;
+
+use super::ProcessTube;
+
+/// The file in the checkpoint directory that records the pipes of stdin and stdout.
+const PIPES: &str = "io-tubes-pipes";
+
+/// A process dumped by [`ProcessTube::checkpoint`] into a directory, which can be restored many
+/// times by [`ProcessTube::restore`].
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    dir: PathBuf,
+    /// The pipes of stdin and stdout in the dumped process, e.g. `pipe:[1234]`.
+    stdin: String,
+    stdout: String,
+}
+
+impl Checkpoint {
+    /// Open a checkpoint made earlier, e.g. by another run of the exploit.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        let pipes = fs::read_to_string(dir.join(PIPES))?;
+        let mut lines = pipes.lines();
+        match (lines.next(), lines.next()) {
+            (Some(stdin), Some(stdout)) => Ok(Self {
+                stdin: stdin.to_string(),
+                stdout: stdout.to_string(),
+                dir,
+            }),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid checkpoint in {}", dir.display()),
+            )),
+        }
+    }
+
+    /// The directory of the images.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl ProcessTube {
+    /// Dump the process into the directory with [CRIU](https://criu.org), so that a setup phase
+    /// that takes long can be restored for every attempt of the exploit instead of being
+    /// repeated. `criu` must be in `PATH` and have the privilege to dump the process, which
+    /// usually means running as root.
+    ///
+    /// The process is stopped after it is dumped, since the restored processes keep its pid.
+    /// For the same reason, only one of them can run at a time. Data buffered by the
+    /// [`Tube`](super::Tube) wrapping this is not part of the checkpoint, so the interaction
+    /// should be in a quiet state.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::{ProcessTube, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn checkpoint() -> io::Result<()> {
+    ///     let mut p = Tube::process("./challenge")?;
+    ///     p.send_line_after("> ", "slow setup").await?;
+    ///     p.recv_until("> ").await?;
+    ///     let checkpoint = p.inner.get_mut().checkpoint("/tmp/challenge-ckpt").await?;
+    ///
+    ///     for guess in 0..16 {
+    ///         let mut p = Tube::new(ProcessTube::restore(&checkpoint)?);
+    ///         p.send_line(guess.to_string()).await?;
+    ///         if p.recv_line().await?.starts_with(b"flag") {
+    ///             break;
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// checkpoint();
+    /// ```
+    pub async fn checkpoint(&mut self, dir: impl AsRef<Path>) -> io::Result<Checkpoint> {
+        let dir = dir.as_ref();
+        let pid = self.id().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "the process has already exited")
+        })?;
+        let pipe = |fd: u32| -> io::Result<String> {
+            let link = fs::read_link(format!("/proc/{}/fd/{}", pid, fd))?;
+            let link = link.to_string_lossy().into_owned();
+            if !link.starts_with("pipe:") {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("fd {} of the process is not a pipe but {}", fd, link),
+                ));
+            }
+            Ok(link)
+        };
+        let (stdin, stdout) = (pipe(0)?, pipe(1)?);
+
+        fs::create_dir_all(dir)?;
+        let status = Command::new("criu")
+            .arg("dump")
+            .arg("--tree")
+            .arg(pid.to_string())
+            .arg("--images-dir")
+            .arg(dir)
+            .args(["--shell-job", "--log-file", "dump.log"])
+            .stdin(Stdio::null())
+            .status()
+            .await?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "criu dump failed with {}, see {}",
+                status,
+                dir.join("dump.log").display()
+            )));
+        }
+        fs::write(dir.join(PIPES), format!("{}\n{}\n", stdin, stdout))?;
+        Ok(Checkpoint {
+            dir: dir.to_owned(),
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Restore the process from the checkpoint, with stdin and stdout connected to the new tube.
+    /// The child process is `criu`, which exits with the restored process, so
+    /// [`id`](ProcessTube::id) is not the pid of the restored process.
+    pub fn restore(checkpoint: &Checkpoint) -> io::Result<Self> {
+        // The pipes of criu are passed to the restored process in place of the dumped ones.
+        let mut command = Command::new("criu");
+        command
+            .arg("restore")
+            .arg("--images-dir")
+            .arg(&checkpoint.dir)
+            .args(["--shell-job", "--log-file", "restore.log"])
+            .arg("--inherit-fd")
+            .arg(format!("fd[0]:{}", checkpoint.stdin))
+            .arg("--inherit-fd")
+            .arg(format!("fd[1]:{}", checkpoint.stdout));
+        Self::from_command(command)
+    }
+}
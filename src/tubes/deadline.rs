@@ -0,0 +1,57 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Run the future with an ambient deadline `duration` from now, which every tube operation with a
+/// timeout inside the future respects in addition to its own timeout and [`Tube::deadline`].
+///
+/// Nested calls can only shorten the deadline. The deadline is task-local, so it does not apply
+/// to tasks spawned inside the future.
+/// ```rust
+/// use io_tubes::tubes::{with_deadline, Tube};
+/// use std::{
+///     io,
+///     time::{Duration, Instant},
+/// };
+///
+/// async fn solve() -> io::Result<Vec<u8>> {
+///     let mut p = Tube::echo();
+///     p.send_line("leak").await?;
+///     p.recv_line().await?;
+///     p.recv_until("flag{").await
+/// }
+///
+/// #[tokio::main]
+/// async fn attempt() -> io::Result<()> {
+///     let start = Instant::now();
+///     let result = with_deadline(Duration::from_millis(100), solve()).await?;
+///     assert_eq!(result, b"");
+///     assert!(start.elapsed() < Duration::from_millis(500));
+///
+///     Ok(())
+/// }
+///
+/// attempt();
+/// ```
+///
+/// [`Tube::deadline`]: super::Tube::deadline
+pub async fn with_deadline<F: Future>(duration: Duration, future: F) -> F::Output {
+    let deadline = match (Instant::now().checked_add(duration), ambient_deadline()) {
+        (Some(deadline), Some(outer)) => Some(deadline.min(outer)),
+        (deadline, outer) => deadline.or(outer),
+    };
+    match deadline {
+        Some(deadline) => DEADLINE.scope(deadline, future).await,
+        None => future.await,
+    }
+}
+
+/// The deadline set by [`with_deadline`] for the current task, if any.
+pub fn ambient_deadline() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
@@ -0,0 +1,238 @@
+use std::{fmt, sync::Arc};
+
+use super::{Direction, Tube};
+
+/// Describes the traffic of a tube at the protocol level, added by [`Tube::add_decoder`].
+///
+/// It is implemented for closures taking the direction and the data.
+pub trait Decoder: Send + Sync {
+    /// Describe the data sent or received, e.g. `DNS query for example.com (A)`, or return
+    /// `None` if it is not understood.
+    fn decode(&self, direction: Direction, data: &[u8]) -> Option<String>;
+}
+
+impl<F> Decoder for F
+where
+    F: Fn(Direction, &[u8]) -> Option<String> + Send + Sync,
+{
+    fn decode(&self, direction: Direction, data: &[u8]) -> Option<String> {
+        self(direction, data)
+    }
+}
+
+/// The decoders added by [`Tube::add_decoder`].
+#[derive(Default, Clone)]
+pub(super) struct Decoders(Vec<Arc<dyn Decoder>>);
+
+impl Decoders {
+    /// The annotations of the decoders that understand the data, in the order they are added.
+    pub(super) fn annotate(&self, direction: Direction, data: &[u8]) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|decoder| decoder.decode(direction, data))
+            .collect()
+    }
+}
+
+impl fmt::Debug for Decoders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Decoders({})", self.0.len())
+    }
+}
+
+impl<T> Tube<T> {
+    /// Annotate the traffic with the description of the decoder, such as [`JsonDecoder`] or
+    /// [`DnsDecoder`], so that the log is readable without going through the hexdumps. The
+    /// annotations are logged after the data, and written as comments of the form
+    /// `# <seq> <annotation>` into the transcript of [`Tube::record`]. Data redacted by
+    /// [`Tube::set_log_filter`] is not decoded for the log.
+    /// ```rust
+    /// use io_tubes::tubes::{Direction, JsonDecoder, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn add_decoder() -> io::Result<()> {
+    ///     let path = std::env::temp_dir().join("io-tubes-decoder.txt");
+    ///
+    ///     let mut p = Tube::echo();
+    ///     p.add_decoder(JsonDecoder);
+    ///     p.add_decoder(|direction, data: &[u8]| {
+    ///         (direction == Direction::Send && data.starts_with(b"GET "))
+    ///             .then(|| "HTTP request".to_string())
+    ///     });
+    ///     p.record(&path)?;
+    ///
+    ///     p.send_line(r#"{"op": "login", "user": "admin"}"#).await?;
+    ///     p.recv_line().await?;
+    ///     p.send_line("GET / HTTP/1.0").await?;
+    ///     p.stop_recording()?;
+    ///
+    ///     let transcript = std::fs::read_to_string(&path)?;
+    ///     assert!(transcript.contains(r#"# 1 JSON: {"op":"login","user":"admin"}"#));
+    ///     assert!(transcript.contains("HTTP request"));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// add_decoder();
+    /// ```
+    pub fn add_decoder(&mut self, decoder: impl Decoder + 'static) {
+        self.traffic.decoders.0.push(Arc::new(decoder));
+    }
+
+    /// Remove the decoders added by [`Tube::add_decoder`].
+    pub fn clear_decoders(&mut self) {
+        self.traffic.decoders.0.clear();
+    }
+}
+
+/// Recognizes JSON documents, e.g. of web APIs, and shows them compacted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonDecoder;
+
+impl Decoder for JsonDecoder {
+    fn decode(&self, _direction: Direction, data: &[u8]) -> Option<String> {
+        let text = std::str::from_utf8(data).ok()?.trim();
+        let closing = match text.as_bytes().first()? {
+            b'{' => '}',
+            b'[' => ']',
+            _ => return None,
+        };
+        if !text.ends_with(closing) {
+            return None;
+        }
+
+        let mut compact = String::with_capacity(text.len() + 6);
+        compact.push_str("JSON: ");
+        let mut depth = Vec::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        for c in text.chars() {
+            if in_string {
+                compact.push(c);
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    in_string = false;
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                '{' => depth.push('}'),
+                '[' => depth.push(']'),
+                '}' | ']' if depth.pop() != Some(c) => return None,
+                c if c.is_whitespace() => continue,
+                _ => {}
+            }
+            compact.push(c);
+        }
+        (!in_string && depth.is_empty()).then_some(compact)
+    }
+}
+
+/// Recognizes DNS messages over UDP or TCP, and shows the question, e.g.
+/// `DNS query for example.com (A)` or `DNS response for example.com (A), 2 answers`.
+/// ```rust
+/// use io_tubes::tubes::{Decoder, Direction, DnsDecoder};
+///
+/// let query = b"\x12\x34\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\x07example\x03com\x00\x00\x01\x00\x01";
+/// assert_eq!(
+///     DnsDecoder.decode(Direction::Send, query).as_deref(),
+///     Some("DNS query for example.com (A)")
+/// );
+/// assert_eq!(DnsDecoder.decode(Direction::Send, b"Hello"), None);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsDecoder;
+
+impl Decoder for DnsDecoder {
+    fn decode(&self, _direction: Direction, data: &[u8]) -> Option<String> {
+        // Messages over TCP are prefixed by their length.
+        let message = match data {
+            [hi, lo, rest @ ..] if usize::from(u16::from_be_bytes([*hi, *lo])) == rest.len() => {
+                rest
+            }
+            _ => data,
+        };
+        describe_dns(message)
+    }
+}
+
+fn describe_dns(message: &[u8]) -> Option<String> {
+    let header = message.get(..12)?;
+    let flags = u16::from_be_bytes([header[2], header[3]]);
+    let questions = u16::from_be_bytes([header[4], header[5]]);
+    let answers = u16::from_be_bytes([header[6], header[7]]);
+    let response = flags & 0x8000 != 0;
+    let opcode = (flags >> 11) & 0xf;
+    if questions != 1 || opcode != 0 || flags & 0x0040 != 0 || (!response && answers != 0) {
+        return None;
+    }
+
+    let mut name = String::new();
+    let mut pos = 12;
+    loop {
+        let len = usize::from(*message.get(pos)?);
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        // Compression pointers do not appear in the question of sane messages.
+        if len > 63 {
+            return None;
+        }
+        let label = message.get(pos..pos + len)?;
+        if !label
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || b"-_*".contains(c))
+        {
+            return None;
+        }
+        if !name.is_empty() {
+            name.push('.');
+        }
+        name.extend(label.iter().map(|&c| char::from(c)));
+        pos += len;
+    }
+    if name.is_empty() {
+        name.push('.');
+    }
+    let qtype = message.get(pos..pos + 4)?;
+    let qtype = match u16::from_be_bytes([qtype[0], qtype[1]]) {
+        1 => "A".to_string(),
+        2 => "NS".to_string(),
+        5 => "CNAME".to_string(),
+        6 => "SOA".to_string(),
+        12 => "PTR".to_string(),
+        15 => "MX".to_string(),
+        16 => "TXT".to_string(),
+        28 => "AAAA".to_string(),
+        33 => "SRV".to_string(),
+        255 => "ANY".to_string(),
+        other => format!("TYPE{}", other),
+    };
+
+    if !response {
+        return Some(format!("DNS query for {} ({})", name, qtype));
+    }
+    let rcode = match flags & 0xf {
+        0 => "",
+        1 => ", FORMERR",
+        2 => ", SERVFAIL",
+        3 => ", NXDOMAIN",
+        4 => ", NOTIMP",
+        5 => ", REFUSED",
+        _ => ", error",
+    };
+    Some(format!(
+        "DNS response for {} ({}), {} answer{}{}",
+        name,
+        qtype,
+        answers,
+        if answers == 1 { "" } else { "s" },
+        rcode
+    ))
+}
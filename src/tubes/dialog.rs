@@ -0,0 +1,151 @@
+use std::{error::Error, fmt, io};
+
+use regex::bytes::Regex;
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use super::Tube;
+
+#[derive(Debug, Clone)]
+enum Step {
+    Expect(Vec<u8>),
+    Send(Vec<u8>),
+    SendLine(Vec<u8>),
+    Capture(Regex),
+}
+
+/// A script of steps run against a tube in order, so that a whole solver can be written as data
+/// and reused for the local process and the remote target.
+/// ```rust
+/// use io_tubes::tubes::{Dialog, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn dialog() -> io::Result<()> {
+///     let solve = Dialog::new()
+///         .expect("name? ")
+///         .send_line("foo")
+///         .capture_regex(r"flag\{.*\}");
+///
+///     let mut p = Tube::echo();
+///     p.send("name? ").await?;
+///     p.send("flag{d1al0g}\n").await?;
+///     let captures = solve.run(&mut p).await?;
+///     assert_eq!(captures, [b"flag{d1al0g}".to_vec()]);
+///     // cat echoes the name after the flag
+///     assert_eq!(p.recv_line().await?, b"\n");
+///
+///     Ok(())
+/// }
+///
+/// dialog();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Dialog {
+    steps: Vec<Step>,
+}
+
+impl Dialog {
+    /// Create a dialog without any step.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive until the pattern, failing on timeout or EOF.
+    pub fn expect(mut self, pattern: impl AsRef<[u8]>) -> Self {
+        self.steps.push(Step::Expect(pattern.as_ref().to_vec()));
+        self
+    }
+
+    /// Send the data.
+    pub fn send(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.steps.push(Step::Send(data.as_ref().to_vec()));
+        self
+    }
+
+    /// Send the data followed by a new line.
+    pub fn send_line(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.steps.push(Step::SendLine(data.as_ref().to_vec()));
+        self
+    }
+
+    /// Receive until the regex matches and capture the first group, or the whole match if the
+    /// regex has no group. Panics if the regex is invalid.
+    pub fn capture_regex(mut self, regex: &str) -> Self {
+        let regex = Regex::new(regex).expect("invalid regex");
+        self.steps.push(Step::Capture(regex));
+        self
+    }
+
+    /// The number of steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns true if there is no step.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Run the steps against the tube, returning the values captured in order. Fails with the
+    /// index of the step that failed.
+    pub async fn run<T>(&self, tube: &mut Tube<T>) -> Result<Vec<Vec<u8>>, DialogError>
+    where
+        T: AsyncBufRead + AsyncWrite + Unpin,
+    {
+        let mut captures = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            let result = match step {
+                Step::Expect(pattern) => tube
+                    .recv_until_checked(pattern)
+                    .await
+                    .map(drop)
+                    .map_err(io::Error::from),
+                Step::Send(data) => tube.send(data).await,
+                Step::SendLine(data) => tube.send_line(data).await,
+                Step::Capture(regex) => tube.recv_regex(regex).await.and_then(|(_, groups)| {
+                    let capture = match groups.len() {
+                        0 => None,
+                        1 => groups.into_iter().next().flatten(),
+                        _ => groups.into_iter().nth(1).flatten(),
+                    };
+                    captures.push(capture.ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("regex {} did not match", regex),
+                        )
+                    })?);
+                    Ok(())
+                }),
+            };
+            result.map_err(|error| DialogError { step: index, error })?;
+        }
+        Ok(captures)
+    }
+}
+
+/// The error returned by [`Dialog::run`].
+#[derive(Debug)]
+pub struct DialogError {
+    /// The index of the step that failed.
+    pub step: usize,
+    /// The error of the step.
+    pub error: io::Error,
+}
+
+impl fmt::Display for DialogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {} failed: {}", self.step, self.error)
+    }
+}
+
+impl Error for DialogError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<DialogError> for io::Error {
+    fn from(err: DialogError) -> Self {
+        io::Error::new(err.error.kind(), err)
+    }
+}
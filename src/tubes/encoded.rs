@@ -0,0 +1,287 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::utils::{base64_decode, base64_encode};
+
+/// The text encodings of [`EncodedTube`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Two hex digits per byte. Both cases are decoded, and lowercase is sent.
+    Hex,
+    /// Base64 with the standard alphabet. The padding is optional when decoding.
+    Base64,
+}
+
+impl Encoding {
+    /// The number of characters that are decoded together.
+    fn group(self) -> usize {
+        match self {
+            Encoding::Hex => 2,
+            Encoding::Base64 => 4,
+        }
+    }
+
+    fn encode(self, data: &[u8], out: &mut Vec<u8>) {
+        match self {
+            Encoding::Hex => {
+                const DIGITS: &[u8; 16] = b"0123456789abcdef";
+                for &byte in data {
+                    out.extend_from_slice(&[
+                        DIGITS[byte as usize >> 4],
+                        DIGITS[byte as usize & 0xf],
+                    ]);
+                }
+            }
+            Encoding::Base64 => out.extend_from_slice(base64_encode(data).as_bytes()),
+        }
+    }
+
+    fn decode(self, chars: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+        let decoded = match self {
+            Encoding::Hex => match chars {
+                [high, low] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                    let digit = |c: u8| (c as char).to_digit(16).unwrap() as u8;
+                    Some(vec![digit(*high) << 4 | digit(*low)])
+                }
+                _ => None,
+            },
+            Encoding::Base64 => base64_decode(chars),
+        };
+        let decoded = decoded.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid {:?}: {}", self, String::from_utf8_lossy(chars)),
+            )
+        })?;
+        out.extend_from_slice(&decoded);
+        Ok(())
+    }
+}
+
+/// How the data sent by an [`EncodedTube`] is framed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// Every flush, which happens after each send of a tube, sends the data written so far as one
+    /// encoded line.
+    #[default]
+    Lines,
+    /// The data is encoded as it is written without any newline. For base64, the bytes that do
+    /// not fill a group of 3 are kept until the tube is shut down, where they are sent padded,
+    /// since padding in the middle would end the stream.
+    Stream,
+}
+
+/// Decodes everything read from the inner stream and encodes everything written to it as hex or
+/// base64, for services that wrap their protocol in text, so that binary payloads can be sent
+/// and received with the helpers of [`Tube`](super::Tube).
+///
+/// Whitespace between the characters received is skipped. With [`Framing::Lines`], a line can
+/// end in the middle of a group, e.g. unpadded base64. Invalid characters fail the read with
+/// [`InvalidData`](io::ErrorKind::InvalidData).
+/// ```rust
+/// use io_tubes::tubes::{EncodedTube, Encoding, Framing, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn encoded() -> io::Result<()> {
+///     let (p, mut server) = Tube::pair();
+///     let mut p = p.map_inner(|inner| EncodedTube::new(inner, Encoding::Hex));
+///
+///     server.send("48 65 6C 6C 6F 0a\n").await?;
+///     assert_eq!(p.recv_line().await?, b"Hello\n");
+///     p.send(b"\x00\xff").await?;
+///     assert_eq!(server.recv_line().await?, b"00ff\n");
+///     server.send("+f\n").await?;
+///     assert!(p.recv_line().await.is_err());
+///
+///     // The base64 stream is echoed back and decoded again
+///     let mut p = Tube::echo()
+///         .map_inner(|inner| EncodedTube::new(inner, Encoding::Base64).framing(Framing::Stream));
+///     p.send(b"\x00\x01").await?;
+///     p.send_line(b"\xfe\xff\xfd").await?;
+///     assert_eq!(p.recv_line().await?, b"\x00\x01\xfe\xff\xfd\n");
+///     p.send("!").await?;
+///     p.close_send().await?;
+///     assert_eq!(p.recv_all().await?, b"!");
+///
+///     Ok(())
+/// }
+///
+/// encoded();
+/// ```
+#[derive(Debug)]
+pub struct EncodedTube<T> {
+    inner: T,
+    encoding: Encoding,
+    framing: Framing,
+    /// The characters received that do not fill a group yet.
+    chars: Vec<u8>,
+    /// The data decoded, of which `decoded[decoded_pos..]` is not read yet.
+    decoded: Vec<u8>,
+    decoded_pos: usize,
+    /// The data written that is not encoded yet.
+    unencoded: Vec<u8>,
+    /// The encoded data that is not written to the inner stream yet.
+    encoded: Vec<u8>,
+}
+
+impl<T> EncodedTube<T> {
+    /// Encode the inner stream in both directions with [`Framing::Lines`].
+    pub fn new(inner: T, encoding: Encoding) -> Self {
+        Self {
+            inner,
+            encoding,
+            framing: Framing::default(),
+            chars: Vec::new(),
+            decoded: Vec::new(),
+            decoded_pos: 0,
+            unencoded: Vec::new(),
+            encoded: Vec::new(),
+        }
+    }
+
+    /// Frame the data sent as specified.
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data transferred directly is not encoded.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the adapter to get back the inner stream. The data received but not decoded yet
+    /// and the data written but not flushed are lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Decode the characters received.
+    fn feed(&mut self, data: &[u8]) -> io::Result<()> {
+        for &c in data {
+            match c {
+                b'\n' if self.framing == Framing::Lines => self.end_group()?,
+                c if c.is_ascii_whitespace() => {}
+                c => {
+                    self.chars.push(c);
+                    if self.chars.len() == self.encoding.group() {
+                        self.end_group()?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode the characters that are left, which may not fill a group.
+    fn end_group(&mut self) -> io::Result<()> {
+        if self.chars.is_empty() {
+            return Ok(());
+        }
+        let chars = std::mem::take(&mut self.chars);
+        self.encoding.decode(&chars, &mut self.decoded)
+    }
+
+    /// Encode the data written so far. The lines are ended on `flush`, and the base64 stream is
+    /// only padded on `shutdown`.
+    fn encode(&mut self, flush: bool, shutdown: bool) {
+        let len = match (self.framing, self.encoding) {
+            (Framing::Lines, _) if flush || shutdown => self.unencoded.len(),
+            (Framing::Lines, _) => 0,
+            (Framing::Stream, Encoding::Hex) => self.unencoded.len(),
+            (Framing::Stream, Encoding::Base64) if shutdown => self.unencoded.len(),
+            (Framing::Stream, Encoding::Base64) => self.unencoded.len() / 3 * 3,
+        };
+        if len == 0 {
+            return;
+        }
+        self.encoding
+            .encode(&self.unencoded[..len], &mut self.encoded);
+        self.unencoded.drain(..len);
+        if self.framing == Framing::Lines {
+            self.encoded.push(b'\n');
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> EncodedTube<T> {
+    /// Write the encoded data to the inner stream.
+    fn poll_encoded(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.encoded.is_empty() {
+            let numb = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.encoded))?;
+            if numb == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.encoded.drain(..numb);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for EncodedTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.decoded_pos == this.decoded.len() {
+            this.decoded.clear();
+            this.decoded_pos = 0;
+            let mut raw = [0; 4096];
+            let mut raw = ReadBuf::new(&mut raw);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut raw))?;
+            if raw.filled().is_empty() {
+                this.end_group()?;
+                if this.decoded.is_empty() {
+                    return Poll::Ready(Ok(()));
+                }
+                break;
+            }
+            this.feed(raw.filled())?;
+        }
+        let len = (this.decoded.len() - this.decoded_pos).min(buf.remaining());
+        buf.put_slice(&this.decoded[this.decoded_pos..this.decoded_pos + len]);
+        this.decoded_pos += len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for EncodedTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_encoded(cx))?;
+        this.unencoded.extend_from_slice(buf);
+        this.encode(false, false);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.encode(true, false);
+        ready!(this.poll_encoded(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.encode(true, true);
+        ready!(this.poll_encoded(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
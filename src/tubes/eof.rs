@@ -0,0 +1,109 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncRead, ReadBuf},
+    time::{self, Sleep},
+};
+
+/// What [`Tube::recv_until`](super::Tube::recv_until) and the other methods receiving until a
+/// pattern do when the stream returns no data, which usually means EOF. See
+/// [`Tube::on_eof`](super::Tube::on_eof).
+/// ```rust
+/// use io_tubes::tubes::{EofPolicy, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn on_eof() -> io::Result<()> {
+///     let (mut a, mut b) = Tube::pair();
+///     a.send("Leak: 0x4011").await?;
+///     drop(a);
+///
+///     b.on_eof = EofPolicy::Error;
+///     let err = b.recv_until("\n").await.unwrap_err();
+///     assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+///
+///     Ok(())
+/// }
+///
+/// on_eof();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EofPolicy {
+    /// Stop and return the data received so far. The checked methods fail with
+    /// [`TubeError::Eof`](super::TubeError::Eof) either way.
+    #[default]
+    Partial,
+    /// Fail with [`UnexpectedEof`](io::ErrorKind::UnexpectedEof), so that a dropped connection
+    /// is not mistaken for data without the pattern.
+    Error,
+    /// Wait this long and receive again until the pattern arrives or the timeout is reached, for
+    /// transports that return no data without being closed, like serial ports or pipes that are
+    /// reopened.
+    Retry(Duration),
+}
+
+/// Turns EOF into waiting and receiving again for [`EofPolicy::Retry`].
+#[derive(Debug)]
+pub(super) struct RetryEof<'a, T> {
+    inner: &'a mut T,
+    delay: Option<Duration>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<'a, T> RetryEof<'a, T> {
+    pub(super) fn new(inner: &'a mut T, policy: EofPolicy) -> Self {
+        let delay = match policy {
+            EofPolicy::Retry(delay) => Some(delay),
+            _ => None,
+        };
+        Self {
+            inner,
+            delay,
+            sleep: None,
+        }
+    }
+}
+
+impl<T: AsyncBufRead + Unpin> AsyncBufRead for RetryEof<'_, T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if let Some(delay) = this.delay {
+            loop {
+                if let Some(sleep) = &mut this.sleep {
+                    ready!(sleep.as_mut().poll(cx));
+                    this.sleep = None;
+                }
+                if !ready!(Pin::new(&mut *this.inner).poll_fill_buf(cx))?.is_empty() {
+                    break;
+                }
+                this.sleep = Some(Box::pin(time::sleep(delay)));
+            }
+        }
+        Pin::new(&mut *this.inner).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut *self.get_mut().inner).consume(amt);
+    }
+}
+
+impl<T: AsyncBufRead + Unpin> AsyncRead for RetryEof<'_, T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = data.len().min(buf.remaining());
+        buf.put_slice(&data[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,161 @@
+use std::{error::Error, fmt, io};
+
+/// The error returned by the checked receive methods like [`Tube::recv_until_checked`].
+///
+/// Unlike the lenient methods which return partial data on timeout or EOF, this distinguishes
+/// the reasons of failure while keeping the data received so far.
+///
+/// [`Tube::recv_until_checked`]: super::Tube::recv_until_checked
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TubeError {
+    /// The timeout is reached before the operation completes.
+    Timeout {
+        /// The data received before the timeout.
+        partial: Vec<u8>,
+    },
+    /// EOF is reached before the operation completes.
+    Eof {
+        /// The data received before EOF.
+        partial: Vec<u8>,
+    },
+    /// More data is available than the limit of [`Tube::max_recv_size`] or
+    /// [`RecvUntilOptions::max_size`] allows. The data up to the limit is consumed.
+    ///
+    /// [`Tube::max_recv_size`]: super::Tube::max_recv_size
+    /// [`RecvUntilOptions::max_size`]: super::RecvUntilOptions::max_size
+    TooLarge {
+        /// The data received up to the limit.
+        partial: Vec<u8>,
+    },
+    /// An IO error occurred.
+    Io(io::Error),
+}
+
+/// What [`Tube::recv`], [`Tube::recv_line`] and [`Tube::recv_until`] return when the timeout is
+/// reached. See [`Tube::on_timeout`].
+///
+/// [`Tube::recv`]: super::Tube::recv
+/// [`Tube::recv_line`]: super::Tube::recv_line
+/// [`Tube::recv_until`]: super::Tube::recv_until
+/// [`Tube::on_timeout`]: super::Tube::on_timeout
+/// ```rust
+/// use io_tubes::tubes::{TimeoutPolicy, Tube, TubeError};
+/// use std::{io, time::Duration};
+///
+/// #[tokio::main]
+/// async fn on_timeout() -> io::Result<()> {
+///     let mut p = Tube::echo();
+///     p.timeout = Duration::from_millis(50);
+///     p.send("Name: ").await?;
+///
+///     p.on_timeout = TimeoutPolicy::Error;
+///     let err = p.recv_line().await.unwrap_err();
+///     assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+///
+///     p.on_timeout = TimeoutPolicy::ErrorWithPartial;
+///     let err = p.recv_line().await.unwrap_err();
+///     let err = err.into_inner().unwrap().downcast::<TubeError>().unwrap();
+///     assert_eq!(err.partial(), b"Name: ");
+///
+///     Ok(())
+/// }
+///
+/// on_timeout();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimeoutPolicy {
+    /// Return the data received so far, which may be empty.
+    #[default]
+    Partial,
+    /// Fail with [`TimedOut`](io::ErrorKind::TimedOut) and keep the data received so far, so
+    /// that it is received by the next call.
+    Error,
+    /// Fail with [`TimedOut`](io::ErrorKind::TimedOut) wrapping [`TubeError::Timeout`], which
+    /// holds the data received so far.
+    ErrorWithPartial,
+}
+
+impl TubeError {
+    /// The data received before the error, which is empty for IO errors.
+    pub fn partial(&self) -> &[u8] {
+        match self {
+            TubeError::Timeout { partial }
+            | TubeError::Eof { partial }
+            | TubeError::TooLarge { partial } => partial,
+            TubeError::Io(_) => &[],
+        }
+    }
+
+    /// Recover the partial data on timeout or EOF, which is what the lenient methods return.
+    /// Other errors are converted to [`io::Error`].
+    pub fn into_partial(self) -> io::Result<Vec<u8>> {
+        match self {
+            TubeError::Timeout { partial } | TubeError::Eof { partial } => Ok(partial),
+            err => Err(err.into()),
+        }
+    }
+
+    /// Returns true if this is a timeout.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, TubeError::Timeout { .. })
+    }
+
+    /// Returns true if this is EOF.
+    pub fn is_eof(&self) -> bool {
+        matches!(self, TubeError::Eof { .. })
+    }
+
+    /// Returns true if the receive limit is exceeded.
+    pub fn is_too_large(&self) -> bool {
+        matches!(self, TubeError::TooLarge { .. })
+    }
+}
+
+impl fmt::Display for TubeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TubeError::Timeout { partial } => {
+                write!(f, "timed out after receiving {} bytes", partial.len())
+            }
+            TubeError::Eof { partial } => {
+                write!(f, "reached EOF after receiving {} bytes", partial.len())
+            }
+            TubeError::TooLarge { partial } => {
+                write!(
+                    f,
+                    "exceeded the limit after receiving {} bytes",
+                    partial.len()
+                )
+            }
+            TubeError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for TubeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TubeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TubeError {
+    fn from(err: io::Error) -> Self {
+        TubeError::Io(err)
+    }
+}
+
+impl From<TubeError> for io::Error {
+    fn from(err: TubeError) -> Self {
+        match err {
+            TubeError::Timeout { .. } => io::Error::new(io::ErrorKind::TimedOut, err),
+            TubeError::Eof { .. } => io::Error::new(io::ErrorKind::UnexpectedEof, err),
+            TubeError::TooLarge { .. } => io::Error::new(io::ErrorKind::InvalidData, err),
+            TubeError::Io(err) => err,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use tokio::io::AsyncBufRead;
+
+use crate::utils::{timeout, RecvUntilAny};
+
+use super::{limit::Limited, Tube, TubeError};
+
+/// The result of [`Tube::expect`], telling which pattern appeared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchArm {
+    /// The index of the pattern found. If several patterns end at the same position, the longest
+    /// one is reported.
+    pub index: usize,
+    /// The data received before the pattern.
+    pub before: Vec<u8>,
+    /// The pattern found.
+    pub matched: Vec<u8>,
+}
+
+impl MatchArm {
+    /// All the data received, which is the data before the pattern followed by the pattern.
+    pub fn into_data(self) -> Vec<u8> {
+        let mut data = self.before;
+        data.extend_from_slice(&self.matched);
+        data
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Receive until any of the patterns appears, like `expect` of the classic expect tool and
+    /// pexpect, so that the script can branch on which prompt it got.
+    ///
+    /// Fails with [`TubeError::Timeout`] or [`TubeError::Eof`] if none of the patterns appears,
+    /// and with [`TubeError::TooLarge`] if [`Tube::max_recv_size`] is exceeded first, which keep
+    /// the data received so far.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn expect() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("Welcome\nPassword: ").await?;
+    ///     let arm = p.expect(&["login: ", "Password: ", "denied"]).await?;
+    ///     match arm.index {
+    ///         0 => p.send_line("admin").await?,
+    ///         1 => p.send_line("hunter2").await?,
+    ///         _ => panic!("access denied"),
+    ///     }
+    ///     assert_eq!(arm.before, b"Welcome\n");
+    ///     assert_eq!(arm.matched, b"Password: ");
+    ///     assert_eq!(p.recv_line().await?, b"hunter2\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// expect();
+    /// ```
+    pub async fn expect(&mut self, patterns: &[impl AsRef<[u8]>]) -> Result<MatchArm, TubeError> {
+        let recv_timeout = self.recv_timeout();
+        self.expect_inner(patterns, recv_timeout).await
+    }
+
+    /// Same as [`expect`](Tube::expect), but with a timeout for this call only instead of
+    /// [`Tube::timeout`]. The deadlines still apply.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn expect_timeout() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("$ ").await?;
+    ///     let err = p
+    ///         .expect_timeout(&["# "], Duration::from_millis(50))
+    ///         .await
+    ///         .unwrap_err();
+    ///     assert!(err.is_timeout());
+    ///     assert_eq!(err.partial(), b"$ ");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// expect_timeout();
+    /// ```
+    pub async fn expect_timeout(
+        &mut self,
+        patterns: &[impl AsRef<[u8]>],
+        timeout: Duration,
+    ) -> Result<MatchArm, TubeError> {
+        let recv_timeout = self.limit_to_deadline(timeout);
+        self.expect_inner(patterns, recv_timeout).await
+    }
+
+    async fn expect_inner(
+        &mut self,
+        patterns: &[impl AsRef<[u8]>],
+        recv_timeout: Duration,
+    ) -> Result<MatchArm, TubeError> {
+        let patterns: Vec<&[u8]> = patterns.iter().map(AsRef::as_ref).collect();
+        let mut buf = Vec::new();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        let found = timeout(
+            recv_timeout,
+            RecvUntilAny::new(&mut limited, &patterns, &mut buf),
+        )
+        .await;
+        match found {
+            Ok(Err(_)) if limited.exceeded() => Err(TubeError::TooLarge { partial: buf }),
+            Ok(Ok(Some(index))) => {
+                let matched = buf.split_off(buf.len() - patterns[index].len());
+                Ok(MatchArm {
+                    index,
+                    before: buf,
+                    matched,
+                })
+            }
+            Ok(Ok(None)) => Err(TubeError::Eof { partial: buf }),
+            Ok(Err(err)) => Err(TubeError::Io(err)),
+            Err(_) => Err(TubeError::Timeout { partial: buf }),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// A fault injected by [`FaultTube`] when the offset is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Return [`Poll::Pending`] once and wake up immediately, like a spurious wake up.
+    Pending,
+    /// Fail once with an error of the kind.
+    Error(io::ErrorKind),
+    /// Transfer at most this many bytes in the next read or write, which must not be 0.
+    Short(usize),
+    /// Reach EOF early. Every read returns EOF afterwards, and every write returns 0 so that
+    /// [`write_all`](tokio::io::AsyncWriteExt::write_all) fails with
+    /// [`WriteZero`](io::ErrorKind::WriteZero).
+    Eof,
+}
+
+/// The faults of a direction and how far the direction is.
+#[derive(Debug, Default)]
+struct Script {
+    /// Sorted by offset, faults at the same offset are injected in the order they are added.
+    faults: VecDeque<(u64, Fault)>,
+    pos: u64,
+    eof: bool,
+}
+
+impl Script {
+    fn add(&mut self, offset: u64, fault: Fault) {
+        if let Fault::Short(len) = fault {
+            assert!(len > 0, "short transfer must not be 0");
+        }
+        let idx = self.faults.partition_point(|&(other, _)| other <= offset);
+        self.faults.insert(idx, (offset, fault));
+    }
+
+    /// Inject the faults that are reached, and return how many bytes can be transferred.
+    fn poll_limit(&mut self, cx: &mut Context<'_>, want: usize) -> Poll<io::Result<usize>> {
+        let mut limit = want;
+        while let Some(&(offset, fault)) = self.faults.front() {
+            if offset > self.pos {
+                limit = limit.min((offset - self.pos).try_into().unwrap_or(usize::MAX));
+                break;
+            }
+            self.faults.pop_front();
+            match fault {
+                Fault::Pending => {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                Fault::Error(kind) => {
+                    return Poll::Ready(Err(io::Error::new(kind, "fault injected by FaultTube")))
+                }
+                Fault::Short(len) => limit = limit.min(len),
+                Fault::Eof => self.eof = true,
+            }
+        }
+        if self.eof {
+            limit = 0;
+        }
+        Poll::Ready(Ok(limit))
+    }
+}
+
+/// Injects scripted faults at byte offsets of the data received from and sent to the inner
+/// stream, to test how code copes with flaky targets.
+/// ```rust
+/// use io_tubes::tubes::{Fault, FaultTube, LineEcho, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn fault() -> io::Result<()> {
+///     let faulty = FaultTube::new(LineEcho::new())
+///         .read_fault(0, Fault::Error(io::ErrorKind::ConnectionReset))
+///         .read_fault(2, Fault::Pending)
+///         .read_fault(5, Fault::Eof)
+///         .write_fault(4, Fault::Short(1));
+///     let mut p = Tube::new(faulty);
+///
+///     p.send_line("Hello World").await?;
+///     let err = p.recv_line().await.unwrap_err();
+///     assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+///     assert_eq!(p.recv_line().await?, b"Hello");
+///
+///     Ok(())
+/// }
+///
+/// fault();
+/// ```
+#[derive(Debug)]
+pub struct FaultTube<T> {
+    inner: T,
+    read: Script,
+    write: Script,
+}
+
+impl<T> FaultTube<T> {
+    /// Pass everything through until faults are added.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            read: Script::default(),
+            write: Script::default(),
+        }
+    }
+
+    /// Inject the fault when `offset` bytes are received from the inner stream.
+    pub fn read_fault(mut self, offset: u64, fault: Fault) -> Self {
+        self.read.add(offset, fault);
+        self
+    }
+
+    /// Inject the fault when `offset` bytes are sent to the inner stream.
+    pub fn write_fault(mut self, offset: u64, fault: Fault) -> Self {
+        self.write.add(offset, fault);
+        self
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data transferred directly is not counted.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the fault injector to get back the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> AsyncRead for FaultTube<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let limit = ready!(this.read.poll_limit(cx, buf.remaining()))?;
+        if limit == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(limit));
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let len = limited.filled().len();
+        this.read.pos += len as u64;
+        buf.advance(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncWrite for FaultTube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        let limit = ready!(this.write.poll_limit(cx, buf.len()))?;
+        if limit == 0 {
+            return Poll::Ready(Ok(0));
+        }
+        let numb = ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..limit]))?;
+        this.write.pos += numb as u64;
+        Poll::Ready(Ok(numb))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
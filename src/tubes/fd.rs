@@ -0,0 +1,169 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::fd::{AsRawFd, OwnedFd},
+    path::Path,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use nix::{
+    errno::Errno,
+    fcntl::{fcntl, FcntlArg, OFlag},
+    sys::socket::{shutdown, Shutdown},
+};
+use tokio::{
+    io::{unix::AsyncFd, AsyncRead, AsyncWrite, BufReader, ReadBuf},
+    task,
+};
+
+use super::Tube;
+
+/// A tube over a file descriptor, created by [`Tube::from_fd`] or [`Tube::file`].
+pub type FdTube = Tube<BufReader<FdStream>>;
+
+/// A stream over any file descriptor, like an inherited socket or a character device.
+///
+/// Descriptors that can be polled are made non-blocking and driven by the reactor. Regular files
+/// and devices that can't be polled, like `/dev/zero`, are read and written on the blocking
+/// thread pool instead.
+#[derive(Debug)]
+pub struct FdStream {
+    inner: FdInner,
+}
+
+#[derive(Debug)]
+enum FdInner {
+    Polled(AsyncFd<File>),
+    Blocking(tokio::fs::File),
+}
+
+impl FdStream {
+    /// Wrap the file descriptor, which must be used inside a tokio runtime.
+    pub fn new(fd: OwnedFd) -> io::Result<Self> {
+        let inner = match AsyncFd::try_new(File::from(fd)) {
+            Ok(fd) => {
+                let flags = OFlag::from_bits_retain(fcntl(fd.as_raw_fd(), FcntlArg::F_GETFL)?);
+                fcntl(fd.as_raw_fd(), FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK))?;
+                FdInner::Polled(fd)
+            }
+            Err(err) => {
+                let (file, err) = err.into_parts();
+                if err.raw_os_error() != Some(Errno::EPERM as i32) {
+                    return Err(err);
+                }
+                FdInner::Blocking(tokio::fs::File::from_std(file))
+            }
+        };
+        Ok(Self { inner })
+    }
+}
+
+impl Tube<BufReader<FdStream>> {
+    /// Construct a tube from a file descriptor, e.g. a socket inherited from xinetd or a serial
+    /// port opened elsewhere. Must be called inside a tokio runtime.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, os::unix::net::UnixStream};
+    ///
+    /// #[tokio::main]
+    /// async fn from_fd() -> io::Result<()> {
+    ///     let (a, b) = UnixStream::pair()?;
+    ///     let mut a = Tube::from_fd(a.into())?;
+    ///     let mut b = Tube::from_fd(b.into())?;
+    ///
+    ///     a.send_line("Hello").await?;
+    ///     a.close_send().await?;
+    ///     assert_eq!(b.recv_all().await?, b"Hello\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// from_fd();
+    /// ```
+    pub fn from_fd(fd: OwnedFd) -> io::Result<Self> {
+        Ok(Tube::new(FdStream::new(fd)?))
+    }
+
+    /// Open the file at `path` with `options` and construct a tube from it, e.g. a character
+    /// device or a FIFO. Opening happens on the blocking thread pool, since it may wait for the
+    /// other end of a FIFO.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{fs::OpenOptions, io};
+    ///
+    /// #[tokio::main]
+    /// async fn file() -> io::Result<()> {
+    ///     let mut p = Tube::file("/dev/zero", OpenOptions::new().read(true).write(true)).await?;
+    ///     p.send("ignored").await?;
+    ///     assert_eq!(p.recv(4).await?, [0; 4]);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// file();
+    /// ```
+    pub async fn file(path: impl AsRef<Path>, options: &OpenOptions) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let options = options.clone();
+        let file = task::spawn_blocking(move || options.open(path)).await??;
+        Self::from_fd(file.into())
+    }
+}
+
+impl AsyncRead for FdStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().inner {
+            FdInner::Polled(fd) => loop {
+                let mut guard = ready!(fd.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                if let Ok(result) = guard.try_io(|fd| fd.get_ref().read(unfilled)) {
+                    buf.advance(result?);
+                    return Poll::Ready(Ok(()));
+                }
+            },
+            FdInner::Blocking(file) => Pin::new(file).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for FdStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.get_mut().inner {
+            FdInner::Polled(fd) => loop {
+                let mut guard = ready!(fd.poll_write_ready(cx))?;
+                if let Ok(result) = guard.try_io(|fd| fd.get_ref().write(buf)) {
+                    return Poll::Ready(result);
+                }
+            },
+            FdInner::Blocking(file) => Pin::new(file).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().inner {
+            FdInner::Polled(_) => Poll::Ready(Ok(())),
+            FdInner::Blocking(file) => Pin::new(file).poll_flush(cx),
+        }
+    }
+
+    /// Shuts down the sending direction of sockets. Other descriptors can't be half closed, so
+    /// nothing happens for them.
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().inner {
+            FdInner::Polled(fd) => match shutdown(fd.as_raw_fd(), Shutdown::Write) {
+                Ok(()) | Err(Errno::ENOTSOCK) => Poll::Ready(Ok(())),
+                Err(err) => Poll::Ready(Err(err.into())),
+            },
+            FdInner::Blocking(file) => Pin::new(file).poll_shutdown(cx),
+        }
+    }
+}
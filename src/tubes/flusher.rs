@@ -0,0 +1,228 @@
+use std::{
+    future, io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{ready, Context, Poll, Waker},
+};
+
+use tokio::{
+    io::{split, AsyncRead, AsyncWrite, BufReader, ReadBuf, ReadHalf, WriteHalf},
+    sync::Notify,
+};
+
+use super::Tube;
+
+/// Writes the data sent through it from a task that owns the write side of the inner stream, so
+/// that the data queued by [`Tube::send_nowait`] goes out while the tube is idle. Created by
+/// [`Tube::spawn_flusher`].
+///
+/// A write is taken as soon as less than the capacity is waiting to be written, and flushing
+/// waits until the task has written and flushed everything. An error of the task is returned by
+/// the next write or flush. Dropping the adapter lets the task write what is left before it
+/// exits.
+#[derive(Debug)]
+pub struct FlusherTube<T> {
+    read: ReadHalf<T>,
+    shared: Arc<Shared<T>>,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    /// Wakes the task when there is data to write or the adapter is dropped.
+    notify: Notify,
+    /// The number of bytes waiting to be written, counted by the send queue of the tube.
+    pending_len: Arc<AtomicUsize>,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    write: WriteHalf<T>,
+    pending: Vec<u8>,
+    /// The task is writing, so the adapter must not touch `write`.
+    busy: bool,
+    /// The adapter is dropped, so the task exits once everything is written.
+    dropped: bool,
+    error: Option<io::Error>,
+    /// The adapter waiting for room or for the task to finish.
+    waker: Option<Waker>,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<T> State<T> {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> FlusherTube<T>
+where
+    T: AsyncRead + AsyncWrite + Send + 'static,
+{
+    /// Write to `inner` from a spawned task, taking writes while less than `capacity` bytes are
+    /// waiting.
+    pub fn new(inner: T, capacity: usize) -> Self {
+        let (read, write) = split(inner);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                write,
+                pending: Vec::new(),
+                busy: false,
+                dropped: false,
+                error: None,
+                waker: None,
+            }),
+            notify: Notify::new(),
+            pending_len: Arc::default(),
+            capacity: capacity.max(1),
+        });
+        tokio::spawn(flush(shared.clone()));
+        Self { read, shared }
+    }
+}
+
+impl<T> FlusherTube<T> {
+    /// Fail with the error of the task, or else wait until it is idle with nothing to write.
+    fn poll_idle(&self, cx: &mut Context<'_>) -> Poll<io::Result<MutexGuard<'_, State<T>>>> {
+        let mut state = self.shared.lock();
+        if let Some(err) = state.error.take() {
+            return Poll::Ready(Err(err));
+        }
+        if state.busy || !state.pending.is_empty() {
+            state.waker = Some(cx.waker().clone());
+            self.shared.notify.notify_one();
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(state))
+    }
+}
+
+impl<T> Drop for FlusherTube<T> {
+    fn drop(&mut self) {
+        self.shared.lock().dropped = true;
+        self.shared.notify.notify_one();
+    }
+}
+
+async fn flush<T: AsyncWrite>(shared: Arc<Shared<T>>) {
+    loop {
+        shared.notify.notified().await;
+        shared.lock().busy = true;
+        let result = future::poll_fn(|cx| {
+            let mut state = shared.lock();
+            let state = &mut *state;
+            while !state.pending.is_empty() {
+                let numb = ready!(Pin::new(&mut state.write).poll_write(cx, &state.pending))?;
+                if numb == 0 {
+                    return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                }
+                state.pending.drain(..numb);
+                shared
+                    .pending_len
+                    .store(state.pending.len(), Ordering::Relaxed);
+                state.wake();
+            }
+            Pin::new(&mut state.write).poll_flush(cx)
+        })
+        .await;
+        let mut state = shared.lock();
+        state.busy = false;
+        state.wake();
+        if let Err(err) = result {
+            state.error = Some(err);
+            return;
+        }
+        if state.dropped && state.pending.is_empty() {
+            return;
+        }
+    }
+}
+
+impl<T> Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Write the data sent from a spawned task, see [`FlusherTube`], so that the data queued by
+    /// [`Tube::send_nowait`] is sent even if the tube is not used again. The task takes up to
+    /// [`Tube::send_queue_capacity`] bytes, which count towards the capacity of the queue.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn spawn_flusher() -> io::Result<()> {
+    ///     let (mut p, mut server) = Tube::pair_with_capacity(4);
+    ///     p.send_queue_capacity = 8;
+    ///     let mut p = p.spawn_flusher();
+    ///
+    ///     p.send_nowait("AAAA")?;
+    ///     p.send_nowait("BBBB")?;
+    ///     // Only 4 bytes fit at once, the rest is written while `p` is not used.
+    ///     assert_eq!(server.recv_until("BBBB").await?, b"AAAABBBB");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// spawn_flusher();
+    /// ```
+    pub fn spawn_flusher(self) -> Tube<BufReader<FlusherTube<T>>> {
+        let capacity = self.send_queue_capacity;
+        let mut tube = self.map_inner(|inner| FlusherTube::new(inner, capacity));
+        tube.send_queue.flusher = Some(tube.inner.get_ref().shared.pending_len.clone());
+        tube
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for FlusherTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().read).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for FlusherTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.shared.lock();
+        if let Some(err) = state.error.take() {
+            return Poll::Ready(Err(err));
+        }
+        if state.pending.len() >= self.shared.capacity {
+            state.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        state.pending.extend_from_slice(buf);
+        self.shared
+            .pending_len
+            .store(state.pending.len(), Ordering::Relaxed);
+        self.shared.notify.notify_one();
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut state = ready!(self.poll_idle(cx))?;
+        Pin::new(&mut state.write).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut state = ready!(self.poll_idle(cx))?;
+        Pin::new(&mut state.write).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(feature = "process")]
+use std::ffi::OsStr;
+use std::{convert::Infallible, future, future::Future, io, net::SocketAddr, path::PathBuf};
+
+use log::info;
+
+#[cfg(feature = "process")]
+use tokio::process::Command;
+use tokio::{
+    io::{AsyncBufRead, AsyncWrite, BufReader},
+    net::{lookup_host, TcpStream, ToSocketAddrs},
+};
+
+#[cfg(feature = "process")]
+use super::ProcessTube;
+use super::{Listener, Server, Tube};
+
+/// Options for [`Listener::relay_forever`].
+#[derive(Debug, Clone, Default)]
+pub struct RelayOptions {
+    /// Record every session into a transcript named `session-<n>.txt` in this directory, which
+    /// can be replayed with [`Tube::replay`] in place of the upstream.
+    pub record_dir: Option<PathBuf>,
+}
+
+/// A running port forwarder returned by [`Listener::forward_to`]. It is stopped when dropped.
+#[derive(Debug)]
+pub struct Forwarder {
+    server: Server,
+}
+
+impl Forwarder {
+    /// Returns the address that is listened.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.server.local_addr()
+    }
+
+    /// Returns the port that is listened.
+    pub fn port(&self) -> u16 {
+        self.server.port()
+    }
+
+    /// Stop accepting connections and close all the forwarded connections.
+    pub fn stop(self) {
+        self.server.stop();
+    }
+}
+
+impl Listener {
+    /// Forward every accepted connection to the address, until the returned [`Forwarder`] is
+    /// stopped. The address is resolved once before accepting.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn forward_to() -> io::Result<()> {
+    ///     let target = Listener::bind("127.0.0.1:0").await?;
+    ///     let forwarder = Listener::bind("127.0.0.1:0")
+    ///         .await?
+    ///         .forward_to(("127.0.0.1", target.port()?))
+    ///         .await?;
+    ///
+    ///     let mut p = Tube::remote(("127.0.0.1", forwarder.port())).await?;
+    ///     let mut server = target.accept().await?;
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(server.recv_line().await?, b"Hello\n");
+    ///
+    ///     forwarder.stop();
+    ///     assert_eq!(server.recv_line().await?, b"");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// forward_to();
+    /// ```
+    pub async fn forward_to(self, addr: impl ToSocketAddrs) -> io::Result<Forwarder> {
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+        self.forward_with(move || {
+            let addrs = addrs.clone();
+            async move { Tube::remote(&addrs[..]).await }
+        })
+    }
+
+    /// Spawn the program for every accepted connection and forward the connection to its stdin
+    /// and stdout, until the returned [`Forwarder`] is stopped. The process receives EOF when the
+    /// client shuts down its side, and is killed when the forwarder is stopped.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn forward_to_process() -> io::Result<()> {
+    ///     let forwarder = Listener::bind("127.0.0.1:0")
+    ///         .await?
+    ///         .forward_to_process("/usr/bin/cat")?;
+    ///
+    ///     let mut p = Tube::remote(("127.0.0.1", forwarder.port())).await?;
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// forward_to_process();
+    /// ```
+    #[cfg(feature = "process")]
+    pub fn forward_to_process(self, program: impl AsRef<OsStr>) -> io::Result<Forwarder> {
+        self.forward_to_command(Command::new(program))
+    }
+
+    /// Same as [`Listener::forward_to_process`], but spawn a copy of the command with its
+    /// arguments, environment and working directory for every connection. A command that clears
+    /// its environment is copied without clearing it.
+    #[cfg(feature = "process")]
+    pub fn forward_to_command(self, command: Command) -> io::Result<Forwarder> {
+        self.forward_with(move || {
+            let mut command = copy_command(&command);
+            command.kill_on_drop(true);
+            async move { Ok(Tube::new(ProcessTube::from_command(command)?)) }
+        })
+    }
+
+    /// Bind to the address and serve the command like `xinetd`, spawning a fresh process for
+    /// every connection with its stdin and stdout relayed to the connection, see
+    /// [`Listener::forward_to_command`].
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::io;
+    /// use tokio::process::Command;
+    ///
+    /// #[tokio::main]
+    /// async fn serve_process() -> io::Result<()> {
+    ///     let mut command = Command::new("/bin/sh");
+    ///     command.args(["-c", "read name; echo \"Hello, $name from $GREETER\""]);
+    ///     command.env("GREETER", "xinetd");
+    ///     let service = Listener::serve_process("127.0.0.1:0", command).await?;
+    ///
+    ///     for name in ["alice", "bob"] {
+    ///         let mut p = Tube::remote(("127.0.0.1", service.port())).await?;
+    ///         p.send_line(name).await?;
+    ///         let expected = format!("Hello, {} from xinetd\n", name);
+    ///         assert_eq!(p.recv_line().await?, expected.as_bytes());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// serve_process();
+    /// ```
+    #[cfg(feature = "process")]
+    pub async fn serve_process(
+        addr: impl ToSocketAddrs,
+        command: Command,
+    ) -> io::Result<Forwarder> {
+        Listener::bind(addr).await?.forward_to_command(command)
+    }
+
+    /// Call `connect` for every accepted connection and relay the traffic between the two tubes,
+    /// until the returned [`Forwarder`] is stopped. The outbound tube can be anything, e.g. a
+    /// tube that logs the traffic.
+    pub fn forward_with<F, Fut, T>(self, mut connect: F) -> io::Result<Forwarder>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<Tube<T>>> + Send + 'static,
+        T: AsyncBufRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        self.forward_each(move |mut inbound| {
+            let outbound = connect();
+            async move {
+                let mut outbound = outbound.await?;
+                inbound.join(&mut outbound).await?;
+                Ok(())
+            }
+        })
+    }
+
+    /// Relay every accepted connection to a new upstream from `connect` for as long as the
+    /// returned future is polled, like `socat` with recording. Every session is numbered and
+    /// logged with its peer and the bytes copied. A failure of the upstream only ends its session.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, RelayOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn relay_forever() -> io::Result<()> {
+    ///     let upstream = Listener::bind("127.0.0.1:0")
+    ///         .await?
+    ///         .forward_to_process("/usr/bin/cat")?;
+    ///     let upstream_port = upstream.port();
+    ///
+    ///     let dir = std::env::temp_dir().join("io-tubes-relay");
+    ///     std::fs::create_dir_all(&dir)?;
+    ///     let options = RelayOptions {
+    ///         record_dir: Some(dir.clone()),
+    ///     };
+    ///     let listener = Listener::bind("127.0.0.1:0").await?;
+    ///     let port = listener.port()?;
+    ///     let daemon = tokio::spawn(async move {
+    ///         let connect = move || Tube::remote(("127.0.0.1", upstream_port));
+    ///         listener.relay_forever(connect, &options).await
+    ///     });
+    ///
+    ///     for _ in 0..2 {
+    ///         let mut p = Tube::remote(("127.0.0.1", port)).await?;
+    ///         p.send_line("Hello").await?;
+    ///         assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///     }
+    ///     daemon.abort();
+    ///
+    ///     let mut r = Tube::replay(dir.join("session-1.txt"))?;
+    ///     r.send_line("Hello").await?;
+    ///     assert_eq!(r.recv_line().await?, b"Hello\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// relay_forever();
+    /// ```
+    pub async fn relay_forever<F, Fut, T>(
+        self,
+        mut connect: F,
+        options: &RelayOptions,
+    ) -> io::Result<Infallible>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<Tube<T>>> + Send + 'static,
+        T: AsyncBufRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let record_dir = options.record_dir.clone();
+        let mut sessions = 0u64;
+        let _server = self.serve(move |mut inbound| {
+            sessions += 1;
+            let session = sessions;
+            let peer = inbound.inner.get_ref().peer_addr();
+            let outbound = connect();
+            let record_dir = record_dir.clone();
+            async move {
+                match &peer {
+                    Ok(peer) => info!(target: "Listener::relay", "Session {} from {}", session, peer),
+                    Err(_) => info!(target: "Listener::relay", "Session {}", session),
+                }
+                let mut outbound = outbound.await.inspect_err(|err| {
+                    info!(target: "Listener::relay", "Session {}: upstream failed: {}", session, err)
+                })?;
+                if let Some(dir) = record_dir {
+                    outbound.record(dir.join(format!("session-{}.txt", session)))?;
+                }
+                let result = inbound.join(&mut outbound).await;
+                outbound.stop_recording()?;
+                let (sent, received) = result?;
+                info!(
+                    target: "Listener::relay",
+                    "Session {} closed, {} bytes sent and {} bytes received", session, sent, received
+                );
+                Ok(())
+            }
+        })?;
+        future::pending().await
+    }
+
+    /// Call `handle` for every accepted connection like [`Listener::serve`], but return a
+    /// [`Forwarder`].
+    pub(super) fn forward_each<F, Fut>(self, handle: F) -> io::Result<Forwarder>
+    where
+        F: FnMut(Tube<BufReader<TcpStream>>) -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<()>> + Send + 'static,
+    {
+        Ok(Forwarder {
+            server: self.serve(handle)?,
+        })
+    }
+}
+
+/// Build a command with the same program, arguments, environment changes and working directory.
+#[cfg(feature = "process")]
+fn copy_command(command: &Command) -> Command {
+    let command = command.as_std();
+    let mut copy = Command::new(command.get_program());
+    copy.args(command.get_args());
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => copy.env(key, value),
+            None => copy.env_remove(key),
+        };
+    }
+    if let Some(dir) = command.get_current_dir() {
+        copy.current_dir(dir);
+    }
+    copy
+}
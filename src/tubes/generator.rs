@@ -0,0 +1,328 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{self, Instant, Sleep},
+};
+
+/// The length of a chargen line without the line ending, as in RFC 864.
+const CHARGEN_LINE: u64 = 72;
+
+#[derive(Debug)]
+enum Source {
+    Random(u64),
+    Pattern(Vec<u8>),
+    Chargen,
+}
+
+/// The byte at the position of the pseudo-random data, which is splitmix64 of the word index so
+/// that it only depends on the seed and position.
+pub(crate) fn random_byte(seed: u64, pos: u64) -> u8 {
+    let mut z = seed.wrapping_add((pos / 8 + 1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (z ^ (z >> 31)).to_le_bytes()[(pos % 8) as usize]
+}
+
+impl Source {
+    fn byte_at(&self, pos: u64) -> u8 {
+        match self {
+            Source::Random(seed) => random_byte(*seed, pos),
+            Source::Pattern(pattern) => pattern[(pos % pattern.len() as u64) as usize],
+            Source::Chargen => {
+                let (line, col) = (pos / (CHARGEN_LINE + 2), pos % (CHARGEN_LINE + 2));
+                match col {
+                    CHARGEN_LINE => b'\r',
+                    col if col > CHARGEN_LINE => b'\n',
+                    col => b' ' + ((line + col) % 95) as u8,
+                }
+            }
+        }
+    }
+}
+
+/// A stream that generates data for testing and benchmarks, and discards everything written to
+/// it. Wrap it with [`Tube::new`](super::Tube::new) to receive from it.
+/// ```rust
+/// use io_tubes::tubes::{Generator, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn generator() -> io::Result<()> {
+///     let mut p = Tube::new(Generator::pattern("ABCD").limit(10));
+///     assert_eq!(p.recv_until("DA").await?, b"ABCDA");
+///     assert_eq!(p.recv(100).await?, b"BCDAB");
+///     assert_eq!(p.recv(100).await?, b"");
+///
+///     let mut p = Tube::new(Generator::chargen());
+///     assert_eq!(&p.recv_line().await?[..10], b" !\"#$%&'()");
+///     assert_eq!(&p.recv_line().await?[..10], b"!\"#$%&'()*");
+///
+///     Ok(())
+/// }
+///
+/// generator();
+/// ```
+#[derive(Debug)]
+pub struct Generator {
+    source: Source,
+    pos: u64,
+    limit: Option<u64>,
+    chunk_size: usize,
+    /// Bytes per second, and the start time once the first byte is generated.
+    rate: Option<(u64, Option<Instant>)>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Generator {
+    fn from_source(source: Source) -> Self {
+        Self {
+            source,
+            pos: 0,
+            limit: None,
+            chunk_size: 8192,
+            rate: None,
+            sleep: None,
+        }
+    }
+
+    /// Generate pseudo-random bytes, which are always the same for the same seed.
+    pub fn random(seed: u64) -> Self {
+        Self::from_source(Source::Random(seed))
+    }
+
+    /// Repeat the pattern, which must not be empty.
+    pub fn pattern(pattern: impl Into<Vec<u8>>) -> Self {
+        let pattern = pattern.into();
+        assert!(!pattern.is_empty(), "pattern must not be empty");
+        Self::from_source(Source::Pattern(pattern))
+    }
+
+    /// Generate the lines of the character generator protocol (RFC 864), which are 72 printable
+    /// characters rotating by one each line followed by `"\r\n"`.
+    pub fn chargen() -> Self {
+        Self::from_source(Source::Chargen)
+    }
+
+    /// Reach EOF after `len` bytes. The data is unlimited by default.
+    pub fn limit(mut self, len: u64) -> Self {
+        self.limit = Some(len);
+        self
+    }
+
+    /// Generate at most `size` bytes per read, which is 8192 by default. Small chunks exercise
+    /// the matching across several reads.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "chunk size must not be 0");
+        self.chunk_size = size;
+        self
+    }
+
+    /// Generate at most `bytes_per_sec` bytes per second on average. The rate is unlimited by
+    /// default.
+    /// ```rust
+    /// use io_tubes::tubes::{Generator, Tube};
+    /// use std::{io, time::Instant};
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// #[tokio::main]
+    /// async fn rate() -> io::Result<()> {
+    ///     let mut p = Tube::new(Generator::random(1337).rate(1000).chunk_size(100));
+    ///
+    ///     let start = Instant::now();
+    ///     p.read_exact(&mut [0; 300]).await?;
+    ///     assert!(start.elapsed().as_millis() >= 200);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// rate();
+    /// ```
+    pub fn rate(mut self, bytes_per_sec: u64) -> Self {
+        assert!(bytes_per_sec > 0, "rate must not be 0");
+        self.rate = Some((bytes_per_sec, None));
+        self
+    }
+
+    /// The number of bytes generated so far.
+    pub fn generated(&self) -> u64 {
+        self.pos
+    }
+
+    /// The number of bytes that can be generated now without exceeding the rate.
+    fn poll_allowed(&mut self, cx: &mut Context<'_>) -> Poll<u64> {
+        let Some((rate, started)) = &mut self.rate else {
+            return Poll::Ready(u64::MAX);
+        };
+        let now = Instant::now();
+        let started = *started.get_or_insert(now);
+        let elapsed = now.duration_since(started).as_nanos();
+        let allowed = (elapsed * *rate as u128 / 1_000_000_000) as u64;
+        if allowed > self.pos {
+            self.sleep = None;
+            return Poll::Ready(allowed - self.pos);
+        }
+        // Wait until the next chunk is allowed, so that the chunks are not too small.
+        let next = self.pos + self.chunk_size as u64;
+        let wait = Duration::from_nanos((next as u128 * 1_000_000_000 / *rate as u128) as u64);
+        let deadline = started + wait;
+        let sleep = self
+            .sleep
+            .get_or_insert_with(|| Box::pin(time::sleep_until(deadline)));
+        sleep.as_mut().reset(deadline);
+        match sleep.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(next - self.pos),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl AsyncRead for Generator {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let remaining = self.limit.map_or(u64::MAX, |limit| limit - self.pos);
+        if remaining == 0 || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let allowed = match self.poll_allowed(cx) {
+            Poll::Ready(allowed) => allowed,
+            Poll::Pending => return Poll::Pending,
+        };
+        let len = (buf.remaining() as u64)
+            .min(self.chunk_size as u64)
+            .min(remaining)
+            .min(allowed) as usize;
+        let start = self.pos;
+        let data = buf.initialize_unfilled_to(len);
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = self.source.byte_at(start + i as u64);
+        }
+        buf.advance(len);
+        self.pos += len as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Generator {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A stream that echoes back every complete line written to it, like a line based echo server.
+///
+/// An incomplete line is echoed back after shutdown, followed by EOF.
+/// ```rust
+/// use io_tubes::tubes::{LineEcho, Tube};
+/// use std::{io, time::Duration};
+///
+/// #[tokio::main]
+/// async fn line_echo() -> io::Result<()> {
+///     let mut p = Tube::new(LineEcho::new());
+///     p.timeout = Duration::from_millis(50);
+///
+///     p.send("Hello").await?;
+///     assert_eq!(p.recv(5).await?, b"");
+///     p.send(" World\nrest").await?;
+///     assert_eq!(p.recv_line().await?, b"Hello World\n");
+///
+///     Ok(())
+/// }
+///
+/// line_echo();
+/// ```
+#[derive(Debug, Default)]
+pub struct LineEcho {
+    buf: Vec<u8>,
+    /// The length of the complete lines at the start of `buf`.
+    complete: usize,
+    shutdown: bool,
+    read_waker: Option<Waker>,
+}
+
+impl LineEcho {
+    /// Create an empty echo stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AsyncRead for LineEcho {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let available = if self.shutdown {
+            self.buf.len()
+        } else {
+            self.complete
+        };
+        if available == 0 {
+            if self.shutdown {
+                return Poll::Ready(Ok(()));
+            }
+            self.read_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let len = available.min(buf.remaining());
+        buf.put_slice(&self.buf[..len]);
+        self.buf.drain(..len);
+        self.complete = self.complete.saturating_sub(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for LineEcho {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.shutdown {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        let old_len = self.buf.len();
+        self.buf.extend_from_slice(buf);
+        if let Some(pos) = buf.iter().rposition(|&byte| byte == b'\n') {
+            self.complete = old_len + pos + 1;
+            if let Some(waker) = self.read_waker.take() {
+                waker.wake();
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.shutdown = true;
+        if let Some(waker) = self.read_waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
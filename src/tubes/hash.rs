@@ -0,0 +1,173 @@
+use std::{
+    fmt::Write,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use sha2::{Digest as _, Sha256};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// The digests of the data passed in a direction of [`HashTube`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    /// The number of bytes hashed.
+    pub len: u64,
+    /// The CRC-32 of the data, as used by zlib and gzip.
+    pub crc32: u32,
+    /// The SHA-256 of the data.
+    pub sha256: [u8; 32],
+}
+
+impl Digest {
+    /// The SHA-256 in lowercase hex, as printed by `sha256sum`.
+    pub fn sha256_hex(&self) -> String {
+        self.sha256
+            .iter()
+            .fold(String::with_capacity(64), |mut hex, byte| {
+                let _ = write!(hex, "{:02x}", byte);
+                hex
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Hasher {
+    len: u64,
+    crc32: crc32fast::Hasher,
+    sha256: Sha256,
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.len += data.len() as u64;
+        self.crc32.update(data);
+        self.sha256.update(data);
+    }
+
+    fn digest(&self) -> Digest {
+        Digest {
+            len: self.len,
+            crc32: self.crc32.clone().finalize(),
+            sha256: self.sha256.clone().finalize().into(),
+        }
+    }
+}
+
+/// Computes the SHA-256 and CRC-32 of everything received from and sent to the inner stream as
+/// it passes, e.g. to verify a file exfiltrated through a shell without keeping a second copy.
+///
+/// Since a [`Tube`](super::Tube) reads ahead, the received digest covers the data buffered by the
+/// tube but not received from it yet. Delimit the data to verify by the commands sent, and reset
+/// the digest when nothing is buffered, e.g. right after the prompt is received.
+/// ```rust
+/// use io_tubes::tubes::{HashTube, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn hash() -> io::Result<()> {
+///     let mut p = Tube::echo().map_inner(HashTube::new);
+///
+///     p.send_line("Hello").await?;
+///     assert_eq!(p.recv_line().await?, b"Hello\n");
+///
+///     let received = p.inner.get_ref().received();
+///     assert_eq!(received, p.inner.get_ref().sent());
+///     assert_eq!(received.len, 6);
+///     assert_eq!(received.crc32, 0x31963516);
+///     assert_eq!(
+///         received.sha256_hex(),
+///         "66a045b452102c59d840ec097d59d9467e13a3f34f6494e539ffd32c1bb35f18"
+///     );
+///
+///     p.inner.get_mut().reset();
+///     assert_eq!(p.inner.get_ref().received().len, 0);
+///
+///     Ok(())
+/// }
+///
+/// hash();
+/// ```
+#[derive(Debug)]
+pub struct HashTube<T> {
+    inner: T,
+    received: Hasher,
+    sent: Hasher,
+}
+
+impl<T> HashTube<T> {
+    /// Hash the data in both directions of the inner stream.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            received: Hasher::default(),
+            sent: Hasher::default(),
+        }
+    }
+
+    /// The digests of the data received since the last reset.
+    pub fn received(&self) -> Digest {
+        self.received.digest()
+    }
+
+    /// The digests of the data sent since the last reset.
+    pub fn sent(&self) -> Digest {
+        self.sent.digest()
+    }
+
+    /// Start hashing both directions again from nothing.
+    pub fn reset(&mut self) {
+        self.received = Hasher::default();
+        self.sent = Hasher::default();
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data transferred directly is not hashed.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the adapter, returning the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for HashTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.received.update(&buf.filled()[filled..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for HashTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let len = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.sent.update(&buf[..len]);
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
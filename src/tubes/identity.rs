@@ -0,0 +1,71 @@
+use super::Tube;
+
+impl<T> Tube<T> {
+    /// A number that identifies the tube, unique within the program. It is attached to the
+    /// tracing events of the tube with the `tracing` feature.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn identity() -> io::Result<()> {
+    ///     let mut a = Tube::echo();
+    ///     let b = Tube::echo();
+    ///     assert_ne!(a.id(), b.id());
+    ///     assert_eq!(a.name(), None);
+    ///
+    ///     a.set_name("stage1");
+    ///     assert_eq!(a.name(), Some("stage1"));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// identity();
+    /// ```
+    pub fn id(&self) -> u64 {
+        self.traffic.id
+    }
+
+    /// The name given by [`Tube::set_name`], or the one given when the tube is created, like
+    /// `process:./chall[1234]` or `remote:127.0.0.1:1337`.
+    pub fn name(&self) -> Option<&str> {
+        self.traffic.name.as_deref()
+    }
+
+    /// Name the tube to tell it apart from other tubes in the log and the tracing events. Every
+    /// log line of the tube starts with the time, the name, the id and the sequence number of the
+    /// event, like `[+1.234567s leak(3):5 recv]`.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.traffic.set_name(name.into());
+    }
+
+    /// The span that the tracing events of the tube belong to, with the fields `tube_id`, `name`,
+    /// `peer` and `pid`. The peer address is filled for remote tubes and the process id for
+    /// process tubes.
+    ///
+    /// Every send and receive emits a `DEBUG` event in the span with the fields `tube_id`,
+    /// `direction`, `bytes` and `data`. Enter the span to attribute other events to the tube.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn span() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.set_name("leak");
+    ///
+    ///     let _entered = p.span().clone().entered();
+    ///     io_tubes::tracing::info!("leaking the canary");
+    ///     p.send_line("0x1337").await?;
+    ///     p.recv_line().await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// span();
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn span(&self) -> &tracing::Span {
+        &self.traffic.span
+    }
+}
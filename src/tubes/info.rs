@@ -0,0 +1,61 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use super::Tube;
+
+/// What a tube is connected to, returned by [`Tube::info`]. The fields that do not apply to the
+/// backend of the tube are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TubeInfo {
+    /// The address of the peer of a TCP or UDP tube. For [`Tube::remote_via`], it is the address
+    /// of the proxy.
+    pub peer_addr: Option<SocketAddr>,
+    /// The local address of a TCP or UDP tube.
+    pub local_addr: Option<SocketAddr>,
+    /// The id of the child of a process tube.
+    pub pid: Option<u32>,
+    /// The program run by a process tube, which is the shell for [`Tube::shell`].
+    pub program: Option<PathBuf>,
+    /// The parameters negotiated by a TLS tube.
+    pub tls: Option<TlsInfo>,
+}
+
+/// The parameters negotiated by the TLS handshake of a tube.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsInfo {
+    /// The protocol version, like `TLSv1_3`.
+    pub protocol: Option<String>,
+    /// The cipher suite, like `TLS13_AES_256_GCM_SHA384`.
+    pub cipher_suite: Option<String>,
+    /// The protocol agreed with ALPN, if any.
+    pub alpn: Option<Vec<u8>>,
+}
+
+impl<T> Tube<T> {
+    /// Returns what the tube is connected to, as known when it is created, e.g. to tell the tubes
+    /// apart in a report. The information is kept by [`Tube::map_inner`].
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn info() -> io::Result<()> {
+    ///     let l = Listener::bind("127.0.0.1:0").await?;
+    ///     let p = Tube::remote(("127.0.0.1", l.port()?)).await?;
+    ///     let server = l.accept().await?;
+    ///     assert_eq!(p.info().peer_addr, server.info().local_addr);
+    ///     assert_eq!(p.info().local_addr, server.info().peer_addr);
+    ///
+    ///     let p = Tube::process("/usr/bin/cat")?;
+    ///     assert!(p.info().pid.is_some());
+    ///     assert_eq!(p.info().program.as_deref(), Some("/usr/bin/cat".as_ref()));
+    ///     assert_eq!(p.info().peer_addr, None);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// info();
+    /// ```
+    pub fn info(&self) -> &TubeInfo {
+        &self.traffic.info
+    }
+}
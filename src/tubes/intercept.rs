@@ -0,0 +1,190 @@
+use std::{future::Future, io, net::SocketAddr, sync::Mutex, time::Duration};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt},
+    net::{lookup_host, ToSocketAddrs},
+    time,
+};
+
+use super::{Forwarder, Listener, Tube};
+
+/// The way data passes through an intercepting proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Flow {
+    /// From the client to the upstream server.
+    Upstream,
+    /// From the upstream server back to the client.
+    Downstream,
+}
+
+/// What an [`Intercept`] hook does with the data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Pass the data on, which may be modified.
+    Forward(Vec<u8>),
+    /// Pass the data on after the duration. The later data in the same flow is held back until
+    /// then to keep the order.
+    Delay(Duration, Vec<u8>),
+    /// Drop the data.
+    Drop,
+    /// Close both sides of the connection.
+    Close,
+}
+
+/// A hook that sees every chunk of data passing through [`Listener::intercept`], in both flows.
+///
+/// It is implemented for closures taking the flow and the data.
+pub trait Intercept: Send + 'static {
+    /// Decide what to do with the data, which is received in chunks as they arrive.
+    fn intercept(&mut self, flow: Flow, data: Vec<u8>) -> Verdict;
+}
+
+impl<F> Intercept for F
+where
+    F: FnMut(Flow, Vec<u8>) -> Verdict + Send + 'static,
+{
+    fn intercept(&mut self, flow: Flow, data: Vec<u8>) -> Verdict {
+        self(flow, data)
+    }
+}
+
+impl Listener {
+    /// Forward every accepted connection to the address like [`Listener::forward_to`], and pass
+    /// the data of both flows through a hook created by `make_hook` for each connection. The
+    /// hook can observe, modify, delay or drop the data, which makes a programmable man in the
+    /// middle for reverse engineering protocols.
+    /// ```rust
+    /// use io_tubes::tubes::{Flow, Listener, Tube, Verdict};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn intercept() -> io::Result<()> {
+    ///     let target = Listener::bind("127.0.0.1:0").await?;
+    ///     let proxy = Listener::bind("127.0.0.1:0")
+    ///         .await?
+    ///         .intercept(("127.0.0.1", target.port()?), || {
+    ///             |flow, data: Vec<u8>| match flow {
+    ///                 Flow::Upstream => Verdict::Forward(data.to_ascii_uppercase()),
+    ///                 Flow::Downstream => Verdict::Forward(data),
+    ///             }
+    ///         })
+    ///         .await?;
+    ///
+    ///     let mut p = Tube::remote(("127.0.0.1", proxy.port())).await?;
+    ///     let mut server = target.accept().await?;
+    ///     p.send_line("hello").await?;
+    ///     assert_eq!(server.recv_line().await?, b"HELLO\n");
+    ///
+    ///     server.send_line("welcome").await?;
+    ///     assert_eq!(p.recv_line().await?, b"welcome\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// intercept();
+    /// ```
+    pub async fn intercept<F, H>(
+        self,
+        addr: impl ToSocketAddrs,
+        make_hook: F,
+    ) -> io::Result<Forwarder>
+    where
+        F: FnMut() -> H + Send + 'static,
+        H: Intercept,
+    {
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+        self.intercept_with(
+            move || {
+                let addrs = addrs.clone();
+                async move { Tube::remote(&addrs[..]).await }
+            },
+            make_hook,
+        )
+    }
+
+    /// Same as [`Listener::intercept`], but call `connect` for every accepted connection to get
+    /// the upstream tube like [`Listener::forward_with`].
+    pub fn intercept_with<C, Fut, T, F, H>(
+        self,
+        mut connect: C,
+        mut make_hook: F,
+    ) -> io::Result<Forwarder>
+    where
+        C: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<Tube<T>>> + Send + 'static,
+        T: AsyncBufRead + AsyncWrite + Unpin + Send + 'static,
+        F: FnMut() -> H + Send + 'static,
+        H: Intercept,
+    {
+        self.forward_each(move |inbound| {
+            let outbound = connect();
+            let hook = make_hook();
+            async move { intercept_tubes(inbound, outbound.await?, hook).await }
+        })
+    }
+}
+
+/// Pass the data between the client and the server through the hook until both flows reach EOF
+/// or the hook closes the connection.
+pub(super) async fn intercept_tubes<A, B, H>(
+    client: Tube<A>,
+    server: Tube<B>,
+    hook: H,
+) -> io::Result<()>
+where
+    A: AsyncBufRead + AsyncWrite + Unpin,
+    B: AsyncBufRead + AsyncWrite + Unpin,
+    H: Intercept,
+{
+    let hook = Mutex::new(hook);
+    let (mut client_rx, mut client_tx) = client.split();
+    let (mut server_rx, mut server_tx) = server.split();
+    let result = tokio::try_join!(
+        pump(&mut client_rx, &mut server_tx, Flow::Upstream, &hook),
+        pump(&mut server_rx, &mut client_tx, Flow::Downstream, &hook),
+    );
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::ConnectionAborted => Ok(()),
+        result => result.map(|_| ()),
+    }
+}
+
+/// Pass the data from one tube to the other through the hook until EOF, which is passed on by
+/// shutting down the other tube. Fails with [`ConnectionAborted`](io::ErrorKind::ConnectionAborted)
+/// if the hook closes the connection.
+async fn pump<R, W, H>(
+    from: &mut Tube<R>,
+    to: &mut Tube<W>,
+    flow: Flow,
+    hook: &Mutex<H>,
+) -> io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+    H: Intercept,
+{
+    loop {
+        let data = from.fill_buf().await?.to_vec();
+        if data.is_empty() {
+            return to.shutdown().await;
+        }
+        from.consume(data.len());
+        let verdict = hook.lock().unwrap().intercept(flow, data);
+        let data = match verdict {
+            Verdict::Forward(data) => data,
+            Verdict::Delay(delay, data) => {
+                time::sleep(delay).await;
+                data
+            }
+            Verdict::Drop => continue,
+            Verdict::Close => {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "closed by the intercept hook",
+                ))
+            }
+        };
+        to.write_all(&data).await?;
+        to.flush().await?;
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    future, io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{split, AsyncRead, AsyncWrite, BufReader, ReadBuf, ReadHalf, WriteHalf},
+    task::JoinHandle,
+    time::{self, Instant},
+};
+
+use super::Tube;
+
+/// Sends a payload in the background whenever the inner stream has been idle for an interval, so
+/// that the connection is not dropped by an idle timeout, e.g. during a slow brute force.
+/// Created by [`Tube::keepalive`].
+///
+/// Sending or receiving anything counts as activity. Nothing is sent while the tube is in
+/// [`Tube::interactive`], and the payload is never interleaved with the data sent by the tube.
+/// For a TCP connection, keepalive probes without any payload can be enabled with
+/// [`SocketOptions::keepalive`](super::SocketOptions::keepalive) instead.
+#[derive(Debug)]
+pub struct KeepaliveTube<T> {
+    read: ReadHalf<T>,
+    shared: Arc<Shared<T>>,
+    task: JoinHandle<()>,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    paused: Arc<AtomicBool>,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    write: WriteHalf<T>,
+    /// The part of the payload not written yet, written before any more data.
+    pending: Vec<u8>,
+    last_active: Instant,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<T: AsyncWrite> State<T> {
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let numb = ready!(Pin::new(&mut self.write).poll_write(cx, &self.pending))?;
+            if numb == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending.drain(..numb);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> KeepaliveTube<T>
+where
+    T: AsyncRead + AsyncWrite + Send + 'static,
+{
+    /// Send `payload` to `inner` whenever nothing is sent or received for `interval`. Panics if
+    /// the interval is zero.
+    pub fn new(inner: T, interval: Duration, payload: impl Into<Vec<u8>>) -> Self {
+        assert!(!interval.is_zero(), "interval must not be 0");
+        let (read, write) = split(inner);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                write,
+                pending: Vec::new(),
+                last_active: Instant::now(),
+            }),
+            paused: Arc::new(AtomicBool::new(false)),
+        });
+        let task = tokio::spawn(keepalive(shared.clone(), interval, payload.into()));
+        Self { read, shared, task }
+    }
+}
+
+impl<T> KeepaliveTube<T> {
+    /// Stop sending the payload until [`KeepaliveTube::resume`] is called.
+    pub fn pause(&self) {
+        self.shared.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Send the payload again when idle.
+    pub fn resume(&self) {
+        self.shared.paused.store(false, Ordering::Relaxed);
+    }
+
+    fn active(&self) {
+        self.shared.lock().last_active = Instant::now();
+    }
+}
+
+impl<T> Drop for KeepaliveTube<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn keepalive<T: AsyncWrite>(shared: Arc<Shared<T>>, interval: Duration, payload: Vec<u8>) {
+    loop {
+        let idle_at = shared.lock().last_active + interval;
+        time::sleep_until(idle_at).await;
+        {
+            let mut state = shared.lock();
+            if shared.paused.load(Ordering::Relaxed) {
+                state.last_active = Instant::now();
+                continue;
+            }
+            if state.last_active + interval > Instant::now() {
+                continue;
+            }
+            state.pending.extend_from_slice(&payload);
+            state.last_active = Instant::now();
+        }
+        loop {
+            let sent = future::poll_fn(|cx| {
+                let mut state = shared.lock();
+                ready!(state.poll_pending(cx))?;
+                Pin::new(&mut state.write).poll_flush(cx)
+            });
+            // Polled again after a while in case the tube took over the wakeup by writing.
+            match time::timeout(interval, sent).await {
+                Ok(Ok(())) => break,
+                // The error is seen by the tube on its next operation.
+                Ok(Err(_)) => return,
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+impl<T> Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Send `payload` whenever the tube is idle for `interval`, e.g. a newline to a menu that
+    /// ignores it, see [`KeepaliveTube`]. Panics if the interval is zero.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn keepalive() -> io::Result<()> {
+    ///     let (p, mut server) = Tube::pair();
+    ///     let mut p = p.keepalive(Duration::from_millis(100), "\n");
+    ///
+    ///     p.send_line("1").await?;
+    ///     tokio::time::sleep(Duration::from_millis(250)).await;
+    ///     p.send_line("2").await?;
+    ///     let received = server.recv_until("2\n").await?;
+    ///     assert!(received.starts_with(b"1\n\n"));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// keepalive();
+    /// ```
+    pub fn keepalive(
+        self,
+        interval: Duration,
+        payload: impl Into<Vec<u8>>,
+    ) -> Tube<BufReader<KeepaliveTube<T>>> {
+        let mut tube = self.map_inner(|inner| KeepaliveTube::new(inner, interval, payload));
+        tube.traffic.keepalive_paused = Some(tube.inner.get_ref().shared.paused.clone());
+        tube
+    }
+}
+
+/// Pauses the keepalive of a tube until dropped.
+#[derive(Debug)]
+pub(super) struct KeepalivePause(Option<Arc<AtomicBool>>);
+
+impl KeepalivePause {
+    pub(super) fn new(paused: Option<&Arc<AtomicBool>>) -> Self {
+        let paused = paused.filter(|paused| !paused.swap(true, Ordering::Relaxed));
+        Self(paused.cloned())
+    }
+}
+
+impl Drop for KeepalivePause {
+    fn drop(&mut self) {
+        if let Some(paused) = &self.0 {
+            paused.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for KeepaliveTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let len = buf.filled().len();
+        ready!(Pin::new(&mut this.read).poll_read(cx, buf))?;
+        if buf.filled().len() > len {
+            this.active();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for KeepaliveTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.shared.lock();
+        ready!(state.poll_pending(cx))?;
+        let numb = ready!(Pin::new(&mut state.write).poll_write(cx, buf))?;
+        state.last_active = Instant::now();
+        Poll::Ready(Ok(numb))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut state = self.shared.lock();
+        ready!(state.poll_pending(cx))?;
+        Pin::new(&mut state.write).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut state = self.shared.lock();
+        ready!(state.poll_pending(cx))?;
+        Pin::new(&mut state.write).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{self, Instant, Sleep},
+};
+
+use super::generator::random_byte;
+
+/// The most bytes that are read ahead from the inner stream while waiting for the delay.
+const MAX_QUEUED: usize = 64 * 1024;
+
+/// Data received from the inner stream, which is an empty vector for EOF.
+#[derive(Debug)]
+struct Arrival {
+    at: Instant,
+    data: io::Result<Vec<u8>>,
+}
+
+/// Simulates a slow link for testing by delaying the data received from the inner stream and
+/// splitting it into small reads. Data written is passed to the inner stream immediately.
+///
+/// Each read from the inner stream is delayed by [`delay`](LaggyTube::delay) plus a random
+/// [`jitter`](LaggyTube::jitter), but the order is kept as with TCP. EOF and errors are delayed
+/// the same way.
+/// ```rust
+/// use io_tubes::tubes::{LaggyTube, LineEcho, Tube, TubeError};
+/// use std::{io, time::Duration};
+///
+/// #[tokio::main]
+/// async fn laggy() -> io::Result<()> {
+///     let laggy = LaggyTube::new(LineEcho::new())
+///         .delay(Duration::from_millis(100))
+///         .jitter(Duration::from_millis(20))
+///         .chunk_size(2);
+///     let mut p = Tube::new(laggy);
+///     p.timeout = Duration::from_millis(50);
+///
+///     p.send_line("Hello").await?;
+///     assert!(matches!(
+///         p.recv_line_checked().await,
+///         Err(TubeError::Timeout { .. })
+///     ));
+///
+///     p.timeout = Duration::from_secs(1);
+///     assert_eq!(p.recv_line().await?, b"Hello\n");
+///
+///     Ok(())
+/// }
+///
+/// laggy();
+/// ```
+#[derive(Debug)]
+pub struct LaggyTube<T> {
+    inner: T,
+    delay: Duration,
+    jitter: Duration,
+    seed: u64,
+    samples: u64,
+    chunk_size: usize,
+    queue: VecDeque<Arrival>,
+    queued: usize,
+    /// Whether EOF or an error is queued, after which the inner stream is not read until it is
+    /// received.
+    stopped: bool,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<T> LaggyTube<T> {
+    /// Pass the data through without delay until configured.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+            seed: 0,
+            samples: 0,
+            chunk_size: usize::MAX,
+            queue: VecDeque::new(),
+            queued: 0,
+            stopped: false,
+            sleep: None,
+        }
+    }
+
+    /// Delay the received data by `delay`, which is 0 by default.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Add a random delay up to `jitter` on top of [`delay`](LaggyTube::delay) to each read from
+    /// the inner stream, which is 0 by default.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The seed of the jitter, so that the delays are the same across runs. It is 0 by default.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Return at most `size` bytes per read. The reads are not limited by default.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "chunk size must not be 0");
+        self.chunk_size = size;
+        self
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data read directly is not delayed.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the laggy tube to get back the inner stream. Data that is still delayed is lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// The time that data received now arrives, which is never before the previous data.
+    fn arrival_time(&mut self) -> Instant {
+        let mut lag = self.delay;
+        if !self.jitter.is_zero() {
+            let random = u64::from_le_bytes(std::array::from_fn(|i| {
+                random_byte(self.seed, self.samples * 8 + i as u64)
+            }));
+            self.samples += 1;
+            lag += Duration::from_nanos(random % (self.jitter.as_nanos() as u64 + 1));
+        }
+        let at = Instant::now() + lag;
+        self.queue.back().map_or(at, |last| at.max(last.at))
+    }
+
+    fn push(&mut self, data: io::Result<Vec<u8>>) {
+        match &data {
+            Ok(data) if !data.is_empty() => self.queued += data.len(),
+            _ => self.stopped = true,
+        }
+        let at = self.arrival_time();
+        self.queue.push_back(Arrival { at, data });
+    }
+}
+
+impl<T> LaggyTube<T>
+where
+    T: AsyncRead + Unpin,
+{
+    /// Read ahead from the inner stream so that the delay applies from the time data is received.
+    fn poll_fill(&mut self, cx: &mut Context<'_>) {
+        let mut chunk = [0; 8192];
+        while !self.stopped && self.queued < MAX_QUEUED {
+            let mut buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut self.inner).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) => self.push(Ok(buf.filled().to_vec())),
+                Poll::Ready(Err(err)) => self.push(Err(err)),
+                Poll::Pending => break,
+            }
+        }
+    }
+}
+
+impl<T> AsyncRead for LaggyTube<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            this.poll_fill(cx);
+            let Some(front) = this.queue.front_mut() else {
+                return Poll::Pending;
+            };
+            if front.at > Instant::now() {
+                let at = front.at;
+                let sleep = this
+                    .sleep
+                    .get_or_insert_with(|| Box::pin(time::sleep_until(at)));
+                sleep.as_mut().reset(at);
+                match sleep.as_mut().poll(cx) {
+                    Poll::Ready(()) => continue,
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            this.sleep = None;
+            return match &mut front.data {
+                // EOF stays in the queue so that it is received again.
+                Ok(data) if data.is_empty() => Poll::Ready(Ok(())),
+                Ok(data) => {
+                    let len = data.len().min(buf.remaining()).min(this.chunk_size);
+                    buf.put_slice(&data[..len]);
+                    data.drain(..len);
+                    this.queued -= len;
+                    if data.is_empty() {
+                        this.queue.pop_front();
+                    }
+                    Poll::Ready(Ok(()))
+                }
+                Err(_) => {
+                    let err = this.queue.pop_front().unwrap().data.unwrap_err();
+                    this.stopped = false;
+                    Poll::Ready(Err(err))
+                }
+            };
+        }
+    }
+}
+
+impl<T> AsyncWrite for LaggyTube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
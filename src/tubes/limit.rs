@@ -0,0 +1,72 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// Limits the data that can be received through it, failing once more is available so that the
+/// receive methods don't buffer without bound, see [`Tube::max_recv_size`](super::Tube).
+#[derive(Debug)]
+pub(super) struct Limited<'a, T> {
+    inner: &'a mut T,
+    remaining: Option<usize>,
+    exceeded: bool,
+}
+
+impl<'a, T> Limited<'a, T> {
+    /// Allow at most `limit` bytes, or any amount if it is `None`.
+    pub(super) fn new(inner: &'a mut T, limit: Option<usize>) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+            exceeded: false,
+        }
+    }
+
+    /// Whether the receive failed since the limit is reached.
+    pub(super) fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
+impl<T: AsyncBufRead + Unpin> AsyncBufRead for Limited<'_, T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        let buf = ready!(Pin::new(&mut *this.inner).poll_fill_buf(cx))?;
+        match this.remaining {
+            Some(0) if !buf.is_empty() => {
+                this.exceeded = true;
+                Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "max_recv_size exceeded",
+                )))
+            }
+            Some(remaining) => Poll::Ready(Ok(&buf[..buf.len().min(remaining)])),
+            None => Poll::Ready(Ok(buf)),
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        if let Some(remaining) = &mut this.remaining {
+            *remaining -= amt;
+        }
+        Pin::new(&mut *this.inner).consume(amt);
+    }
+}
+
+impl<T: AsyncBufRead + Unpin> AsyncRead for Limited<'_, T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = data.len().min(buf.remaining());
+        buf.put_slice(&data[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(()))
+    }
+}
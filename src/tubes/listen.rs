@@ -1,23 +1,223 @@
-use std::{io, net::SocketAddr};
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+use std::{
+    future, io,
+    net::SocketAddr,
+    sync::Mutex,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "stream")]
+use futures_core::Stream;
 
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
 use tokio::{
-    io::BufReader,
-    net::{TcpListener, TcpStream, ToSocketAddrs},
+    io::{duplex, AsyncRead, AsyncWrite, BufReader, DuplexStream},
+    net::{lookup_host, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc,
 };
 
-use super::Tube;
+use super::{SocketOptions, Tube};
+
+/// The bytes buffered in each direction of the connections of [`Listener::memory`].
+const MEMORY_CAPACITY: usize = 64 * 1024;
 
 /// A TcpListener that returns Tube when a connection is accepted.
-pub struct Listener {
-    /// The inner TcpListener
-    pub inner: TcpListener,
+///
+/// The connections can also come from any other [`Accept`], e.g. [`Listener::memory`] to test
+/// server code without binding ports.
+pub struct Listener<A = TcpListener> {
+    /// The source of the accepted connections
+    pub inner: A,
+    /// Applied to the accepted connections.
+    options: Option<SocketOptions>,
+}
+
+/// A source of connections for a [`Listener`].
+pub trait Accept {
+    /// The stream of an accepted connection.
+    type Stream: AsyncRead + AsyncWrite + Unpin;
+
+    /// Polls to accept a connection, returning the stream and the address of the peer if any.
+    fn poll_accept(&self, cx: &mut Context<'_>)
+        -> Poll<io::Result<(Self::Stream, Option<String>)>>;
+
+    /// Apply the socket options of the listener to an accepted stream. It does nothing by
+    /// default.
+    fn apply(&self, stream: &Self::Stream, options: &SocketOptions) -> io::Result<()> {
+        let _ = (stream, options);
+        Ok(())
+    }
+
+    /// The local address of an accepted stream, reported by [`Tube::info`]. It is `None` by
+    /// default.
+    fn local_addr(&self, stream: &Self::Stream) -> Option<SocketAddr> {
+        let _ = stream;
+        None
+    }
+}
+
+impl Accept for TcpListener {
+    type Stream = TcpStream;
+
+    fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(TcpStream, Option<String>)>> {
+        TcpListener::poll_accept(self, cx).map_ok(|(stream, peer)| (stream, Some(peer.to_string())))
+    }
+
+    fn apply(&self, stream: &TcpStream, options: &SocketOptions) -> io::Result<()> {
+        options.apply(stream)
+    }
+
+    fn local_addr(&self, stream: &TcpStream) -> Option<SocketAddr> {
+        stream.local_addr().ok()
+    }
+}
+
+#[cfg(unix)]
+impl Accept for UnixListener {
+    type Stream = UnixStream;
+
+    fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(UnixStream, Option<String>)>> {
+        UnixListener::poll_accept(self, cx).map_ok(|(stream, peer)| {
+            let peer = peer.as_pathname().map(|path| path.display().to_string());
+            (stream, peer)
+        })
+    }
+}
+
+/// The connections of [`Listener::memory`], which are made by a [`MemoryConnector`].
+#[derive(Debug)]
+pub struct MemoryAcceptor {
+    connections: Mutex<mpsc::UnboundedReceiver<DuplexStream>>,
+}
+
+impl Accept for MemoryAcceptor {
+    type Stream = DuplexStream;
+
+    fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<(DuplexStream, Option<String>)>> {
+        let mut connections = self
+            .connections
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        connections.poll_recv(cx).map(|stream| match stream {
+            Some(stream) => Ok((stream, None)),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "all the connectors are dropped",
+            )),
+        })
+    }
+}
+
+/// Connects to the listener created by [`Listener::memory`]. It can be cloned to connect from
+/// several tasks.
+#[derive(Debug, Clone)]
+pub struct MemoryConnector {
+    connections: mpsc::UnboundedSender<DuplexStream>,
+}
+
+impl MemoryConnector {
+    /// Make a connection, which is accepted by the listener. Fails with
+    /// [`ConnectionRefused`](io::ErrorKind::ConnectionRefused) if the listener is dropped.
+    pub fn connect(&self) -> io::Result<Tube<BufReader<DuplexStream>>> {
+        let (client, server) = duplex(MEMORY_CAPACITY);
+        self.connections.send(server).map_err(|_| {
+            io::Error::new(io::ErrorKind::ConnectionRefused, "the listener is dropped")
+        })?;
+        Ok(Tube::new(client))
+    }
+}
+
+impl Listener<MemoryAcceptor> {
+    /// Create a listener that accepts in-memory connections made with the returned connector,
+    /// so that server code written against [`Listener`] can be tested without binding ports.
+    /// Accepting fails once all the connectors are dropped.
+    /// ```rust
+    /// use io_tubes::tubes::{Accept, Listener};
+    /// use std::io;
+    ///
+    /// async fn greet<A: Accept>(listener: &Listener<A>) -> io::Result<()> {
+    ///     let mut tube = listener.accept().await?;
+    ///     let name = tube.recv_line().await?;
+    ///     tube.send(b"Hello ").await?;
+    ///     tube.send(name).await
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn memory() -> io::Result<()> {
+    ///     let (listener, connector) = Listener::memory();
+    ///     let mut p = connector.connect()?;
+    ///     p.send_line("Alice").await?;
+    ///
+    ///     greet(&listener).await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello Alice\n");
+    ///
+    ///     drop(connector);
+    ///     assert!(listener.accept().await.is_err());
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// memory();
+    /// ```
+    pub fn memory() -> (Self, MemoryConnector) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let listener = Listener {
+            inner: MemoryAcceptor {
+                connections: Mutex::new(receiver),
+            },
+            options: None,
+        };
+        (
+            listener,
+            MemoryConnector {
+                connections: sender,
+            },
+        )
+    }
 }
 
 impl Listener {
     /// Create a listener by binding to the supplied address.
     pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(TcpListener::bind(addr).await?.into())
+    }
+
+    /// Create a listener with the socket options, which are also applied to the accepted
+    /// connections. The first address that can be bound is used.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, SocketOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn bind_with() -> io::Result<()> {
+    ///     let options = SocketOptions {
+    ///         nodelay: true,
+    ///         reuse_addr: true,
+    ///         ..SocketOptions::default()
+    ///     };
+    ///     let l = Listener::bind_with("127.0.0.1:0", &options).await?;
+    ///     let mut p = Tube::remote(("127.0.0.1", l.port()?)).await?;
+    ///     let mut server = l.accept().await?;
+    ///     assert!(server.inner.get_ref().nodelay()?);
+    ///
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(server.recv_line().await?, b"Hello\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// bind_with();
+    /// ```
+    pub async fn bind_with(addr: impl ToSocketAddrs, options: &SocketOptions) -> io::Result<Self> {
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
         Ok(Listener {
-            inner: TcpListener::bind(addr).await?,
+            inner: options.listen(&addrs)?,
+            options: Some(options.clone()),
         })
     }
 
@@ -26,11 +226,6 @@ impl Listener {
         Listener::bind("0.0.0.0:0").await
     }
 
-    /// Accepts a connection.
-    pub async fn accept(&self) -> io::Result<Tube<BufReader<TcpStream>>> {
-        Ok(Tube::new(self.inner.accept().await?.0))
-    }
-
     /// Returns the port that is listened.
     pub fn port(&self) -> io::Result<u16> {
         Ok(match self.inner.local_addr()? {
@@ -40,9 +235,63 @@ impl Listener {
     }
 }
 
+impl<A: Accept> Listener<A> {
+    /// Create a listener accepting the connections of any other source, like a vsock listener.
+    pub fn from_accept(inner: A) -> Self {
+        Self {
+            inner,
+            options: None,
+        }
+    }
+
+    /// Accepts a connection. It can be called concurrently, e.g. from several tasks sharing the
+    /// listener in an [`Arc`](std::sync::Arc) or in the branches of [`tokio::select!`].
+    pub async fn accept(&self) -> io::Result<Tube<BufReader<A::Stream>>> {
+        future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    /// Polls to accept a connection, for implementing futures and streams by hand.
+    pub fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<Tube<BufReader<A::Stream>>>> {
+        self.inner
+            .poll_accept(cx)
+            .map(|result| result.and_then(|(stream, peer)| self.tube(stream, peer)))
+    }
+
+    /// Yields the accepted connections forever, see [`Incoming`].
+    #[cfg(feature = "stream")]
+    pub fn incoming(&self) -> Incoming<'_, A> {
+        Incoming { listener: self }
+    }
+
+    fn tube(
+        &self,
+        stream: A::Stream,
+        peer: Option<String>,
+    ) -> io::Result<Tube<BufReader<A::Stream>>> {
+        if let Some(options) = &self.options {
+            self.inner.apply(&stream, options)?;
+        }
+        let local_addr = self.inner.local_addr(&stream);
+        let mut tube = Tube::new(stream);
+        tube.traffic.info.local_addr = local_addr;
+        if let Some(peer) = peer {
+            tube.traffic.info.peer_addr = peer.parse().ok();
+            tube.traffic.describe("peer", &peer);
+            tube.traffic.set_name(format!("accept:{}", peer));
+        }
+        Ok(tube)
+    }
+}
+
 impl From<TcpListener> for Listener {
     fn from(inner: TcpListener) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            options: None,
+        }
     }
 }
 
@@ -51,3 +300,55 @@ impl From<Listener> for TcpListener {
         listener.inner
     }
 }
+
+/// The connections accepted by [`Listener::incoming`]. The stream never ends, and a failed accept
+/// is yielded as an error without ending it.
+/// ```rust
+/// use futures::StreamExt;
+/// use io_tubes::tubes::{Listener, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn incoming() -> io::Result<()> {
+///     let listener = Listener::bind("127.0.0.1:0").await?;
+///     let port = listener.port()?;
+///     let clients = tokio::spawn(async move {
+///         let mut alice = Tube::remote(("127.0.0.1", port)).await?;
+///         let mut bob = Tube::remote(("127.0.0.1", port)).await?;
+///         alice.send_line("Alice").await?;
+///         bob.send_line("Bob").await?;
+///         Ok::<_, io::Error>((alice.recv_line().await?, bob.recv_line().await?))
+///     });
+///
+///     listener
+///         .incoming()
+///         .take(2)
+///         .for_each_concurrent(None, |tube| async move {
+///             let mut tube = tube.unwrap();
+///             let name = tube.recv_line().await.unwrap();
+///             tube.send(name.to_ascii_uppercase()).await.unwrap();
+///         })
+///         .await;
+///
+///     let (alice, bob) = clients.await??;
+///     assert_eq!(alice, b"ALICE\n");
+///     assert_eq!(bob, b"BOB\n");
+///
+///     Ok(())
+/// }
+///
+/// incoming();
+/// ```
+#[cfg(feature = "stream")]
+pub struct Incoming<'a, A = TcpListener> {
+    listener: &'a Listener<A>,
+}
+
+#[cfg(feature = "stream")]
+impl<A: Accept> Stream for Incoming<'_, A> {
+    type Item = io::Result<Tube<BufReader<A::Stream>>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.listener.poll_accept(cx).map(Some)
+    }
+}
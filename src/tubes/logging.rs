@@ -0,0 +1,190 @@
+use std::{fmt, sync::Arc};
+
+use pretty_hex::PrettyHex;
+
+use super::{Direction, Tube};
+
+/// What to log for the data sent or received, returned by the filter of
+/// [`Tube::set_log_filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogDecision {
+    /// Log the data as usual.
+    Log,
+    /// Log only the number of bytes, e.g. for passwords and tokens.
+    Redact,
+    /// Don't log anything.
+    Skip,
+}
+
+/// How the data sent and received is logged, see [`Tube::set_log_options`].
+#[derive(Debug, Clone)]
+pub struct LogOptions {
+    /// Log the traffic at all, which is the default.
+    pub enabled: bool,
+    /// Only dump the first bytes of each send or receive. Everything is dumped if `None`, which
+    /// is the default.
+    pub max_bytes: Option<usize>,
+    /// Log the data as an escaped byte string instead of a hexdump.
+    pub ascii: bool,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_bytes: None,
+            ascii: false,
+        }
+    }
+}
+
+type FilterFn = dyn Fn(Direction, &[u8]) -> LogDecision + Send + Sync;
+
+/// The filter set by [`Tube::set_log_filter`].
+#[derive(Clone)]
+pub(super) struct LogFilter(Arc<FilterFn>);
+
+impl LogFilter {
+    pub(super) fn decide(&self, direction: Direction, data: &[u8]) -> LogDecision {
+        (self.0)(direction, data)
+    }
+}
+
+impl fmt::Debug for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LogFilter")
+    }
+}
+
+/// Formats the data according to the options when it is actually logged.
+pub(super) struct Dump<'a> {
+    pub(super) data: &'a [u8],
+    pub(super) options: &'a LogOptions,
+}
+
+impl fmt::Display for Dump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_bytes = self.options.max_bytes.unwrap_or(usize::MAX);
+        let shown = &self.data[..self.data.len().min(max_bytes)];
+        if self.options.ascii {
+            write!(f, "b\"{}\"", shown.escape_ascii())?;
+        } else {
+            write!(f, "{:?}", shown.hex_dump())?;
+        }
+        if shown.len() < self.data.len() {
+            write!(f, " ... {} more bytes", self.data.len() - shown.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Tube<T> {
+    /// Decide what to log for each send and receive, e.g. to keep secrets out of the log. The
+    /// filter is only called if the traffic is logged at all. It also applies to the tracing
+    /// events, but not to the transcript of [`Tube::record`].
+    /// ```rust
+    /// use io_tubes::tubes::{Direction, LogDecision, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn set_log_filter() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.set_log_filter(|direction, data| match direction {
+    ///         Direction::Send if data.starts_with(b"PASS ") => LogDecision::Redact,
+    ///         _ => LogDecision::Log,
+    ///     });
+    ///
+    ///     p.send_line("PASS hunter2").await?;
+    ///     assert_eq!(p.recv_line().await?, b"PASS hunter2\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// set_log_filter();
+    /// ```
+    ///
+    /// The halves of [`Tube::split`] keep the filter:
+    /// ```rust
+    /// use io_tubes::tubes::{LogDecision, Tube};
+    /// use log::{LevelFilter, Log, Metadata, Record};
+    /// use std::{io, sync::Mutex};
+    ///
+    /// static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    ///
+    /// struct Capture;
+    ///
+    /// impl Log for Capture {
+    ///     fn enabled(&self, _: &Metadata) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn log(&self, record: &Record) {
+    ///         LINES.lock().unwrap().push(record.args().to_string());
+    ///     }
+    ///
+    ///     fn flush(&self) {}
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn split_log_filter() -> io::Result<()> {
+    ///     log::set_logger(&Capture).unwrap();
+    ///     log::set_max_level(LevelFilter::Debug);
+    ///
+    ///     let mut p = Tube::echo();
+    ///     p.set_log_filter(|_, data| match data.starts_with(b"PASS ") {
+    ///         true => LogDecision::Redact,
+    ///         false => LogDecision::Log,
+    ///     });
+    ///     let (mut rx, mut tx) = p.split();
+    ///
+    ///     tx.send_line("PASS hunter2").await?;
+    ///     assert_eq!(rx.recv_line().await?, b"PASS hunter2\n");
+    ///
+    ///     let lines = LINES.lock().unwrap();
+    ///     assert!(lines.iter().any(|line| line.ends_with("Sent 12 bytes (redacted)")));
+    ///     assert!(lines.iter().any(|line| line.ends_with("Received 13 bytes (redacted)")));
+    ///     assert!(!lines.iter().any(|line| line.contains("hunter2")));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// split_log_filter();
+    /// ```
+    pub fn set_log_filter(
+        &mut self,
+        filter: impl Fn(Direction, &[u8]) -> LogDecision + Send + Sync + 'static,
+    ) {
+        self.traffic.log_filter = Some(LogFilter(Arc::new(filter)));
+    }
+
+    /// Remove the filter set by [`Tube::set_log_filter`].
+    pub fn clear_log_filter(&mut self) {
+        self.traffic.log_filter = None;
+    }
+
+    /// Change how the traffic is logged, e.g. to keep large transfers from flooding the log.
+    /// ```rust
+    /// use io_tubes::tubes::{LogOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn set_log_options() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.set_log_options(LogOptions {
+    ///         max_bytes: Some(64),
+    ///         ascii: true,
+    ///         ..LogOptions::default()
+    ///     });
+    ///
+    ///     p.send(vec![b'A'; 4096]).await?;
+    ///     assert_eq!(p.recv(4096).await?.len(), 4096);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// set_log_options();
+    /// ```
+    pub fn set_log_options(&mut self, options: LogOptions) {
+        self.traffic.log_options = options;
+    }
+}
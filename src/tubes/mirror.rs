@@ -0,0 +1,155 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, BufReader, ReadBuf},
+    sync::broadcast,
+};
+
+use super::{Direction, Tube};
+
+/// A copy of the data sent or received by a [`MirrorTube`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorChunk {
+    /// Whether the data is sent or received.
+    pub direction: Direction,
+    /// The data as it is written to or read from the inner stream.
+    pub data: Arc<[u8]>,
+}
+
+/// Passes the traffic through while publishing a copy of every chunk sent and received to a
+/// [`broadcast`] channel, so that other tasks like a GUI, a detector or a statistics collector
+/// can follow the traffic live without being in the data path. Created by [`Tube::mirror`].
+///
+/// The channel keeps up to `capacity` chunks for each subscriber. A subscriber that falls behind
+/// misses the oldest chunks and gets [`Lagged`](broadcast::error::RecvError::Lagged) instead of
+/// slowing down the tube, and nothing is kept while there is no subscriber. The chunks are the
+/// writes and reads of the inner stream, so a single send may be split into several chunks.
+/// ```rust
+/// use io_tubes::tubes::{Direction, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn mirror() -> io::Result<()> {
+///     let (p, mut server) = Tube::pair();
+///     let mut p = p.mirror(16);
+///     let mut chunks = p.subscribe();
+///
+///     p.send("hello\n").await?;
+///     server.recv_line().await?;
+///     server.send("world\n").await?;
+///     p.recv_line().await?;
+///
+///     let chunk = chunks.recv().await.unwrap();
+///     assert_eq!((chunk.direction, &chunk.data[..]), (Direction::Send, &b"hello\n"[..]));
+///     let chunk = chunks.recv().await.unwrap();
+///     assert_eq!((chunk.direction, &chunk.data[..]), (Direction::Recv, &b"world\n"[..]));
+///
+///     Ok(())
+/// }
+///
+/// mirror();
+/// ```
+#[derive(Debug)]
+pub struct MirrorTube<T> {
+    inner: T,
+    sender: broadcast::Sender<MirrorChunk>,
+}
+
+impl<T> MirrorTube<T> {
+    /// Mirror the traffic of `inner`, keeping up to `capacity` chunks for each subscriber.
+    /// Panics if the capacity is zero.
+    pub fn new(inner: T, capacity: usize) -> Self {
+        Self {
+            inner,
+            sender: broadcast::Sender::new(capacity),
+        }
+    }
+
+    /// Receive the chunks sent and received from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<MirrorChunk> {
+        self.sender.subscribe()
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data transferred directly is not mirrored.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the adapter to get back the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn publish(&self, direction: Direction, data: &[u8]) {
+        if !data.is_empty() && self.sender.receiver_count() > 0 {
+            // Fails only if every subscriber is dropped in the meantime.
+            let _ = self.sender.send(MirrorChunk {
+                direction,
+                data: data.into(),
+            });
+        }
+    }
+}
+
+impl<T> Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Publish a copy of the traffic to the subscribers of [`Tube::subscribe`], see
+    /// [`MirrorTube`]. Panics if the capacity is zero.
+    pub fn mirror(self, capacity: usize) -> Tube<BufReader<MirrorTube<T>>> {
+        self.map_inner(|inner| MirrorTube::new(inner, capacity))
+    }
+}
+
+impl<T: AsyncRead + Unpin> Tube<BufReader<MirrorTube<T>>> {
+    /// Receive the chunks sent and received by the tube from now on, see [`Tube::mirror`].
+    pub fn subscribe(&self) -> broadcast::Receiver<MirrorChunk> {
+        self.inner.get_ref().subscribe()
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for MirrorTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let len = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.publish(Direction::Recv, &buf.filled()[len..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for MirrorTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let numb = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.publish(Direction::Send, &buf[..numb]);
+        Poll::Ready(Ok(numb))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// A scripted peer to test code that talks to a target, without the target.
+///
+/// The dialogue is declared from the side of the mock: [`expect_recv`](MockTube::expect_recv)
+/// is data the code under test must send, and [`then_send`](MockTube::then_send) is data it
+/// receives. Each reply becomes readable once everything expected before it has been written, and
+/// EOF is reached at the end of the dialogue.
+///
+/// A write that deviates from the dialogue fails with [`InvalidData`](io::ErrorKind::InvalidData)
+/// and a message comparing the expected and received data of the step.
+/// ```rust
+/// use io_tubes::tubes::{MockTube, Tube};
+/// use std::io;
+/// use tokio::io::{AsyncBufRead, AsyncWrite};
+///
+/// async fn login<T>(p: &mut Tube<T>, user: &str, password: &str) -> io::Result<bool>
+/// where
+///     T: AsyncBufRead + AsyncWrite + Unpin,
+/// {
+///     p.send_line_after("ready\n", format!("USER {}", user)).await?;
+///     p.send_line_after("\n", format!("PASS {}", password)).await?;
+///     Ok(p.recv_line().await?.starts_with(b"230"))
+/// }
+///
+/// #[tokio::main]
+/// async fn mock() -> io::Result<()> {
+///     let mock = MockTube::new()
+///         .then_send("220 ready\n")
+///         .expect_recv("USER admin\n")
+///         .then_send("331 password?\n")
+///         .expect_recv("PASS hunter2\n")
+///         .then_send("230 ok\n");
+///     let mut p = Tube::new(mock);
+///     assert!(login(&mut p, "admin", "hunter2").await?);
+///     p.inner.get_ref().verify()?;
+///
+///     let mock = MockTube::new()
+///         .then_send("220 ready\n")
+///         .expect_recv("USER admin\n");
+///     let mut p = Tube::new(mock);
+///     let err = login(&mut p, "root", "toor").await.unwrap_err();
+///     assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+///     assert!(err.to_string().contains(r#"expected: b"USER admin\n""#));
+///     assert!(err.to_string().contains(r#"received: b"USER root"#));
+///
+///     Ok(())
+/// }
+///
+/// mock();
+/// ```
+#[derive(Debug, Default)]
+pub struct MockTube {
+    /// The replies, with the number of bytes that must be written before each of them.
+    replies: Vec<(usize, Vec<u8>)>,
+    reply_index: usize,
+    reply_pos: usize,
+    /// Everything expected to be written, and where each step starts in it.
+    expected: Vec<u8>,
+    steps: Vec<usize>,
+    written: Vec<u8>,
+    read_waker: Option<Waker>,
+}
+
+impl MockTube {
+    /// Start an empty dialogue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect the code under test to send the data next.
+    pub fn expect_recv(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.steps.push(self.expected.len());
+        self.expected.extend_from_slice(data.as_ref());
+        self
+    }
+
+    /// Then send the data to the code under test.
+    pub fn then_send(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.replies.push((self.expected.len(), data.into()));
+        self
+    }
+
+    /// Whether the whole dialogue has taken place.
+    pub fn is_done(&self) -> bool {
+        self.reply_index == self.replies.len() && self.written.len() == self.expected.len()
+    }
+
+    /// Fails with [`InvalidData`](io::ErrorKind::InvalidData) describing the rest of the dialogue
+    /// if it has not all taken place, e.g. at the end of a test.
+    pub fn verify(&self) -> io::Result<()> {
+        if self.written.len() < self.expected.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the dialogue is not finished, still expected: b\"{}\"",
+                    self.expected[self.written.len()..].escape_ascii()
+                ),
+            ));
+        }
+        if let Some((_, data)) = self.replies.get(self.reply_index) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the dialogue is not finished, not received: b\"{}\"",
+                    data[self.reply_pos..].escape_ascii()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Describe how the write deviates from the dialogue.
+    fn deviation(&self, buf: &[u8]) -> String {
+        let start = self.written.len();
+        let at = start
+            + buf
+                .iter()
+                .zip(&self.expected[start..])
+                .take_while(|(a, b)| a == b)
+                .count();
+        if at >= self.expected.len() {
+            return format!(
+                "unexpected data after the end of the dialogue: b\"{}\"",
+                buf[at - start..].escape_ascii()
+            );
+        }
+        let step = self.steps.partition_point(|&step| step <= at) - 1;
+        let step_start = self.steps[step];
+        let step_end = self
+            .steps
+            .get(step + 1)
+            .copied()
+            .unwrap_or(self.expected.len());
+        let mut received = self.written[step_start..].to_vec();
+        received.extend_from_slice(buf);
+        format!(
+            "unexpected data at offset {} in step {}\n  expected: b\"{}\"\n  received: b\"{}\"",
+            at - step_start,
+            step,
+            self.expected[step_start..step_end].escape_ascii(),
+            received.escape_ascii()
+        )
+    }
+}
+
+impl AsyncRead for MockTube {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let Some((required, data)) = this.replies.get(this.reply_index) else {
+            if this.written.len() < this.expected.len() {
+                this.read_waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            return Poll::Ready(Ok(()));
+        };
+        if this.written.len() < *required {
+            this.read_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let data = &data[this.reply_pos..];
+        let len = data.len().min(buf.remaining());
+        buf.put_slice(&data[..len]);
+        this.reply_pos += len;
+        if this.reply_pos == this.replies[this.reply_index].1.len() {
+            this.reply_index += 1;
+            this.reply_pos = 0;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for MockTube {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let start = this.written.len();
+        if this.expected.get(start..start + buf.len()) != Some(buf) {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                this.deviation(buf),
+            )));
+        }
+        this.written.extend_from_slice(buf);
+        if let Some(waker) = this.read_waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -1,8 +1,302 @@
+#[cfg(feature = "process")]
 mod process;
+#[cfg(feature = "process")]
 pub use process::*;
 
+#[cfg(feature = "process")]
+mod suspend;
+
+#[cfg(feature = "process")]
+mod crash;
+#[cfg(feature = "process")]
+pub use crash::*;
+
+#[cfg(all(unix, feature = "process"))]
+mod trace;
+#[cfg(all(unix, feature = "process"))]
+pub use trace::*;
+
+#[cfg(all(target_os = "linux", feature = "criu"))]
+mod criu;
+#[cfg(all(target_os = "linux", feature = "criu"))]
+pub use criu::*;
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    feature = "sandbox"
+))]
+mod sandbox;
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    feature = "sandbox"
+))]
+pub use sandbox::*;
+
 mod tube;
 pub use tube::*;
 
+mod queue;
+
+mod parts;
+pub use parts::TubeParts;
+
+mod limit;
+
+mod eof;
+pub use eof::EofPolicy;
+
+mod traffic;
+pub use traffic::Direction;
+
+mod logging;
+pub use logging::{LogDecision, LogOptions};
+
+mod decode;
+pub use decode::{Decoder, DnsDecoder, JsonDecoder};
+
+mod identity;
+
+mod stats;
+pub use stats::TubeStats;
+
+mod info;
+pub use info::{TlsInfo, TubeInfo};
+
+mod adaptive;
+
+mod transcript;
+pub use transcript::*;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+
+#[cfg(feature = "zstd")]
+mod zstd_transcript;
+#[cfg(feature = "zstd")]
+pub use zstd_transcript::*;
+
+mod record;
+pub use record::ReplayTube;
+
+mod verify;
+
+#[cfg(feature = "pcap")]
+mod pcap;
+
+mod error;
+pub use error::*;
+
+mod timed;
+pub use timed::*;
+
+mod rtt;
+pub use rtt::RttStats;
+
+mod deadline;
+pub use deadline::*;
+
+#[cfg(all(unix, feature = "net"))]
+mod unix;
+#[cfg(all(unix, feature = "net"))]
+pub use unix::*;
+
+#[cfg(not(target_family = "wasm"))]
+mod stdio;
+#[cfg(not(target_family = "wasm"))]
+pub use stdio::*;
+
+#[cfg(unix)]
+mod fd;
+#[cfg(unix)]
+pub use fd::*;
+
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+mod vsock;
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+pub use vsock::*;
+
+#[cfg(all(windows, feature = "net"))]
+mod named_pipe;
+#[cfg(all(windows, feature = "net"))]
+pub use named_pipe::*;
+
+#[cfg(feature = "net")]
 mod listen;
+#[cfg(feature = "net")]
 pub use listen::*;
+
+#[cfg(feature = "net")]
+mod socket;
+#[cfg(feature = "net")]
+pub use socket::*;
+
+#[cfg(feature = "net")]
+mod udp;
+#[cfg(feature = "net")]
+pub use udp::*;
+
+#[cfg(feature = "net")]
+mod via;
+#[cfg(feature = "net")]
+pub use via::*;
+
+#[cfg(feature = "net")]
+mod serve;
+#[cfg(feature = "net")]
+pub use serve::*;
+
+#[cfg(feature = "net")]
+mod forward;
+#[cfg(feature = "net")]
+pub use forward::*;
+
+#[cfg(feature = "net")]
+mod intercept;
+#[cfg(feature = "net")]
+pub use intercept::*;
+
+#[cfg(feature = "tls")]
+mod tls;
+#[cfg(feature = "tls")]
+pub use tls::*;
+
+mod packing;
+
+mod text;
+
+mod expect;
+pub use expect::*;
+
+mod dialog;
+pub use dialog::*;
+
+mod copy;
+pub use copy::CopyOptions;
+
+mod close;
+pub use close::CloseOptions;
+
+mod recv_into;
+
+mod peek;
+
+mod scrape;
+pub use scrape::Scraped;
+
+mod tee;
+pub use tee::*;
+
+mod stage;
+pub use stage::*;
+
+mod packet;
+pub use packet::*;
+
+mod proxy;
+pub use proxy::*;
+
+mod telnet;
+pub use telnet::*;
+
+mod encoded;
+pub use encoded::*;
+
+mod mirror;
+pub use mirror::*;
+
+#[cfg(not(target_family = "wasm"))]
+mod multi;
+#[cfg(not(target_family = "wasm"))]
+pub use multi::*;
+
+mod broadcast;
+pub use broadcast::*;
+
+mod bruteforce;
+pub use bruteforce::*;
+
+mod select;
+pub use select::select_recv;
+
+mod generator;
+pub use generator::*;
+
+mod relay;
+pub use relay::*;
+
+mod throttle;
+pub use throttle::*;
+
+mod keepalive;
+pub use keepalive::KeepaliveTube;
+
+mod flusher;
+pub use flusher::FlusherTube;
+
+mod laggy;
+pub use laggy::*;
+
+mod fault;
+pub use fault::*;
+
+mod transform;
+pub use transform::*;
+
+mod mock;
+pub use mock::*;
+
+mod reconnect;
+pub use reconnect::*;
+
+mod boxed;
+pub use boxed::*;
+
+#[cfg(any(feature = "net", feature = "process"))]
+mod connect;
+
+#[cfg(feature = "screen")]
+mod screen;
+#[cfg(feature = "screen")]
+pub use screen::*;
+
+#[cfg(feature = "stream")]
+mod stream;
+
+#[cfg(feature = "codec")]
+mod codec;
+
+#[cfg(feature = "cancel")]
+mod cancel;
+#[cfg(feature = "cancel")]
+pub use cancel::*;
+
+#[cfg(feature = "futures-io")]
+mod compat;
+#[cfg(feature = "futures-io")]
+pub use compat::*;
+
+#[cfg(feature = "compress")]
+mod compress;
+#[cfg(feature = "compress")]
+pub use compress::*;
+
+#[cfg(feature = "hash")]
+mod hash;
+#[cfg(feature = "hash")]
+pub use hash::*;
+
+#[cfg(feature = "adb")]
+mod adb;
+#[cfg(feature = "adb")]
+pub use adb::*;
+
+#[cfg(feature = "qemu")]
+mod qemu;
+#[cfg(feature = "qemu")]
+mod qmp;
+#[cfg(feature = "qemu")]
+pub use qmp::*;
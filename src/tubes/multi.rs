@@ -0,0 +1,38 @@
+use std::io;
+#[cfg(unix)]
+use std::io::IsTerminal;
+
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use crate::utils::Multiplex;
+#[cfg(unix)]
+use crate::utils::RawMode;
+
+use super::Tube;
+
+/// Attach stdin and stdout to several tubes at once, similar to a terminal multiplexer.
+///
+/// Input is sent to the active tube and only the output of the active tube is shown, the output
+/// of the other tubes stays buffered until they are switched to. Press the `prefix` key (e.g.
+/// `0x01` for Ctrl-A) followed by:
+///
+/// - a digit to switch to the tube with that index,
+/// - `n` to switch to the next tube,
+/// - `d` to detach and return,
+/// - the prefix key again to send the prefix key itself.
+///
+/// On unix, the terminal is put into raw mode like [`Tube::interactive_raw`] while attached, so
+/// that the keys are sent as they are pressed. The tubes are flushed after every input.
+///
+/// Returns when stdin reaches EOF, the user detaches or all the tubes are closed.
+pub async fn interactive_multi<T>(tubes: &mut [Tube<T>], prefix: u8) -> io::Result<()>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    #[cfg(unix)]
+    let _raw_mode = match io::stdin().is_terminal() {
+        true => Some(RawMode::enable()?),
+        false => None,
+    };
+    Multiplex::new(tubes, prefix).await
+}
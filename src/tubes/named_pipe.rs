@@ -0,0 +1,85 @@
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    time::Duration,
+};
+
+use tokio::{
+    io::BufReader,
+    net::windows::named_pipe::{ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions},
+    time,
+};
+use windows_sys::Win32::Foundation::ERROR_PIPE_BUSY;
+
+use super::Tube;
+
+/// How long to wait before trying again when all instances of the pipe are busy.
+const PIPE_BUSY_DELAY: Duration = Duration::from_millis(50);
+
+/// A tube connected to a named pipe, created by [`Tube::named_pipe`].
+pub type NamedPipeTube = Tube<BufReader<NamedPipeClient>>;
+
+impl Tube<BufReader<NamedPipeClient>> {
+    /// Connect to a named pipe like `\\.\pipe\challenge`, trying again while all instances of
+    /// the pipe are busy. The timeout of the tube doesn't apply, so wrap it in
+    /// [`tokio::time::timeout`] if the server may never be free.
+    /// ```rust
+    /// use io_tubes::tubes::{NamedPipeListener, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn named_pipe() -> io::Result<()> {
+    ///     let name = r"\\.\pipe\io-tubes-named-pipe";
+    ///     let mut listener = NamedPipeListener::bind(name)?;
+    ///     let mut p = Tube::named_pipe(name).await?;
+    ///     let mut server = listener.accept().await?;
+    ///
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(server.recv_line().await?, b"Hello\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// named_pipe();
+    /// ```
+    pub async fn named_pipe(name: impl AsRef<OsStr>) -> io::Result<Self> {
+        loop {
+            match ClientOptions::new().open(name.as_ref()) {
+                Ok(client) => return Ok(Tube::new(client)),
+                Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+                    time::sleep(PIPE_BUSY_DELAY).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// A named pipe server that returns Tube when a client connects, like
+/// [`UnixListener`](super::UnixListener) on Unix.
+#[derive(Debug)]
+pub struct NamedPipeListener {
+    name: OsString,
+    /// The instance of the pipe that the next client connects to.
+    next: NamedPipeServer,
+}
+
+impl NamedPipeListener {
+    /// Create the first instance of the pipe. Fails if the pipe already exists, so that another
+    /// program can't serve the same name.
+    pub fn bind(name: impl AsRef<OsStr>) -> io::Result<Self> {
+        let name = name.as_ref().to_owned();
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&name)?;
+        Ok(Self { name, next })
+    }
+
+    /// Wait for a client to connect, and create the next instance of the pipe for the next
+    /// client.
+    pub async fn accept(&mut self) -> io::Result<Tube<BufReader<NamedPipeServer>>> {
+        self.next.connect().await?;
+        let next = ServerOptions::new().create(&self.name)?;
+        Ok(Tube::new(std::mem::replace(&mut self.next, next)))
+    }
+}
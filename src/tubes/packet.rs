@@ -0,0 +1,163 @@
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncReadExt, AsyncWrite};
+
+pub use crate::packing::Endian;
+use crate::{
+    packing::{pack, unpack},
+    utils::timeout,
+};
+
+use super::Tube;
+
+/// Options for the length-prefixed packets of [`Tube::send_packet_with`] and
+/// [`Tube::recv_packet_with`].
+#[derive(Debug, Clone)]
+pub struct PacketOptions {
+    /// The size of the length prefix in bytes, which must be 1, 2, 4 or 8.
+    pub prefix_size: usize,
+    /// The byte order of the length prefix.
+    pub endian: Endian,
+    /// The maximum length of the body. Larger packets are rejected with
+    /// [`InvalidData`](io::ErrorKind::InvalidData) when received, before reading the body.
+    pub max_len: usize,
+}
+
+impl Default for PacketOptions {
+    /// A little endian `u32` length prefix without a maximum length.
+    fn default() -> Self {
+        Self {
+            prefix_size: 4,
+            endian: Endian::Little,
+            max_len: usize::MAX,
+        }
+    }
+}
+
+impl PacketOptions {
+    fn check_prefix_size(&self) -> io::Result<()> {
+        match self.prefix_size {
+            1 | 2 | 4 | 8 => Ok(()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "packet prefix size must be 1, 2, 4 or 8",
+            )),
+        }
+    }
+
+    fn encode_len(&self, len: usize) -> io::Result<Vec<u8>> {
+        self.check_prefix_size()?;
+        let len = len as u64;
+        if len > self.max_len as u64 || (self.prefix_size < 8 && len >> (self.prefix_size * 8) != 0)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "packet is too long",
+            ));
+        }
+        Ok(pack(len, self.prefix_size, self.endian))
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Receive a packet prefixed by its length as a little endian `u32`.
+    pub async fn recv_packet(&mut self) -> io::Result<Vec<u8>> {
+        self.recv_packet_with(&PacketOptions::default()).await
+    }
+
+    /// Receive a length-prefixed packet with the supplied options, returning the body.
+    ///
+    /// Fails with [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) or
+    /// [`TimedOut`](io::ErrorKind::TimedOut) if the whole packet is not received.
+    /// ```rust
+    /// use io_tubes::tubes::{Endian, PacketOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_packet_with() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     let options = PacketOptions {
+    ///         prefix_size: 2,
+    ///         endian: Endian::Big,
+    ///         max_len: 0x100,
+    ///     };
+    ///
+    ///     p.send(b"\x00\x05Hello\x00\x05World").await?;
+    ///     assert_eq!(p.recv_packet_with(&options).await?, b"Hello");
+    ///     assert_eq!(p.recv_packet_with(&options).await?, b"World");
+    ///
+    ///     p.send_packet_with("too large", &PacketOptions { max_len: 4, ..options.clone() })
+    ///         .await
+    ///         .unwrap_err();
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_packet_with();
+    /// ```
+    pub async fn recv_packet_with(&mut self, options: &PacketOptions) -> io::Result<Vec<u8>> {
+        options.check_prefix_size()?;
+        let duration = self.recv_timeout();
+        let recv = async {
+            let mut prefix = vec![0; options.prefix_size];
+            self.read_exact(&mut prefix).await?;
+            let len = unpack(&prefix, options.endian);
+            if len > options.max_len as u64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("packet length {} exceeds the maximum", len),
+                ));
+            }
+            let mut body = vec![0; len as usize];
+            self.read_exact(&mut body).await?;
+            Ok(body)
+        };
+        timeout(duration, recv)
+            .await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "recv timed out")))
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Send the data prefixed by its length as a little endian `u32`.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn send_packet() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send_packet("Hello").await?;
+    ///     assert_eq!(p.recv(9).await?, b"\x05\x00\x00\x00Hello");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// send_packet();
+    /// ```
+    pub async fn send_packet(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
+        self.send_packet_with(data, &PacketOptions::default()).await
+    }
+
+    /// Send the data prefixed by its length with the supplied options.
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if the length exceeds the
+    /// maximum or doesn't fit in the prefix.
+    pub async fn send_packet_with(
+        &mut self,
+        data: impl AsRef<[u8]>,
+        options: &PacketOptions,
+    ) -> io::Result<()> {
+        let data = data.as_ref();
+        let mut packet = options.encode_len(data.len())?;
+        packet.extend_from_slice(data);
+        self.send(packet).await
+    }
+}
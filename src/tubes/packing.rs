@@ -0,0 +1,195 @@
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncReadExt, AsyncWrite};
+
+use crate::{
+    context,
+    packing::{pack, unpack, Endian},
+    utils::timeout,
+};
+
+use super::Tube;
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Receive an integer of `size` bytes, which must be at most 8.
+    ///
+    /// Fails with [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) or
+    /// [`TimedOut`](io::ErrorKind::TimedOut) if not enough bytes are received.
+    /// ```rust
+    /// use io_tubes::{packing::Endian, tubes::Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_unpacked() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send(b"\x10\xe0\xff\xf7\xff\x7f\x00\x00\x13\x37").await?;
+    ///     assert_eq!(p.recv_u64_le().await?, 0x7ffff7ffe010);
+    ///     assert_eq!(p.recv_unpacked(2, Endian::Big).await?, 0x1337);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_unpacked();
+    /// ```
+    pub async fn recv_unpacked(&mut self, size: usize, endian: Endian) -> io::Result<u64> {
+        if size > 8 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot unpack more than 8 bytes",
+            ));
+        }
+        let mut buf = [0; 8];
+        timeout(self.recv_timeout(), self.read_exact(&mut buf[..size]))
+            .await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "recv timed out")))?;
+        Ok(unpack(&buf[..size], endian))
+    }
+
+    /// Receive exactly as many bytes as the lengths add up to, split into one field per length,
+    /// e.g. to parse a fixed binary header from a leak.
+    ///
+    /// Fails with [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) or
+    /// [`TimedOut`](io::ErrorKind::TimedOut) if not enough bytes are received.
+    /// ```rust
+    /// use io_tubes::{packing::u64, tubes::Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_fields() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send(b"\x10\xe0\xff\xf7\xff\x7f\x00\x00canarycaOK\n").await?;
+    ///     let fields = p.recv_fields(&[8, 8, 2]).await?;
+    ///     assert_eq!(u64(&fields[0]), 0x7ffff7ffe010);
+    ///     assert_eq!(fields[1], b"canaryca");
+    ///     assert_eq!(fields[2], b"OK");
+    ///     assert_eq!(p.recv_line().await?, b"\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_fields();
+    /// ```
+    pub async fn recv_fields(&mut self, lens: &[usize]) -> io::Result<Vec<Vec<u8>>> {
+        let mut buf = vec![0; lens.iter().sum()];
+        timeout(self.recv_timeout(), self.read_exact(&mut buf))
+            .await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "recv timed out")))?;
+        let mut rest = &buf[..];
+        let fields = lens
+            .iter()
+            .map(|&len| {
+                let (field, tail) = rest.split_at(len);
+                rest = tail;
+                field.to_vec()
+            })
+            .collect();
+        Ok(fields)
+    }
+
+    /// Receive a word in the size and byte order of the global [context](crate::context).
+    pub async fn recv_uint(&mut self) -> io::Result<u64> {
+        let context = context::get();
+        self.recv_unpacked(context.word_size, context.endian).await
+    }
+
+    /// Receive a `u8`.
+    pub async fn recv_u8(&mut self) -> io::Result<u8> {
+        Ok(self.recv_unpacked(1, Endian::Little).await? as u8)
+    }
+
+    /// Receive a little endian `u16`.
+    pub async fn recv_u16_le(&mut self) -> io::Result<u16> {
+        Ok(self.recv_unpacked(2, Endian::Little).await? as u16)
+    }
+
+    /// Receive a little endian `u32`.
+    pub async fn recv_u32_le(&mut self) -> io::Result<u32> {
+        Ok(self.recv_unpacked(4, Endian::Little).await? as u32)
+    }
+
+    /// Receive a little endian `u64`.
+    pub async fn recv_u64_le(&mut self) -> io::Result<u64> {
+        self.recv_unpacked(8, Endian::Little).await
+    }
+
+    /// Receive a big endian `u16`.
+    pub async fn recv_u16_be(&mut self) -> io::Result<u16> {
+        Ok(self.recv_unpacked(2, Endian::Big).await? as u16)
+    }
+
+    /// Receive a big endian `u32`.
+    pub async fn recv_u32_be(&mut self) -> io::Result<u32> {
+        Ok(self.recv_unpacked(4, Endian::Big).await? as u32)
+    }
+
+    /// Receive a big endian `u64`.
+    pub async fn recv_u64_be(&mut self) -> io::Result<u64> {
+        self.recv_unpacked(8, Endian::Big).await
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Send the lowest `size` bytes of the value, which must be at most 8.
+    /// ```rust
+    /// use io_tubes::{packing::Endian, tubes::Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn send_packed() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send_p64(0x401136).await?;
+    ///     p.send_packed(0x1337, 2, Endian::Big).await?;
+    ///     assert_eq!(p.recv(10).await?, b"\x36\x11\x40\x00\x00\x00\x00\x00\x13\x37");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// send_packed();
+    /// ```
+    pub async fn send_packed(&mut self, value: u64, size: usize, endian: Endian) -> io::Result<()> {
+        if size > 8 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot pack more than 8 bytes",
+            ));
+        }
+        self.send(pack(value, size, endian)).await
+    }
+
+    /// Send the lowest bytes of the value as a word in the size and byte order of the global
+    /// [context](crate::context).
+    pub async fn send_uint(&mut self, value: u64) -> io::Result<()> {
+        let context = context::get();
+        self.send_packed(value, context.word_size, context.endian)
+            .await
+    }
+
+    /// Send a `u8`.
+    pub async fn send_p8(&mut self, value: u8) -> io::Result<()> {
+        self.send([value]).await
+    }
+
+    /// Send a `u16` in little endian.
+    pub async fn send_p16(&mut self, value: u16) -> io::Result<()> {
+        self.send(value.to_le_bytes()).await
+    }
+
+    /// Send a `u32` in little endian.
+    pub async fn send_p32(&mut self, value: u32) -> io::Result<()> {
+        self.send(value.to_le_bytes()).await
+    }
+
+    /// Send a `u64` in little endian.
+    pub async fn send_p64(&mut self, value: u64) -> io::Result<()> {
+        self.send(value.to_le_bytes()).await
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, BufReader, DuplexStream};
+#[cfg(feature = "net")]
+use tokio::net::TcpStream;
+#[cfg(all(unix, feature = "net"))]
+use tokio::net::UnixStream;
+
+#[cfg(feature = "process")]
+use super::ProcessTube;
+use super::Tube;
+
+/// Tubes are handed to other tasks and threads, so they must stay `Send`.
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<Tube<BufReader<DuplexStream>>>();
+    #[cfg(feature = "process")]
+    assert_send::<Tube<BufReader<ProcessTube>>>();
+    #[cfg(feature = "net")]
+    assert_send::<Tube<BufReader<TcpStream>>>();
+    #[cfg(all(unix, feature = "net"))]
+    assert_send::<Tube<BufReader<UnixStream>>>();
+};
+
+/// A tube taken apart by [`Tube::into_parts`], which is put back together by
+/// [`Tube::from_parts`].
+#[derive(Debug)]
+pub struct TubeParts<T> {
+    /// The underlying stream.
+    pub inner: T,
+    /// The data already read from the stream but not received yet.
+    pub buffered: Vec<u8>,
+    /// The data queued by [`Tube::send_nowait`] but not written yet.
+    pub queued: Vec<u8>,
+    /// See [`Tube::timeout`].
+    pub timeout: Duration,
+    /// See [`Tube::newline`].
+    pub newline: Vec<u8>,
+    /// See [`Tube::write_timeout`].
+    pub write_timeout: Option<Duration>,
+    /// See [`Tube::max_recv_size`].
+    pub max_recv_size: Option<usize>,
+}
+
+impl<T> Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Take the tube apart into the underlying stream, the data read from it but not received
+    /// yet, the data queued and the settings. Unlike [`Tube::into_inner`], no data is lost, so
+    /// a connection accepted in one task or runtime can be handed to a worker, e.g. after
+    /// converting the stream to a std one. The logging, recording and statistics end with the
+    /// tube.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, thread};
+    ///
+    /// #[tokio::main]
+    /// async fn into_parts() -> io::Result<()> {
+    ///     let (mut client, mut server) = Tube::pair();
+    ///     client.send("Hello\nWorld\n").await?;
+    ///     assert_eq!(server.recv_line().await?, b"Hello\n");
+    ///
+    ///     let parts = server.into_parts();
+    ///     assert_eq!(parts.buffered, b"World\n");
+    ///     let worker = thread::spawn(move || {
+    ///         let runtime = tokio::runtime::Builder::new_current_thread()
+    ///             .enable_all()
+    ///             .build()?;
+    ///         runtime.block_on(async {
+    ///             let mut server = Tube::from_parts(parts);
+    ///             server.recv_line().await
+    ///         })
+    ///     });
+    ///     assert_eq!(worker.join().unwrap()?, b"World\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// into_parts();
+    /// ```
+    pub fn into_parts(mut self) -> TubeParts<T> {
+        self.append_unread(&[]);
+        let buffered = self.take_unread();
+        let mut queued = self.send_queue.pending().to_vec();
+        queued.extend(self.corked.unwrap_or_default());
+        TubeParts {
+            buffered,
+            queued,
+            timeout: self.timeout,
+            newline: self.newline,
+            write_timeout: self.write_timeout,
+            max_recv_size: self.max_recv_size,
+            inner: self.inner.into_inner(),
+        }
+    }
+
+    /// Put a tube back together from the parts returned by [`Tube::into_parts`]. The buffered
+    /// data is received before anything else from the stream, and the queued data is written in
+    /// the background like data queued by [`Tube::send_nowait`].
+    pub fn from_parts(parts: TubeParts<T>) -> Self {
+        let mut tube = Self::new(parts.inner);
+        tube.timeout = parts.timeout;
+        tube.newline = parts.newline;
+        tube.write_timeout = parts.write_timeout;
+        tube.max_recv_size = parts.max_recv_size;
+        tube.unrecv(&parts.buffered);
+        tube.send_queue.push(&parts.queued);
+        tube
+    }
+}
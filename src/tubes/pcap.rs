@@ -0,0 +1,199 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::{Direction, TranscriptSink, Tube};
+
+/// Raw IPv4 packets without a link layer header.
+const LINKTYPE_RAW: u16 = 101;
+const IP_HEADER_LEN: usize = 20;
+const TCP_HEADER_LEN: usize = 20;
+/// The most data that fits in an IPv4 packet.
+const MAX_SEGMENT: usize = u16::MAX as usize - IP_HEADER_LEN - TCP_HEADER_LEN;
+
+/// The synthetic endpoints, the local side sends to the remote side.
+const LOCAL: ([u8; 4], u16) = ([10, 0, 0, 1], 40000);
+const REMOTE: ([u8; 4], u16) = ([10, 0, 0, 2], 1337);
+
+/// Writes the traffic as a pcapng capture of a single TCP connection.
+#[derive(Debug)]
+struct Pcapng {
+    writer: BufWriter<File>,
+    /// The time that the capture starts, in microseconds since the unix epoch.
+    epoch: u64,
+    /// The next sequence number sent by the local and the remote side.
+    seq: [u32; 2],
+}
+
+impl Pcapng {
+    /// Create the file and write the section header and the interface description.
+    fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        // Section header block.
+        let mut shb = Vec::new();
+        shb.extend_from_slice(&0x1a2b_3c4d_u32.to_le_bytes());
+        shb.extend_from_slice(&1_u16.to_le_bytes());
+        shb.extend_from_slice(&0_u16.to_le_bytes());
+        // The section length is not specified.
+        shb.extend_from_slice(&(-1_i64).to_le_bytes());
+        write_block(&mut writer, 0x0a0d_0d0a, &shb)?;
+
+        // Interface description block, the timestamps are in microseconds by default.
+        let mut idb = Vec::new();
+        idb.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        idb.extend_from_slice(&0_u16.to_le_bytes());
+        idb.extend_from_slice(&0_u32.to_le_bytes());
+        write_block(&mut writer, 1, &idb)?;
+
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        Ok(Self {
+            writer,
+            epoch,
+            seq: [1, 1],
+        })
+    }
+
+    /// Build an IPv4 packet carrying a TCP segment with the data, acknowledging everything that
+    /// the other side has sent.
+    fn packet(&mut self, direction: Direction, data: &[u8]) -> Vec<u8> {
+        let (side, (src, src_port), (dst, dst_port)) = match direction {
+            Direction::Send => (0, LOCAL, REMOTE),
+            Direction::Recv => (1, REMOTE, LOCAL),
+        };
+        let seq = self.seq[side];
+        let ack = self.seq[1 - side];
+        self.seq[side] = seq.wrapping_add(data.len() as u32);
+
+        let total_len = (IP_HEADER_LEN + TCP_HEADER_LEN + data.len()) as u16;
+        let mut packet = Vec::with_capacity(total_len as usize);
+        // Version 4 with 5 words of header, no DSCP.
+        packet.extend_from_slice(&[0x45, 0]);
+        packet.extend_from_slice(&total_len.to_be_bytes());
+        // No fragmentation, TTL 64, protocol TCP, the checksum is filled below.
+        packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+        packet.extend_from_slice(&src);
+        packet.extend_from_slice(&dst);
+        let ip_checksum = checksum(0, &packet);
+        packet[10..12].copy_from_slice(&ip_checksum.to_be_bytes());
+
+        let tcp_start = packet.len();
+        packet.extend_from_slice(&src_port.to_be_bytes());
+        packet.extend_from_slice(&dst_port.to_be_bytes());
+        packet.extend_from_slice(&seq.to_be_bytes());
+        packet.extend_from_slice(&ack.to_be_bytes());
+        // 5 words of header with PSH and ACK, the checksum is filled below.
+        packet.extend_from_slice(&[0x50, 0x18]);
+        packet.extend_from_slice(&u16::MAX.to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0, 0]);
+        packet.extend_from_slice(data);
+
+        let tcp_len = (packet.len() - tcp_start) as u16;
+        let mut pseudo_header = Vec::with_capacity(12);
+        pseudo_header.extend_from_slice(&src);
+        pseudo_header.extend_from_slice(&dst);
+        pseudo_header.extend_from_slice(&[0, 6]);
+        pseudo_header.extend_from_slice(&tcp_len.to_be_bytes());
+        let tcp_checksum = checksum(sum_words(0, &pseudo_header), &packet[tcp_start..]);
+        packet[tcp_start + 16..tcp_start + 18].copy_from_slice(&tcp_checksum.to_be_bytes());
+        packet
+    }
+}
+
+impl TranscriptSink for Pcapng {
+    /// Write the data as TCP segments sent at `elapsed` since the capture starts.
+    fn write_event(
+        &mut self,
+        _seq: u64,
+        elapsed: Duration,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let timestamp = self.epoch + elapsed.as_micros() as u64;
+        for segment in data.chunks(MAX_SEGMENT) {
+            let packet = self.packet(direction, segment);
+
+            // Enhanced packet block.
+            let mut epb = Vec::with_capacity(20 + packet.len());
+            epb.extend_from_slice(&0_u32.to_le_bytes());
+            epb.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+            epb.extend_from_slice(&(timestamp as u32).to_le_bytes());
+            epb.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            epb.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            epb.extend_from_slice(&packet);
+            write_block(&mut self.writer, 6, &epb)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Write a block with the type and the body, padded to 32 bits.
+fn write_block(writer: &mut impl Write, block_type: u32, body: &[u8]) -> io::Result<()> {
+    let padding = (4 - body.len() % 4) % 4;
+    let total_len = (12 + body.len() + padding) as u32;
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&total_len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&[0; 3][..padding])?;
+    writer.write_all(&total_len.to_le_bytes())
+}
+
+/// Add the data as big endian 16 bit words in one's complement.
+fn sum_words(mut sum: u32, data: &[u8]) -> u32 {
+    for word in data.chunks(2) {
+        let high = word[0] as u32;
+        let low = word.get(1).copied().unwrap_or(0) as u32;
+        sum += (high << 8) | low;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum
+}
+
+/// The internet checksum of the data, continuing from the partial sum.
+fn checksum(sum: u32, data: &[u8]) -> u16 {
+    !(sum_words(sum, data) as u16)
+}
+
+impl<T> Tube<T> {
+    /// Same as record, but writes the traffic as a pcapng capture that can be inspected in
+    /// Wireshark.
+    ///
+    /// The traffic is framed as a single TCP connection between `10.0.0.1:40000`, which is this
+    /// side, and `10.0.0.2:1337`, regardless of the actual transport. The timestamps are the times
+    /// that the data is sent or received by the tube.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn record_pcap() -> io::Result<()> {
+    ///     let path = std::env::temp_dir().join("io-tubes-record.pcapng");
+    ///
+    ///     let mut p = Tube::echo();
+    ///     p.record_pcap(&path)?;
+    ///     p.send("Hello\n").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///     p.stop_recording()?;
+    ///
+    ///     let capture = std::fs::read(&path)?;
+    ///     assert_eq!(capture[..4], [0x0a, 0x0d, 0x0d, 0x0a]);
+    ///     assert!(capture.windows(6).any(|w| w == b"Hello\n"));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// record_pcap();
+    /// ```
+    pub fn record_pcap(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.record_to(Pcapng::create(path.as_ref())?)
+    }
+}
@@ -0,0 +1,125 @@
+use std::{io, time::Duration};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt},
+    time,
+};
+
+use crate::utils::{timeout, Needle, RecvNeedle};
+
+use super::{limit::Limited, Tube, TubeError};
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Wait for `len` bytes and return them without consuming them, so that they are received
+    /// again by the next receive, e.g. to decide how to parse a message from its header. Fewer
+    /// bytes are returned if the timeout or EOF is reached first.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn peek() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send(b"\x16\x03\x01").await?;
+    ///     assert_eq!(p.peek(1).await?, b"\x16");
+    ///     assert_eq!(p.peek(3).await?, b"\x16\x03\x01");
+    ///     assert_eq!(p.recv(3).await?, b"\x16\x03\x01");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// peek();
+    /// ```
+    pub async fn peek(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let recv_timeout = self.recv_timeout();
+        let result = timeout(recv_timeout, async {
+            while buf.len() < len {
+                let data = self.fill_buf().await?;
+                if data.is_empty() {
+                    break;
+                }
+                let numb = data.len().min(len - buf.len());
+                buf.extend_from_slice(&data[..numb]);
+                self.consume(numb);
+            }
+            Ok::<_, io::Error>(())
+        })
+        .await;
+        self.unrecv(&buf);
+        result.unwrap_or(Ok(()))?;
+        Ok(buf)
+    }
+
+    /// Same as [`recv_until`](Tube::recv_until), but the data is not consumed and is received
+    /// again by the next receive, even if [`Tube::max_recv_size`] is exceeded.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn peek_until() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("GET / HTTP/1.1\r\n").await?;
+    ///     let request_line = p.peek_until("\r\n").await?;
+    ///     assert!(request_line.starts_with(b"GET "));
+    ///     assert_eq!(p.recv_line().await?, b"GET / HTTP/1.1\r\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// peek_until();
+    /// ```
+    pub async fn peek_until(&mut self, delims: impl Needle) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let recv_timeout = self.recv_timeout();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        let result = timeout(
+            recv_timeout,
+            RecvNeedle::new(&mut limited, &delims, &mut buf),
+        )
+        .await;
+        let exceeded = limited.exceeded();
+        self.unrecv(&buf);
+        match result {
+            Ok(Err(_)) if exceeded => Err(TubeError::TooLarge { partial: buf }.into()),
+            Ok(result) => result.map(|_| buf),
+            Err(_) => Ok(buf),
+        }
+    }
+
+    /// Returns true if data is already available or arrives within `duration`, without consuming
+    /// it, e.g. to check whether the service printed an error before going on. Returns false if
+    /// EOF is reached. The deadlines of the tube apply, but waiting in vain is not counted as a
+    /// timeout in the [report](crate::report).
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn can_recv() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     assert!(!p.can_recv(Duration::from_millis(50)).await?);
+    ///     p.send("Error: invalid size\n").await?;
+    ///     assert!(p.can_recv(Duration::from_millis(50)).await?);
+    ///     assert_eq!(p.recv_line().await?, b"Error: invalid size\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// can_recv();
+    /// ```
+    pub async fn can_recv(&mut self, duration: Duration) -> io::Result<bool> {
+        let duration = self.limit_to_deadline(duration);
+        match time::timeout(duration, self.fill_buf()).await {
+            Ok(data) => Ok(!data?.is_empty()),
+            Err(_) => Ok(false),
+        }
+    }
+}
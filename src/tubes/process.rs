@@ -2,20 +2,74 @@ use std::{
     ffi::OsStr,
     io::{self, Error, ErrorKind},
     pin::Pin,
-    process::Stdio,
-    task::{Context, Poll},
+    process::{ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
 };
 use tokio::{
-    io::{AsyncRead, AsyncWrite, ReadBuf},
-    process::{Child, ChildStdin, ChildStdout, Command},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf},
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command},
+    task::JoinHandle,
 };
 
+/// The most recent stderr output kept by [`ProcessTube::stderr`], where sanitizer reports are.
+const STDERR_CAPACITY: usize = 1 << 20;
+
+/// Options for [`ProcessTube::from_command_with`].
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOptions {
+    /// Suspend the process before it runs the program until [`ProcessTube::resume`] is called,
+    /// e.g. to attach a debugger or a monitor first.
+    ///
+    /// On Unix, the process is started by `/bin/sh`, which stops itself with `SIGSTOP` and then
+    /// executes the program in the same process. The environment and working directory of the
+    /// command are kept. It is only supported on Linux, Android and FreeBSD, where the spawn
+    /// waits for the stop. On Windows, the process is created with `CREATE_SUSPENDED`, replacing
+    /// the creation flags of the command.
+    pub start_suspended: bool,
+}
+
 /// A tube-like struct that allows easy access to spawned process's stdin and stdout.
+///
+/// If stderr is piped in the command, it is captured in the background, see
+/// [`ProcessTube::stderr`].
 #[derive(Debug)]
 pub struct ProcessTube {
     inner: Child,
-    stdin: ChildStdin,
+    /// Dropped on shutdown so that the process receives EOF.
+    stdin: Option<ChildStdin>,
     stdout: ChildStdout,
+    pub(super) stderr: Option<StderrCapture>,
+}
+
+/// Reads stderr in the background so that the process never blocks on it.
+#[derive(Debug)]
+pub(super) struct StderrCapture {
+    pub(super) output: Arc<Mutex<Vec<u8>>>,
+    /// Finishes when stderr reaches EOF, taken when it is waited.
+    pub(super) task: Option<JoinHandle<()>>,
+}
+
+impl StderrCapture {
+    fn spawn(mut stderr: ChildStderr) -> Self {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let captured = output.clone();
+        let task = tokio::spawn(async move {
+            let mut buf = [0; 4096];
+            while let Ok(len @ 1..) = stderr.read(&mut buf).await {
+                let mut output = captured.lock().unwrap_or_else(|err| err.into_inner());
+                output.extend_from_slice(&buf[..len]);
+                if output.len() > STDERR_CAPACITY {
+                    let excess = output.len() - STDERR_CAPACITY;
+                    output.drain(..excess);
+                }
+            }
+        });
+        Self {
+            output,
+            task: Some(task),
+        }
+    }
 }
 
 impl ProcessTube {
@@ -28,6 +82,67 @@ impl ProcessTube {
     pub fn from_command(cmd: Command) -> io::Result<Self> {
         cmd.try_into()
     }
+
+    /// Same as [`ProcessTube::from_command`], but spawn the process according to the options.
+    pub fn from_command_with(cmd: Command, options: &ProcessOptions) -> io::Result<Self> {
+        match options.start_suspended {
+            true => super::suspend::spawn_suspended(cmd),
+            false => Self::from_command(cmd),
+        }
+    }
+
+    /// Returns the OS-assigned process identifier, which is `None` once the process has exited
+    /// and been waited.
+    pub fn id(&self) -> Option<u32> {
+        self.inner.id()
+    }
+
+    /// Returns the exit status if the process has exited, without waiting.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.inner.try_wait()
+    }
+
+    /// Kill the process and wait for it to exit.
+    pub async fn kill(&mut self) -> io::Result<()> {
+        self.inner.kill().await
+    }
+
+    /// Close stdin and wait for the process to exit.
+    pub(super) async fn wait(&mut self) -> io::Result<ExitStatus> {
+        self.stdin = None;
+        self.inner.wait().await
+    }
+
+    /// The output of the process to stderr so far, if stderr is piped in the command. Only the
+    /// last 1 MiB is kept.
+    pub fn stderr(&self) -> Option<Vec<u8>> {
+        let capture = self.stderr.as_ref()?;
+        Some(
+            capture
+                .output
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .clone(),
+        )
+    }
+}
+
+/// Run the program of `command` with its arguments under `wrapper`, e.g. a tracer, keeping the
+/// environment and working directory of `command`.
+#[cfg(unix)]
+pub(super) fn wrap_command(command: &Command, mut wrapper: Command) -> Command {
+    let command = command.as_std();
+    wrapper.arg(command.get_program()).args(command.get_args());
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => wrapper.env(key, value),
+            None => wrapper.env_remove(key),
+        };
+    }
+    if let Some(dir) = command.get_current_dir() {
+        wrapper.current_dir(dir);
+    }
+    wrapper
 }
 
 impl TryFrom<Command> for ProcessTube {
@@ -52,17 +167,20 @@ impl TryFrom<Child> for ProcessTube {
         let stdout = inner.stdout.take().ok_or_else(|| {
             Error::new(ErrorKind::BrokenPipe, "Unable to extract stdout from child")
         })?;
+        let stderr = inner.stderr.take().map(StderrCapture::spawn);
         Ok(ProcessTube {
             inner,
-            stdin,
+            stdin: Some(stdin),
             stdout,
+            stderr,
         })
     }
 }
 
+/// The captured stderr is not given back.
 impl From<ProcessTube> for Child {
     fn from(mut tube: ProcessTube) -> Self {
-        tube.inner.stdin = Some(tube.stdin);
+        tube.inner.stdin = tube.stdin;
         tube.inner.stdout = Some(tube.stdout);
         tube.inner
     }
@@ -78,17 +196,38 @@ impl AsyncRead for ProcessTube {
     }
 }
 
+impl ProcessTube {
+    fn stdin(&mut self) -> io::Result<Pin<&mut ChildStdin>> {
+        match &mut self.stdin {
+            Some(stdin) => Ok(Pin::new(stdin)),
+            None => Err(Error::new(
+                ErrorKind::BrokenPipe,
+                "stdin is already shut down",
+            )),
+        }
+    }
+}
+
 impl AsyncWrite for ProcessTube {
     fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.get_mut().stdin).poll_write(cx, buf)
+        self.get_mut().stdin()?.poll_write(cx, buf)
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.get_mut().stdin).poll_flush(cx)
+        match &mut self.get_mut().stdin {
+            Some(stdin) => Pin::new(stdin).poll_flush(cx),
+            None => Poll::Ready(Ok(())),
+        }
     }
 
+    /// Close stdin so that the process receives EOF.
     fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.get_mut().stdin).poll_shutdown(cx)
+        let this = self.get_mut();
+        if let Some(stdin) = &mut this.stdin {
+            ready!(Pin::new(stdin).poll_shutdown(cx))?;
+            this.stdin = None;
+        }
+        Poll::Ready(Ok(()))
     }
 
     fn poll_write_vectored(
@@ -96,10 +235,12 @@ impl AsyncWrite for ProcessTube {
         cx: &mut Context,
         bufs: &[io::IoSlice],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.get_mut().stdin).poll_write_vectored(cx, bufs)
+        self.get_mut().stdin()?.poll_write_vectored(cx, bufs)
     }
 
     fn is_write_vectored(&self) -> bool {
-        self.stdin.is_write_vectored()
+        self.stdin
+            .as_ref()
+            .is_some_and(|stdin| stdin.is_write_vectored())
     }
 }
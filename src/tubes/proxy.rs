@@ -0,0 +1,307 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite};
+#[cfg(feature = "net")]
+use tokio::{io::BufReader, net::TcpStream};
+
+use crate::utils::timeout;
+
+#[cfg(feature = "net")]
+use super::Listener;
+use super::Tube;
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+/// The longest possible v1 header including CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// The version of the PROXY protocol header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyVersion {
+    /// The human readable header.
+    V1,
+    /// The binary header.
+    V2,
+}
+
+/// A PROXY protocol header, as prepended by load balancers like HAProxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyHeader {
+    /// The connection is not proxied or the addresses are unknown, e.g. health checks.
+    Local,
+    /// The connection is proxied on behalf of the source.
+    Proxy {
+        /// The address of the original client.
+        source: SocketAddr,
+        /// The address the client connected to.
+        destination: SocketAddr,
+    },
+}
+
+impl ProxyHeader {
+    /// Encode the header in the specified version.
+    ///
+    /// If the source and destination are from different address families, both are encoded as
+    /// IPv6 addresses.
+    pub fn encode(&self, version: ProxyVersion) -> Vec<u8> {
+        let addresses = match self {
+            ProxyHeader::Local => None,
+            ProxyHeader::Proxy {
+                source,
+                destination,
+            } => Some(same_family(*source, *destination)),
+        };
+        match version {
+            ProxyVersion::V1 => match addresses {
+                None => b"PROXY UNKNOWN\r\n".to_vec(),
+                Some((source, destination)) => format!(
+                    "PROXY {} {} {} {} {}\r\n",
+                    if source.is_ipv4() { "TCP4" } else { "TCP6" },
+                    source.ip(),
+                    destination.ip(),
+                    source.port(),
+                    destination.port()
+                )
+                .into_bytes(),
+            },
+            ProxyVersion::V2 => {
+                let mut header = V2_SIGNATURE.to_vec();
+                match addresses {
+                    None => header.extend_from_slice(&[0x20, 0x00, 0, 0]),
+                    Some((source, destination)) => {
+                        let (family, len) = if source.is_ipv4() {
+                            (0x11, 12u16)
+                        } else {
+                            (0x21, 36u16)
+                        };
+                        header.extend_from_slice(&[0x21, family]);
+                        header.extend_from_slice(&len.to_be_bytes());
+                        for addr in [source, destination] {
+                            match addr.ip() {
+                                IpAddr::V4(ip) => header.extend_from_slice(&ip.octets()),
+                                IpAddr::V6(ip) => header.extend_from_slice(&ip.octets()),
+                            }
+                        }
+                        header.extend_from_slice(&source.port().to_be_bytes());
+                        header.extend_from_slice(&destination.port().to_be_bytes());
+                    }
+                }
+                header
+            }
+        }
+    }
+
+    fn parse_v1(line: &[u8]) -> io::Result<Self> {
+        let line = std::str::from_utf8(line)
+            .ok()
+            .and_then(|line| line.strip_suffix("\r\n"))
+            .ok_or_else(invalid_header)?;
+        let fields: Vec<&str> = line.split(' ').skip(1).collect();
+        match fields[..] {
+            ["UNKNOWN", ..] => Ok(ProxyHeader::Local),
+            [proto @ ("TCP4" | "TCP6"), source, destination, source_port, destination_port] => {
+                let parse = |ip: &str, port: &str| -> io::Result<SocketAddr> {
+                    let ip: IpAddr = ip.parse().map_err(|_| invalid_header())?;
+                    if ip.is_ipv4() != (proto == "TCP4") {
+                        return Err(invalid_header());
+                    }
+                    Ok(SocketAddr::new(
+                        ip,
+                        port.parse().map_err(|_| invalid_header())?,
+                    ))
+                };
+                Ok(ProxyHeader::Proxy {
+                    source: parse(source, source_port)?,
+                    destination: parse(destination, destination_port)?,
+                })
+            }
+            _ => Err(invalid_header()),
+        }
+    }
+
+    fn parse_v2(ver_cmd: u8, family: u8, addresses: &[u8]) -> io::Result<Self> {
+        if ver_cmd >> 4 != 2 {
+            return Err(invalid_header());
+        }
+        match ver_cmd & 0xF {
+            0 => return Ok(ProxyHeader::Local),
+            1 => {}
+            _ => return Err(invalid_header()),
+        }
+        let port = |bytes: &[u8]| u16::from_be_bytes([bytes[0], bytes[1]]);
+        match family >> 4 {
+            1 if addresses.len() >= 12 => {
+                let ip =
+                    |bytes: &[u8]| IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).unwrap()));
+                Ok(ProxyHeader::Proxy {
+                    source: SocketAddr::new(ip(&addresses[0..4]), port(&addresses[8..10])),
+                    destination: SocketAddr::new(ip(&addresses[4..8]), port(&addresses[10..12])),
+                })
+            }
+            2 if addresses.len() >= 36 => {
+                let ip =
+                    |bytes: &[u8]| IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).unwrap()));
+                Ok(ProxyHeader::Proxy {
+                    source: SocketAddr::new(ip(&addresses[0..16]), port(&addresses[32..34])),
+                    destination: SocketAddr::new(ip(&addresses[16..32]), port(&addresses[34..36])),
+                })
+            }
+            // Unix sockets and unspecified families carry no usable address.
+            0 | 3 => Ok(ProxyHeader::Local),
+            _ => Err(invalid_header()),
+        }
+    }
+}
+
+fn same_family(source: SocketAddr, destination: SocketAddr) -> (SocketAddr, SocketAddr) {
+    if source.is_ipv4() == destination.is_ipv4() {
+        return (source, destination);
+    }
+    let to_v6 = |addr: SocketAddr| match addr.ip() {
+        IpAddr::V4(ip) => SocketAddr::new(IpAddr::V6(ip.to_ipv6_mapped()), addr.port()),
+        IpAddr::V6(_) => addr,
+    };
+    (to_v6(source), to_v6(destination))
+}
+
+fn invalid_header() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid PROXY protocol header")
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Receive the PROXY protocol header (v1 or v2) if the data starts with one.
+    ///
+    /// Returns `None` and keeps the data intact if there is no header, so this can be called
+    /// unconditionally before the first [`Tube::recv_until`].
+    /// ```rust
+    /// use io_tubes::tubes::{ProxyHeader, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_proxy_header() -> io::Result<()> {
+    ///     let (mut p, mut server) = Tube::pair();
+    ///
+    ///     server
+    ///         .send("PROXY TCP4 10.0.0.1 10.0.0.2 4444 1337\r\nName: ")
+    ///         .await?;
+    ///     let header = p.recv_proxy_header().await?;
+    ///     assert_eq!(
+    ///         header,
+    ///         Some(ProxyHeader::Proxy {
+    ///             source: "10.0.0.1:4444".parse().unwrap(),
+    ///             destination: "10.0.0.2:1337".parse().unwrap(),
+    ///         })
+    ///     );
+    ///     assert_eq!(p.recv_until(": ").await?, b"Name: ");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_proxy_header();
+    /// ```
+    pub async fn recv_proxy_header(&mut self) -> io::Result<Option<ProxyHeader>> {
+        timeout(self.recv_timeout(), self.recv_proxy_header_inner())
+            .await
+            .unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out receiving PROXY protocol header",
+                ))
+            })
+    }
+
+    async fn recv_proxy_header_inner(&mut self) -> io::Result<Option<ProxyHeader>> {
+        let mut prefix = Vec::new();
+        let signature = loop {
+            if prefix == V1_PREFIX {
+                break V1_PREFIX;
+            }
+            if prefix.len() == V2_SIGNATURE.len() {
+                break V2_SIGNATURE;
+            }
+            let byte = match self.fill_buf().await?.first() {
+                Some(&byte) => byte,
+                None => {
+                    self.unrecv(&prefix);
+                    return Ok(None);
+                }
+            };
+            self.consume(1);
+            prefix.push(byte);
+            if !V1_PREFIX.starts_with(&prefix) && !V2_SIGNATURE.starts_with(&prefix) {
+                self.unrecv(&prefix);
+                return Ok(None);
+            }
+        };
+
+        if signature == V1_PREFIX {
+            let mut line = prefix;
+            (&mut *self)
+                .take((V1_MAX_LEN - line.len()) as u64)
+                .read_until(b'\n', &mut line)
+                .await?;
+            return ProxyHeader::parse_v1(&line).map(Some);
+        }
+
+        let mut fixed = [0; 4];
+        self.read_exact(&mut fixed).await?;
+        let mut addresses = vec![0; u16::from_be_bytes([fixed[2], fixed[3]]) as usize];
+        self.read_exact(&mut addresses).await?;
+        ProxyHeader::parse_v2(fixed[0], fixed[1], &addresses).map(Some)
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Send the PROXY protocol header, e.g. to connect through a service that expects one.
+    pub async fn send_proxy_header(
+        &mut self,
+        header: &ProxyHeader,
+        version: ProxyVersion,
+    ) -> io::Result<()> {
+        self.send(header.encode(version)).await
+    }
+}
+
+#[cfg(feature = "net")]
+impl Listener {
+    /// Accepts a connection and receives the PROXY protocol header if there is one.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, ProxyHeader, ProxyVersion, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn accept_proxied() -> io::Result<()> {
+    ///     let l = Listener::listen().await?;
+    ///     let mut p = Tube::remote(("127.0.0.1", l.port()?)).await?;
+    ///     let header = ProxyHeader::Proxy {
+    ///         source: "[::1]:4444".parse().unwrap(),
+    ///         destination: "[::1]:1337".parse().unwrap(),
+    ///     };
+    ///     p.send_proxy_header(&header, ProxyVersion::V2).await?;
+    ///     p.send("Hello").await?;
+    ///
+    ///     let (mut server, received) = l.accept_proxied().await?;
+    ///     assert_eq!(received, Some(header));
+    ///     assert_eq!(server.recv_until("Hello").await?, b"Hello");
+    ///     Ok(())
+    /// }
+    ///
+    /// accept_proxied();
+    /// ```
+    pub async fn accept_proxied(
+        &self,
+    ) -> io::Result<(Tube<BufReader<TcpStream>>, Option<ProxyHeader>)> {
+        let mut tube = self.accept().await?;
+        let header = tube.recv_proxy_header().await?;
+        Ok((tube, header))
+    }
+}
@@ -0,0 +1,134 @@
+use std::{ffi::OsStr, io};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncWrite, BufReader},
+    process::Command,
+};
+
+use crate::utils::{base64_encode, Needle};
+
+use super::{ProcessTube, Tube};
+
+/// The number of base64 characters sent in each line when uploading, which is well below the
+/// 4096 bytes limit of a canonical mode terminal line.
+const UPLOAD_CHUNK_SIZE: usize = 1024;
+
+impl Tube<BufReader<ProcessTube>> {
+    /// Boot a VM with the QEMU command line, e.g. `qemu-system-x86_64` with the arguments from
+    /// the `run.sh` of a kernel challenge. The serial console must be on stdio, e.g. with
+    /// `-nographic` or `-serial stdio`.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn qemu() -> io::Result<()> {
+    ///     let mut p = Tube::qemu(
+    ///         "qemu-system-x86_64",
+    ///         [
+    ///             "-kernel", "bzImage", "-initrd", "rootfs.cpio", "-nographic",
+    ///             "-append", "console=ttyS0",
+    ///         ],
+    ///     )?;
+    ///
+    ///     p.login("ctf", None, "$ ").await?;
+    ///     p.upload(std::fs::read("exploit")?, "/tmp/exploit", "$ ").await?;
+    ///     p.send_line("/tmp/exploit").await?;
+    ///     p.interactive().await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// qemu();
+    /// ```
+    pub fn qemu<I, S>(program: impl AsRef<OsStr>, args: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(program);
+        command.args(args).kill_on_drop(true);
+        Ok(Self::new(ProcessTube::from_command(command)?))
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Wait for the login prompt of the console and log in, then wait for the shell prompt.
+    ///
+    /// The password is only sent if it is supplied, as challenge VMs usually don't have one.
+    pub async fn login(
+        &mut self,
+        user: &str,
+        password: Option<&str>,
+        prompt: impl Needle,
+    ) -> io::Result<()> {
+        self.recv_until_checked("login: ").await?;
+        self.send_line(user).await?;
+        if let Some(password) = password {
+            self.recv_until_checked("assword: ").await?;
+            self.send_line(password).await?;
+        }
+        self.recv_until_checked(prompt).await?;
+        Ok(())
+    }
+
+    /// Upload the file by sending it as base64 through the shell, then make it executable.
+    ///
+    /// The shell must have `base64` (e.g. from busybox) and be waiting at the prompt, which is
+    /// awaited after each command so the terminal is never overrun.
+    /// ```rust
+    /// use io_tubes::tubes::{ProcessTube, Tube};
+    /// use std::io;
+    /// use tokio::process::Command;
+    ///
+    /// #[tokio::main]
+    /// async fn upload() -> io::Result<()> {
+    ///     let path = std::env::temp_dir().join("io-tubes-uploaded");
+    ///
+    ///     // A shell that prints a prompt after each command like a console.
+    ///     let mut shell = Command::new("/bin/sh");
+    ///     shell.args(["-c", r#"while read -r line; do eval "$line"; printf '$ '; done"#]);
+    ///     let mut p = Tube::new(ProcessTube::from_command(shell)?);
+    ///     p.upload(b"\x7fELF\x00\xff", path.to_str().unwrap(), "$ ").await?;
+    ///     assert_eq!(std::fs::read(&path)?, b"\x7fELF\x00\xff");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// upload();
+    /// ```
+    pub async fn upload(
+        &mut self,
+        data: impl AsRef<[u8]>,
+        path: &str,
+        prompt: impl Needle,
+    ) -> io::Result<()> {
+        let prompt = &prompt;
+        let encoded = base64_encode(data.as_ref());
+        let staging = format!("{}.b64", path);
+        self.run_command(&format!("rm -f '{}'", staging), prompt)
+            .await?;
+        for chunk in encoded.as_bytes().chunks(UPLOAD_CHUNK_SIZE) {
+            let chunk = std::str::from_utf8(chunk).expect("base64 is ASCII");
+            self.run_command(&format!("echo '{}' >> '{}'", chunk, staging), prompt)
+                .await?;
+        }
+        self.run_command(
+            &format!(
+                "base64 -d '{0}' > '{1}' && rm '{0}' && chmod +x '{1}'",
+                staging, path
+            ),
+            prompt,
+        )
+        .await
+    }
+
+    async fn run_command(&mut self, command: &str, prompt: &impl Needle) -> io::Result<()> {
+        self.send_line(command).await?;
+        self.recv_until_checked(prompt).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use std::{ffi::OsStr, io, time::Duration};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncWrite, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+    process::Command,
+    time,
+};
+
+use super::{ProcessTube, Tube};
+
+/// How long to wait for QEMU to create the QMP socket after it is started.
+const QMP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A connection to the QEMU Machine Protocol (QMP), e.g. to snapshot and restore the VM between
+/// exploit attempts.
+///
+/// The responses are returned as raw JSON text, as only a few commands are useful here.
+#[derive(Debug)]
+pub struct Qmp<T> {
+    /// The tube to the QMP socket.
+    pub tube: Tube<T>,
+}
+
+impl Qmp<BufReader<TcpStream>> {
+    /// Connect to the QMP server started with e.g. `-qmp tcp:127.0.0.1:4444,server=on,wait=off`.
+    pub async fn remote(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::new(Tube::remote(addr).await?).await
+    }
+}
+
+#[cfg(unix)]
+impl Qmp<BufReader<tokio::net::UnixStream>> {
+    /// Connect to the QMP server started with e.g. `-qmp unix:/tmp/qmp.sock,server=on,wait=off`.
+    pub async fn unix(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        Self::new(Tube::unix(path).await?).await
+    }
+}
+
+impl<T> Qmp<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Wait for the greeting and negotiate the capabilities, after which commands can be sent.
+    pub async fn new(tube: Tube<T>) -> io::Result<Self> {
+        let mut qmp = Self { tube };
+        let greeting = qmp.tube.recv_line_checked().await?;
+        if !greeting.starts_with(b"{\"QMP\"") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a QMP server",
+            ));
+        }
+        qmp.execute("qmp_capabilities", None).await?;
+        Ok(qmp)
+    }
+
+    /// Execute the command with the arguments as a JSON object, returning the JSON value of the
+    /// result. Asynchronous events received meanwhile are skipped.
+    pub async fn execute(&mut self, command: &str, arguments: Option<&str>) -> io::Result<String> {
+        let request = match arguments {
+            Some(arguments) => format!(
+                "{{\"execute\": {}, \"arguments\": {}}}",
+                json_string(command),
+                arguments
+            ),
+            None => format!("{{\"execute\": {}}}", json_string(command)),
+        };
+        self.tube.send_line(request).await?;
+        loop {
+            let line = self.tube.recv_line_checked().await?;
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if let Some(value) = strip_member(line, "return") {
+                return Ok(value.to_string());
+            }
+            if let Some(error) = strip_member(line, "error") {
+                return Err(io::Error::other(format!("QMP error: {}", error)));
+            }
+        }
+    }
+
+    /// Run the command of the human monitor, e.g. `info registers`, returning its output.
+    pub async fn human_command(&mut self, command: &str) -> io::Result<String> {
+        let arguments = format!("{{\"command-line\": {}}}", json_string(command));
+        let output = self
+            .execute("human-monitor-command", Some(&arguments))
+            .await?;
+        Ok(parse_json_string(&output).unwrap_or(output))
+    }
+
+    /// Save a snapshot of the VM with the name. The disk must support snapshots, e.g. qcow2.
+    pub async fn savevm(&mut self, name: &str) -> io::Result<()> {
+        self.human_command_checked(&format!("savevm {}", name))
+            .await
+    }
+
+    /// Restore the VM to the snapshot with the name.
+    pub async fn loadvm(&mut self, name: &str) -> io::Result<()> {
+        self.human_command_checked(&format!("loadvm {}", name))
+            .await
+    }
+
+    /// Terminate QEMU immediately.
+    pub async fn quit(&mut self) -> io::Result<()> {
+        self.execute("quit", None).await?;
+        Ok(())
+    }
+
+    /// Human monitor commands report errors as output, so any output is treated as an error for
+    /// commands that are silent on success.
+    async fn human_command_checked(&mut self, command: &str) -> io::Result<()> {
+        let output = self.human_command(command).await?;
+        if output.trim().is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::other(output.trim().to_string()))
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Tube<BufReader<ProcessTube>> {
+    /// Same as [`Tube::qemu`], but also open the QMP socket at the path.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn qemu_with_qmp() -> io::Result<()> {
+    ///     let (mut p, mut qmp) = Tube::qemu_with_qmp(
+    ///         "qemu-system-x86_64",
+    ///         ["-hda", "disk.qcow2", "-nographic"],
+    ///         "/tmp/qmp.sock",
+    ///     )
+    ///     .await?;
+    ///
+    ///     p.login("root", None, "# ").await?;
+    ///     qmp.savevm("booted").await?;
+    ///     for _ in 0..10 {
+    ///         p.send_line("/exploit").await?;
+    ///         if p.recv_until("flag{").await?.ends_with(b"flag{") {
+    ///             break;
+    ///         }
+    ///         qmp.loadvm("booted").await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// qemu_with_qmp();
+    /// ```
+    pub async fn qemu_with_qmp<I, S>(
+        program: impl AsRef<OsStr>,
+        args: I,
+        qmp_path: impl AsRef<std::path::Path>,
+    ) -> io::Result<(Self, Qmp<BufReader<tokio::net::UnixStream>>)>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let qmp_path = qmp_path.as_ref();
+        let _ = std::fs::remove_file(qmp_path);
+        let mut qmp_arg = std::ffi::OsString::from("unix:");
+        qmp_arg.push(qmp_path);
+        qmp_arg.push(",server=on,wait=off");
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .arg("-qmp")
+            .arg(qmp_arg)
+            .kill_on_drop(true);
+        let tube = Self::new(ProcessTube::from_command(command)?);
+
+        let connect = async {
+            loop {
+                match Qmp::unix(qmp_path).await {
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+                        ) =>
+                    {
+                        time::sleep(Duration::from_millis(50)).await
+                    }
+                    result => return result,
+                }
+            }
+        };
+        let qmp = time::timeout(QMP_CONNECT_TIMEOUT, connect)
+            .await
+            .unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out connecting to QMP",
+                ))
+            })?;
+        Ok((tube, qmp))
+    }
+}
+
+/// Returns the value of the member if the JSON object has only that member.
+fn strip_member<'a>(object: &'a str, name: &str) -> Option<&'a str> {
+    let rest = object.strip_prefix('{')?.trim_start();
+    let rest = rest.strip_prefix(&format!("\"{}\"", name))?.trim_start();
+    let rest = rest.strip_prefix(':')?;
+    Some(rest.strip_suffix('}')?.trim())
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn parse_json_string(value: &str) -> Option<String> {
+    let mut chars = value.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut parsed = String::new();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            parsed.push(c);
+            continue;
+        }
+        parsed.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'u' => {
+                let code: String = chars.by_ref().take(4).collect();
+                char::from_u32(u32::from_str_radix(&code, 16).ok()?).unwrap_or('\u{fffd}')
+            }
+            c => c,
+        });
+    }
+    Some(parsed)
+}
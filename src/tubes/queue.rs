@@ -0,0 +1,54 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Data queued by [`Tube::send_nowait`](super::Tube::send_nowait) waiting to be written.
+#[derive(Debug, Default)]
+pub(super) struct SendQueue {
+    buf: Vec<u8>,
+    pos: usize,
+    /// An error occurred while writing the queue in the background, reported by the next send.
+    pub(super) error: Option<io::Error>,
+    /// The bytes taken but not written yet by [`Tube::spawn_flusher`](super::Tube::spawn_flusher).
+    pub(super) flusher: Option<Arc<AtomicUsize>>,
+}
+
+impl SendQueue {
+    pub(super) fn len(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// The number of bytes queued, including those waiting in the flusher.
+    pub(super) fn queued(&self) -> usize {
+        let flusher = self.flusher.as_ref();
+        self.len() + flusher.map_or(0, |pending| pending.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(super) fn push(&mut self, data: &[u8]) {
+        if self.pos > 0 && self.pos * 2 >= self.buf.len() {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    pub(super) fn pending(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    pub(super) fn advance(&mut self, amt: usize) {
+        self.pos += amt;
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    fmt,
+    future::Future,
+    io, mem,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use log::debug;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+type Connect<S> = Pin<Box<dyn Future<Output = io::Result<S>> + Send>>;
+
+/// The consecutive failed connections after which the failure is returned.
+const DEFAULT_MAX_FAILURES: u32 = 3;
+
+enum State<S> {
+    Disconnected,
+    Connecting(Connect<S>),
+    /// Sending the unanswered data again from the position.
+    Resending(S, usize),
+    Connected(S),
+}
+
+/// Re-establishes the connection with the factory whenever it is closed or reset, e.g. for brute
+/// force loops where every attempt kills the connection. The first connection is made when the
+/// tube is first used.
+///
+/// A login or banner sequence is replayed by doing it in the factory, which can return a
+/// [`Tube`](super::Tube). The data sent since the last data was received is sent again on the
+/// new connection, since the peer may not have seen it. An error of the factory is returned as
+/// is, while the connection is given up after 3 consecutive connections fail without receiving
+/// anything.
+/// ```rust
+/// use io_tubes::tubes::{Listener, ReconnectTube, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn reconnect() -> io::Result<()> {
+///     // Checks a single password per connection.
+///     let server = Listener::bind("127.0.0.1:0").await?.serve(|mut p| async move {
+///         p.send("Password: ").await?;
+///         match &p.recv_line().await?[..] {
+///             b"42\n" => p.send_line("flag").await,
+///             _ => p.send_line("wrong").await,
+///         }
+///     })?;
+///
+///     let port = server.port();
+///     let mut p = Tube::new(ReconnectTube::new(move || async move {
+///         let mut p = Tube::remote(("127.0.0.1", port)).await?;
+///         p.recv_until("Password: ").await?;
+///         Ok(p)
+///     }));
+///     for guess in 40.. {
+///         p.send_line(guess.to_string()).await?;
+///         if p.recv_line().await? == b"flag\n" {
+///             break;
+///         }
+///     }
+///     assert_eq!(p.inner.get_ref().reconnects(), 2);
+///
+///     Ok(())
+/// }
+///
+/// reconnect();
+/// ```
+pub struct ReconnectTube<S> {
+    connect: Box<dyn FnMut() -> Connect<S> + Send>,
+    state: State<S>,
+    /// The data sent since the last data was received, if it is sent again.
+    unanswered: Option<Vec<u8>>,
+    connections: u64,
+    failures: u32,
+    max_failures: u32,
+}
+
+impl<S> ReconnectTube<S> {
+    /// Call `connect` for every connection.
+    pub fn new<F, Fut>(mut connect: F) -> Self
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<S>> + Send + 'static,
+    {
+        Self {
+            connect: Box::new(move || Box::pin(connect())),
+            state: State::Disconnected,
+            unanswered: Some(Vec::new()),
+            connections: 0,
+            failures: 0,
+            max_failures: DEFAULT_MAX_FAILURES,
+        }
+    }
+
+    /// Don't send the unanswered data again on the new connection, e.g. if it is not idempotent.
+    pub fn no_resend(mut self) -> Self {
+        self.unanswered = None;
+        self
+    }
+
+    /// Return the failure after this many consecutive connections fail without receiving
+    /// anything, which is 3 by default.
+    pub fn max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    /// The number of times the connection is re-established.
+    pub fn reconnects(&self) -> u64 {
+        self.connections.saturating_sub(1)
+    }
+
+    /// Gets a mutable reference to the current connection, if connected.
+    pub fn get_mut(&mut self) -> Option<&mut S> {
+        match &mut self.state {
+            State::Connected(stream) => Some(stream),
+            _ => None,
+        }
+    }
+
+    /// Drop the connection after it fails, and return the failure if it fails too many times.
+    fn disconnected(&mut self, err: io::Error) -> io::Result<()> {
+        self.state = State::Disconnected;
+        self.failures += 1;
+        if self.failures > self.max_failures {
+            self.failures = 0;
+            return Err(err);
+        }
+        debug!(target: "ReconnectTube", "Reconnecting: {}", err);
+        Ok(())
+    }
+}
+
+impl<S> ReconnectTube<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    fn poll_connected(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&mut S>> {
+        loop {
+            match &mut self.state {
+                State::Disconnected => self.state = State::Connecting((self.connect)()),
+                State::Connecting(connect) => {
+                    let stream = match ready!(connect.as_mut().poll(cx)) {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            self.state = State::Disconnected;
+                            return Poll::Ready(Err(err));
+                        }
+                    };
+                    self.connections += 1;
+                    self.state = State::Resending(stream, 0);
+                }
+                State::Resending(stream, pos) => {
+                    let unanswered = self.unanswered.as_deref().unwrap_or_default();
+                    if *pos == unanswered.len() {
+                        let State::Resending(stream, _) =
+                            mem::replace(&mut self.state, State::Disconnected)
+                        else {
+                            unreachable!()
+                        };
+                        self.state = State::Connected(stream);
+                        continue;
+                    }
+                    match ready!(Pin::new(stream).poll_write(cx, &unanswered[*pos..])) {
+                        Ok(0) => self.disconnected(io::ErrorKind::WriteZero.into())?,
+                        Ok(numb) => *pos += numb,
+                        Err(err) if is_disconnect(&err) => self.disconnected(err)?,
+                        Err(err) => return Poll::Ready(Err(err)),
+                    }
+                }
+                State::Connected(_) => break,
+            }
+        }
+        match &mut self.state {
+            State::Connected(stream) => Poll::Ready(Ok(stream)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+impl<S> fmt::Debug for ReconnectTube<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectTube")
+            .field("connections", &self.connections)
+            .field("failures", &self.failures)
+            .field("max_failures", &self.max_failures)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> AsyncRead for ReconnectTube<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            let stream = ready!(this.poll_connected(cx))?;
+            let filled = buf.filled().len();
+            let err = match ready!(Pin::new(stream).poll_read(cx, buf)) {
+                Ok(()) if buf.filled().len() > filled => {
+                    this.failures = 0;
+                    if let Some(unanswered) = &mut this.unanswered {
+                        unanswered.clear();
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+                Ok(()) => io::ErrorKind::UnexpectedEof.into(),
+                Err(err) if is_disconnect(&err) => err,
+                Err(err) => return Poll::Ready(Err(err)),
+            };
+            if this.disconnected(err).is_err() {
+                // Give up with EOF like the connection would.
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+impl<S> AsyncWrite for ReconnectTube<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            let stream = ready!(this.poll_connected(cx))?;
+            match ready!(Pin::new(stream).poll_write(cx, buf)) {
+                Ok(numb) => {
+                    if let Some(unanswered) = &mut this.unanswered {
+                        unanswered.extend_from_slice(&buf[..numb]);
+                    }
+                    return Poll::Ready(Ok(numb));
+                }
+                Err(err) if is_disconnect(&err) => this.disconnected(err)?,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().state {
+            State::Connected(stream) => Pin::new(stream).poll_flush(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().state {
+            State::Connected(stream) => Pin::new(stream).poll_shutdown(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+}
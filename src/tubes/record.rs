@@ -0,0 +1,275 @@
+use std::{
+    fmt, io,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    time::Instant,
+};
+
+use log::debug;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader, ReadBuf};
+
+use super::{
+    traffic::Direction, transcript::parse_transcript, TranscriptEvent, TranscriptFile,
+    TranscriptSink, Tube,
+};
+
+/// Stores the traffic into the sink given to [`Tube::record_to`].
+#[derive(Clone)]
+pub(super) struct Recorder {
+    /// Shared by the halves of a split tube, so that both directions go into one transcript.
+    sink: Arc<Mutex<Box<dyn TranscriptSink>>>,
+    start: Instant,
+}
+
+impl Recorder {
+    fn sink(&self) -> MutexGuard<'_, Box<dyn TranscriptSink>> {
+        self.sink.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("start", &self.start)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Store the data and its annotations if recording. Recording stops if they cannot be stored.
+pub(super) fn record(
+    recorder: &mut Option<Recorder>,
+    seq: u64,
+    direction: Direction,
+    data: &[u8],
+    annotations: &[String],
+) {
+    let Some(writer) = recorder else {
+        return;
+    };
+    if data.is_empty() {
+        return;
+    }
+    let elapsed = writer.start.elapsed();
+    let mut sink = writer.sink();
+    let result = sink
+        .write_event(seq, elapsed, direction, data)
+        .and_then(|()| {
+            annotations
+                .iter()
+                .try_for_each(|annotation| sink.write_annotation(seq, annotation))
+        });
+    drop(sink);
+    if let Err(err) = result {
+        debug!(target: "Tube::record", "Recording stopped: {}", err);
+        *recorder = None;
+    }
+}
+
+/// Store a note for the event `seq` if recording. Recording stops if it cannot be stored.
+pub(super) fn annotate(recorder: &mut Option<Recorder>, seq: u64, note: &str) {
+    let Some(writer) = recorder else {
+        return;
+    };
+    let result = writer.sink().write_annotation(seq, note);
+    if let Err(err) = result {
+        debug!(target: "Tube::record", "Recording stopped: {}", err);
+        *recorder = None;
+    }
+}
+
+impl<T> Tube<T> {
+    /// Record everything sent and received from now on into a transcript at the path, which can
+    /// be replayed later by [`ReplayTube`]. A previous recording is stopped.
+    ///
+    /// The transcript is a text file starting with the line `# io-tubes transcript`. Each
+    /// following line is an event of the form `<seq> <micros> <send|recv> <hex>`, where `seq` is
+    /// the sequence number of the event in the tube, which is also shown in the log, `micros` is
+    /// the time since the recording started and `hex` is the data in lowercase hex. Other lines
+    /// starting with `#` are comments. Events without `seq` are also accepted.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn record() -> io::Result<()> {
+    ///     let path = std::env::temp_dir().join("io-tubes-record.txt");
+    ///
+    ///     let mut p = Tube::echo();
+    ///     p.record(&path)?;
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///     p.stop_recording()?;
+    ///
+    ///     let transcript = std::fs::read_to_string(&path)?;
+    ///     assert!(transcript.lines().nth(1).unwrap().ends_with(" send 48656c6c6f"));
+    ///
+    ///     // Replay the recorded server side without the process.
+    ///     let mut r = Tube::replay(&path)?;
+    ///     r.send_line("Hello").await?;
+    ///     assert_eq!(r.recv_line().await?, b"Hello\n");
+    ///     assert_eq!(r.recv_line().await?, b"");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// record();
+    /// ```
+    pub fn record(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.record_to(TranscriptFile::create(path)?)
+    }
+
+    /// Record everything sent and received from now on into the sink, e.g. a
+    /// [`MemoryTranscript`](super::MemoryTranscript). A previous recording is stopped.
+    pub fn record_to(&mut self, sink: impl TranscriptSink + 'static) -> io::Result<()> {
+        self.stop_recording()?;
+        self.traffic.recorder = Some(Recorder {
+            sink: Arc::new(Mutex::new(Box::new(sink))),
+            start: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Add a note to the log and the transcript at the current point of the traffic, e.g. to
+    /// mark where a stage of the exploit starts. The note is stored as an annotation of the last
+    /// event, see [`TranscriptSink::write_annotation`].
+    pub fn annotate(&mut self, note: &str) {
+        self.traffic.annotate(note);
+    }
+
+    /// Stop the recording started by [`Tube::record`] or [`Tube::record_to`] and flush the sink.
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        match self.traffic.recorder.take() {
+            Some(recorder) => recorder.sink().flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Replays the server side of a transcript written by [`Tube::record`], for testing exploit
+/// scripts offline.
+///
+/// Each received event becomes readable once everything sent before it in the transcript has
+/// been written, regardless of the recorded timing. EOF is reached after the last received event.
+#[derive(Debug)]
+pub struct ReplayTube {
+    /// The received data, with the number of bytes that must be written before it.
+    recv: Vec<(u64, Vec<u8>)>,
+    recv_index: usize,
+    recv_pos: usize,
+    /// All the data sent in the transcript, compared with the writes if strict.
+    sent: Vec<u8>,
+    written: u64,
+    strict: bool,
+    read_waker: Option<Waker>,
+}
+
+impl ReplayTube {
+    /// Read the transcript at the path.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_transcript(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse the transcript, see [`Tube::record`] for the format.
+    pub fn from_transcript(transcript: &str) -> io::Result<Self> {
+        Ok(Self::from_events(parse_transcript(transcript)?))
+    }
+
+    /// Replay the events, e.g. from a [`MemoryTranscript`](super::MemoryTranscript).
+    pub fn from_events(events: impl IntoIterator<Item = TranscriptEvent>) -> Self {
+        let mut recv = Vec::new();
+        let mut sent = Vec::new();
+        for event in events {
+            match event.direction {
+                Direction::Send => sent.extend_from_slice(&event.data),
+                Direction::Recv => recv.push((sent.len() as u64, event.data)),
+            }
+        }
+        Self {
+            recv,
+            recv_index: 0,
+            recv_pos: 0,
+            sent,
+            written: 0,
+            strict: false,
+            read_waker: None,
+        }
+    }
+
+    /// Fail writes with [`InvalidData`](io::ErrorKind::InvalidData) if they differ from the
+    /// data sent in the transcript. By default only the number of bytes is counted.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+}
+
+impl AsyncRead for ReplayTube {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let Some((required, data)) = this.recv.get(this.recv_index) else {
+            return Poll::Ready(Ok(()));
+        };
+        if this.written < *required {
+            this.read_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let data = &data[this.recv_pos..];
+        let len = data.len().min(buf.remaining());
+        buf.put_slice(&data[..len]);
+        this.recv_pos += len;
+        if this.recv_pos == this.recv[this.recv_index].1.len() {
+            this.recv_index += 1;
+            this.recv_pos = 0;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for ReplayTube {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.strict {
+            let start = (this.written as usize).min(this.sent.len());
+            let expected = &this.sent[start..(start + buf.len()).min(this.sent.len())];
+            if expected != buf {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "data written at offset {} differs from the transcript",
+                        start
+                    ),
+                )));
+            }
+        }
+        this.written += buf.len() as u64;
+        if let Some(waker) = this.read_waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Tube<BufReader<ReplayTube>> {
+    /// Create a tube that replays the transcript at the path, see [`ReplayTube`].
+    pub fn replay(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(ReplayTube::open(path)?))
+    }
+}
@@ -0,0 +1,104 @@
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+use crate::utils::{timeout, Needle, RecvNeedle};
+
+use super::{limit::Limited, Tube, TubeError};
+
+const NEW_LINE: u8 = 0xA;
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Same as [`recv`](Tube::recv), but receives into the buffer instead of allocating, e.g. in
+    /// a brute-force loop. Returns the number of bytes received, which is 0 on timeout or EOF.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_into() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     let mut buf = [0; 4];
+    ///
+    ///     for guess in ["AAAA", "BBBB"] {
+    ///         p.send(guess).await?;
+    ///         let numb = p.recv_into(&mut buf).await?;
+    ///         assert_eq!(&buf[..numb], guess.as_bytes());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_into();
+    /// ```
+    pub async fn recv_into(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        timeout(self.recv_timeout(), self.read(buf))
+            .await
+            .unwrap_or(Ok(0))
+    }
+
+    /// Same as [`recv_until`](Tube::recv_until), but appends to the buffer instead of
+    /// allocating, so that the buffer can be cleared and reused. Returns the number of bytes
+    /// appended, which includes the delims if they are found. The data received before the
+    /// timeout or EOF is kept in the buffer, and so is the data received before
+    /// [`Tube::max_recv_size`] is exceeded, which fails with [`TubeError::TooLarge`].
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_into() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     let mut buf = Vec::with_capacity(64);
+    ///
+    ///     p.send("Wrong!\n> Correct!\n> ").await?;
+    ///     for expected in ["Wrong!\n> ", "Correct!\n> "] {
+    ///         buf.clear();
+    ///         p.recv_until_into("> ", &mut buf).await?;
+    ///         assert_eq!(buf, expected.as_bytes());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_into();
+    /// ```
+    pub async fn recv_until_into(
+        &mut self,
+        delims: impl Needle,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        let old_len = buf.len();
+        let recv_timeout = self.recv_timeout();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        match timeout(recv_timeout, RecvNeedle::new(&mut limited, &delims, buf)).await {
+            Ok(Err(_)) if limited.exceeded() => {
+                let partial = buf[old_len..].to_vec();
+                return Err(TubeError::TooLarge { partial }.into());
+            }
+            Ok(result) => result?,
+            Err(_) => None,
+        };
+        Ok(buf.len() - old_len)
+    }
+
+    /// Same as [`recv_line`](Tube::recv_line), but appends to the buffer instead of allocating.
+    /// Returns the number of bytes appended, see [`Tube::recv_until_into`].
+    pub async fn recv_line_into(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let old_len = buf.len();
+        let recv_timeout = self.recv_timeout();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        match timeout(recv_timeout, limited.read_until(NEW_LINE, buf)).await {
+            Ok(Err(_)) if limited.exceeded() => {
+                let partial = buf[old_len..].to_vec();
+                return Err(TubeError::TooLarge { partial }.into());
+            }
+            Ok(result) => result?,
+            Err(_) => 0,
+        };
+        Ok(buf.len() - old_len)
+    }
+}
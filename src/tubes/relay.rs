@@ -0,0 +1,60 @@
+use std::io;
+
+use tokio::io::{copy_bidirectional, AsyncBufRead, AsyncWrite};
+
+use super::Tube;
+
+/// Copy data between the tubes in both directions until both of them reach EOF.
+///
+/// When one tube reaches EOF, the other tube is shut down for writing. Returns the number of
+/// bytes copied from `a` to `b` and from `b` to `a`.
+/// ```rust
+/// use io_tubes::tubes::{relay, LineEcho, Tube};
+/// use std::io;
+/// use tokio::io::{duplex, AsyncWriteExt};
+///
+/// #[tokio::main]
+/// async fn relay_tubes() -> io::Result<()> {
+///     let (client, proxy) = duplex(64);
+///     let mut client = Tube::new(client);
+///     let mut a = Tube::new(proxy);
+///     let mut b = Tube::new(LineEcho::new());
+///
+///     let (copied, line) = tokio::join!(relay(&mut a, &mut b), async move {
+///         client.send_line("Hello").await?;
+///         let line = client.recv_line().await?;
+///         client.shutdown().await?;
+///         client.recv(1).await?;
+///         Ok::<_, io::Error>(line)
+///     });
+///     assert_eq!(line?, b"Hello\n");
+///     assert_eq!(copied?, (6, 6));
+///
+///     Ok(())
+/// }
+///
+/// relay_tubes();
+/// ```
+pub async fn relay<A, B>(a: &mut Tube<A>, b: &mut Tube<B>) -> io::Result<(u64, u64)>
+where
+    A: AsyncBufRead + AsyncWrite + Unpin,
+    B: AsyncBufRead + AsyncWrite + Unpin,
+{
+    copy_bidirectional(a, b).await
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Connect this tube to the other tube, see [`relay`].
+    ///
+    /// This is useful to forward a local process to a remote socket, or to sit in the middle of
+    /// a connection and log the traffic.
+    pub async fn join<U>(&mut self, other: &mut Tube<U>) -> io::Result<(u64, u64)>
+    where
+        U: AsyncBufRead + AsyncWrite + Unpin,
+    {
+        relay(self, other).await
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use crate::utils::Needle;
+
+use super::Tube;
+
+/// The round-trip times measured by [`Tube::rtt_probe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RttStats {
+    /// Every round-trip time in the order they are measured.
+    pub samples: Vec<Duration>,
+    /// The shortest round-trip time.
+    pub min: Duration,
+    /// The median round-trip time.
+    pub median: Duration,
+    /// The mean round-trip time.
+    pub mean: Duration,
+    /// The longest round-trip time.
+    pub max: Duration,
+    /// The standard deviation of the round-trip times.
+    pub stddev: Duration,
+}
+
+impl RttStats {
+    fn new(samples: Vec<Duration>) -> Self {
+        let mut sorted = samples.clone();
+        sorted.sort();
+        let len = sorted.len();
+        let median = match len % 2 {
+            0 => (sorted[len / 2 - 1] + sorted[len / 2]) / 2,
+            _ => sorted[len / 2],
+        };
+        let mean = sorted.iter().sum::<Duration>() / len as u32;
+        let variance = sorted
+            .iter()
+            .map(|sample| (sample.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / len as f64;
+        Self {
+            min: sorted[0],
+            median,
+            mean,
+            max: sorted[len - 1],
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+            samples,
+        }
+    }
+
+    /// A receive timeout that the responses are unlikely to exceed, the longest round-trip time
+    /// plus 4 standard deviations, e.g. to set [`Tube::timeout`].
+    pub fn suggested_timeout(&self) -> Duration {
+        self.max.saturating_add(self.stddev.saturating_mul(4))
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Send `payload` and receive until `expect` the given number of times, measuring the time
+    /// from the send to the end of the response, e.g. to tell apart the responses of a timing
+    /// side channel or to calibrate the timeout.
+    ///
+    /// Every response must arrive within the receive timeout, or the probe fails with
+    /// [`TimedOut`](io::ErrorKind::TimedOut). Fails with
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if `samples` is 0.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn rtt_probe() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     let stats = p.rtt_probe("ping\n", "ping\n", 5).await?;
+    ///     assert_eq!(stats.samples.len(), 5);
+    ///     assert!(stats.min <= stats.median && stats.median <= stats.max);
+    ///     p.timeout = stats.suggested_timeout();
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// rtt_probe();
+    /// ```
+    pub async fn rtt_probe(
+        &mut self,
+        payload: impl AsRef<[u8]>,
+        expect: impl Needle,
+        samples: usize,
+    ) -> io::Result<RttStats> {
+        if samples == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "at least one sample is needed",
+            ));
+        }
+        let payload = payload.as_ref();
+        let mut rtts = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            let start = Instant::now();
+            self.send(payload).await?;
+            self.recv_until_checked(&expect).await?;
+            rtts.push(start.elapsed());
+        }
+        Ok(RttStats::new(rtts))
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    fs, io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::fs::OpenOptionsExt,
+    },
+    path::PathBuf,
+    sync::Arc,
+};
+
+use libc::{c_long, sock_filter};
+use tokio::process::Command;
+
+use super::ProcessTube;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// System calls denied by the default profile, which a challenge has no business making but
+/// which could affect the host or other processes.
+const DANGEROUS_SYSCALLS: &[c_long] = &[
+    libc::SYS_ptrace,
+    libc::SYS_process_vm_readv,
+    libc::SYS_process_vm_writev,
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_chroot,
+    libc::SYS_unshare,
+    libc::SYS_setns,
+    libc::SYS_reboot,
+    libc::SYS_kexec_load,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_swapon,
+    libc::SYS_swapoff,
+    libc::SYS_bpf,
+    libc::SYS_perf_event_open,
+    libc::SYS_keyctl,
+    libc::SYS_add_key,
+    libc::SYS_request_key,
+    // Operations submitted through io_uring are not filtered by seccomp.
+    libc::SYS_io_uring_setup,
+];
+
+// Access rights of landlock, see linux/landlock.h.
+const ACCESS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_TRUNCATE: u64 = 1 << 14;
+/// The rights to modify the file system in the first version of landlock, which are
+/// `WRITE_FILE` and the rights from `REMOVE_DIR` to `MAKE_SYM`.
+const ACCESS_WRITE: u64 = ACCESS_WRITE_FILE | 0x1ff0;
+const ACCESS_REFER: u64 = 1 << 13;
+const RULE_PATH_BENEATH: libc::c_int = 1;
+const CREATE_RULESET_VERSION: libc::c_uint = 1;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+/// The containment applied to a spawned process before it executes the program, see
+/// [`ProcessTube::sandboxed`]. It is basic containment for running untrusted challenges locally,
+/// not a replacement for a container or a virtual machine.
+///
+/// The process and its children can't gain privileges, e.g. through setuid binaries. System
+/// calls are filtered with seccomp, and writes to the file system are restricted with landlock,
+/// which needs Linux 5.13 or later. Files opened before the program is executed, e.g. stdin and
+/// stdout, are not affected.
+#[derive(Debug, Clone)]
+pub struct SandboxProfile {
+    /// Allow the process to open network sockets. Unix sockets are always allowed.
+    pub allow_network: bool,
+    /// Restrict writes to the file system to the paths in `writable`.
+    pub restrict_writes: bool,
+    /// The files and directories that can be written if writes are restricted, including
+    /// everything beneath the directories.
+    pub writable: Vec<PathBuf>,
+    /// The system calls that fail with `EPERM`, by their numbers, e.g. `libc::SYS_ptrace`.
+    pub denied_syscalls: Vec<c_long>,
+}
+
+impl Default for SandboxProfile {
+    /// Deny network sockets, writes to the file system other than to `/dev/null`, and the system
+    /// calls that affect the host or other processes, e.g. `ptrace`, `mount` and `init_module`.
+    fn default() -> Self {
+        Self {
+            allow_network: false,
+            restrict_writes: true,
+            writable: vec![PathBuf::from("/dev/null")],
+            denied_syscalls: DANGEROUS_SYSCALLS.to_vec(),
+        }
+    }
+}
+
+impl SandboxProfile {
+    /// Apply the profile to the processes spawned by the command. The landlock ruleset is made
+    /// here, so an error is returned if the kernel doesn't support landlock while writes are
+    /// restricted, or if a writable path doesn't exist.
+    pub fn apply(&self, command: &mut Command) -> io::Result<()> {
+        let ruleset = match self.restrict_writes {
+            true => Some(Arc::new(self.ruleset()?)),
+            false => None,
+        };
+        let filter = self.filter();
+        let hook = move || {
+            // SAFETY: only system calls are made, which is safe between fork and exec.
+            unsafe {
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if let Some(ruleset) = &ruleset {
+                    if libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0) != 0
+                    {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                let program = libc::sock_fprog {
+                    len: filter.len() as u16,
+                    filter: filter.as_ptr() as *mut sock_filter,
+                };
+                if libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &program as *const libc::sock_fprog,
+                ) != 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        };
+        // SAFETY: the hook doesn't allocate or take locks.
+        unsafe {
+            command.pre_exec(hook);
+        }
+        Ok(())
+    }
+
+    fn ruleset(&self) -> io::Result<OwnedFd> {
+        // SAFETY: querying the version doesn't take any pointer.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        if version < 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "landlock is not available to restrict writes: {}",
+                    io::Error::last_os_error()
+                ),
+            ));
+        }
+        let mut handled = ACCESS_WRITE;
+        if version >= 2 {
+            handled |= ACCESS_REFER;
+        }
+        if version >= 3 {
+            handled |= ACCESS_TRUNCATE;
+        }
+
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        // SAFETY: the attribute outlives the call and its size is passed.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the ruleset fd is newly created and owned by nobody else.
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+        for path in &self.writable {
+            let parent = fs::File::options()
+                .read(true)
+                .custom_flags(libc::O_PATH)
+                .open(path)
+                .map_err(|err| {
+                    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+                })?;
+            let allowed_access = match parent.metadata()?.is_dir() {
+                true => handled,
+                false => handled & (ACCESS_WRITE_FILE | ACCESS_TRUNCATE),
+            };
+            let rule = PathBeneathAttr {
+                allowed_access,
+                parent_fd: parent.as_raw_fd(),
+            };
+            // SAFETY: the rule outlives the call, and the fds are open.
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset.as_raw_fd(),
+                    RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0,
+                )
+            };
+            if ret != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(ruleset)
+    }
+
+    /// The seccomp filter, which is built before the process is forked.
+    fn filter(&self) -> Vec<sock_filter> {
+        let stmt = |code: u32, k: u32| sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        };
+        let jump = |code: u32, k: u32, jt: u8, jf: u8| sock_filter {
+            code: (libc::BPF_JMP | code | libc::BPF_K) as u16,
+            jt,
+            jf,
+            k,
+        };
+        let load = |offset: u32| stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset);
+        let ret = |k: u32| stmt(libc::BPF_RET | libc::BPF_K, k);
+        let deny = |errno: i32| ret(libc::SECCOMP_RET_ERRNO | errno as u32);
+
+        // The offsets of the fields of seccomp_data.
+        let (nr, arch, arg0) = (0, 4, 16);
+        let mut filter = vec![
+            load(arch),
+            jump(libc::BPF_JEQ, AUDIT_ARCH, 1, 0),
+            ret(libc::SECCOMP_RET_KILL_PROCESS),
+            load(nr),
+        ];
+        #[cfg(target_arch = "x86_64")]
+        filter.extend([
+            // The x32 ABI shares the architecture, but its system calls are numbered apart.
+            jump(libc::BPF_JGE, 0x4000_0000, 0, 1),
+            ret(libc::SECCOMP_RET_KILL_PROCESS),
+        ]);
+        for &syscall in &self.denied_syscalls {
+            filter.extend([jump(libc::BPF_JEQ, syscall as u32, 0, 1), deny(libc::EPERM)]);
+        }
+        if !self.allow_network {
+            filter.extend([
+                jump(libc::BPF_JEQ, libc::SYS_socket as u32, 0, 4),
+                load(arg0),
+                jump(libc::BPF_JEQ, libc::AF_UNIX as u32, 0, 1),
+                ret(libc::SECCOMP_RET_ALLOW),
+                deny(libc::EACCES),
+            ]);
+        }
+        filter.push(ret(libc::SECCOMP_RET_ALLOW));
+        filter
+    }
+}
+
+impl ProcessTube {
+    /// Spawn the command in the sandbox, e.g. to analyze an untrusted challenge without setting
+    /// up a container.
+    /// ```rust
+    /// use io_tubes::tubes::{ProcessTube, SandboxProfile, Tube};
+    /// use std::{env, io};
+    /// use tokio::process::Command;
+    ///
+    /// #[tokio::main]
+    /// async fn sandboxed() -> io::Result<()> {
+    ///     let dir = env::temp_dir().join("io-tubes-sandbox");
+    ///     std::fs::create_dir_all(&dir)?;
+    ///     let mut command = Command::new("/bin/sh");
+    ///     command.arg("-c").arg(format!(
+    ///         "echo 1 > {0}/allowed && echo 2 > {0}/../denied || echo denied",
+    ///         dir.display()
+    ///     ));
+    ///     let mut profile = SandboxProfile::default();
+    ///     profile.writable.push(dir.clone());
+    ///     let mut p = Tube::new(ProcessTube::sandboxed(command, &profile)?);
+    ///
+    ///     assert_eq!(p.recv_line().await?, b"denied\n");
+    ///     assert!(dir.join("allowed").exists());
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// sandboxed();
+    /// ```
+    pub fn sandboxed(mut command: Command, profile: &SandboxProfile) -> io::Result<Self> {
+        profile.apply(&mut command)?;
+        Self::from_command(command)
+    }
+}
@@ -0,0 +1,117 @@
+use std::{borrow::Cow, collections::HashMap, io};
+
+use regex::bytes::Regex;
+use tokio::io::AsyncBufRead;
+
+use super::Tube;
+
+/// The named groups captured by [`Tube::scrape`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scraped {
+    groups: HashMap<String, Vec<u8>>,
+}
+
+impl Scraped {
+    /// The data captured by the group, or `None` if the group didn't participate in the match.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.groups.get(name).map(Vec::as_slice)
+    }
+
+    /// The data captured by the group as a string, where invalid UTF-8 is replaced with `U+FFFD`.
+    pub fn str(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.get(name).map(String::from_utf8_lossy)
+    }
+
+    /// Parse the group as an unsigned integer in ASCII, which is decimal unless it starts with
+    /// `0x`. Fails with [`NotFound`](io::ErrorKind::NotFound) if the group didn't participate
+    /// in the match and with [`InvalidData`](io::ErrorKind::InvalidData) if it is not an
+    /// integer.
+    pub fn int(&self, name: &str) -> io::Result<u64> {
+        let value = self.text(name)?;
+        match value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+        {
+            Some(hex) => parse(hex, 16),
+            None => parse(value, 10),
+        }
+    }
+
+    /// Same as [`Scraped::int`], but the integer is always hex, with or without `0x`.
+    pub fn hex(&self, name: &str) -> io::Result<u64> {
+        let value = self.text(name)?;
+        let hex = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .unwrap_or(value);
+        parse(hex, 16)
+    }
+
+    /// Consume the result, returning the data of each group that participated in the match.
+    pub fn into_map(self) -> HashMap<String, Vec<u8>> {
+        self.groups
+    }
+
+    fn text(&self, name: &str) -> io::Result<&str> {
+        let value = self.get(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("group {} is not captured", name),
+            )
+        })?;
+        std::str::from_utf8(value)
+            .map(str::trim)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+fn parse(digits: &str, radix: u32) -> io::Result<u64> {
+    u64::from_str_radix(digits, radix)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Receive until the regex matches and return its named groups, collapsing the usual
+    /// receive, match and parse into one call. Fails with [`NotFound`](io::ErrorKind::NotFound)
+    /// if the timeout or EOF is reached before the regex matches.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use regex::bytes::Regex;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn scrape() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     let leak = Regex::new(r"puts: (?P<puts>0x[0-9a-f]+), canary: (?P<canary>\d+)").unwrap();
+    ///
+    ///     p.send("Debug info\nputs: 0x7ffff7a62aa0, canary: 1337\n").await?;
+    ///     let scraped = p.scrape(&leak).await?;
+    ///     assert_eq!(scraped.int("puts")?, 0x7ffff7a62aa0);
+    ///     assert_eq!(scraped.int("canary")?, 1337);
+    ///     assert_eq!(scraped.get("puts"), Some(&b"0x7ffff7a62aa0"[..]));
+    ///     assert_eq!(p.recv_line().await?, b"\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// scrape();
+    /// ```
+    pub async fn scrape(&mut self, regex: &Regex) -> io::Result<Scraped> {
+        let (_, captures) = self.recv_regex(regex).await?;
+        if captures.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("regex {} did not match", regex),
+            ));
+        }
+        let groups = regex
+            .capture_names()
+            .zip(captures)
+            .filter_map(|(name, capture)| Some((name?.to_owned(), capture?)))
+            .collect();
+        Ok(Scraped { groups })
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, ReadBuf};
+
+use crate::utils::timeout;
+
+use super::Tube;
+
+/// A wrapper that feeds everything received into a VT100 screen emulator.
+///
+/// Curses-based programs redraw the terminal with escape sequences, so matching the raw bytes
+/// is unreliable. Wrapping the transport in a `ScreenTube` keeps track of what would be
+/// displayed on the screen instead. Note that most curses programs only draw when connected to a
+/// terminal, so the target usually has to be started under a pty (e.g. through `script` or
+/// `socat`) or be a remote service that already speaks VT100.
+pub struct ScreenTube<T> {
+    inner: T,
+    parser: vt100::Parser,
+}
+
+impl<T> ScreenTube<T> {
+    /// Wrap the transport with a screen of the supplied size.
+    pub fn new(inner: T, rows: u16, cols: u16) -> Self {
+        Self {
+            inner,
+            parser: vt100::Parser::new(rows, cols, 0),
+        }
+    }
+
+    /// The current state of the screen.
+    pub fn screen(&self) -> &vt100::Screen {
+        self.parser.screen()
+    }
+
+    /// Consume the wrapper to get back the transport.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ScreenTube<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScreenTube")
+            .field("inner", &self.inner)
+            .field("size", &self.screen().size())
+            .finish()
+    }
+}
+
+impl<T> AsyncRead for ScreenTube<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let Self { inner, parser } = self.get_mut();
+        let olen = buf.filled().len();
+
+        if Pin::new(inner).poll_read(cx, buf)?.is_pending() {
+            return Poll::Pending;
+        }
+
+        parser.process(&buf.filled()[olen..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncWrite for ScreenTube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl<T> Tube<BufReader<ScreenTube<T>>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Construct a tube that emulates a screen of the supplied size on the receive side.
+    pub fn with_screen(inner: T, rows: u16, cols: u16) -> Self {
+        Self::new(ScreenTube::new(inner, rows, cols))
+    }
+
+    /// The current state of the emulated screen.
+    pub fn screen(&self) -> &vt100::Screen {
+        self.inner.get_ref().screen()
+    }
+
+    /// The text currently displayed on the emulated screen.
+    pub fn screen_text(&self) -> String {
+        self.screen().contents()
+    }
+
+    /// Keep receiving until the screen contains the supplied text. The received bytes are
+    /// discarded as they are already reflected on the screen.
+    ///
+    /// Returns `false` if the timeout or EOF is reached before the text is displayed.
+    /// ```rust
+    /// use io_tubes::tubes::{ScreenTube, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn wait_for_screen() -> io::Result<()> {
+    ///     let mut p = Tube::echo().map_inner(|inner| ScreenTube::new(inner, 24, 80));
+    ///
+    ///     // Draw "GAME OVER" at row 10, column 30
+    ///     p.send("\x1b[2J\x1b[10;30HGAME OVER").await?;
+    ///     assert!(p.wait_for_screen_contains("GAME OVER").await?);
+    ///     assert_eq!(p.screen().cell(9, 29).unwrap().contents(), "G");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// wait_for_screen();
+    /// ```
+    pub async fn wait_for_screen_contains(&mut self, text: impl AsRef<str>) -> io::Result<bool> {
+        let text = text.as_ref();
+        timeout(self.recv_timeout(), async {
+            loop {
+                if self.screen_text().contains(text) {
+                    return Ok(true);
+                }
+                let len = self.fill_buf().await?.len();
+                if len == 0 {
+                    return Ok(false);
+                }
+                self.consume(len);
+            }
+        })
+        .await
+        .unwrap_or(Ok(false))
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    future, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use crate::utils::timeout;
+
+use super::{Tube, TubeError};
+
+/// Wait until any of the tubes receives data, and return its index along with everything it has
+/// buffered. The other tubes are left untouched, so their data can still be received afterwards.
+///
+/// Tubes that reach EOF are skipped, and the result is
+/// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) once all of them have. It fails with
+/// [`TimedOut`](io::ErrorKind::TimedOut) if nothing is received within the longest timeout of
+/// the tubes. Use [`Tube::boxed`] to race different kinds of tubes, e.g. a process and a
+/// callback connection.
+/// ```rust
+/// use io_tubes::tubes::{select_recv, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn select() -> io::Result<()> {
+///     let (a, mut a_server) = Tube::pair();
+///     let (b, mut b_server) = Tube::pair();
+///     let mut tubes = [a, b];
+///
+///     b_server.send("from b").await?;
+///     assert_eq!(select_recv(&mut tubes).await?, (1, b"from b".to_vec()));
+///
+///     a_server.send("from a").await?;
+///     drop(b_server);
+///     assert_eq!(select_recv(&mut tubes).await?, (0, b"from a".to_vec()));
+///
+///     Ok(())
+/// }
+///
+/// select();
+/// ```
+pub async fn select_recv<T>(tubes: &mut [Tube<T>]) -> io::Result<(usize, Vec<u8>)>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    let recv_timeout = tubes
+        .iter()
+        .map(Tube::recv_timeout)
+        .max()
+        .unwrap_or_default();
+    let mut closed = vec![false; tubes.len()];
+    let recv = future::poll_fn(|cx| poll_select(tubes, &mut closed, cx));
+    match timeout(recv_timeout, recv).await {
+        Ok(result) => result,
+        Err(_) => Err(TubeError::Timeout {
+            partial: Vec::new(),
+        }
+        .into()),
+    }
+}
+
+fn poll_select<T>(
+    tubes: &mut [Tube<T>],
+    closed: &mut [bool],
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<(usize, Vec<u8>)>>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    for (i, tube) in tubes.iter_mut().enumerate() {
+        if closed[i] {
+            continue;
+        }
+        let Poll::Ready(buf) = Pin::new(&mut *tube).poll_fill_buf(cx)? else {
+            continue;
+        };
+        if buf.is_empty() {
+            closed[i] = true;
+            continue;
+        }
+        let data = buf.to_vec();
+        Pin::new(tube).consume(data.len());
+        return Poll::Ready(Ok((i, data)));
+    }
+    if closed.iter().all(|&closed| closed) {
+        return Poll::Ready(Err(TubeError::Eof {
+            partial: Vec::new(),
+        }
+        .into()));
+    }
+    Poll::Pending
+}
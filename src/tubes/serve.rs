@@ -0,0 +1,125 @@
+use std::{future::Future, io, net::SocketAddr};
+
+use log::debug;
+use tokio::{
+    io::BufReader,
+    net::TcpStream,
+    sync::oneshot,
+    task::{JoinHandle, JoinSet},
+};
+
+use super::{Listener, Tube};
+
+/// A running accept loop returned by [`Listener::serve`]. It is stopped when dropped.
+#[derive(Debug)]
+pub struct Server {
+    task: JoinHandle<()>,
+    shutdown: Option<oneshot::Sender<()>>,
+    local_addr: SocketAddr,
+}
+
+impl Server {
+    /// Returns the address that is listened.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the port that is listened.
+    pub fn port(&self) -> u16 {
+        self.local_addr.port()
+    }
+
+    /// Stop accepting connections and abort the handlers that are running.
+    pub fn stop(self) {
+        self.task.abort();
+    }
+
+    /// Stop accepting connections and wait for the handlers that are running to finish.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Listener {
+    /// Accept connections in a loop and call `handle` for each of them in its own task, until the
+    /// returned [`Server`] is stopped. Errors of accepting and of the handlers are logged, e.g. for
+    /// small services that answer the challenge or act as a honeypot.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn serve() -> io::Result<()> {
+    ///     let server = Listener::bind("127.0.0.1:0").await?.serve(|mut tube| async move {
+    ///         let name = tube.recv_line().await?;
+    ///         tube.send(b"Hello ").await?;
+    ///         tube.send(name).await?;
+    ///         Ok(())
+    ///     })?;
+    ///
+    ///     let mut alice = Tube::remote(("127.0.0.1", server.port())).await?;
+    ///     let mut bob = Tube::remote(("127.0.0.1", server.port())).await?;
+    ///     bob.send_line("Bob").await?;
+    ///     alice.send_line("Alice").await?;
+    ///     assert_eq!(bob.recv_line().await?, b"Hello Bob\n");
+    ///     assert_eq!(alice.recv_line().await?, b"Hello Alice\n");
+    ///
+    ///     let port = server.port();
+    ///     server.shutdown().await;
+    ///     assert!(Tube::remote(("127.0.0.1", port)).await.is_err());
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// serve();
+    /// ```
+    pub fn serve<F, Fut>(self, mut handle: F) -> io::Result<Server>
+    where
+        F: FnMut(Tube<BufReader<TcpStream>>) -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<()>> + Send + 'static,
+    {
+        let local_addr = self.inner.local_addr()?;
+        let (shutdown, mut shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            // The handlers are aborted when the set is dropped with the task.
+            let mut handlers = JoinSet::new();
+            loop {
+                tokio::select! {
+                    accepted = self.accept() => {
+                        let tube = match accepted {
+                            Ok(tube) => tube,
+                            Err(err) => {
+                                debug!(target: "Listener::serve", "Accept failed: {}", err);
+                                continue;
+                            }
+                        };
+                        let handled = handle(tube);
+                        handlers.spawn(async move {
+                            if let Err(err) = handled.await {
+                                debug!(target: "Listener::serve", "Handler failed: {}", err);
+                            }
+                        });
+                    }
+                    Some(_) = handlers.join_next() => {}
+                    _ = &mut shutdown_rx => break,
+                }
+            }
+            drop(self);
+            while handlers.join_next().await.is_some() {}
+        });
+        Ok(Server {
+            task,
+            shutdown: Some(shutdown),
+            local_addr,
+        })
+    }
+}
@@ -0,0 +1,128 @@
+use std::{io, net::SocketAddr, time::Duration};
+
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+/// The backlog of the listening sockets made by [`SocketOptions::listen`].
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Options of TCP sockets, for [`RemoteOptions::socket`](super::RemoteOptions::socket) and
+/// [`Listener::bind_with`](super::Listener::bind_with). The defaults leave everything to the OS.
+#[derive(Debug, Clone, Default)]
+pub struct SocketOptions {
+    /// Set `TCP_NODELAY` to disable Nagle's algorithm, so that small sends go out at once
+    /// instead of waiting for the previous data to be acknowledged.
+    pub nodelay: bool,
+    /// Enable keepalive probes after the connection is idle for this long.
+    pub keepalive: Option<Duration>,
+    /// Set `SO_REUSEADDR`, e.g. to listen on a port again while old connections are in
+    /// `TIME_WAIT`.
+    pub reuse_addr: bool,
+    /// Bind to this address before connecting, e.g. to pick the interface or the source port.
+    /// Only used for connecting.
+    pub local_addr: Option<SocketAddr>,
+    /// Set `IPV6_V6ONLY` on IPv6 sockets. Whether IPv4 is also accepted on an IPv6 socket
+    /// depends on the OS if `None`.
+    pub only_v6: Option<bool>,
+}
+
+impl SocketOptions {
+    /// Apply the options that can change after the connection is made, which are `nodelay` and
+    /// `keepalive`, e.g. to a stream accepted elsewhere.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, SocketOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn apply() -> io::Result<()> {
+    ///     let l = Listener::bind("127.0.0.1:0").await?;
+    ///     let p = Tube::remote(("127.0.0.1", l.port()?)).await?;
+    ///
+    ///     let options = SocketOptions {
+    ///         nodelay: true,
+    ///         ..SocketOptions::default()
+    ///     };
+    ///     options.apply(p.inner.get_ref())?;
+    ///     assert!(p.inner.get_ref().nodelay()?);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// apply();
+    /// ```
+    pub fn apply(&self, stream: &TcpStream) -> io::Result<()> {
+        self.apply_connected(&SockRef::from(stream))
+    }
+
+    fn apply_connected(&self, socket: &Socket) -> io::Result<()> {
+        if self.nodelay {
+            socket.set_tcp_nodelay(true)?;
+        }
+        if let Some(time) = self.keepalive {
+            socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+        }
+        Ok(())
+    }
+
+    /// Make a nonblocking socket for the address with the options set.
+    fn socket(&self, addr: SocketAddr) -> io::Result<Socket> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        socket.set_nonblocking(true)?;
+        if self.reuse_addr {
+            socket.set_reuse_address(true)?;
+        }
+        if let (SocketAddr::V6(_), Some(only_v6)) = (addr, self.only_v6) {
+            socket.set_only_v6(only_v6)?;
+        }
+        self.apply_connected(&socket)?;
+        Ok(socket)
+    }
+
+    /// Connect to the addresses in order, returning the error of the last one if all fail.
+    pub(super) async fn connect(&self, addrs: &[SocketAddr]) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for &addr in addrs {
+            let result = async {
+                let socket = self.socket(addr)?;
+                if let Some(local_addr) = self.local_addr {
+                    socket.bind(&local_addr.into())?;
+                }
+                TcpSocket::from_std_stream(socket.into())
+                    .connect(addr)
+                    .await
+            };
+            match result.await {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    /// Listen on the first of the addresses that can be bound.
+    pub(super) fn listen(&self, addrs: &[SocketAddr]) -> io::Result<TcpListener> {
+        let mut last_err = None;
+        for &addr in addrs {
+            let result = self.socket(addr).and_then(|socket| {
+                socket.bind(&addr.into())?;
+                socket.listen(LISTEN_BACKLOG)?;
+                TcpListener::from_std(socket.into())
+            });
+            match result {
+                Ok(listener) => return Ok(listener),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+}
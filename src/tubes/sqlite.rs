@@ -0,0 +1,159 @@
+use std::{io, path::Path, time::Duration};
+
+use rusqlite::{params, Connection};
+
+use super::{Direction, TranscriptEvent, TranscriptSink};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS io_tubes_events (
+    session TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    elapsed_micros INTEGER NOT NULL,
+    direction TEXT NOT NULL,
+    data BLOB NOT NULL
+)";
+
+fn sql_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+/// Stores the events of a session in the `io_tubes_events` table of a SQLite database, so that
+/// the sessions of many tubes can be kept in one place and queried later. Recording into an
+/// existing session appends to it, so the events are ordered by `rowid` while `seq` is the
+/// sequence number of the event in its tube.
+/// ```rust
+/// use io_tubes::tubes::{ReplayTube, SqliteTranscript, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn sqlite_transcript() -> io::Result<()> {
+///     let path = std::env::temp_dir().join("io-tubes-sessions.db");
+///     let _ = std::fs::remove_file(&path);
+///
+///     let mut p = Tube::echo();
+///     p.record_to(SqliteTranscript::open(&path, "attempt-1")?)?;
+///     p.send("Hello\n").await?;
+///     assert_eq!(p.recv_line().await?, b"Hello\n");
+///     p.stop_recording()?;
+///
+///     assert_eq!(SqliteTranscript::sessions(&path)?, ["attempt-1"]);
+///     let events = SqliteTranscript::load(&path, "attempt-1")?;
+///     assert_eq!(events.len(), 2);
+///
+///     let mut r = Tube::new(ReplayTube::from_events(events));
+///     r.send("Hello\n").await?;
+///     assert_eq!(r.recv_line().await?, b"Hello\n");
+///
+///     Ok(())
+/// }
+///
+/// sqlite_transcript();
+/// ```
+#[derive(Debug)]
+pub struct SqliteTranscript {
+    conn: Connection,
+    session: String,
+}
+
+impl SqliteTranscript {
+    /// Open or create the database at the path and record into the session.
+    pub fn open(path: impl AsRef<Path>, session: impl Into<String>) -> io::Result<Self> {
+        Self::from_connection(Connection::open(path).map_err(sql_error)?, session)
+    }
+
+    /// Record into the session of an opened database, creating the table if it doesn't exist.
+    pub fn from_connection(conn: Connection, session: impl Into<String>) -> io::Result<Self> {
+        conn.execute(SCHEMA, []).map_err(sql_error)?;
+        Ok(Self {
+            conn,
+            session: session.into(),
+        })
+    }
+
+    /// Load the events of the session from the database at the path.
+    pub fn load(path: impl AsRef<Path>, session: &str) -> io::Result<Vec<TranscriptEvent>> {
+        let conn = Connection::open(path).map_err(sql_error)?;
+        conn.execute(SCHEMA, []).map_err(sql_error)?;
+        let mut statement = conn
+            .prepare(
+                "SELECT seq, elapsed_micros, direction, data FROM io_tubes_events
+                WHERE session = ?1 ORDER BY rowid",
+            )
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map([session], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Vec<u8>>(3)?,
+                ))
+            })
+            .map_err(sql_error)?;
+        rows.map(|row| {
+            let (seq, micros, direction, data) = row.map_err(sql_error)?;
+            let direction = match direction.as_str() {
+                "send" => Direction::Send,
+                "recv" => Direction::Recv,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid direction {:?}", direction),
+                    ))
+                }
+            };
+            Ok(TranscriptEvent {
+                seq: seq as u64,
+                elapsed: Duration::from_micros(micros as u64),
+                direction,
+                data,
+            })
+        })
+        .collect()
+    }
+
+    /// The sessions stored in the database at the path, in the order they are first recorded.
+    pub fn sessions(path: impl AsRef<Path>) -> io::Result<Vec<String>> {
+        let conn = Connection::open(path).map_err(sql_error)?;
+        conn.execute(SCHEMA, []).map_err(sql_error)?;
+        let mut statement = conn
+            .prepare("SELECT session FROM io_tubes_events GROUP BY session ORDER BY MIN(rowid)")
+            .map_err(sql_error)?;
+        let sessions = statement
+            .query_map([], |row| row.get(0))
+            .map_err(sql_error)?
+            .collect::<Result<_, _>>()
+            .map_err(sql_error)?;
+        Ok(sessions)
+    }
+}
+
+impl TranscriptSink for SqliteTranscript {
+    fn write_event(
+        &mut self,
+        seq: u64,
+        elapsed: Duration,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let direction = match direction {
+            Direction::Send => "send",
+            Direction::Recv => "recv",
+        };
+        self.conn
+            .prepare_cached(
+                "INSERT INTO io_tubes_events (session, seq, elapsed_micros, direction, data)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .and_then(|mut statement| {
+                statement.execute(params![
+                    self.session,
+                    seq as i64,
+                    elapsed.as_micros() as i64,
+                    direction,
+                    data
+                ])
+            })
+            .map_err(sql_error)?;
+        Ok(())
+    }
+}
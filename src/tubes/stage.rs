@@ -0,0 +1,177 @@
+use std::{error::Error, fmt, future::Future, io, time::Duration};
+
+use log::debug;
+use tokio::time;
+
+use super::{with_deadline, Tube};
+
+/// A stage of an exploit driven by [`Tube::run_stages`]. The stages of one exploit are usually
+/// the variants of an enum, so that a stage can hand what it learned to the next one and each
+/// stage can be tested on its own against a [`MockTube`](super::MockTube) or a
+/// [`ReplayTube`](super::ReplayTube).
+pub trait Stage<T>: Sized {
+    /// The result of the last stage.
+    type Output;
+
+    /// The name of the stage used in the log, the transcript markers and [`StageError`].
+    fn name(&self) -> &str;
+
+    /// Run the stage, returning the next stage or the final result. The stage runs again on
+    /// failure if [`Stage::retries`] allows it, so it should not assume a fresh tube.
+    fn run(
+        &mut self,
+        tube: &mut Tube<T>,
+    ) -> impl Future<Output = io::Result<Next<Self, Self::Output>>>;
+
+    /// The time limit of a single attempt. It is applied as a deadline to the operations on the
+    /// tube, see [`with_deadline`], and the attempt fails with [`io::ErrorKind::TimedOut`] if it
+    /// is still running after the limit. No limit by default.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The number of times the stage is run again after failing, 0 by default.
+    fn retries(&self) -> usize {
+        0
+    }
+}
+
+/// What comes after a [`Stage`].
+#[derive(Debug)]
+pub enum Next<S, O> {
+    /// Continue with another stage.
+    Stage(S),
+    /// The exploit is finished with the result.
+    Done(O),
+}
+
+impl<T> Tube<T> {
+    /// Drive the stages from `stage` until one of them is [`Next::Done`]. The start of every
+    /// attempt is marked in the log and the transcript with [`Tube::annotate`], and a stage that
+    /// fails after all its retries stops the run.
+    /// ```rust
+    /// use io_tubes::tubes::{Next, Stage, Tube};
+    /// use std::io;
+    /// use tokio::io::{AsyncBufRead, AsyncWrite};
+    ///
+    /// enum Exploit {
+    ///     Leak,
+    ///     Win { addr: u64 },
+    /// }
+    ///
+    /// impl<T: AsyncBufRead + AsyncWrite + Unpin> Stage<T> for Exploit {
+    ///     type Output = Vec<u8>;
+    ///
+    ///     fn name(&self) -> &str {
+    ///         match self {
+    ///             Exploit::Leak => "leak",
+    ///             Exploit::Win { .. } => "win",
+    ///         }
+    ///     }
+    ///
+    ///     async fn run(&mut self, p: &mut Tube<T>) -> io::Result<Next<Self, Vec<u8>>> {
+    ///         match self {
+    ///             Exploit::Leak => {
+    ///                 p.send_line("401136").await?;
+    ///                 let line = String::from_utf8_lossy(&p.recv_line().await?).into_owned();
+    ///                 let addr = u64::from_str_radix(line.trim(), 16)
+    ///                     .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    ///                 Ok(Next::Stage(Exploit::Win { addr }))
+    ///             }
+    ///             Exploit::Win { addr } => {
+    ///                 p.send_line(format!("flag{{{addr:x}}}")).await?;
+    ///                 Ok(Next::Done(p.recv_line().await?))
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn run_stages() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     assert_eq!(p.run_stages(Exploit::Leak).await?, b"flag{401136}\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// run_stages();
+    /// ```
+    pub async fn run_stages<S>(&mut self, mut stage: S) -> Result<S::Output, StageError>
+    where
+        S: Stage<T>,
+    {
+        loop {
+            let mut attempts = 0;
+            let next = loop {
+                attempts += 1;
+                self.annotate(&format!("stage {} (attempt {})", stage.name(), attempts));
+                let result = match stage.timeout() {
+                    Some(timeout) => {
+                        match time::timeout(timeout, with_deadline(timeout, stage.run(self))).await
+                        {
+                            Ok(result) => result,
+                            Err(_) => Err(io::Error::new(
+                                io::ErrorKind::TimedOut,
+                                format!("stage timed out after {:?}", timeout),
+                            )),
+                        }
+                    }
+                    None => stage.run(self).await,
+                };
+                match result {
+                    Ok(next) => break next,
+                    Err(error) if attempts > stage.retries() => {
+                        self.annotate(&format!("stage {} failed: {}", stage.name(), error));
+                        return Err(StageError {
+                            stage: stage.name().to_string(),
+                            attempts,
+                            error,
+                        });
+                    }
+                    Err(error) => {
+                        let name = stage.name();
+                        let label = self.traffic.label(None);
+                        debug!(target: "Tube::run_stages", "[{}] Stage {} failed: {}", label, name, error);
+                    }
+                }
+            };
+            match next {
+                Next::Stage(next) => stage = next,
+                Next::Done(output) => return Ok(output),
+            }
+        }
+    }
+}
+
+/// The error returned by [`Tube::run_stages`].
+#[derive(Debug)]
+pub struct StageError {
+    /// The name of the stage that failed.
+    pub stage: String,
+    /// The number of times the stage was run.
+    pub attempts: usize,
+    /// The error of the last attempt.
+    pub error: io::Error,
+}
+
+impl fmt::Display for StageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stage {} failed after {} attempt(s): {}",
+            self.stage, self.attempts, self.error
+        )
+    }
+}
+
+impl Error for StageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<StageError> for io::Error {
+    fn from(err: StageError) -> Self {
+        io::Error::new(err.error.kind(), err)
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use crate::utils::clock;
+
+use super::{Direction, Tube};
+
+/// The traffic of a tube, returned by [`Tube::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TubeStats {
+    /// The number of bytes sent.
+    pub bytes_sent: u64,
+    /// The number of bytes received.
+    pub bytes_received: u64,
+    /// The number of writes that sent data.
+    pub writes: u64,
+    /// The number of reads that received data.
+    pub reads: u64,
+    /// The time since the tube is created or the statistics are reset.
+    pub elapsed: Duration,
+    /// The time from the first to the last write or read.
+    pub active: Duration,
+}
+
+/// Counts the traffic for [`TubeStats`]. The times stay zero without a clock, e.g. on
+/// `wasm32-unknown-unknown`.
+#[derive(Debug, Clone)]
+pub(super) struct StatsCounter {
+    stats: TubeStats,
+    start: Option<Instant>,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl Default for StatsCounter {
+    fn default() -> Self {
+        Self {
+            stats: TubeStats::default(),
+            start: clock::now(),
+            first: None,
+            last: None,
+        }
+    }
+}
+
+impl StatsCounter {
+    pub(super) fn count(&mut self, direction: Direction, len: usize) {
+        match direction {
+            Direction::Send => {
+                self.stats.bytes_sent += len as u64;
+                self.stats.writes += 1;
+            }
+            Direction::Recv => {
+                self.stats.bytes_received += len as u64;
+                self.stats.reads += 1;
+            }
+        }
+        if let Some(now) = clock::now() {
+            self.first.get_or_insert(now);
+            self.last = Some(now);
+        }
+    }
+
+    /// Add up the traffic counted by the other half of a split tube.
+    pub(super) fn merge(&mut self, other: StatsCounter) {
+        self.stats.bytes_sent += other.stats.bytes_sent;
+        self.stats.bytes_received += other.stats.bytes_received;
+        self.stats.writes += other.stats.writes;
+        self.stats.reads += other.stats.reads;
+        self.first = self.first.into_iter().chain(other.first).min();
+        self.last = self.last.into_iter().chain(other.last).max();
+    }
+
+    fn snapshot(&self) -> TubeStats {
+        let active = match (self.first, self.last) {
+            (Some(first), Some(last)) => last - first,
+            _ => Duration::ZERO,
+        };
+        TubeStats {
+            elapsed: self.start.map_or(Duration::ZERO, |start| start.elapsed()),
+            active,
+            ..self.stats.clone()
+        }
+    }
+}
+
+impl<T> Tube<T> {
+    /// Returns the traffic of the tube so far, e.g. to measure the throughput or to check that an
+    /// exchange stays within the expected size. Data put back by the tube itself is not counted
+    /// again.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn stats() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("Hello\n").await?;
+    ///     p.recv_line().await?;
+    ///     let stats = p.stats();
+    ///     assert_eq!(stats.bytes_sent, 6);
+    ///     assert_eq!(stats.bytes_received, 6);
+    ///     assert_eq!(stats.writes, 1);
+    ///     assert!(stats.active <= stats.elapsed);
+    ///
+    ///     p.reset_stats();
+    ///     assert_eq!(p.stats().bytes_sent, 0);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// stats();
+    /// ```
+    pub fn stats(&self) -> TubeStats {
+        self.traffic.stats.snapshot()
+    }
+
+    /// Reset the statistics returned by [`Tube::stats`] to zero.
+    pub fn reset_stats(&mut self) {
+        self.traffic.stats = StatsCounter::default();
+    }
+}
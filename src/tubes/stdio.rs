@@ -0,0 +1,38 @@
+use tokio::io::{self, BufReader, Join, Stdin, Stdout};
+
+use super::Tube;
+
+/// A tube over the stdin and stdout of this process, created by [`Tube::stdio`].
+pub type StdioTube = Tube<BufReader<Join<Stdin, Stdout>>>;
+
+impl Tube<BufReader<Join<Stdin, Stdout>>> {
+    /// Receive from the stdin and send to the stdout of this process, e.g. when it is run by
+    /// inetd or socat as the program being connected to. Together with [`Tube::boxed`], the same
+    /// code can talk to a remote target or over stdio, chosen at runtime.
+    ///
+    /// The logger must not write to stdout, or the log ends up in the traffic.
+    /// ```rust
+    /// use io_tubes::tubes::{BoxTube, Tube};
+    /// use std::io;
+    ///
+    /// async fn connect(remote: Option<String>) -> io::Result<BoxTube> {
+    ///     Ok(match remote {
+    ///         Some(addr) => Tube::remote(addr).await?.boxed(),
+    ///         None => Tube::stdio().boxed(),
+    ///     })
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn stdio() -> io::Result<()> {
+    ///     let mut p = connect(std::env::var("REMOTE").ok()).await?;
+    ///     p.send_line("Hello").await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// stdio();
+    /// ```
+    pub fn stdio() -> Self {
+        Tube::new(io::join(io::stdin(), io::stdout()))
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use super::Tube;
+
+/// Yields the data in chunks as it arrives, and ends when EOF is reached.
+///
+/// The timeout of the tube doesn't apply, use [`tokio::time::timeout`] on the futures instead.
+/// ```rust
+/// use futures::{SinkExt, StreamExt};
+/// use io_tubes::{bytes::Bytes, tubes::Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn stream() -> io::Result<()> {
+///     let mut p = Tube::echo();
+///
+///     p.feed(Bytes::from_static(b"Hello ")).await?;
+///     p.feed(Bytes::from_static(b"World")).await?;
+///     SinkExt::flush(&mut p).await?;
+///
+///     let mut received = Vec::new();
+///     while received.len() < 11 {
+///         received.extend_from_slice(&p.next().await.unwrap()?);
+///     }
+///     assert_eq!(received, b"Hello World");
+///
+///     Ok(())
+/// }
+///
+/// stream();
+/// ```
+impl<T> Stream for Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let chunk = match self.as_mut().poll_fill_buf(cx)? {
+            Poll::Ready([]) => return Poll::Ready(None),
+            Poll::Ready(buf) => Bytes::copy_from_slice(buf),
+            Poll::Pending => return Poll::Pending,
+        };
+        self.consume(chunk.len());
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+/// Sends each item in order. An item is buffered until the previous one is written completely,
+/// and flushing waits for all of them to be sent.
+impl<T> Sink<Bytes> for Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.get_mut().poll_send_queue(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> io::Result<()> {
+        self.get_mut().send_queue.push(&item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        if AsyncWrite::poll_flush(self.as_mut(), cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        self.poll_shutdown(cx)
+    }
+}
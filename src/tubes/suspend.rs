@@ -0,0 +1,165 @@
+use std::io;
+
+use tokio::process::Command;
+
+use super::ProcessTube;
+
+impl ProcessTube {
+    /// Resume the process started with
+    /// [`start_suspended`](super::ProcessOptions::start_suspended). Resuming a process that is
+    /// not suspended does nothing.
+    /// ```rust
+    /// use io_tubes::tubes::{ProcessOptions, ProcessTube, Tube};
+    /// use std::io;
+    /// use tokio::process::Command;
+    ///
+    /// #[tokio::main]
+    /// async fn resume() -> io::Result<()> {
+    ///     let options = ProcessOptions {
+    ///         start_suspended: true,
+    ///     };
+    ///     let p = ProcessTube::from_command_with(Command::new("/usr/bin/cat"), &options)?;
+    ///     // Attach the debugger to p.id() here.
+    ///     p.resume()?;
+    ///
+    ///     let mut p = Tube::new(p);
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// resume();
+    /// ```
+    pub fn resume(&self) -> io::Result<()> {
+        let pid = self.id().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "the process has already exited")
+        })?;
+        imp::resume(pid)
+    }
+}
+
+pub(super) fn spawn_suspended(command: Command) -> io::Result<ProcessTube> {
+    imp::spawn_suspended(command)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+mod imp {
+    use std::io;
+
+    use nix::{
+        errno::Errno,
+        sys::{
+            signal::{kill, Signal},
+            wait::{waitid, Id, WaitPidFlag, WaitStatus},
+        },
+        unistd::Pid,
+    };
+    use tokio::process::Command;
+
+    use super::super::{process::wrap_command, ProcessTube};
+
+    pub(super) fn spawn_suspended(command: Command) -> io::Result<ProcessTube> {
+        let mut trampoline = Command::new("/bin/sh");
+        trampoline.args(["-c", "kill -STOP $$ && exec \"$@\"", "sh"]);
+        let tube = ProcessTube::from_command(wrap_command(&command, trampoline))?;
+        let pid = Pid::from_raw(tube.id().unwrap_or_default() as i32);
+        // The exit is left to be reaped by tokio.
+        let flags = WaitPidFlag::WSTOPPED | WaitPidFlag::WEXITED | WaitPidFlag::WNOWAIT;
+        let status = loop {
+            match waitid(Id::Pid(pid), flags) {
+                Err(Errno::EINTR) => continue,
+                status => break status?,
+            }
+        };
+        match status {
+            WaitStatus::Stopped(..) => Ok(tube),
+            _ => Err(io::Error::other(
+                "the process exited before it was suspended",
+            )),
+        }
+    }
+
+    pub(super) fn resume(pid: u32) -> io::Result<()> {
+        kill(Pid::from_raw(pid as i32), Signal::SIGCONT)?;
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::{io, mem};
+
+    use tokio::process::Command;
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, INVALID_HANDLE_VALUE},
+        System::{
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD,
+                THREADENTRY32,
+            },
+            Threading::{OpenThread, ResumeThread, CREATE_SUSPENDED, THREAD_SUSPEND_RESUME},
+        },
+    };
+
+    use super::super::ProcessTube;
+
+    pub(super) fn spawn_suspended(mut command: Command) -> io::Result<ProcessTube> {
+        command.creation_flags(CREATE_SUSPENDED);
+        ProcessTube::from_command(command)
+    }
+
+    /// Resume every thread of the process, since the handle of the main thread is not kept.
+    pub(super) fn resume(pid: u32) -> io::Result<()> {
+        // SAFETY: the handles are checked before use and closed after.
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+            if snapshot == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+            let mut entry: THREADENTRY32 = mem::zeroed();
+            entry.dwSize = mem::size_of::<THREADENTRY32>() as u32;
+            let mut result = Ok(());
+            let mut more = Thread32First(snapshot, &mut entry) != 0;
+            while more {
+                if entry.th32OwnerProcessID == pid {
+                    let thread = OpenThread(THREAD_SUSPEND_RESUME, 0, entry.th32ThreadID);
+                    if thread.is_null() || ResumeThread(thread) == u32::MAX {
+                        result = Err(io::Error::last_os_error());
+                    }
+                    if !thread.is_null() {
+                        CloseHandle(thread);
+                    }
+                }
+                more = Thread32Next(snapshot, &mut entry) != 0;
+            }
+            CloseHandle(snapshot);
+            result
+        }
+    }
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "linux", target_os = "android", target_os = "freebsd"))
+))]
+mod imp {
+    use std::io;
+
+    use nix::sys::signal::{kill, Signal};
+    use tokio::process::Command;
+
+    use super::super::ProcessTube;
+
+    pub(super) fn spawn_suspended(_command: Command) -> io::Result<ProcessTube> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "starting a process suspended is not supported on this platform",
+        ))
+    }
+
+    pub(super) fn resume(pid: u32) -> io::Result<()> {
+        kill(nix::unistd::Pid::from_raw(pid as i32), Signal::SIGCONT)?;
+        Ok(())
+    }
+}
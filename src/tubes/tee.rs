@@ -0,0 +1,244 @@
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+
+/// What [`TeeWrite`] does when writing to a sink fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeePolicy {
+    /// Fail the write to the tube with the error of the sink.
+    FailFast,
+    /// Stop writing to the sink and keep the error, see [`TeeWrite::sink_error`].
+    BestEffort,
+}
+
+type Sink = Pin<Box<dyn AsyncWrite + Send>>;
+
+struct TeeSink {
+    writer: Sink,
+    policy: TeePolicy,
+    /// The data sent to the inner stream but not written to the sink yet.
+    pending: Vec<u8>,
+    error: Option<io::Error>,
+}
+
+impl TeeSink {
+    /// Write the pending data, returning the error if the policy is to fail fast.
+    fn poll_drain(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while self.error.is_none() && !self.pending.is_empty() {
+            let result = match ready!(self.writer.as_mut().poll_write(cx, &self.pending)) {
+                Ok(0) => Err(io::ErrorKind::WriteZero.into()),
+                result => result,
+            };
+            match result {
+                Ok(len) => {
+                    self.pending.drain(..len);
+                }
+                Err(err) => self.fail(err)?,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.poll_drain(cx))?;
+        if self.error.is_none() {
+            if let Err(err) = ready!(self.writer.as_mut().poll_flush(cx)) {
+                self.fail(err)?;
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn fail(&mut self, err: io::Error) -> io::Result<()> {
+        self.pending.clear();
+        match self.policy {
+            TeePolicy::FailFast => Err(err),
+            TeePolicy::BestEffort => {
+                self.error = Some(err);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TeeSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TeeSink")
+            .field("policy", &self.policy)
+            .field("pending", &self.pending.len())
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Duplicates the data sent to the inner stream into additional sinks like files or channels,
+/// e.g. to mirror the traffic of a live session to an analysis tool. Reads pass through.
+///
+/// The data is written to the sinks only after the inner stream accepted it, and a sink that is
+/// not ready holds back further writes to the tube. The policy of each sink decides whether its
+/// errors fail the tube or just stop the mirroring to it.
+/// ```rust
+/// use io_tubes::tubes::{TeePolicy, TeeWrite, Tube};
+/// use std::io;
+/// use tokio::io::{duplex, AsyncReadExt};
+///
+/// #[tokio::main]
+/// async fn tee() -> io::Result<()> {
+///     let (sink, mut mirror) = duplex(64);
+///     let mut p = Tube::echo()
+///         .map_inner(|inner| TeeWrite::new(inner).sink(sink, TeePolicy::BestEffort));
+///
+///     p.send_line("Hello").await?;
+///     assert_eq!(p.recv_line().await?, b"Hello\n");
+///
+///     let mut mirrored = [0; 6];
+///     mirror.read_exact(&mut mirrored).await?;
+///     assert_eq!(&mirrored, b"Hello\n");
+///     assert!(p.inner.get_ref().sink_error(0).is_none());
+///
+///     Ok(())
+/// }
+///
+/// tee();
+/// ```
+#[derive(Debug)]
+pub struct TeeWrite<T> {
+    inner: T,
+    sinks: Vec<TeeSink>,
+}
+
+impl<T> TeeWrite<T> {
+    /// Pass the data through to the inner stream without any sink yet.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Add a sink receiving a copy of everything sent from now on. Sinks are numbered in the
+    /// order they are added, starting from 0.
+    pub fn sink(mut self, writer: impl AsyncWrite + Send + 'static, policy: TeePolicy) -> Self {
+        self.sinks.push(TeeSink {
+            writer: Box::pin(writer),
+            policy,
+            pending: Vec::new(),
+            error: None,
+        });
+        self
+    }
+
+    /// The error that stopped the mirroring to the sink with [`TeePolicy::BestEffort`], if any.
+    /// The sinks with [`TeePolicy::FailFast`] report their error to the tube instead.
+    pub fn sink_error(&self, index: usize) -> Option<&io::Error> {
+        self.sinks.get(index)?.error.as_ref()
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data written directly is not mirrored.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the adapter, returning the inner stream. Data not written to the sinks yet is
+    /// lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// The error of a sink with [`TeePolicy::FailFast`] that happened after the data was already
+    /// sent, which is reported by the next write or flush.
+    fn take_error(&mut self) -> io::Result<()> {
+        match self
+            .sinks
+            .iter_mut()
+            .find(|sink| sink.policy == TeePolicy::FailFast && sink.error.is_some())
+        {
+            Some(sink) => Err(sink.error.take().unwrap()),
+            None => Ok(()),
+        }
+    }
+
+    fn poll_drain_sinks(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.take_error()?;
+        let mut pending = false;
+        for sink in &mut self.sinks {
+            pending |= sink.poll_drain(cx)?.is_pending();
+        }
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for TeeWrite<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncBufRead + Unpin> AsyncBufRead for TeeWrite<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.get_mut().inner).consume(amt)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for TeeWrite<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain_sinks(cx))?;
+        let len = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        for sink in &mut this.sinks {
+            if sink.error.is_none() {
+                sink.pending.extend_from_slice(&buf[..len]);
+            }
+        }
+        // The data is already sent, so the sinks are only given a chance to make progress here.
+        for sink in &mut this.sinks {
+            if let Poll::Ready(Err(err)) = sink.poll_drain(cx) {
+                sink.error = Some(err);
+            }
+        }
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.take_error()?;
+        let mut pending = false;
+        for sink in &mut this.sinks {
+            pending |= sink.poll_flush(cx)?.is_pending();
+        }
+        if pending {
+            return Poll::Pending;
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::HashSet,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, BufReader, ReadBuf};
+
+use super::Tube;
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+/// Where the parser is in the telnet commands received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    /// After a CR, which may be followed by a NUL that is dropped.
+    Cr,
+    Iac,
+    /// After `IAC WILL`, `IAC WONT`, `IAC DO` or `IAC DONT`, waiting for the option.
+    Negotiate(u8),
+    Sub,
+    SubIac,
+}
+
+/// Speaks the telnet protocol over the inner stream, so that the tube sees the application data
+/// only. The option negotiations are answered and the other commands are dropped from the
+/// received data, and `0xff` bytes are escaped when sending.
+///
+/// Every option is refused unless it is accepted by [`TelnetTube::accept`]. A CR followed by NUL
+/// is received as a bare CR, and the other line endings are left alone.
+/// ```rust
+/// use io_tubes::tubes::Tube;
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn telnet() -> io::Result<()> {
+///     let (p, mut server) = Tube::pair();
+///     let mut p = p.telnet();
+///
+///     // IAC DO ECHO, IAC WILL SUPPRESS-GO-AHEAD
+///     server.send(b"\xff\xfd\x01\xff\xfb\x03login: ").await?;
+///     assert_eq!(p.recv_until("login: ").await?, b"login: ");
+///     p.send_line(b"r\xffot").await?;
+///
+///     // IAC WONT ECHO, IAC DONT SUPPRESS-GO-AHEAD
+///     assert_eq!(server.recv_checked(6).await?, b"\xff\xfc\x01\xff\xfe\x03");
+///     assert_eq!(server.recv_line().await?, b"r\xff\xffot\n");
+///
+///     Ok(())
+/// }
+///
+/// telnet();
+/// ```
+#[derive(Debug)]
+pub struct TelnetTube<T> {
+    inner: T,
+    state: State,
+    /// The options that are agreed to be enabled on either side.
+    accepted: HashSet<u8>,
+    /// The options that are enabled on either side, so that requests to enable them again are not
+    /// answered in a loop.
+    enabled: HashSet<(u8, u8)>,
+    /// The bytes to write to the inner stream before any more data, i.e. the answers and the
+    /// escaped `0xff`.
+    pending: Vec<u8>,
+}
+
+impl<T> TelnetTube<T> {
+    /// Speak telnet over `inner`, refusing every option.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            state: State::Data,
+            accepted: HashSet::new(),
+            enabled: HashSet::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Agree to enable the option on either side when the peer asks to, e.g. 1 for ECHO or 3 for
+    /// SUPPRESS-GO-AHEAD.
+    pub fn accept(mut self, option: u8) -> Self {
+        self.accepted.insert(option);
+        self
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data transferred directly bypasses the
+    /// protocol.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the adapter to get back the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Answer `IAC <command> <option>`.
+    fn negotiate(&mut self, command: u8, option: u8) {
+        // The side of the option, and the answers that enable and disable it.
+        let (side, agree, refuse) = match command {
+            DO | DONT => (DO, WILL, WONT),
+            _ => (WILL, DO, DONT),
+        };
+        let answer = match command {
+            DO | WILL if self.accepted.contains(&option) => {
+                if !self.enabled.insert((side, option)) {
+                    return;
+                }
+                agree
+            }
+            DO | WILL => refuse,
+            _ => {
+                // Disabling is only acknowledged if the option is enabled.
+                if !self.enabled.remove(&(side, option)) {
+                    return;
+                }
+                refuse
+            }
+        };
+        self.pending.extend_from_slice(&[IAC, answer, option]);
+    }
+
+    /// Drop the telnet commands from `data` in place, and return the length of the application
+    /// data left.
+    fn filter(&mut self, data: &mut [u8]) -> usize {
+        let mut len = 0;
+        for i in 0..data.len() {
+            let byte = data[i];
+            self.state = match (self.state, byte) {
+                (State::Data | State::Cr, IAC) => State::Iac,
+                (State::Cr, 0) => State::Data,
+                (State::Data | State::Cr, _) => {
+                    data[len] = byte;
+                    len += 1;
+                    match byte {
+                        b'\r' => State::Cr,
+                        _ => State::Data,
+                    }
+                }
+                (State::Iac, IAC) => {
+                    data[len] = IAC;
+                    len += 1;
+                    State::Data
+                }
+                (State::Iac, WILL | WONT | DO | DONT) => State::Negotiate(byte),
+                (State::Iac, SB) => State::Sub,
+                (State::Iac, _) => State::Data,
+                (State::Negotiate(command), _) => {
+                    self.negotiate(command, byte);
+                    State::Data
+                }
+                (State::Sub, IAC) => State::SubIac,
+                (State::Sub, _) => State::Sub,
+                (State::SubIac, SE) => State::Data,
+                (State::SubIac, _) => State::Sub,
+            };
+        }
+        len
+    }
+}
+
+impl<T> TelnetTube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Write the pending bytes to the inner stream.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let numb = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending))?;
+            if numb == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending.drain(..numb);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Speak telnet over the tube, refusing every option, see [`TelnetTube`]. To enable some,
+    /// use [`Tube::map_inner`] with [`TelnetTube::accept`] instead.
+    pub fn telnet(self) -> Tube<BufReader<TelnetTube<T>>> {
+        self.map_inner(TelnetTube::new)
+    }
+}
+
+impl<T> AsyncRead for TelnetTube<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            // The answers are sent on a best effort basis, and retried by the next read or write.
+            if let Poll::Ready(Err(err)) = this.poll_pending(cx) {
+                return Poll::Ready(Err(err));
+            }
+            let mut raw = ReadBuf::new(buf.initialize_unfilled());
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut raw))?;
+            let numb = raw.filled().len();
+            if numb == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            let len = this.filter(&mut buf.initialize_unfilled()[..numb]);
+            buf.advance(len);
+            if len > 0 {
+                if let Poll::Ready(Err(err)) = this.poll_pending(cx) {
+                    return Poll::Ready(Err(err));
+                }
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+impl<T> AsyncWrite for TelnetTube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        match buf.iter().position(|&byte| byte == IAC) {
+            Some(0) => {
+                this.pending.extend_from_slice(&[IAC, IAC]);
+                // The escaped byte is written by the next write or flush.
+                Poll::Ready(Ok(1))
+            }
+            Some(pos) => Pin::new(&mut this.inner).poll_write(cx, &buf[..pos]),
+            None => Pin::new(&mut this.inner).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
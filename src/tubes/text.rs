@@ -0,0 +1,226 @@
+use std::{fmt, io};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite};
+
+use crate::utils::{timeout, Needle};
+
+use super::Tube;
+
+/// Decode the data as UTF-8, failing with [`InvalidData`](io::ErrorKind::InvalidData). The data
+/// can be recovered from the [`FromUtf8Error`](std::string::FromUtf8Error) inside the error.
+fn utf8(data: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Same as [`recv_line`](Tube::recv_line), but returns a string where invalid UTF-8 is
+    /// replaced with `U+FFFD`, e.g. to parse a menu.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_line_str() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send(b"1. Add\n2. \xff\n").await?;
+    ///     assert_eq!(p.recv_line_str().await?, "1. Add\n");
+    ///     assert_eq!(p.recv_line_str().await?, "2. \u{fffd}\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_line_str();
+    /// ```
+    pub async fn recv_line_str(&mut self) -> io::Result<String> {
+        Ok(String::from_utf8_lossy(&self.recv_line().await?).into_owned())
+    }
+
+    /// Same as [`recv_until`](Tube::recv_until), but returns a string where invalid UTF-8 is
+    /// replaced with `U+FFFD`.
+    pub async fn recv_until_str(&mut self, delims: impl Needle) -> io::Result<String> {
+        Ok(String::from_utf8_lossy(&self.recv_until(delims).await?).into_owned())
+    }
+
+    /// Same as [`recv_all`](Tube::recv_all), but returns a string where invalid UTF-8 is
+    /// replaced with `U+FFFD`.
+    pub async fn recv_all_str(&mut self) -> io::Result<String> {
+        Ok(String::from_utf8_lossy(&self.recv_all().await?).into_owned())
+    }
+
+    /// Same as [`recv_line_str`](Tube::recv_line_str), but fails with
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the line is not valid UTF-8. The line is
+    /// consumed either way, and can be recovered from the
+    /// [`FromUtf8Error`](std::string::FromUtf8Error) inside the error.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, string::FromUtf8Error};
+    ///
+    /// #[tokio::main]
+    /// async fn recv_line_utf8() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send(b"caf\xc3\xa9\nbad \xff\n").await?;
+    ///     assert_eq!(p.recv_line_utf8().await?, "caf\u{e9}\n");
+    ///
+    ///     let err = p.recv_line_utf8().await.unwrap_err();
+    ///     assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    ///     let err = err.into_inner().unwrap().downcast::<FromUtf8Error>().unwrap();
+    ///     assert_eq!(err.into_bytes(), b"bad \xff\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_line_utf8();
+    /// ```
+    pub async fn recv_line_utf8(&mut self) -> io::Result<String> {
+        utf8(self.recv_line().await?)
+    }
+
+    /// Same as [`recv_until_str`](Tube::recv_until_str), but fails with
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the data is not valid UTF-8.
+    pub async fn recv_until_utf8(&mut self, delims: impl Needle) -> io::Result<String> {
+        utf8(self.recv_until(delims).await?)
+    }
+
+    /// Same as [`recv_all_str`](Tube::recv_all_str), but fails with
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the data is not valid UTF-8.
+    pub async fn recv_all_utf8(&mut self) -> io::Result<String> {
+        utf8(self.recv_all().await?)
+    }
+
+    /// Receive an unsigned integer in ASCII, skipping everything before the first digit. It is
+    /// decimal unless it starts with `0x`, in which case it is hex. A sign is skipped like any
+    /// other byte before the digits.
+    ///
+    /// Fails with [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) or
+    /// [`TimedOut`](io::ErrorKind::TimedOut) if no digit is received, and with
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the integer overflows a `u64`.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_int() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("You have 1337 coins, puts is at 0x7ffff7a62aa0\n").await?;
+    ///     assert_eq!(p.recv_int().await?, 1337);
+    ///     assert_eq!(p.recv_int().await?, 0x7ffff7a62aa0);
+    ///     assert_eq!(p.recv_line().await?, b"\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_int();
+    /// ```
+    pub async fn recv_int(&mut self) -> io::Result<u64> {
+        self.recv_number(10).await
+    }
+
+    /// Same as [`recv_int`](Tube::recv_int), but the integer is always hex, with or without
+    /// `0x`.
+    pub async fn recv_hex(&mut self) -> io::Result<u64> {
+        self.recv_number(16).await
+    }
+
+    /// Receive until the pattern, then receive an integer right after it. The integer is hex if
+    /// the pattern ends with `0x`, otherwise it is parsed as in [`recv_int`](Tube::recv_int).
+    ///
+    /// Fails with [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) or
+    /// [`TimedOut`](io::ErrorKind::TimedOut) if the pattern is not found.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_int_after() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("Welcome to v2.1\nYour address: 0x55555555a2c0\nSize (max 64): ").await?;
+    ///     assert_eq!(p.recv_int_after("Your address: 0x").await?, 0x55555555a2c0);
+    ///
+    ///     p.recv_until("Size (max 64): ").await?;
+    ///     p.send_dec_line(-1).await?;
+    ///     assert_eq!(p.recv_line().await?, b"-1\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_int_after();
+    /// ```
+    pub async fn recv_int_after(&mut self, pattern: impl Needle) -> io::Result<u64> {
+        let data = self
+            .recv_until_checked(pattern)
+            .await
+            .map_err(io::Error::from)?;
+        match data.ends_with(b"0x") || data.ends_with(b"0X") {
+            true => self.recv_hex().await,
+            false => self.recv_int().await,
+        }
+    }
+
+    async fn recv_number(&mut self, mut radix: u32) -> io::Result<u64> {
+        let mut digits = String::new();
+        let mut started = false;
+        let recv_timeout = self.recv_timeout();
+        let recv = async {
+            loop {
+                let buf = self.fill_buf().await?;
+                if buf.is_empty() {
+                    break;
+                }
+                let mut used = 0;
+                let mut ended = false;
+                for &byte in buf {
+                    let is_digit = char::from(byte).is_digit(radix);
+                    if is_digit {
+                        digits.push(char::from(byte));
+                        started = true;
+                    } else if started && matches!(byte, b'x' | b'X') && digits == "0" {
+                        digits.clear();
+                        radix = 16;
+                    } else if started {
+                        ended = true;
+                        break;
+                    }
+                    used += 1;
+                }
+                self.consume(used);
+                if ended {
+                    break;
+                }
+            }
+            io::Result::Ok(())
+        };
+        timeout(recv_timeout, recv)
+            .await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "recv timed out")))?;
+        if !started {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "EOF before an integer",
+            ));
+        }
+        u64::from_str_radix(&digits, radix)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Send the integer in decimal ASCII, e.g. to answer a prompt for a size or an index.
+    pub async fn send_dec(&mut self, value: impl fmt::Display) -> io::Result<()> {
+        self.send(value.to_string()).await
+    }
+
+    /// Same as [`send_dec`](Tube::send_dec), followed by a new line.
+    pub async fn send_dec_line(&mut self, value: impl fmt::Display) -> io::Result<()> {
+        self.send_line(value.to_string()).await
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{self, Instant, Sleep},
+};
+
+/// Limits the rate of a direction with a token bucket.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: u64,
+    burst: u64,
+    tokens: f64,
+    refilled: Instant,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        assert!(rate > 0, "rate must not be 0");
+        let burst = (rate / 10).max(1);
+        Self {
+            rate,
+            burst,
+            tokens: burst as f64,
+            refilled: Instant::now(),
+            sleep: None,
+        }
+    }
+
+    fn set_burst(&mut self, burst: u64) {
+        self.burst = burst;
+        self.tokens = self.tokens.min(burst as f64);
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.refilled = now;
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.burst as f64);
+    }
+
+    /// The number of bytes up to `want` that can be transferred now. Waits until `want` bytes or
+    /// a full burst are available, so that the transfers are not needlessly small.
+    fn poll_available(&mut self, cx: &mut Context<'_>, want: usize) -> Poll<usize> {
+        loop {
+            self.refill();
+            let needed = (want as u64).clamp(1, self.burst) as f64;
+            if self.tokens >= needed {
+                self.sleep = None;
+                return Poll::Ready((self.tokens as usize).min(want));
+            }
+            let wait = Duration::from_secs_f64((needed - self.tokens) / self.rate as f64);
+            let deadline = self.refilled + wait;
+            let sleep = self
+                .sleep
+                .get_or_insert_with(|| Box::pin(time::sleep_until(deadline)));
+            sleep.as_mut().reset(deadline);
+            ready!(sleep.as_mut().poll(cx));
+        }
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.tokens -= len as f64;
+    }
+}
+
+/// Caps the number of bytes sent per second, and optionally received per second, of the inner
+/// stream with a token bucket. Useful for services that ban clients sending too fast.
+///
+/// Bursts of up to a tenth of a second worth of data are allowed by default, see
+/// [`ThrottleTube::burst`].
+/// ```rust
+/// use io_tubes::tubes::{ThrottleTube, Tube};
+/// use std::{
+///     io,
+///     time::{Duration, Instant},
+/// };
+///
+/// #[tokio::main]
+/// async fn throttle() -> io::Result<()> {
+///     let mut p = Tube::echo().map_inner(|inner| ThrottleTube::new(inner, 1000));
+///
+///     let start = Instant::now();
+///     p.send_line(vec![b'A'; 299]).await?;
+///     assert!(start.elapsed() >= Duration::from_millis(150));
+///     assert_eq!(p.recv_line().await?.len(), 300);
+///
+///     Ok(())
+/// }
+///
+/// throttle();
+/// ```
+#[derive(Debug)]
+pub struct ThrottleTube<T> {
+    inner: T,
+    send: TokenBucket,
+    recv: Option<TokenBucket>,
+    burst: Option<u64>,
+}
+
+impl<T> ThrottleTube<T> {
+    /// Send at most `bytes_per_sec` bytes per second to the inner stream. Panics if the rate is
+    /// 0.
+    pub fn new(inner: T, bytes_per_sec: u64) -> Self {
+        Self {
+            inner,
+            send: TokenBucket::new(bytes_per_sec),
+            recv: None,
+            burst: None,
+        }
+    }
+
+    /// Also receive at most `bytes_per_sec` bytes per second from the inner stream. Panics if
+    /// the rate is 0.
+    pub fn recv_rate(mut self, bytes_per_sec: u64) -> Self {
+        let mut recv = TokenBucket::new(bytes_per_sec);
+        if let Some(burst) = self.burst {
+            recv.set_burst(burst);
+        }
+        self.recv = Some(recv);
+        self
+    }
+
+    /// Allow bursts of at most `bytes` bytes in both directions. Panics if it is 0.
+    pub fn burst(mut self, bytes: u64) -> Self {
+        assert!(bytes > 0, "burst must not be 0");
+        self.burst = Some(bytes);
+        self.send.set_burst(bytes);
+        if let Some(recv) = &mut self.recv {
+            recv.set_burst(bytes);
+        }
+        self
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data transferred directly is not throttled.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the throttle to get back the inner stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> AsyncRead for ThrottleTube<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let Some(recv) = &mut this.recv else {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        };
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let allowed = ready!(recv.poll_available(cx, buf.remaining()));
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(allowed));
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let len = limited.filled().len();
+        recv.consume(len);
+        buf.advance(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncWrite for ThrottleTube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        let allowed = ready!(this.send.poll_available(cx, buf.len()));
+        let numb = ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..allowed]))?;
+        this.send.consume(numb);
+        Poll::Ready(Ok(numb))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
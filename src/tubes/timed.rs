@@ -0,0 +1,177 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, ReadBuf};
+
+use crate::utils::{timeout, Needle, RecvNeedle};
+
+use super::{limit::Limited, Tube, TubeError};
+
+/// Data received by [`Tube::recv_timed`] or [`Tube::recv_until_timed`] together with the time
+/// that it arrives.
+///
+/// The time is taken when the data is read by the tube, so data that is already buffered by the
+/// tube arrives at the time of the call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timed {
+    /// The data received.
+    pub data: Vec<u8>,
+    /// When the first byte arrives, `None` if nothing is received.
+    pub first: Option<Instant>,
+    /// When the last byte arrives, `None` if nothing is received.
+    pub last: Option<Instant>,
+}
+
+impl Timed {
+    /// The time between the arrival of the first and the last byte.
+    pub fn spread(&self) -> Option<Duration> {
+        Some(self.last? - self.first?)
+    }
+}
+
+/// Records when data is read from the inner reader.
+struct TimedReader<'a, T> {
+    inner: &'a mut T,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl<'a, T> TimedReader<'a, T> {
+    fn new(inner: &'a mut T) -> Self {
+        Self {
+            inner,
+            first: None,
+            last: None,
+        }
+    }
+
+    fn finish(self, data: Vec<u8>) -> Timed {
+        Timed {
+            data,
+            first: self.first,
+            last: self.last,
+        }
+    }
+}
+
+impl<T> AsyncRead for TimedReader<'_, T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut *self.inner).poll_read(cx, buf))?;
+        if buf.filled().len() > filled {
+            let now = Instant::now();
+            self.first.get_or_insert(now);
+            self.last = Some(now);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncBufRead for TimedReader<'_, T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        let buf = ready!(Pin::new(&mut *this.inner).poll_fill_buf(cx))?;
+        if !buf.is_empty() {
+            let now = Instant::now();
+            this.first.get_or_insert(now);
+            this.last = Some(now);
+        }
+        Poll::Ready(Ok(buf))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut *self.inner).consume(amt);
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Same as recv, but also returns when the data arrives, for timing side channels.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Instant};
+    ///
+    /// #[tokio::main]
+    /// async fn recv_timed() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     let start = Instant::now();
+    ///     p.send("data").await?;
+    ///     let timed = p.recv_timed(4).await?;
+    ///     assert_eq!(timed.data, b"data");
+    ///     assert!(timed.first.unwrap() >= start);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_timed();
+    /// ```
+    pub async fn recv_timed(&mut self, len: usize) -> io::Result<Timed> {
+        let duration = self.recv_timeout();
+        let mut reader = TimedReader::new(self);
+        let mut buf = vec![0; len];
+        let numb = timeout(duration, reader.read(&mut buf[..]))
+            .await
+            .unwrap_or(Ok(0))?;
+        buf.truncate(numb);
+        Ok(reader.finish(buf))
+    }
+
+    /// Same as recv_until, but also returns when the first and the last byte arrive, e.g. to
+    /// time how long a password check takes before the response is complete.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    /// use tokio::time;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_timed() -> io::Result<()> {
+    ///     let (mut p, mut server) = Tube::pair();
+    ///
+    ///     let (timed, written) = tokio::join!(p.recv_until_timed("\n"), async move {
+    ///         server.send("Wrong ").await?;
+    ///         time::sleep(Duration::from_millis(50)).await;
+    ///         server.send("password\n").await
+    ///     });
+    ///     written?;
+    ///     let timed = timed?;
+    ///     assert_eq!(timed.data, b"Wrong password\n");
+    ///     assert!(timed.spread().unwrap() >= Duration::from_millis(50));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_timed();
+    /// ```
+    pub async fn recv_until_timed(&mut self, delims: impl Needle) -> io::Result<Timed> {
+        let duration = self.recv_timeout();
+        let max_size = self.max_recv_size;
+        let mut reader = TimedReader::new(self);
+        let mut limited = Limited::new(&mut reader, max_size);
+        let mut buf = Vec::new();
+        match timeout(duration, RecvNeedle::new(&mut limited, &delims, &mut buf)).await {
+            Ok(Err(_)) if limited.exceeded() => {
+                return Err(TubeError::TooLarge { partial: buf }.into())
+            }
+            Ok(result) => result?,
+            Err(_) => None,
+        };
+        Ok(reader.finish(buf))
+    }
+}
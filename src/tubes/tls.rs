@@ -0,0 +1,342 @@
+use std::{fmt, io, net::SocketAddr, sync::Arc};
+
+use tokio::{
+    io::BufReader,
+    net::{lookup_host, TcpStream, ToSocketAddrs},
+};
+use tokio_rustls::{
+    client,
+    rustls::{
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{self, CryptoProvider},
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        ClientConfig, CommonState, DigitallySignedStruct, ServerConfig, SignatureScheme,
+    },
+    server::TlsStream,
+    TlsAcceptor, TlsConnector,
+};
+
+use super::{intercept::intercept_tubes, Forwarder, Intercept, Listener, TlsInfo, Tube};
+
+/// The TLS settings of [`Listener::intercept_tls`]. TLS from the client is terminated with a
+/// certificate supplied by the user, and optionally a new TLS connection is made to the server,
+/// so that the hook sees the plaintext of both flows.
+#[derive(Clone)]
+pub struct TlsIntercept {
+    acceptor: TlsAcceptor,
+    upstream: Option<(TlsConnector, ServerName<'static>)>,
+}
+
+impl TlsIntercept {
+    /// Terminate TLS from the client with the certificate of the config, and connect to the
+    /// server in plaintext.
+    pub fn new(server_config: Arc<ServerConfig>) -> Self {
+        Self {
+            acceptor: TlsAcceptor::from(server_config),
+            upstream: None,
+        }
+    }
+
+    /// Also connect to the server with TLS, sending and verifying `server_name`. Fails with
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if it is not a valid DNS name or IP address.
+    pub fn upstream(
+        mut self,
+        client_config: Arc<ClientConfig>,
+        server_name: impl Into<String>,
+    ) -> io::Result<Self> {
+        let server_name = ServerName::try_from(server_name.into())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        self.upstream = Some((TlsConnector::from(client_config), server_name));
+        Ok(self)
+    }
+}
+
+impl fmt::Debug for TlsIntercept {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsIntercept")
+            .field(
+                "server_name",
+                &self.upstream.as_ref().map(|(_, server_name)| server_name),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Accepts any certificate of the server, for [`Tube::connect`] where the services of challenges
+/// often use self-signed certificates. Signatures are still checked so that the handshake is
+/// sound.
+#[derive(Debug)]
+struct AnyServerCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AnyServerCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// A client config that accepts any certificate of the server, see [`AnyServerCert`].
+pub(super) fn any_server_cert_config() -> Arc<ClientConfig> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("the default protocol versions are supported")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AnyServerCert(provider)))
+        .with_no_client_auth();
+    Arc::new(config)
+}
+
+impl Tube<BufReader<client::TlsStream<TcpStream>>> {
+    /// Create a tube by connecting to the address with TLS, sending and verifying `server_name`
+    /// with the config. Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if
+    /// `server_name` is not a valid DNS name or IP address.
+    pub async fn remote_tls(
+        addr: impl ToSocketAddrs,
+        server_name: &str,
+        client_config: Arc<ClientConfig>,
+    ) -> io::Result<Self> {
+        let server_name = ServerName::try_from(server_name.to_string())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let stream = TcpStream::connect(addr).await?;
+        let peer = stream.peer_addr();
+        let local = stream.local_addr();
+        let stream = TlsConnector::from(client_config)
+            .connect(server_name, stream)
+            .await?;
+        let tls = tls_info(stream.get_ref().1);
+        let mut tube = Self::new(stream);
+        tube.traffic.info.peer_addr = peer.as_ref().ok().copied();
+        tube.traffic.info.local_addr = local.ok();
+        tube.traffic.info.tls = Some(tls);
+        if let Ok(peer) = peer {
+            tube.traffic.describe("peer", peer);
+            tube.traffic.set_name(format!("tls:{}", peer));
+        }
+        Ok(tube)
+    }
+}
+
+/// The parameters negotiated by a TLS connection, for [`Tube::info`].
+fn tls_info(state: &CommonState) -> TlsInfo {
+    TlsInfo {
+        protocol: state
+            .protocol_version()
+            .map(|version| format!("{:?}", version)),
+        cipher_suite: state
+            .negotiated_cipher_suite()
+            .map(|suite| format!("{:?}", suite.suite())),
+        alpn: state.alpn_protocol().map(<[u8]>::to_vec),
+    }
+}
+
+/// A listener that completes a TLS handshake with the certificate of the config before returning
+/// the accepted connections, e.g. to impersonate a TLS service. Created by
+/// [`Listener::bind_tls`].
+pub struct TlsListener {
+    listener: Listener,
+    acceptor: TlsAcceptor,
+}
+
+impl TlsListener {
+    /// Accept TLS connections on the listener.
+    pub fn new(listener: Listener, server_config: Arc<ServerConfig>) -> Self {
+        Self {
+            listener,
+            acceptor: TlsAcceptor::from(server_config),
+        }
+    }
+
+    /// Accepts a connection and completes the handshake. A failed handshake is returned as an
+    /// error without affecting the later connections.
+    pub async fn accept(&self) -> io::Result<Tube<BufReader<TlsStream<TcpStream>>>> {
+        let (stream, peer) = self.listener.inner.accept().await?;
+        let local = stream.local_addr();
+        let stream = self.acceptor.accept(stream).await?;
+        let tls = tls_info(stream.get_ref().1);
+        let mut tube = Tube::new(stream);
+        tube.traffic.info.peer_addr = Some(peer);
+        tube.traffic.info.local_addr = local.ok();
+        tube.traffic.info.tls = Some(tls);
+        tube.traffic.describe("peer", peer);
+        tube.traffic.set_name(format!("accept:{}", peer));
+        Ok(tube)
+    }
+
+    /// Returns the port that is listened.
+    pub fn port(&self) -> io::Result<u16> {
+        self.listener.port()
+    }
+
+    /// Gets a reference to the inner listener.
+    pub fn get_ref(&self) -> &Listener {
+        &self.listener
+    }
+
+    /// Consume the TLS listener to get back the inner listener.
+    pub fn into_inner(self) -> Listener {
+        self.listener
+    }
+}
+
+impl Listener {
+    /// Create a listener by binding to the address, which completes a TLS handshake with the
+    /// certificate of the config for every accepted connection.
+    /// ```rust,no_run
+    /// use io_tubes::{
+    ///     tokio_rustls::rustls::{
+    ///         pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    ///         ServerConfig,
+    ///     },
+    ///     tubes::Listener,
+    /// };
+    /// use std::{io, sync::Arc};
+    ///
+    /// #[tokio::main]
+    /// async fn bind_tls() -> io::Result<()> {
+    ///     let cert = CertificateDer::pem_file_iter("server.crt")
+    ///         .and_then(|certs| certs.collect::<Result<_, _>>())
+    ///         .map_err(io::Error::other)?;
+    ///     let key = PrivateKeyDer::from_pem_file("server.key").map_err(io::Error::other)?;
+    ///     let server_config = ServerConfig::builder()
+    ///         .with_no_client_auth()
+    ///         .with_single_cert(cert, key)
+    ///         .map_err(io::Error::other)?;
+    ///
+    ///     let listener = Listener::bind_tls("0.0.0.0:443", Arc::new(server_config)).await?;
+    ///     let mut client = listener.accept().await?;
+    ///     let request = client.recv_until("\r\n\r\n").await?;
+    ///     println!("{}", String::from_utf8_lossy(&request));
+    ///     client.send("HTTP/1.0 200 OK\r\n\r\n").await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// bind_tls();
+    /// ```
+    pub async fn bind_tls(
+        addr: impl ToSocketAddrs,
+        server_config: Arc<ServerConfig>,
+    ) -> io::Result<TlsListener> {
+        Ok(TlsListener::new(Listener::bind(addr).await?, server_config))
+    }
+
+    /// Same as [`Listener::intercept`], but the client connects with TLS, see [`TlsIntercept`].
+    /// ```rust,no_run
+    /// use io_tubes::{
+    ///     tokio_rustls::rustls::{
+    ///         pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    ///         ClientConfig, RootCertStore, ServerConfig,
+    ///     },
+    ///     tubes::{Flow, Listener, TlsIntercept, Verdict},
+    /// };
+    /// use std::{io, sync::Arc};
+    ///
+    /// #[tokio::main]
+    /// async fn intercept_tls() -> io::Result<()> {
+    ///     let cert = CertificateDer::pem_file_iter("mitm.crt")
+    ///         .and_then(|certs| certs.collect::<Result<_, _>>())
+    ///         .map_err(io::Error::other)?;
+    ///     let key = PrivateKeyDer::from_pem_file("mitm.key").map_err(io::Error::other)?;
+    ///     let server_config = ServerConfig::builder()
+    ///         .with_no_client_auth()
+    ///         .with_single_cert(cert, key)
+    ///         .map_err(io::Error::other)?;
+    ///
+    ///     let mut roots = RootCertStore::empty();
+    ///     for cert in CertificateDer::pem_file_iter("ca.crt").map_err(io::Error::other)? {
+    ///         roots.add(cert.map_err(io::Error::other)?).map_err(io::Error::other)?;
+    ///     }
+    ///     let client_config = ClientConfig::builder()
+    ///         .with_root_certificates(roots)
+    ///         .with_no_client_auth();
+    ///
+    ///     let tls = TlsIntercept::new(Arc::new(server_config))
+    ///         .upstream(Arc::new(client_config), "challenge.example")?;
+    ///     let forwarder = Listener::bind("127.0.0.1:8443")
+    ///         .await?
+    ///         .intercept_tls("challenge.example:443", tls, || {
+    ///             |flow, data: Vec<u8>| {
+    ///                 if flow == Flow::Upstream {
+    ///                     println!("{}", String::from_utf8_lossy(&data));
+    ///                 }
+    ///                 Verdict::Forward(data)
+    ///             }
+    ///         })
+    ///         .await?;
+    ///
+    ///     tokio::signal::ctrl_c().await?;
+    ///     forwarder.stop();
+    ///     Ok(())
+    /// }
+    ///
+    /// intercept_tls();
+    /// ```
+    pub async fn intercept_tls<F, H>(
+        self,
+        addr: impl ToSocketAddrs,
+        tls: TlsIntercept,
+        mut make_hook: F,
+    ) -> io::Result<Forwarder>
+    where
+        F: FnMut() -> H + Send + 'static,
+        H: Intercept,
+    {
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+        self.forward_each(move |inbound| {
+            let addrs = addrs.clone();
+            let tls = tls.clone();
+            let hook = make_hook();
+            async move {
+                let stream = inbound.into_inner().into_inner();
+                let client = Tube::new(tls.acceptor.accept(stream).await?);
+                let stream = TcpStream::connect(&addrs[..]).await?;
+                match tls.upstream {
+                    Some((connector, server_name)) => {
+                        let server = Tube::new(connector.connect(server_name, stream).await?);
+                        intercept_tubes(client, server, hook).await
+                    }
+                    None => intercept_tubes(client, Tube::new(stream), hook).await,
+                }
+            }
+        })
+    }
+}
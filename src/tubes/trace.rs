@@ -0,0 +1,90 @@
+use std::{io, path::Path};
+
+use tokio::process::Command;
+
+use super::{process::wrap_command, ProcessTube};
+
+/// A program that traces a process, see [`ProcessTube::traced`] and [`ProcessTube::trace`].
+/// Forks of the process are followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tracer {
+    /// Trace the system calls with `strace`.
+    Strace,
+    /// Trace the library calls with `ltrace`.
+    Ltrace,
+}
+
+impl Tracer {
+    fn command(self) -> Command {
+        let program = match self {
+            Tracer::Strace => "strace",
+            Tracer::Ltrace => "ltrace",
+        };
+        let mut command = Command::new(program);
+        command.arg("-f");
+        command
+    }
+}
+
+impl ProcessTube {
+    /// Run the command under the tracer, which writes the trace into the file at `output`, so
+    /// that the trace of a failed attempt is kept next to the log of the interaction. The
+    /// program, arguments, environment and working directory of the command are kept.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::{ProcessTube, Tracer, Tube};
+    /// use std::io;
+    /// use tokio::process::Command;
+    ///
+    /// #[tokio::main]
+    /// async fn traced() -> io::Result<()> {
+    ///     let command = Command::new("./challenge");
+    ///     let mut p = Tube::new(ProcessTube::traced(command, Tracer::Strace, "attempt.strace")?);
+    ///     p.send_line("A".repeat(200)).await?;
+    ///     p.recv_line().await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// traced();
+    /// ```
+    pub fn traced(command: Command, tracer: Tracer, output: impl AsRef<Path>) -> io::Result<Self> {
+        let mut traced = tracer.command();
+        traced.arg("-o").arg(output.as_ref()).arg("--");
+        Self::from_command(wrap_command(&command, traced))
+    }
+
+    /// Attach the tracer to the running process, and receive the trace from the returned tube,
+    /// e.g. to trace only the interesting part of the interaction. The tracer detaches when the
+    /// returned tube is dropped. Attaching needs the privilege to trace the process, which is
+    /// restricted by `kernel.yama.ptrace_scope` on many distributions.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::{Tracer, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn trace() -> io::Result<()> {
+    ///     let mut p = Tube::process("./challenge")?;
+    ///     p.recv_until("> ").await?;
+    ///
+    ///     let mut trace = Tube::new(p.inner.get_ref().trace(Tracer::Strace)?);
+    ///     p.send_line("%p%p%p").await?;
+    ///     p.recv_line().await?;
+    ///     assert!(trace.recv_until("write(").await?.ends_with(b"write("));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// trace();
+    /// ```
+    pub fn trace(&self, tracer: Tracer) -> io::Result<ProcessTube> {
+        let pid = self.id().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "the process has already exited")
+        })?;
+        let mut command = tracer.command();
+        command
+            .args(["-o", "/dev/stdout", "-p"])
+            .arg(pid.to_string())
+            .kill_on_drop(true);
+        Self::from_command(command)
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+    time::Instant,
+};
+
+use log::{debug, log_enabled, Level};
+
+use crate::{report, utils::clock};
+
+use super::{
+    adaptive::{self, AdaptiveTimeout},
+    decode::Decoders,
+    info::TubeInfo,
+    logging::{Dump, LogFilter},
+    record::{self, record, Recorder},
+    stats::StatsCounter,
+    LogDecision, LogOptions,
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The time that the log timestamps count from, which is when the first tube is created. There
+/// are no timestamps without a clock.
+static EPOCH: LazyLock<Option<Instant>> = LazyLock::new(clock::now);
+
+/// The direction of the traffic of a tube.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by the tube.
+    Send,
+    /// Received by the tube.
+    Recv,
+}
+
+/// Everything that observes the data sent and received by a tube, apart from the log.
+#[derive(Debug)]
+pub(super) struct Traffic {
+    /// Identifies the tube in the events, see [`Tube::id`](super::Tube::id).
+    pub(super) id: u64,
+    pub(super) name: Option<String>,
+    /// The sequence number of the last event, increasing with every send and receive.
+    seq: u64,
+    /// The number of events, shared by the halves of a split tube to number them together.
+    events: Arc<AtomicU64>,
+    #[cfg(feature = "tracing")]
+    pub(super) span: tracing::Span,
+    pub(super) log_options: LogOptions,
+    pub(super) log_filter: Option<LogFilter>,
+    pub(super) decoders: Decoders,
+    /// The transcript written by [`Tube::record`](super::Tube::record).
+    pub(super) recorder: Option<Recorder>,
+    /// The traffic counted for [`Tube::stats`](super::Tube::stats).
+    pub(super) stats: StatsCounter,
+    /// The latency measured by [`Tube::adaptive_timeout`](super::Tube::adaptive_timeout).
+    pub(super) latency: Option<Arc<Mutex<AdaptiveTimeout>>>,
+    /// Pauses the payload of [`Tube::keepalive`](super::Tube::keepalive) while interacting.
+    pub(super) keepalive_paused: Option<Arc<AtomicBool>>,
+    /// What the tube is connected to, see [`Tube::info`](super::Tube::info).
+    pub(super) info: TubeInfo,
+}
+
+impl Default for Traffic {
+    fn default() -> Self {
+        LazyLock::force(&EPOCH);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id,
+            name: None,
+            seq: 0,
+            events: Arc::default(),
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(
+                "tube",
+                tube_id = id,
+                name = tracing::field::Empty,
+                peer = tracing::field::Empty,
+                pid = tracing::field::Empty,
+            ),
+            log_options: LogOptions::default(),
+            log_filter: None,
+            decoders: Decoders::default(),
+            recorder: None,
+            stats: StatsCounter::default(),
+            latency: None,
+            keepalive_paused: None,
+            info: TubeInfo::default(),
+        }
+    }
+}
+
+impl Traffic {
+    /// Observe the traffic of the other half of a split tube like this one. The halves share the
+    /// logging, the sequence numbers, the recording and the adaptive timeout, while the
+    /// statistics are counted by each half and added up by [`Traffic::merge`].
+    pub(super) fn share(&self) -> Self {
+        Self {
+            id: self.id,
+            name: self.name.clone(),
+            seq: self.seq,
+            events: self.events.clone(),
+            #[cfg(feature = "tracing")]
+            span: self.span.clone(),
+            log_options: self.log_options.clone(),
+            log_filter: self.log_filter.clone(),
+            decoders: self.decoders.clone(),
+            recorder: self.recorder.clone(),
+            stats: StatsCounter::default(),
+            latency: self.latency.clone(),
+            keepalive_paused: self.keepalive_paused.clone(),
+            info: self.info.clone(),
+        }
+    }
+
+    /// Take back the traffic of the other half when a split tube is joined.
+    pub(super) fn merge(&mut self, other: Traffic) {
+        self.seq = self.seq.max(other.seq);
+        self.stats.merge(other.stats);
+    }
+
+    /// Describe the tube in its span, where the field is one of `name`, `peer` or `pid`.
+    pub(super) fn describe(&mut self, field: &'static str, value: impl fmt::Display) {
+        #[cfg(feature = "tracing")]
+        self.span.record(field, tracing::field::display(value));
+        #[cfg(not(feature = "tracing"))]
+        let _ = (field, value);
+    }
+
+    /// Name the tube, which is also done when it is created, e.g. `remote:127.0.0.1:1337`.
+    pub(super) fn set_name(&mut self, name: String) {
+        self.describe("name", &name);
+        self.name = Some(name);
+    }
+
+    /// The prefix of the log lines of the tube, with the time, the name and the direction.
+    pub(super) fn label(&self, direction: Option<Direction>) -> Label<'_> {
+        Label {
+            traffic: self,
+            direction,
+        }
+    }
+
+    pub(super) fn sent(&mut self, data: &[u8]) {
+        self.observe(Direction::Send, data);
+        report::record_sent(data.len());
+    }
+
+    pub(super) fn received(&mut self, data: &[u8]) {
+        self.observe(Direction::Recv, data);
+        report::record_received(data.len());
+    }
+
+    pub(super) fn annotate(&mut self, note: &str) {
+        if self.log_options.enabled {
+            debug!(target: "Tube::annotate", "[{}] {}", self.label(None), note);
+        }
+        record::annotate(&mut self.recorder, self.seq, note);
+    }
+
+    fn observe(&mut self, direction: Direction, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        self.seq = self.events.fetch_add(1, Ordering::Relaxed) + 1;
+        self.stats.count(direction, data.len());
+        let decision = self.log_decision(direction, data);
+        let annotations = if decision == LogDecision::Log || self.recorder.is_some() {
+            self.decoders.annotate(direction, data)
+        } else {
+            Vec::new()
+        };
+        self.log(direction, decision, data, &annotations);
+        record(&mut self.recorder, self.seq, direction, data, &annotations);
+        if let Some(latency) = &self.latency {
+            let mut latency = adaptive::lock(latency);
+            match direction {
+                Direction::Send => latency.sent(),
+                Direction::Recv => latency.received(),
+            }
+        }
+    }
+
+    /// What to log for the data, which is [`LogDecision::Skip`] if the traffic is not logged.
+    fn log_decision(&self, direction: Direction, data: &[u8]) -> LogDecision {
+        let target = match direction {
+            Direction::Send => "Tube::send",
+            Direction::Recv => "Tube::recv",
+        };
+        #[cfg(feature = "tracing")]
+        let traced = tracing::enabled!(tracing::Level::DEBUG);
+        #[cfg(not(feature = "tracing"))]
+        let traced = false;
+        if !self.log_options.enabled || !(traced || log_enabled!(target: target, Level::Debug)) {
+            return LogDecision::Skip;
+        }
+        match &self.log_filter {
+            Some(filter) => filter.decide(direction, data),
+            None => LogDecision::Log,
+        }
+    }
+
+    fn log(
+        &self,
+        direction: Direction,
+        decision: LogDecision,
+        data: &[u8],
+        annotations: &[String],
+    ) {
+        let (target, verb) = match direction {
+            Direction::Send => ("Tube::send", "Sent"),
+            Direction::Recv => ("Tube::recv", "Received"),
+        };
+        let dump = Dump {
+            data,
+            options: &self.log_options,
+        };
+        let label = self.label(Some(direction));
+        match decision {
+            LogDecision::Log => {
+                debug!(target: target, "[{}] {} {}", label, verb, dump);
+                for annotation in annotations {
+                    debug!(target: target, "[{}] {}", label, annotation);
+                }
+            }
+            LogDecision::Redact => debug!(
+                target: target,
+                "[{}] {} {} bytes (redacted)",
+                label,
+                verb,
+                data.len()
+            ),
+            LogDecision::Skip => {}
+        }
+        #[cfg(feature = "tracing")]
+        if decision != LogDecision::Skip {
+            self.trace(direction, decision, data, annotations);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn trace(
+        &self,
+        direction: Direction,
+        decision: LogDecision,
+        data: &[u8],
+        annotations: &[String],
+    ) {
+        let direction = match direction {
+            Direction::Send => "send",
+            Direction::Recv => "recv",
+        };
+        let max_bytes = self.log_options.max_bytes.unwrap_or(usize::MAX);
+        let shown = &data[..data.len().min(max_bytes)];
+        if decision == LogDecision::Redact {
+            tracing::debug!(
+                parent: &self.span,
+                tube_id = self.id,
+                seq = self.seq,
+                direction,
+                bytes = data.len(),
+                redacted = true,
+            );
+        } else {
+            tracing::debug!(
+                parent: &self.span,
+                tube_id = self.id,
+                seq = self.seq,
+                direction,
+                bytes = data.len(),
+                data = %pretty_hex::simple_hex(&shown),
+            );
+            for annotation in annotations {
+                tracing::debug!(
+                    parent: &self.span,
+                    tube_id = self.id,
+                    seq = self.seq,
+                    direction,
+                    annotation = %annotation,
+                );
+            }
+        }
+    }
+}
+
+/// Attributes a log line to a tube, like `+1.234567s leak(3):5 recv` for the 5th event of the
+/// tube 3 named `leak`. The time is monotonic, in seconds since the first tube was created.
+pub(super) struct Label<'a> {
+    traffic: &'a Traffic,
+    direction: Option<Direction>,
+}
+
+impl fmt::Display for Label<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let traffic = self.traffic;
+        if let Some(epoch) = *EPOCH {
+            write!(f, "+{:.6}s ", epoch.elapsed().as_secs_f64())?;
+        }
+        match &traffic.name {
+            Some(name) => write!(f, "{}({}):{}", name, traffic.id, traffic.seq)?,
+            None => write!(f, "{}:{}", traffic.id, traffic.seq)?,
+        }
+        match self.direction {
+            Some(Direction::Send) => write!(f, " send"),
+            Some(Direction::Recv) => write!(f, " recv"),
+            None => Ok(()),
+        }
+    }
+}
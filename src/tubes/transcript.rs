@@ -0,0 +1,249 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::Direction;
+
+const HEADER: &str = "# io-tubes transcript";
+
+/// Stores the traffic recorded by [`Tube::record_to`](super::Tube::record_to), e.g. to keep the
+/// sessions of a framework in a central place.
+pub trait TranscriptSink: Send {
+    /// Store the data sent or received `elapsed` after the recording started, where `seq` is the
+    /// sequence number of the event in the tube. Recording stops if this fails.
+    fn write_event(
+        &mut self,
+        seq: u64,
+        elapsed: Duration,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()>;
+
+    /// Store the annotation of the event `seq` by a decoder of
+    /// [`Tube::add_decoder`](super::Tube::add_decoder). Annotations are dropped by default.
+    fn write_annotation(&mut self, seq: u64, annotation: &str) -> io::Result<()> {
+        let _ = (seq, annotation);
+        Ok(())
+    }
+
+    /// Called when the recording stops.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An event stored in a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptEvent {
+    /// The sequence number of the event in the tube, which is 0 if it is not recorded.
+    pub seq: u64,
+    /// The time since the recording started.
+    pub elapsed: Duration,
+    /// Whether the data is sent or received.
+    pub direction: Direction,
+    /// The data sent or received.
+    pub data: Vec<u8>,
+}
+
+/// Write the event as a line of the text format, see [`Tube::record`](super::Tube::record).
+pub(super) fn format_event(
+    out: &mut String,
+    seq: u64,
+    elapsed: Duration,
+    direction: Direction,
+    data: &[u8],
+) {
+    let direction = match direction {
+        Direction::Send => "send",
+        Direction::Recv => "recv",
+    };
+    let _ = write!(out, "{} {} {} ", seq, elapsed.as_micros(), direction);
+    for byte in data {
+        let _ = write!(out, "{:02x}", byte);
+    }
+    out.push('\n');
+}
+
+/// Parse the text format, see [`Tube::record`](super::Tube::record).
+pub(crate) fn parse_transcript(transcript: &str) -> io::Result<Vec<TranscriptEvent>> {
+    let mut events = Vec::new();
+    for (line_number, line) in transcript.lines().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid transcript event on line {}", line_number + 1),
+            )
+        };
+        let fields: Vec<&str> = line.split(' ').collect();
+        let (seq, micros, direction, hex) = match fields[..] {
+            [seq, micros, direction, hex] => {
+                (seq.parse().map_err(|_| invalid())?, micros, direction, hex)
+            }
+            [micros, direction, hex] => (0, micros, direction, hex),
+            _ => return Err(invalid()),
+        };
+        let elapsed = Duration::from_micros(micros.parse().map_err(|_| invalid())?);
+        let direction = match direction {
+            "send" => Direction::Send,
+            "recv" => Direction::Recv,
+            _ => return Err(invalid()),
+        };
+        let data = decode_hex(hex).ok_or_else(invalid)?;
+        events.push(TranscriptEvent {
+            seq,
+            elapsed,
+            direction,
+            data,
+        });
+    }
+    Ok(events)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Writes the text format of [`Tube::record`](super::Tube::record) to a file.
+#[derive(Debug)]
+pub struct TranscriptFile {
+    writer: BufWriter<File>,
+}
+
+impl TranscriptFile {
+    /// Create the file, truncating it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", HEADER)?;
+        Ok(Self { writer })
+    }
+}
+
+impl TranscriptSink for TranscriptFile {
+    fn write_event(
+        &mut self,
+        seq: u64,
+        elapsed: Duration,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let mut line = String::with_capacity(data.len() * 2 + 48);
+        format_event(&mut line, seq, elapsed, direction, data);
+        self.writer.write_all(line.as_bytes())
+    }
+
+    fn write_annotation(&mut self, seq: u64, annotation: &str) -> io::Result<()> {
+        for line in annotation.lines() {
+            writeln!(self.writer, "# {} {}", seq, line)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Keeps the events in memory. Clones share the same events, so a clone can be queried while the
+/// tube is recording.
+/// ```rust
+/// use io_tubes::tubes::{Direction, MemoryTranscript, ReplayTube, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn memory_transcript() -> io::Result<()> {
+///     let transcript = MemoryTranscript::new();
+///
+///     let mut p = Tube::echo();
+///     p.record_to(transcript.clone())?;
+///     p.send("Hello\n").await?;
+///     assert_eq!(p.recv_line().await?, b"Hello\n");
+///
+///     let events = transcript.events();
+///     assert_eq!(events.len(), 2);
+///     assert_eq!(events[0].direction, Direction::Send);
+///     assert_eq!(events[1].data, b"Hello\n");
+///     assert!(events[0].seq < events[1].seq);
+///
+///     let mut r = Tube::new(ReplayTube::from_events(events));
+///     r.send("Hello\n").await?;
+///     assert_eq!(r.recv_line().await?, b"Hello\n");
+///
+///     Ok(())
+/// }
+///
+/// memory_transcript();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTranscript {
+    events: Arc<Mutex<Vec<TranscriptEvent>>>,
+}
+
+impl MemoryTranscript {
+    /// Create an empty transcript.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of the events so far.
+    pub fn events(&self) -> Vec<TranscriptEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// The events so far in the text format of [`Tube::record`](super::Tube::record).
+    pub fn to_transcript(&self) -> String {
+        let mut transcript = format!("{}\n", HEADER);
+        for event in self
+            .events
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+        {
+            format_event(
+                &mut transcript,
+                event.seq,
+                event.elapsed,
+                event.direction,
+                &event.data,
+            );
+        }
+        transcript
+    }
+}
+
+impl TranscriptSink for MemoryTranscript {
+    fn write_event(
+        &mut self,
+        seq: u64,
+        elapsed: Duration,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()> {
+        self.events
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(TranscriptEvent {
+                seq,
+                elapsed,
+                direction,
+                data: data.to_vec(),
+            });
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+type Transform = Box<dyn FnMut(&mut [u8]) + Send>;
+
+/// Xor the data with the concatenation of `block(0)`, `block(1)` and so on.
+fn keystream(mut block: impl FnMut(u64) -> Vec<u8> + Send + 'static) -> Transform {
+    let mut counter = 0;
+    let mut stream = Vec::new();
+    let mut pos = 0;
+    Box::new(move |data| {
+        for byte in data {
+            if pos == stream.len() {
+                stream = block(counter);
+                assert!(!stream.is_empty(), "keystream block must not be empty");
+                counter += 1;
+                pos = 0;
+            }
+            *byte ^= stream[pos];
+            pos += 1;
+        }
+    })
+}
+
+/// Applies length preserving transforms to the data sent to and received from the inner stream,
+/// e.g. the keystream of a stream cipher wrapping the protocol, so that the tube works on the
+/// plaintext.
+///
+/// The transforms are called on the bytes in order, and are given every byte once, so they can
+/// keep state such as the position in the keystream. Data is encoded as soon as it is written
+/// and kept until the inner stream accepts it.
+/// ```rust
+/// use io_tubes::tubes::{TransformTube, Tube};
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn transform() -> io::Result<()> {
+///     let mut p = Tube::echo().map_inner(|inner| {
+///         TransformTube::new(inner, |data| data.make_ascii_uppercase(), |_| {})
+///     });
+///     p.send_line("hello").await?;
+///     assert_eq!(p.recv_line().await?, b"HELLO\n");
+///
+///     // The ciphertext is echoed back and decrypted with the same keystream
+///     let mut p = Tube::echo().map_inner(|inner| TransformTube::xor(inner, "key"));
+///     p.send_line("hello").await?;
+///     assert_eq!(p.recv_line().await?, b"hello\n");
+///
+///     Ok(())
+/// }
+///
+/// transform();
+/// ```
+pub struct TransformTube<T> {
+    inner: T,
+    encode: Transform,
+    decode: Transform,
+    /// The encoded data not written to the inner stream yet, of which `pending[written..]` is
+    /// left.
+    pending: Vec<u8>,
+    written: usize,
+}
+
+impl<T> TransformTube<T> {
+    /// Encode the data written with `encode` and decode the data read with `decode`, each of
+    /// which transforms the bytes in place.
+    pub fn new<E, D>(inner: T, encode: E, decode: D) -> Self
+    where
+        E: FnMut(&mut [u8]) + Send + 'static,
+        D: FnMut(&mut [u8]) + Send + 'static,
+    {
+        Self::with_transforms(inner, Box::new(encode), Box::new(decode))
+    }
+
+    fn with_transforms(inner: T, encode: Transform, decode: Transform) -> Self {
+        Self {
+            inner,
+            encode,
+            decode,
+            pending: Vec::new(),
+            written: 0,
+        }
+    }
+
+    /// Xor the data in each direction with the key repeated, starting from the first byte of
+    /// the key. Panics if the key is empty.
+    pub fn xor(inner: T, key: impl AsRef<[u8]>) -> Self {
+        let key = key.as_ref().to_vec();
+        assert!(!key.is_empty(), "key must not be empty");
+        let send_key = key.clone();
+        Self::with_transforms(
+            inner,
+            keystream(move |_| send_key.clone()),
+            keystream(move |_| key.clone()),
+        )
+    }
+
+    /// Xor the data with the keystream of a block cipher in counter mode, which is
+    /// `block(0)`, `block(1)` and so on for each direction. The blocks are usually the
+    /// encryption of the nonce and the counter, e.g. with AES. Panics if a block is empty.
+    /// ```rust
+    /// use io_tubes::tubes::{TransformTube, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn ctr() -> io::Result<()> {
+    ///     // a toy cipher, which is the counter itself
+    ///     let block = |counter: u64| counter.to_le_bytes().to_vec();
+    ///     let mut p = Tube::echo().map_inner(|inner| TransformTube::ctr(inner, block, block));
+    ///
+    ///     p.send_line("hello, counter mode").await?;
+    ///     assert_eq!(p.recv_line().await?, b"hello, counter mode\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// ctr();
+    /// ```
+    pub fn ctr<E, D>(inner: T, send_block: E, recv_block: D) -> Self
+    where
+        E: FnMut(u64) -> Vec<u8> + Send + 'static,
+        D: FnMut(u64) -> Vec<u8> + Send + 'static,
+    {
+        Self::with_transforms(inner, keystream(send_block), keystream(recv_block))
+    }
+
+    /// Gets a reference to the inner stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner stream. Data transferred directly is not
+    /// transformed.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the adapter, returning the inner stream. Encoded data not written yet is lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncWrite + Unpin> TransformTube<T> {
+    /// Write all the pending data to the inner stream.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.written < self.pending.len() {
+            let len =
+                ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending[self.written..]))?;
+            if len == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.written += len;
+        }
+        self.pending.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> fmt::Debug for TransformTube<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransformTube")
+            .field("inner", &self.inner)
+            .field("pending", &(self.pending.len() - self.written))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for TransformTube<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        (this.decode)(&mut buf.filled_mut()[filled..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for TransformTube<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        this.pending.extend_from_slice(buf);
+        (this.encode)(&mut this.pending);
+        // The data is accepted once encoded, since the keystream has moved past it.
+        if let Poll::Ready(Err(err)) = this.poll_pending(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
@@ -1,32 +1,59 @@
+#[cfg(feature = "process")]
+use std::ffi::OsStr;
+#[cfg(feature = "net")]
+use std::net::SocketAddr;
 use std::{
-    ffi::OsStr,
-    io,
+    fmt, future, io,
     pin::Pin,
-    task::{Context, Poll},
-    time::Duration,
+    sync::Arc,
+    task::{ready, Context, Poll, Waker},
+    time::{Duration, Instant},
 };
 
+#[cfg(feature = "net")]
 use log::debug;
-use pretty_hex::PrettyHex;
+use tokio::io::{
+    duplex, join, simplex, AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite,
+    AsyncWriteExt, BufReader, DuplexStream, Join, ReadBuf, ReadHalf, SimplexStream, WriteHalf,
+};
+#[cfg(not(target_family = "wasm"))]
+use tokio::io::{stdin, stdout};
+#[cfg(feature = "process")]
+use tokio::process::Command;
+#[cfg(feature = "net")]
 use tokio::{
-    io::{
-        AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
-        BufReader, ReadBuf,
-    },
-    net::{TcpStream, ToSocketAddrs},
+    net::{lookup_host, TcpStream, ToSocketAddrs},
     time,
 };
 
-use crate::utils::{Interactive, RecvUntil};
+use regex::bytes::Regex;
+
+use crate::context::{self, Rng};
+use crate::utils::{
+    clock, cyclic, fit, fit_with, timeout, tolerant_regex, FlatOptions, FlatValue, Interactive,
+    Needle, RecvNeedle, RecvRegex, RecvUntilAny, RecvUntilFuzzy,
+};
+
+#[cfg(unix)]
+use crate::utils::RawMode;
 
+#[cfg(feature = "process")]
 use super::ProcessTube;
+#[cfg(feature = "net")]
+use super::SocketOptions;
+use super::{
+    ambient_deadline,
+    eof::{EofPolicy, RetryEof},
+    keepalive::KeepalivePause,
+    limit::Limited,
+    queue::SendQueue,
+    traffic::Traffic,
+    TimeoutPolicy, TubeError,
+};
 
 /// A wrapper to provide extra methods. Note that the API from this crate is different from pwntools.
 #[derive(Debug)]
-pub struct Tube<T>
-where
-    T: AsyncBufRead + AsyncWrite + Unpin,
-{
+pub struct Tube<T> {
     /// The inner struct, usually a BufReader containing the original struct.
     pub inner: T,
 
@@ -39,231 +66,2382 @@ where
     /// future is dropped and another future has started polling. As a result, the API will be
     /// producing inconsistent timeout if it is implemented.
     ///
-    /// Luckily, [`tokio::time::timeout`] provides an easy way to add timeout to a future (which is
-    /// how timeout is implemented in this library) so you can still have timeout behaviour on
-    /// functions that doesn't support them.
+    /// Luckily, [`tokio::time::timeout`] provides an easy way to add timeout to a future (which is
+    /// how timeout is implemented in this library) so you can still have timeout behaviour on
+    /// functions that doesn't support them.
+    ///
+    /// Hence, timeout can only be reliably implemented for async fn (which returns a future under
+    /// the hood) or fn that return a future.
+    pub timeout: Duration,
+
+    /// The line ending sent by [`Tube::send_line`] and the other line methods, which is `\n`
+    /// unless changed in the [context](crate::context::TubeDefaults). Received lines always end
+    /// at `\n`, which includes lines ending with `\r\n`.
+    pub newline: Vec<u8>,
+
+    /// The timeout for sending data, which falls back to [`Tube::timeout`] if it is `None`.
+    ///
+    /// A send that times out fails with [`TimedOut`](io::ErrorKind::TimedOut). Part of the data
+    /// may be sent already in that case.
+    pub write_timeout: Option<Duration>,
+
+    /// An absolute point in time that all operations with a timeout must finish before.
+    deadline: Option<Instant>,
+
+    /// The maximum number of bytes that can be queued by [`Tube::send_nowait`]. The queue is
+    /// disabled if it is 0, which is the default.
+    pub send_queue_capacity: usize,
+
+    /// The maximum number of bytes received by a single call to the methods that receive until
+    /// something is found, like [`Tube::recv_until`], [`Tube::recv_line`] and
+    /// [`Tube::recv_all`], so that a target that never sends the delimiter cannot exhaust the
+    /// memory. They fail with [`TubeError::TooLarge`] once more data is available. There is no
+    /// limit if it is `None`, which is the default. See also [`RecvUntilOptions::max_size`].
+    pub max_recv_size: Option<usize>,
+
+    /// What the methods that receive until a pattern, like [`Tube::recv_until`], do when EOF is
+    /// reached before the pattern. They return the data received so far by default. See also
+    /// [`RecvUntilOptions::on_eof`].
+    pub on_eof: EofPolicy,
+
+    /// What [`Tube::recv`], [`Tube::recv_line`] and [`Tube::recv_until`] do when the timeout is
+    /// reached. They return the data received so far by default, use the checked methods like
+    /// [`Tube::recv_until_checked`] to handle the timeout for a single call.
+    pub on_timeout: TimeoutPolicy,
+
+    read_buf_logged: usize,
+
+    /// Data that is already received from `inner` but put back to be received again.
+    unread: Vec<u8>,
+    unread_pos: usize,
+
+    pub(super) send_queue: SendQueue,
+
+    /// The data written while the tube is corked by [`Tube::cork`], sent by [`Tube::uncork`].
+    pub(super) corked: Option<Vec<u8>>,
+
+    /// Writes the send queue while receiving, which is only possible if `T` is also writable.
+    background_send: Option<fn(&mut Tube<T>, &mut Context)>,
+
+    /// The capacity of the buffer of `inner` set by [`Tube::read_chunk_size`], if any. Reads go
+    /// through the buffer so that they respect it.
+    read_chunk_size: Option<usize>,
+
+    /// The capacity of the buffer of `inner` if it is not the default, set by
+    /// [`Tube::buffer_capacity`] or [`Tube::read_chunk_size`].
+    buffer_capacity: Option<usize>,
+
+    pub(super) traffic: Traffic,
+
+    /// The generator of [`Tube::rng`], derived from the context when first used.
+    rng: Option<Rng>,
+
+    /// The buffer reused by [`Tube::copy_to`] and [`Tube::copy_from`].
+    pub(super) copy_buf: Vec<u8>,
+}
+
+const NEW_LINE: u8 = 0xA;
+
+/// The read chunk size of [`Tube::low_latency`].
+const LOW_LATENCY_READ_CHUNK: usize = 16;
+
+/// The bytes buffered in each direction of [`Tube::pair`].
+const DEFAULT_PAIR_CAPACITY: usize = 64 * 1024;
+
+/// Options for [`Tube::recv_until_with`].
+#[derive(Debug, Clone, Default)]
+pub struct RecvUntilOptions {
+    /// Exclude the delims from the returned data. The delims are still consumed from the stream.
+    pub drop: bool,
+    /// Also accept data that is up to this many insertions, deletions or substitutions of bytes
+    /// away from the delims, e.g. for banners containing timestamps or pids. See
+    /// [`FuzzyMatcher`](crate::utils::FuzzyMatcher) for where the match ends. It must be less
+    /// than the length of the delims, and the delims are matched exactly if it is 0, which is the
+    /// default.
+    pub max_edits: usize,
+    /// The maximum number of bytes to receive, overriding [`Tube::max_recv_size`] for this call.
+    pub max_size: Option<usize>,
+    /// What to do when EOF is reached before the delims, overriding [`Tube::on_eof`] for this
+    /// call.
+    pub on_eof: Option<EofPolicy>,
+    /// Match ASCII letters of the delims in any case, e.g. for banners whose casing changes
+    /// between builds. The delims must be literal.
+    pub ignore_case: bool,
+    /// Match each run of whitespace in the delims with any run of at least one ASCII whitespace
+    /// byte, so that `"Password: "` also matches `"Password:\t"`. The delims must be literal.
+    pub collapse_whitespace: bool,
+}
+
+/// Options for [`Tube::remote_with`].
+#[cfg(feature = "net")]
+#[derive(Debug, Clone)]
+pub struct RemoteOptions {
+    /// Give up an attempt to connect after this long. Attempts take as long as the OS allows if
+    /// `None`, which is the default.
+    pub connect_timeout: Option<Duration>,
+    /// The number of times to try again after the first attempt fails, which is 0 by default.
+    pub retries: u32,
+    /// The delay before the first retry, which is doubled for every later retry. 100 ms by
+    /// default.
+    pub backoff: Duration,
+    /// The options of the socket, e.g. to disable Nagle's algorithm.
+    pub socket: SocketOptions,
+}
+
+#[cfg(feature = "net")]
+impl Default for RemoteOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: None,
+            retries: 0,
+            backoff: Duration::from_millis(100),
+            socket: SocketOptions::default(),
+        }
+    }
+}
+
+/// Options for [`Tube::interactive_with`].
+#[derive(Debug, Clone, Default)]
+pub struct InteractiveOptions {
+    /// The sequence typed locally that ends the interaction instead of being sent, e.g. `b"~."`.
+    /// It is disabled if empty, which is the default.
+    pub escape: Vec<u8>,
+    /// Put the local terminal into raw mode, see [`Tube::interactive_raw`].
+    pub raw: bool,
+    /// Pairs of a pattern and the response sent whenever the pattern is received, e.g. to answer
+    /// routine confirmation prompts while staying interactive. Empty patterns are ignored.
+    pub auto_responses: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Call [`InteractiveOptions::on_idle`] whenever nothing is typed or received for this long,
+    /// and again every time it passes after that. Without a hook, the interaction ends with
+    /// [`InteractiveEnd::Idle`]. It is disabled if `None`, which is the default.
+    pub idle_timeout: Option<Duration>,
+    /// Decides what to do when the interaction is idle, see [`IdleHook`].
+    pub on_idle: Option<IdleHook>,
+}
+
+/// What to do when the interaction is idle, returned by an [`IdleHook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Keep waiting, e.g. after printing a warning.
+    Continue,
+    /// Send the data to the tube, e.g. a newline to keep the connection alive.
+    Send(Vec<u8>),
+    /// End the interaction with [`InteractiveEnd::Idle`].
+    End,
+}
+
+type IdleFn = dyn Fn(Duration) -> IdleAction + Send + Sync;
+
+/// The hook of [`InteractiveOptions::on_idle`], called with how long nothing has been typed or
+/// received.
+/// ```rust,no_run
+/// use io_tubes::tubes::{IdleAction, IdleHook, InteractiveEnd, InteractiveOptions, Tube};
+/// use std::{io, time::Duration};
+///
+/// #[tokio::main]
+/// async fn idle_hook() -> io::Result<()> {
+///     let mut p = Tube::remote("127.0.0.1:1337").await?;
+///     let options = InteractiveOptions {
+///         idle_timeout: Some(Duration::from_secs(30)),
+///         on_idle: Some(IdleHook::new(|idle| {
+///             if idle < Duration::from_secs(300) {
+///                 IdleAction::Send(b"\n".to_vec())
+///             } else {
+///                 eprintln!("idle for {:?}, giving up", idle);
+///                 IdleAction::End
+///             }
+///         })),
+///         ..Default::default()
+///     };
+///     if p.interactive_with(&options).await? == InteractiveEnd::Idle {
+///         p.close().await?;
+///     }
+///
+///     Ok(())
+/// }
+///
+/// idle_hook();
+/// ```
+#[derive(Clone)]
+pub struct IdleHook(Arc<IdleFn>);
+
+impl IdleHook {
+    /// Call `f` whenever the interaction is idle.
+    pub fn new(f: impl Fn(Duration) -> IdleAction + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn call(&self, idle: Duration) -> IdleAction {
+        (self.0)(idle)
+    }
+}
+
+impl fmt::Debug for IdleHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IdleHook")
+    }
+}
+
+/// How the interaction ended, returned by [`Tube::interactive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InteractiveEnd {
+    /// EOF is reached on stdin.
+    Eof,
+    /// The escape sequence is typed.
+    Escape,
+    /// EOF is reached on the tube. Data can still be sent if only the other direction is closed.
+    RemoteEof,
+    /// The interaction is idle, see [`InteractiveOptions::idle_timeout`].
+    Idle,
+}
+
+/// The read half of a tube returned by [`Tube::split`].
+pub type TubeReadHalf<T> = Tube<BufReader<ReadHalf<T>>>;
+
+/// The write half of a tube returned by [`Tube::split`].
+pub type TubeWriteHalf<T> = Tube<WriteHalf<T>>;
+
+impl<T> Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Construct a new `Tube<T>`.
+    pub fn new(inner: T) -> Self {
+        Self::from_buffered(BufReader::new(inner))
+    }
+
+    /// Construct a new `Tube<T>` with the supplied timeout argument. Note that timeout is only
+    /// implemented for methods directly provided by this struct and not methods from traits.
+    ///
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    /// use tokio::io::duplex;
+    ///
+    /// #[tokio::main]
+    /// async fn create_with_timeout() -> io::Result<()> {
+    ///     let (stream, _server) = duplex(64);
+    ///     let mut p = Tube::new(stream);
+    ///     p.timeout = Duration::from_millis(50);
+    ///     // Equivalent to
+    ///     let (stream, _server) = duplex(64);
+    ///     let p = Tube::with_timeout(stream, Duration::from_millis(50));
+    ///     assert_eq!(p.timeout, Duration::from_millis(50));
+    ///     Ok(())
+    /// }
+    ///
+    /// create_with_timeout();
+    /// ```
+    pub fn with_timeout(inner: T, timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ..Self::new(inner)
+        }
+    }
+
+    /// Construct a new `Tube<T>` whose read buffer holds `capacity` bytes instead of 8 KiB, see
+    /// [`Tube::buffer_capacity`]. Panics if `capacity` is 0.
+    pub fn with_capacity(inner: T, capacity: usize) -> Self {
+        Self::new(inner).buffer_capacity(capacity)
+    }
+
+    /// Change the capacity of the read buffer, which is 8 KiB by default. Data already received
+    /// is kept. Panics if `capacity` is 0.
+    ///
+    /// A larger buffer needs fewer syscalls when receiving a lot of data with methods like
+    /// [`Tube::recv_until`], while a smaller one saves memory. Unlike
+    /// [`Tube::read_chunk_size`], reads larger than the buffer still go directly to the inner
+    /// stream.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn buffer_capacity() -> io::Result<()> {
+    ///     let mut p = Tube::echo().buffer_capacity(1 << 20);
+    ///
+    ///     p.send(vec![b'A'; 4096]).await?;
+    ///     p.send("\n").await?;
+    ///     assert_eq!(p.recv_line().await?.len(), 4097);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// buffer_capacity();
+    /// ```
+    pub fn buffer_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "buffer capacity must not be 0");
+        self.append_unread(&[]);
+        Self {
+            inner: BufReader::with_capacity(capacity, self.inner.into_inner()),
+            read_chunk_size: None,
+            buffer_capacity: Some(capacity),
+            ..self
+        }
+    }
+
+    /// Read at most `size` bytes from the inner stream at once, which is 8 KiB by default. Data
+    /// already received is kept. Panics if `size` is 0.
+    ///
+    /// Larger chunks need fewer syscalls for bulk data, while smaller chunks hand the data over
+    /// in finer steps as it arrives.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn read_chunk_size() -> io::Result<()> {
+    ///     let mut p = Tube::echo().read_chunk_size(4);
+    ///
+    ///     p.send("Hello World").await?;
+    ///     assert_eq!(p.recv(100).await?, b"Hell");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// read_chunk_size();
+    /// ```
+    pub fn read_chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "read chunk size must not be 0");
+        self.append_unread(&[]);
+        Self {
+            inner: BufReader::with_capacity(size, self.inner.into_inner()),
+            read_chunk_size: Some(size),
+            buffer_capacity: Some(size),
+            ..self
+        }
+    }
+
+    /// A preset for timing attacks that reads eagerly in chunks of at most 16 bytes, see
+    /// [`Tube::read_chunk_size`].
+    pub fn low_latency(self) -> Self {
+        self.read_chunk_size(LOW_LATENCY_READ_CHUNK)
+    }
+
+    /// Replace the inner stream and return the old one, e.g. to carry on over a new connection or
+    /// over TLS after STARTTLS. The data received but not consumed yet is received before the
+    /// data from the new stream, and the settings, logging, recording and statistics are kept.
+    /// Data queued by [`Tube::send_nowait`] is written to the new stream.
+    ///
+    /// The type of the stream stays the same, so switching to another transport needs a type
+    /// that can hold both, e.g. an enum or a boxed stream.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    /// use tokio::io::{duplex, AsyncWriteExt};
+    ///
+    /// #[tokio::main]
+    /// async fn replace_inner() -> io::Result<()> {
+    ///     let (stream, mut old) = duplex(64);
+    ///     let mut p = Tube::new(stream);
+    ///     old.write_all(b"Hello\nWorld\n").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///
+    ///     let (stream, new) = duplex(64);
+    ///     p.replace_inner(stream);
+    ///     let mut new = Tube::new(new);
+    ///     new.send_line("Again").await?;
+    ///     assert_eq!(p.recv_line().await?, b"World\n");
+    ///     assert_eq!(p.recv_line().await?, b"Again\n");
+    ///     assert_eq!(p.stats().bytes_received, 18);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// replace_inner();
+    /// ```
+    pub fn replace_inner(&mut self, inner: T) -> T {
+        self.append_unread(&[]);
+        let inner = match self.buffer_capacity {
+            Some(capacity) => BufReader::with_capacity(capacity, inner),
+            None => BufReader::new(inner),
+        };
+        std::mem::replace(&mut self.inner, inner).into_inner()
+    }
+
+    /// Wrap the inner stream with `f`, e.g. in a [`ThrottleTube`](super::ThrottleTube) or a
+    /// cipher layer, without building the tube again. Like [`Tube::replace_inner`], the data
+    /// received but not consumed yet, the data queued, the settings, logging, recording and
+    /// statistics are kept.
+    /// ```rust
+    /// use io_tubes::tubes::{ThrottleTube, Tube};
+    /// use std::{
+    ///     io,
+    ///     time::{Duration, Instant},
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn map_inner() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.timeout = Duration::from_secs(2);
+    ///     p.send("Hello\nWorld\n").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///
+    ///     let mut p = p.map_inner(|inner| ThrottleTube::new(inner, 1000));
+    ///     assert_eq!(p.timeout, Duration::from_secs(2));
+    ///     assert_eq!(p.recv_line().await?, b"World\n");
+    ///
+    ///     let start = Instant::now();
+    ///     p.send_line(vec![b'A'; 299]).await?;
+    ///     assert!(start.elapsed() >= Duration::from_millis(150));
+    ///     assert_eq!(p.recv_line().await?.len(), 300);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// map_inner();
+    /// ```
+    pub fn map_inner<U, F>(mut self, f: F) -> Tube<BufReader<U>>
+    where
+        U: AsyncRead + AsyncWrite + Unpin,
+        F: FnOnce(T) -> U,
+    {
+        self.append_unread(&[]);
+        let capacity = self.buffer_capacity;
+        self.map_buffered(|inner| {
+            let inner = f(inner.into_inner());
+            match capacity {
+                Some(capacity) => BufReader::with_capacity(capacity, inner),
+                None => BufReader::new(inner),
+            }
+        })
+    }
+
+    /// Append data read directly from the underlying stream, so that it is received after
+    /// everything already buffered.
+    pub(super) fn append_unread(&mut self, data: &[u8]) {
+        self.unread.drain(..self.unread_pos);
+        self.unread_pos = 0;
+        let buffered = self.inner.buffer();
+        let len = buffered.len();
+        if len > self.read_buf_logged {
+            self.traffic.received(&buffered[self.read_buf_logged..]);
+            self.read_buf_logged = len;
+        }
+        self.unread.extend_from_slice(buffered);
+        self.consume_inner(len);
+
+        if !data.is_empty() {
+            self.traffic.received(data);
+        }
+        self.unread.extend_from_slice(data);
+    }
+}
+
+#[cfg(feature = "process")]
+impl Tube<BufReader<ProcessTube>> {
+    /// Create a process with supplied path to program.
+    ///
+    /// On Windows, a program without an extension is looked up with `.exe` in `PATH`, so
+    /// `Tube::process("cmd")` works, but batch files have to be run through `cmd /c`. The
+    /// newline is still `\n`; set [`Tube::newline`] to `\r\n` for programs that expect it.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn create_process() -> io::Result<()> {
+    ///     let mut p = Tube::process("/usr/bin/cat")?;
+    ///     p.send("abcdHi!").await?;
+    ///     let result = p.recv_until("Hi").await?;
+    ///     assert_eq!(result, b"abcdHi");
+    ///     Ok(())
+    /// }
+    ///
+    /// create_process();
+    /// ```
+    pub fn process<S: AsRef<OsStr>>(program: S) -> io::Result<Self> {
+        let process = ProcessTube::new(&program)?;
+        Ok(Self::from_process(process, program.as_ref()))
+    }
+
+    /// Create a process with the program and its arguments.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn process_args() -> io::Result<()> {
+    ///     let mut p = Tube::process_args("/usr/bin/printf", ["%s-%s", "a", "b"])?;
+    ///     assert_eq!(p.recv_all().await?, b"a-b");
+    ///     Ok(())
+    /// }
+    ///
+    /// process_args();
+    /// ```
+    pub fn process_args<S, I>(program: S, args: I) -> io::Result<Self>
+    where
+        S: AsRef<OsStr>,
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let mut command = Command::new(&program);
+        command.args(args);
+        let process = ProcessTube::from_command(command)?;
+        Ok(Self::from_process(process, program.as_ref()))
+    }
+
+    /// Run a command line through the shell, `sh -c` on Unix and `cmd /C` on Windows, so that
+    /// redirections and pipes work. The pid described in the log is the one of the shell.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn shell() -> io::Result<()> {
+    ///     let mut p = Tube::shell("tr a-z A-Z | rev")?;
+    ///     p.send_line("hello").await?;
+    ///     p.close_send().await?;
+    ///     assert_eq!(p.recv_all().await?, b"OLLEH\n");
+    ///     Ok(())
+    /// }
+    ///
+    /// shell();
+    /// ```
+    pub fn shell(command: impl AsRef<OsStr>) -> io::Result<Self> {
+        #[cfg(windows)]
+        let (shell, flag) = ("cmd", "/C");
+        #[cfg(not(windows))]
+        let (shell, flag) = ("/bin/sh", "-c");
+        Self::process_args(shell, [flag.as_ref(), command.as_ref()])
+    }
+
+    fn from_process(process: ProcessTube, program: &OsStr) -> Self {
+        let mut tube = Self::new(process);
+        tube.traffic.info.pid = tube.inner.get_ref().id();
+        tube.traffic.info.program = Some(program.into());
+        if let Some(pid) = tube.inner.get_ref().id() {
+            tube.traffic.describe("pid", pid);
+            let name = format!("process:{}[{}]", program.to_string_lossy(), pid);
+            tube.traffic.set_name(name);
+        }
+        tube
+    }
+}
+
+#[cfg(feature = "net")]
+impl Tube<BufReader<TcpStream>> {
+    /// Create a tube by connecting to the remote address.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::{
+    ///     io,
+    ///     net::{IpAddr, Ipv4Addr, SocketAddr},
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn create_remote() -> io::Result<()> {
+    ///     let l = Listener::listen().await?;
+    ///     let mut p =
+    ///         Tube::remote(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), l.port()?)).await?;
+    ///     let mut server = l.accept().await?;
+    ///     p.send("Client Hello").await?;
+    ///     server.send("Server Hello").await?;
+    ///     assert_eq!(p.recv_until("Hello").await?, b"Server Hello");
+    ///     assert_eq!(server.recv_until("Hello").await?, b"Client Hello");
+    ///     Ok(())
+    /// }
+    ///
+    /// create_remote();
+    /// ```
+    pub async fn remote(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::from_stream(TcpStream::connect(addr).await?))
+    }
+
+    /// Same as [`Tube::remote`], but retry with backoff and time out each attempt according to
+    /// the options, e.g. for a service that is not up yet or drops connections under load. The
+    /// address is resolved once, and the error of the last attempt is returned.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, RemoteOptions, Tube};
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn remote_with() -> io::Result<()> {
+    ///     let port = Listener::bind("127.0.0.1:0").await?.port()?;
+    ///     let server = tokio::spawn(async move {
+    ///         tokio::time::sleep(Duration::from_millis(200)).await;
+    ///         let mut server = Listener::bind(("127.0.0.1", port)).await?.accept().await?;
+    ///         server.send_line("up").await
+    ///     });
+    ///
+    ///     let options = RemoteOptions {
+    ///         connect_timeout: Some(Duration::from_secs(1)),
+    ///         retries: 8,
+    ///         backoff: Duration::from_millis(20),
+    ///         ..RemoteOptions::default()
+    ///     };
+    ///     let mut p = Tube::remote_with(("127.0.0.1", port), &options).await?;
+    ///     assert_eq!(p.recv_line().await?, b"up\n");
+    ///     server.await??;
+    ///
+    ///     let options = RemoteOptions {
+    ///         retries: 1,
+    ///         ..RemoteOptions::default()
+    ///     };
+    ///     let err = Tube::remote_with(("127.0.0.1", port), &options).await.unwrap_err();
+    ///     assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// remote_with();
+    /// ```
+    pub async fn remote_with(
+        addr: impl ToSocketAddrs,
+        options: &RemoteOptions,
+    ) -> io::Result<Self> {
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+        let mut backoff = options.backoff;
+        let mut attempt = 0;
+        loop {
+            let connect = options.socket.connect(&addrs);
+            let result = match options.connect_timeout {
+                Some(connect_timeout) => time::timeout(connect_timeout, connect)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(io::Error::new(io::ErrorKind::TimedOut, "connect timed out"))
+                    }),
+                None => connect.await,
+            };
+            match result {
+                Ok(stream) => return Ok(Self::from_stream(stream)),
+                Err(err) if attempt < options.retries => {
+                    debug!(target: "Tube::remote", "Connect failed, retrying in {:?}: {}", backoff, err);
+                    time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn from_stream(stream: TcpStream) -> Self {
+        let mut tube = Self::new(stream);
+        tube.traffic.info.peer_addr = tube.inner.get_ref().peer_addr().ok();
+        tube.traffic.info.local_addr = tube.inner.get_ref().local_addr().ok();
+        if let Ok(peer) = tube.inner.get_ref().peer_addr() {
+            tube.traffic.describe("peer", peer);
+            tube.traffic.set_name(format!("remote:{}", peer));
+        }
+        tube
+    }
+}
+
+impl Tube<BufReader<DuplexStream>> {
+    /// Create two tubes connected to each other in memory, so that protocol code can be tested
+    /// without a process or a socket. Up to 64 KiB is buffered in each direction.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn pair() -> io::Result<()> {
+    ///     let (mut client, mut server) = Tube::pair();
+    ///
+    ///     server.send("Name: ").await?;
+    ///     client.send_line_after("Name: ", "admin").await?;
+    ///     assert_eq!(server.recv_line().await?, b"admin\n");
+    ///
+    ///     drop(server);
+    ///     assert_eq!(client.recv(1).await?, b"");
+    ///     Ok(())
+    /// }
+    ///
+    /// pair();
+    /// ```
+    pub fn pair() -> (Self, Self) {
+        Self::pair_with_capacity(DEFAULT_PAIR_CAPACITY)
+    }
+
+    /// Same as [`Tube::pair`], but buffer up to `capacity` bytes in each direction before
+    /// sending waits for the other side to receive.
+    pub fn pair_with_capacity(capacity: usize) -> (Self, Self) {
+        let (a, b) = duplex(capacity);
+        (Self::new(a), Self::new(b))
+    }
+}
+
+/// The stream of [`Tube::echo`], which reads back what is written to it.
+pub type EchoStream = Join<ReadHalf<SimplexStream>, WriteHalf<SimplexStream>>;
+
+impl Tube<BufReader<EchoStream>> {
+    /// Create a tube that receives everything sent to it, like `Tube::process("/usr/bin/cat")`
+    /// but in memory, so that tests and examples also run where `cat` is not available, e.g. on
+    /// Windows or in minimal containers. Up to 64 KiB is buffered before sending waits for the
+    /// data to be received, and EOF is received after the sending side is shut down.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// #[tokio::main]
+    /// async fn echo() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(p.recv_line().await?, b"Hello\n");
+    ///
+    ///     p.send("World").await?;
+    ///     p.shutdown().await?;
+    ///     assert_eq!(p.recv_all().await?, b"World");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// echo();
+    /// ```
+    pub fn echo() -> Self {
+        Self::echo_with_capacity(DEFAULT_PAIR_CAPACITY)
+    }
+
+    /// Same as [`Tube::echo`], but buffer up to `capacity` bytes before sending waits for the
+    /// data to be received.
+    pub fn echo_with_capacity(capacity: usize) -> Self {
+        let (reader, writer) = simplex(capacity);
+        Self::new(join(reader, writer))
+    }
+}
+
+impl<T> Tube<T> {
+    fn from_inner(inner: T) -> Self {
+        let defaults = context::tube_defaults();
+        let mut traffic = Traffic::default();
+        traffic.log_options = defaults.log;
+        Self {
+            inner,
+            timeout: defaults.timeout,
+            newline: defaults.newline,
+            write_timeout: None,
+            deadline: None,
+            send_queue_capacity: 0,
+            max_recv_size: None,
+            on_eof: EofPolicy::default(),
+            on_timeout: TimeoutPolicy::default(),
+            read_buf_logged: 0,
+            unread: Vec::new(),
+            unread_pos: 0,
+            send_queue: SendQueue::default(),
+            corked: None,
+            background_send: None,
+            read_chunk_size: None,
+            buffer_capacity: None,
+            traffic,
+            rng: None,
+            copy_buf: Vec::new(),
+        }
+    }
+
+    /// Set a deadline that all subsequent operations with a timeout respect, in addition to their
+    /// own timeout. See [`with_deadline`](super::with_deadline) for a deadline that applies to
+    /// every tube in a task.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{
+    ///     io,
+    ///     time::{Duration, Instant},
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn deadline() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.deadline(Instant::now() + Duration::from_millis(100));
+    ///
+    ///     let start = Instant::now();
+    ///     assert_eq!(p.recv_line().await?, b"");
+    ///     assert_eq!(p.recv_line().await?, b"");
+    ///     assert!(start.elapsed() < Duration::from_millis(500));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// deadline();
+    /// ```
+    pub fn deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Remove the deadline set by [`Tube::deadline`].
+    pub fn clear_deadline(&mut self) {
+        self.deadline = None;
+    }
+
+    /// The random generator of the tube, e.g. for markers and padding. It is derived from the
+    /// global seed when first used, so the run can be reproduced with
+    /// [`context::set_seed`](crate::context::set_seed) as long as the tubes use it in the same
+    /// order.
+    /// ```rust
+    /// use io_tubes::{context, tubes::Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn rng() -> io::Result<()> {
+    ///     context::set_seed(1337);
+    ///     let mut p = Tube::echo();
+    ///     let marker = p.rng().alphanumeric(16);
+    ///     p.send_line(&marker).await?;
+    ///     assert_eq!(p.recv_until(&marker).await?, marker);
+    ///
+    ///     context::set_seed(1337);
+    ///     let mut p = Tube::echo();
+    ///     assert_eq!(p.rng().alphanumeric(16), marker);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// rng();
+    /// ```
+    pub fn rng(&mut self) -> &mut Rng {
+        self.rng.get_or_insert_with(context::rng)
+    }
+
+    /// The timeout for receiving, shortened to the deadlines if any.
+    pub(crate) fn recv_timeout(&self) -> Duration {
+        let timeout = self.estimated_timeout().unwrap_or(self.timeout);
+        self.limit_to_deadline(timeout)
+    }
+
+    /// The timeout for sending, shortened to the deadlines if any.
+    pub(crate) fn send_timeout(&self) -> Duration {
+        self.limit_to_deadline(self.write_timeout.unwrap_or(self.timeout))
+    }
+
+    pub(crate) fn limit_to_deadline(&self, timeout: Duration) -> Duration {
+        let Some(now) = clock::now() else {
+            return timeout;
+        };
+        [self.deadline, ambient_deadline()]
+            .into_iter()
+            .flatten()
+            .fold(timeout, |timeout, deadline| {
+                timeout.min(deadline.saturating_duration_since(now))
+            })
+    }
+
+    /// Take the data put back to be received again.
+    pub(super) fn take_unread(&mut self) -> Vec<u8> {
+        let mut unread = std::mem::take(&mut self.unread);
+        unread.drain(..std::mem::take(&mut self.unread_pos));
+        unread
+    }
+
+    /// Consume the tube to get back the underlying BufReader
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Construct a tube from any custom buffered type.
+    pub fn from_buffered(inner: T) -> Self {
+        Self {
+            background_send: Some(Self::poll_send_queue_background),
+            ..Self::from_inner(inner)
+        }
+    }
+
+    /// Replace the inner stream with `f(inner)`, keeping the settings, the data already received,
+    /// the data queued and the logging of the tube.
+    pub(super) fn map_buffered<U, F>(self, f: F) -> Tube<U>
+    where
+        U: AsyncBufRead + AsyncWrite + Unpin,
+        F: FnOnce(T) -> U,
+    {
+        Tube {
+            inner: f(self.inner),
+            timeout: self.timeout,
+            newline: self.newline,
+            write_timeout: self.write_timeout,
+            deadline: self.deadline,
+            send_queue_capacity: self.send_queue_capacity,
+            max_recv_size: self.max_recv_size,
+            on_eof: self.on_eof,
+            on_timeout: self.on_timeout,
+            read_buf_logged: self.read_buf_logged,
+            unread: self.unread,
+            unread_pos: self.unread_pos,
+            send_queue: self.send_queue,
+            corked: self.corked,
+            background_send: Some(Tube::poll_send_queue_background),
+            read_chunk_size: self.read_chunk_size,
+            buffer_capacity: self.buffer_capacity,
+            traffic: self.traffic,
+            rng: self.rng,
+            copy_buf: self.copy_buf,
+        }
+    }
+
+    /// Split the tube into a read half and a write half, so that one task can send while another
+    /// receives. The settings like timeout and the data already received are kept. The halves
+    /// keep the name, the logging, the recording and the adaptive timeout of the tube together,
+    /// and their statistics are added up again by [`Tube::unsplit`].
+    ///
+    /// Note that the data queued by [`Tube::send_nowait`] is only written in the background when
+    /// the write half is used.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn split() -> io::Result<()> {
+    ///     let p = Tube::echo();
+    ///     let (mut rx, mut tx) = p.split();
+    ///
+    ///     let sender = tokio::spawn(async move {
+    ///         for i in 0..3 {
+    ///             tx.send_line(i.to_string()).await?;
+    ///         }
+    ///         io::Result::Ok(tx)
+    ///     });
+    ///     assert_eq!(rx.recv_lines(3).await?, [b"0\n", b"1\n", b"2\n"]);
+    ///
+    ///     let tx = sender.await??;
+    ///     let mut p = Tube::unsplit(rx, tx);
+    ///     p.send("joined").await?;
+    ///     assert_eq!(p.recv_until("joined").await?, b"joined");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// split();
+    /// ```
+    pub fn split(self) -> (TubeReadHalf<T>, TubeWriteHalf<T>) {
+        let Tube {
+            inner,
+            timeout,
+            newline,
+            write_timeout,
+            deadline,
+            send_queue_capacity,
+            max_recv_size,
+            on_eof,
+            on_timeout,
+            unread,
+            unread_pos,
+            send_queue,
+            corked,
+            traffic,
+            ..
+        } = self;
+        let (read, write) = tokio::io::split(inner);
+        let read_half = Tube {
+            timeout,
+            write_timeout,
+            deadline,
+            max_recv_size,
+            on_eof,
+            on_timeout,
+            unread,
+            unread_pos,
+            traffic: traffic.share(),
+            ..Tube::from_inner(BufReader::new(read))
+        };
+        let write_half = Tube {
+            timeout,
+            newline,
+            write_timeout,
+            deadline,
+            send_queue_capacity,
+            send_queue,
+            corked,
+            traffic,
+            ..Tube::from_inner(write)
+        };
+        (read_half, write_half)
+    }
+
+    /// Join the halves returned by [`Tube::split`] back into a tube, keeping the data already
+    /// received and the data queued. The settings are taken from the write half.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the halves are not from the same tube.
+    pub fn unsplit(read_half: TubeReadHalf<T>, write_half: TubeWriteHalf<T>) -> Self {
+        let mut unread = read_half.unread[read_half.unread_pos..].to_vec();
+        unread.extend_from_slice(read_half.inner.buffer());
+        let inner = read_half.inner.into_inner().unsplit(write_half.inner);
+        let mut traffic = write_half.traffic;
+        traffic.merge(read_half.traffic);
+        Tube {
+            timeout: write_half.timeout,
+            newline: write_half.newline,
+            write_timeout: write_half.write_timeout,
+            deadline: write_half.deadline,
+            send_queue_capacity: write_half.send_queue_capacity,
+            max_recv_size: read_half.max_recv_size,
+            on_eof: read_half.on_eof,
+            on_timeout: read_half.on_timeout,
+            unread,
+            send_queue: write_half.send_queue,
+            corked: write_half.corked,
+            traffic,
+            ..Tube::from_buffered(inner)
+        }
+    }
+
+    /// Send line after receiving the pattern from read.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn send_line_after() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("Hello, what's your name? ").await?;
+    ///     assert_eq!(
+    ///         p.send_line_after("name", "test").await?,
+    ///         b"Hello, what's your name"
+    ///     );
+    ///     assert_eq!(p.recv_line().await?, b"? test\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// send_line_after();
+    /// ```
+    pub async fn send_line_after(
+        &mut self,
+        pattern: impl Needle,
+        data: impl AsRef<[u8]>,
+    ) -> io::Result<Vec<u8>> {
+        let result = self.recv_until(pattern).await?;
+        self.send_line(data).await?;
+        Ok(result)
+    }
+
+    /// Send each line after receiving the prompt, e.g. to walk through a menu, and return what is
+    /// received before each line. It stops at the first error, and the lines before it are
+    /// already sent.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn send_lines_after() -> io::Result<()> {
+    ///     let (mut p, mut server) = Tube::pair();
+    ///     let menu = tokio::spawn(async move {
+    ///         server.send("1. alloc\n> ").await?;
+    ///         let a = server.recv_line().await?;
+    ///         server.send([&a[..], b"> "].concat()).await?;
+    ///         let b = server.recv_line().await?;
+    ///         server.send(b).await
+    ///     });
+    ///
+    ///     let outputs = p.send_lines_after("> ", ["first", "second"]).await?;
+    ///     assert_eq!(outputs, [&b"1. alloc\n> "[..], b"first\n> "]);
+    ///     assert_eq!(p.recv_line().await?, b"second\n");
+    ///     menu.await??;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// send_lines_after();
+    /// ```
+    pub async fn send_lines_after<I>(
+        &mut self,
+        prompt: impl Needle,
+        lines: I,
+    ) -> io::Result<Vec<Vec<u8>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut outputs = Vec::new();
+        for line in lines {
+            outputs.push(self.send_line_after(&prompt, line).await?);
+        }
+        Ok(outputs)
+    }
+
+    /// Connect the tube to stdin and stdout so you can interact with it directly.
+    ///
+    /// Returns how the interaction ended, and the tube can still be used afterwards.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn interactive(&mut self) -> io::Result<InteractiveEnd> {
+        self.interactive_with(&InteractiveOptions::default()).await
+    }
+
+    /// Same as interactive, but put the local terminal into raw mode so that every key press
+    /// including arrow keys and Ctrl-C is sent to the target immediately without local echo,
+    /// which is needed by full-screen programs and remote shells with a pty.
+    ///
+    /// Press Ctrl-] to end the interaction. The terminal mode is restored afterwards, even if the
+    /// interaction fails or panics. Fails with [`Unsupported`](io::ErrorKind::Unsupported) if
+    /// stdin is not a terminal.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn interactive_raw() -> io::Result<()> {
+    ///     let mut p = Tube::process("/usr/bin/bash")?;
+    ///     p.interactive_raw().await?;
+    ///     Ok(())
+    /// }
+    ///
+    /// interactive_raw();
+    /// ```
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn interactive_raw(&mut self) -> io::Result<InteractiveEnd> {
+        let options = InteractiveOptions {
+            escape: vec![0x1d],
+            raw: true,
+            ..Default::default()
+        };
+        self.interactive_with(&options).await
+    }
+
+    /// Interact with the tube with the supplied options.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::{InteractiveEnd, InteractiveOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn interactive_with() -> io::Result<()> {
+    ///     let mut p = Tube::process("/usr/bin/bash")?;
+    ///     let options = InteractiveOptions {
+    ///         escape: b"~.".to_vec(),
+    ///         auto_responses: vec![(b"Are you sure? [y/N] ".to_vec(), b"y\n".to_vec())],
+    ///         ..Default::default()
+    ///     };
+    ///
+    ///     // Take over the shell until "~." is typed, then continue the script. The confirmations
+    ///     // are answered automatically in the meantime.
+    ///     if p.interactive_with(&options).await? == InteractiveEnd::Escape {
+    ///         p.send_line("exit").await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// interactive_with();
+    /// ```
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn interactive_with(
+        &mut self,
+        options: &InteractiveOptions,
+    ) -> io::Result<InteractiveEnd> {
+        #[cfg(unix)]
+        let _raw_mode = options.raw.then(RawMode::enable).transpose()?;
+        #[cfg(not(unix))]
+        if options.raw {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "raw mode is only supported on unix",
+            ));
+        }
+        let _paused = KeepalivePause::new(self.traffic.keepalive_paused.as_ref());
+        let mut interactive = Interactive::new(self, stdin(), stdout(), &options.escape)
+            .responses(&options.auto_responses);
+        if let Some(idle_timeout) = options.idle_timeout {
+            interactive = interactive.idle(idle_timeout, options.on_idle.as_ref());
+        }
+        interactive.await
+    }
+
+    /// Same as interactive, but connect the tube to the reader and writer instead of stdin and
+    /// stdout, so the other side can be another tube, a client connection or a test harness.
+    ///
+    /// Returns [`InteractiveEnd::Eof`] when EOF is reached on the reader.
+    /// ```rust
+    /// use io_tubes::tubes::{InteractiveEnd, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn interact_with() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     // Expose the process to a teammate, which would usually come from Listener::accept.
+    ///     let (human, teammate) = tokio::io::duplex(64);
+    ///     let (reader, writer) = tokio::io::split(human);
+    ///     let mut teammate = Tube::new(teammate);
+    ///
+    ///     let (end, line) = tokio::join!(p.interact_with(reader, writer), async move {
+    ///         teammate.send_line("id").await?;
+    ///         teammate.recv_line().await
+    ///     });
+    ///     assert_eq!(end?, InteractiveEnd::Eof);
+    ///     assert_eq!(line?, b"id\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// interact_with();
+    /// ```
+    pub async fn interact_with(
+        &mut self,
+        reader: impl AsyncRead + Unpin,
+        writer: impl AsyncWrite + Unpin,
+    ) -> io::Result<InteractiveEnd> {
+        let _paused = KeepalivePause::new(self.traffic.keepalive_paused.as_ref());
+        Interactive::new(self, reader, writer, &[]).await
+    }
+}
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + Unpin,
+{
+    /// Receive up to `len` bytes.
+    pub async fn recv(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let result = self.recv_checked(len).await;
+        self.lenient(result)
+    }
+
+    /// Same as recv, but reports timeout and EOF as [`TubeError`] instead of returning an empty
+    /// vector.
+    /// ```rust
+    /// use io_tubes::tubes::{Tube, TubeError};
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn recv_checked() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.timeout = Duration::from_millis(50);
+    ///
+    ///     assert!(matches!(p.recv_checked(4).await, Err(TubeError::Timeout { .. })));
+    ///     p.send("data").await?;
+    ///     assert_eq!(p.recv_checked(4).await?, b"data");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_checked();
+    /// ```
+    pub async fn recv_checked(&mut self, len: usize) -> Result<Vec<u8>, TubeError> {
+        let mut buf = vec![0; len];
+        let numb = match timeout(self.recv_timeout(), self.read(&mut buf[..])).await {
+            Ok(numb) => numb?,
+            Err(_) => {
+                return Err(TubeError::Timeout {
+                    partial: Vec::new(),
+                })
+            }
+        };
+        if numb == 0 && len != 0 {
+            return Err(TubeError::Eof {
+                partial: Vec::new(),
+            });
+        }
+        buf.truncate(numb);
+        Ok(buf)
+    }
+
+    /// Receive up to `len` bytes that are already available without waiting.
+    ///
+    /// Returns `None` if nothing is available yet and an empty vector if EOF is reached.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn try_recv() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     assert_eq!(p.try_recv(4)?, None);
+    ///     p.send("data").await?;
+    ///     p.readable().await?;
+    ///     assert_eq!(p.try_recv(4)?, Some(b"data".to_vec()));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// try_recv();
+    /// ```
+    pub fn try_recv(&mut self, len: usize) -> io::Result<Option<Vec<u8>>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let buf = match Pin::new(&mut *self).poll_fill_buf(&mut cx)? {
+            Poll::Ready(buf) => buf,
+            Poll::Pending => return Ok(None),
+        };
+        let data = buf[..len.min(buf.len())].to_vec();
+        self.consume(data.len());
+        Ok(Some(data))
+    }
+
+    /// Discard everything that is already received without waiting for more data, e.g. before
+    /// sending a command whose response must be parsed strictly. Returns the discarded data.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn flush_read() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.timeout = Duration::from_millis(50);
+    ///
+    ///     p.send("banner\n").await?;
+    ///     p.readable().await?;
+    ///     assert_eq!(p.flush_read()?, b"banner\n");
+    ///     assert_eq!(p.flush_read()?, b"");
+    ///
+    ///     p.send("reply").await?;
+    ///     assert_eq!(p.recv(5).await?, b"reply");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// flush_read();
+    /// ```
+    pub fn flush_read(&mut self) -> io::Result<Vec<u8>> {
+        let mut discarded = Vec::new();
+        while let Some(data) = self.try_recv(usize::MAX)? {
+            if data.is_empty() {
+                break;
+            }
+            discarded.extend_from_slice(&data);
+        }
+        Ok(discarded)
+    }
+
+    /// Receive a line if a complete line is already available without waiting.
+    ///
+    /// Returns `None` if no complete line is available yet. The incomplete line is kept and will
+    /// be received by later calls. At EOF, the remaining data is returned even if it doesn't end
+    /// with new line. Fails with [`TubeError::TooLarge`] once the incomplete line is longer than
+    /// [`Tube::max_recv_size`].
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    /// use tokio::time;
+    ///
+    /// #[tokio::main]
+    /// async fn try_recv_line() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("Hello").await?;
+    ///     time::sleep(Duration::from_millis(50)).await;
+    ///     assert_eq!(p.try_recv_line()?, None);
+    ///
+    ///     p.send(" World\n").await?;
+    ///     time::sleep(Duration::from_millis(50)).await;
+    ///     assert_eq!(p.try_recv_line()?, Some(b"Hello World\n".to_vec()));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// try_recv_line();
+    /// ```
+    pub fn try_recv_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            let unread = &self.unread[self.unread_pos..];
+            let max_size = self.max_recv_size.unwrap_or(usize::MAX);
+            let searched = &unread[..unread.len().min(max_size)];
+            if let Some(pos) = searched.iter().position(|&byte| byte == NEW_LINE) {
+                let line = unread[..=pos].to_vec();
+                self.consume(line.len());
+                return Ok(Some(line));
+            }
+            if unread.len() > max_size {
+                let partial = searched.to_vec();
+                self.consume(partial.len());
+                return Err(TubeError::TooLarge { partial }.into());
+            }
+
+            // Move everything into the unread buffer so that the line can span across several
+            // reads from the inner reader.
+            self.unread.drain(..self.unread_pos);
+            self.unread_pos = 0;
+            let buf = match Self::poll_fill_logged(
+                &mut self.inner,
+                &mut self.read_buf_logged,
+                &mut self.traffic,
+                &mut cx,
+            )? {
+                Poll::Ready(buf) => buf,
+                Poll::Pending => return Ok(None),
+            };
+            if buf.is_empty() {
+                return Ok(Some(std::mem::take(&mut self.unread)));
+            }
+            let len = buf.len();
+            self.unread.extend_from_slice(buf);
+            self.consume_inner(len);
+        }
+    }
+
+    /// Receive until new line (0xA byte) is reached or EOF is reached.
+    pub async fn recv_line(&mut self) -> io::Result<Vec<u8>> {
+        let result = self.recv_line_checked().await;
+        self.lenient(result)
+    }
+
+    /// Same as recv_line, but reports timeout and EOF before new line as [`TubeError`].
+    /// ```rust
+    /// use io_tubes::tubes::{Tube, TubeError};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_line_checked() -> io::Result<()> {
+    ///     let (mut p, mut server) = Tube::pair();
+    ///
+    ///     server.send(b"line\nno new line").await?;
+    ///     drop(server);
+    ///     assert_eq!(p.recv_line_checked().await?, b"line\n");
+    ///     match p.recv_line_checked().await {
+    ///         Err(TubeError::Eof { partial }) => assert_eq!(partial, b"no new line"),
+    ///         other => panic!("unexpected {:?}", other),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_line_checked();
+    /// ```
+    pub async fn recv_line_checked(&mut self) -> Result<Vec<u8>, TubeError> {
+        let mut buf = Vec::new();
+        let recv_timeout = self.recv_timeout();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        match timeout(recv_timeout, limited.read_until(NEW_LINE, &mut buf)).await {
+            Ok(Err(_)) if limited.exceeded() => return Err(TubeError::TooLarge { partial: buf }),
+            Ok(result) => result?,
+            Err(_) => return Err(TubeError::Timeout { partial: buf }),
+        };
+        if buf.last() != Some(&NEW_LINE) {
+            return Err(TubeError::Eof { partial: buf });
+        }
+        Ok(buf)
+    }
+
+    /// Receive `n` lines. Fewer lines are returned if the timeout or EOF is reached first, in
+    /// which case the last line may not end with new line.
+    ///
+    /// A line longer than [`Tube::max_recv_size`] fails with [`TubeError::TooLarge`] if it is the
+    /// first one. Otherwise, the lines before it are returned and it is kept to be received
+    /// again.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_lines() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("1. Add\n2. Delete\n3. Exit\n").await?;
+    ///     assert_eq!(
+    ///         p.recv_lines(2).await?,
+    ///         vec![b"1. Add\n".to_vec(), b"2. Delete\n".to_vec()]
+    ///     );
+    ///
+    ///     p.max_recv_size = Some(8);
+    ///     p.send("4. Edit\nA line longer than the limit\n").await?;
+    ///     assert_eq!(
+    ///         p.recv_lines(3).await?,
+    ///         vec![b"3. Exit\n".to_vec(), b"4. Edit\n".to_vec()]
+    ///     );
+    ///     assert!(p.recv_lines(1).await.is_err());
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_lines();
+    /// ```
+    pub async fn recv_lines(&mut self, n: usize) -> io::Result<Vec<Vec<u8>>> {
+        let mut lines = Vec::with_capacity(n);
+        let mut line = Vec::new();
+        timeout(self.recv_timeout(), async {
+            while lines.len() < n {
+                let mut limited = Limited::new(self, self.max_recv_size);
+                let result = limited.read_until(NEW_LINE, &mut line).await;
+                if limited.exceeded() {
+                    let partial = std::mem::take(&mut line);
+                    if lines.is_empty() {
+                        return Err(TubeError::TooLarge { partial }.into());
+                    }
+                    self.unrecv(&partial);
+                    break;
+                }
+                if result? == 0 {
+                    break;
+                }
+                lines.push(std::mem::take(&mut line));
+            }
+            io::Result::Ok(())
+        })
+        .await
+        .unwrap_or(Ok(()))?;
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+
+    /// Receive until EOF is reached, returning everything received. The data received so far is
+    /// returned if the timeout is reached first.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_all() -> io::Result<()> {
+    ///     let (mut p, mut server) = Tube::pair();
+    ///
+    ///     server.send("line 1\nline 2\n").await?;
+    ///     drop(server);
+    ///     assert_eq!(p.recv_all().await?, b"line 1\nline 2\n");
+    ///     assert_eq!(p.recv_all().await?, b"");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_all();
+    /// ```
+    pub async fn recv_all(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let recv_timeout = self.recv_timeout();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        match timeout(recv_timeout, limited.read_to_end(&mut buf)).await {
+            Ok(Err(_)) if limited.exceeded() => Err(TubeError::TooLarge { partial: buf }.into()),
+            Ok(Err(err)) => Err(err),
+            _ => Ok(buf),
+        }
+    }
+
+    /// Receive lines until one of them contains any of the keywords, and return that line. The
+    /// lines before it are discarded.
+    ///
+    /// An empty vector is returned if the timeout or EOF is reached first. Each line is limited
+    /// by [`Tube::max_recv_size`], failing with [`TubeError::TooLarge`] if it is exceeded.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_line_contains() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("Welcome!\nYour gift: 0x1337\nBye\n").await?;
+    ///     assert_eq!(
+    ///         p.recv_line_contains(&["gift", "flag"]).await?,
+    ///         b"Your gift: 0x1337\n"
+    ///     );
+    ///
+    ///     p.max_recv_size = Some(16);
+    ///     p.send("A line longer than the limit\n").await?;
+    ///     let err = p.recv_line_contains(&["flag"]).await.unwrap_err();
+    ///     assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_line_contains();
+    /// ```
+    pub async fn recv_line_contains(
+        &mut self,
+        keywords: &[impl AsRef<[u8]>],
+    ) -> io::Result<Vec<u8>> {
+        timeout(self.recv_timeout(), async {
+            loop {
+                let mut line = Vec::new();
+                let mut limited = Limited::new(self, self.max_recv_size);
+                let result = limited.read_until(NEW_LINE, &mut line).await;
+                if limited.exceeded() {
+                    return Err(TubeError::TooLarge { partial: line }.into());
+                }
+                if result? == 0 {
+                    return Ok(line);
+                }
+                let found = keywords.iter().any(|keyword| {
+                    let keyword = keyword.as_ref();
+                    keyword.is_empty() || line.windows(keyword.len()).any(|w| w == keyword)
+                });
+                if found {
+                    return Ok(line);
+                }
+            }
+        })
+        .await
+        .unwrap_or(Ok(Vec::new()))
+    }
+
+    /// Receive until the delims are found or EOF is reached. The delims can be anything that
+    /// implements [`Needle`], like a string, a byte, a char or a regex.
+    ///
+    /// Literal delims are searched with [`Matcher`](crate::utils::Matcher) as the data arrives,
+    /// so that long delims are cheap to match.
+    /// ```rust
+    /// use io_tubes::{regex::bytes::Regex, tubes::Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("1. Add\n> Size: 0x20\n").await?;
+    ///     assert_eq!(p.recv_until(0xA).await?, b"1. Add\n");
+    ///     assert_eq!(p.recv_until('>').await?, b">");
+    ///     assert_eq!(p.recv_until(b": ").await?, b" Size: ");
+    ///     let hex = Regex::new(r"0x[0-9a-f]+\n").unwrap();
+    ///     assert_eq!(p.recv_until(&hex).await?, b"0x20\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until();
+    /// ```
+    pub async fn recv_until(&mut self, delims: impl Needle) -> io::Result<Vec<u8>> {
+        self.recv_until_with(delims, &RecvUntilOptions::default())
+            .await
+    }
+
+    /// Same as recv_until, but the delims are excluded from the returned data.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_drop() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("token=abcd;rest").await?;
+    ///     p.recv_until("token=").await?;
+    ///     assert_eq!(p.recv_until_drop(";").await?, b"abcd");
+    ///     assert_eq!(p.recv(4).await?, b"rest");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_drop();
+    /// ```
+    pub async fn recv_until_drop(&mut self, delims: impl Needle) -> io::Result<Vec<u8>> {
+        self.recv_until_with(
+            delims,
+            &RecvUntilOptions {
+                drop: true,
+                ..RecvUntilOptions::default()
+            },
+        )
+        .await
+    }
+
+    /// Receive until the delims are found or EOF is reached with the supplied options.
+    /// ```rust
+    /// use io_tubes::tubes::{RecvUntilOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_with() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("[12:01:33] Welcome, user 4127!\nmenu").await?;
+    ///     let options = RecvUntilOptions {
+    ///         drop: true,
+    ///         max_edits: 4,
+    ///         ..RecvUntilOptions::default()
+    ///     };
+    ///     let banner = p.recv_until_with("Welcome, user 1000!\n", &options).await?;
+    ///     assert_eq!(banner, b"[12:01:33] ");
+    ///     assert_eq!(p.recv(4).await?, b"menu");
+    ///
+    ///     p.send("PASSWORD:\t").await?;
+    ///     let options = RecvUntilOptions {
+    ///         ignore_case: true,
+    ///         collapse_whitespace: true,
+    ///         ..RecvUntilOptions::default()
+    ///     };
+    ///     assert_eq!(p.recv_until_with("Password: ", &options).await?, b"PASSWORD:\t");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_with();
+    /// ```
+    pub async fn recv_until_with(
+        &mut self,
+        delims: impl Needle,
+        options: &RecvUntilOptions,
+    ) -> io::Result<Vec<u8>> {
+        let on_eof = options.on_eof.unwrap_or(self.on_eof);
+        match self.recv_until_checked_with(delims, options).await {
+            Err(err @ TubeError::Eof { .. }) if on_eof == EofPolicy::Error => Err(err.into()),
+            result => self.lenient(result),
+        }
+    }
+
+    /// Same as recv_until, but reports timeout and EOF before the delims as [`TubeError`].
+    /// ```rust
+    /// use io_tubes::tubes::{Tube, TubeError};
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_checked() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.timeout = Duration::from_millis(50);
+    ///
+    ///     p.send("Name: ").await?;
+    ///     match p.recv_until_checked("> ").await {
+    ///         Err(TubeError::Timeout { partial }) => assert_eq!(partial, b"Name: "),
+    ///         other => panic!("unexpected {:?}", other),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_checked();
+    /// ```
+    pub async fn recv_until_checked(&mut self, delims: impl Needle) -> Result<Vec<u8>, TubeError> {
+        self.recv_until_checked_with(delims, &RecvUntilOptions::default())
+            .await
+    }
+
+    /// Same as recv_until_with, but reports timeout and EOF before the delims as [`TubeError`].
+    /// ```rust
+    /// use io_tubes::tubes::{RecvUntilOptions, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_checked_with() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.max_recv_size = Some(1024);
+    ///
+    ///     p.send(vec![b'A'; 2048]).await?;
+    ///     assert!(p.recv_until("> ").await.is_err());
+    ///
+    ///     p.send("> ").await?;
+    ///     let options = RecvUntilOptions {
+    ///         max_size: Some(4096),
+    ///         ..RecvUntilOptions::default()
+    ///     };
+    ///     match p.recv_until_checked_with("> ", &options).await {
+    ///         Ok(data) => assert_eq!(data.len(), 1026),
+    ///         Err(err) => panic!("unexpected {:?}", err),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_checked_with();
+    /// ```
+    pub async fn recv_until_checked_with(
+        &mut self,
+        delims: impl Needle,
+        options: &RecvUntilOptions,
+    ) -> Result<Vec<u8>, TubeError> {
+        let tolerant = match options.ignore_case || options.collapse_whitespace {
+            false => None,
+            true if options.max_edits > 0 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "max_edits cannot be combined with ignore_case or collapse_whitespace",
+                )
+                .into())
+            }
+            true => match delims.literal() {
+                Some(literal) => Some(tolerant_regex(
+                    &literal,
+                    options.ignore_case,
+                    options.collapse_whitespace,
+                )),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "ignore_case and collapse_whitespace require literal delims",
+                    )
+                    .into())
+                }
+            },
+        };
+        let literal = match options.max_edits {
+            0 => None,
+            max_edits => match delims.literal() {
+                Some(literal) if max_edits < literal.len() => Some(literal),
+                Some(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "max_edits must be less than the length of the delims",
+                    )
+                    .into())
+                }
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "max_edits requires literal delims",
+                    )
+                    .into())
+                }
+            },
+        };
+        let mut buf = Vec::new();
+        let recv_timeout = self.recv_timeout();
+        let max_size = options.max_size.or(self.max_recv_size);
+        let on_eof = options.on_eof.unwrap_or(self.on_eof);
+        let mut limited = Limited::new(self, max_size);
+        let mut reader = RetryEof::new(&mut limited, on_eof);
+        let recv = async {
+            match (&literal, &tolerant) {
+                (Some(literal), _) => {
+                    RecvUntilFuzzy::new(&mut reader, literal, options.max_edits, &mut buf).await
+                }
+                (None, Some(regex)) => RecvNeedle::new(&mut reader, regex, &mut buf).await,
+                (None, None) => RecvNeedle::new(&mut reader, &delims, &mut buf).await,
+            }
+        };
+        let match_len = match timeout(recv_timeout, recv).await {
+            Ok(Err(_)) if limited.exceeded() => return Err(TubeError::TooLarge { partial: buf }),
+            Ok(match_len) => match_len?,
+            Err(_) => return Err(TubeError::Timeout { partial: buf }),
+        };
+        let Some(match_len) = match_len else {
+            return Err(TubeError::Eof { partial: buf });
+        };
+        if options.drop {
+            buf.truncate(buf.len() - match_len);
+        }
+        Ok(buf)
+    }
+
+    /// Same as recv_until, but `clear` chooses what happens to the data when the timeout is
+    /// reached before the delims.
+    ///
+    /// If `clear` is true, the data received and everything else already available are consumed
+    /// and returned, so that the next pattern starts from a clean stream. Otherwise, the data is
+    /// put back to be received again and an empty vector is returned.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_or_clear() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.timeout = Duration::from_millis(50);
+    ///
+    ///     p.send("Invalid choice\n").await?;
+    ///     assert_eq!(p.recv_until_or_clear("> ", false).await?, b"");
+    ///     assert_eq!(p.recv_until_or_clear("> ", true).await?, b"Invalid choice\n");
+    ///
+    ///     p.send("1. Add\n> ").await?;
+    ///     assert_eq!(p.recv_until_or_clear("> ", true).await?, b"1. Add\n> ");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_or_clear();
+    /// ```
+    pub async fn recv_until_or_clear(
+        &mut self,
+        delims: impl Needle,
+        clear: bool,
+    ) -> io::Result<Vec<u8>> {
+        match self.recv_until_checked(delims).await {
+            Err(TubeError::Timeout { mut partial }) if clear => {
+                partial.extend(self.flush_read()?);
+                Ok(partial)
+            }
+            Err(TubeError::Timeout { partial }) => {
+                self.unrecv(&partial);
+                Ok(Vec::new())
+            }
+            result => result.or_else(TubeError::into_partial),
+        }
+    }
+
+    /// Receive until any of the delims is found or EOF is reached.
+    ///
+    /// Returns the received data and the index of the delimiter found, which is `None` if the
+    /// timeout or EOF is reached instead. If several delimiters end at the same position, the
+    /// longest one is reported, or the earlier one if the same delimiter is given twice.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_until_any() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("1. Add\n2. Delete\nchoice: ").await?;
+    ///     let (data, found) = p.recv_until_any(&["> ", "choice: ", "Error"]).await?;
+    ///     assert_eq!(data, b"1. Add\n2. Delete\nchoice: ");
+    ///     assert_eq!(found, Some(1));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// recv_until_any();
+    /// ```
+    pub async fn recv_until_any(
+        &mut self,
+        delims: &[impl AsRef<[u8]>],
+    ) -> io::Result<(Vec<u8>, Option<usize>)> {
+        let delims: Vec<&[u8]> = delims.iter().map(AsRef::as_ref).collect();
+        let mut buf = Vec::new();
+        let recv_timeout = self.recv_timeout();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        let found = match timeout(
+            recv_timeout,
+            RecvUntilAny::new(&mut limited, &delims, &mut buf),
+        )
+        .await
+        {
+            Ok(Err(_)) if limited.exceeded() => {
+                return Err(TubeError::TooLarge { partial: buf }.into())
+            }
+            result => result.unwrap_or(Ok(None))?,
+        };
+        Ok((buf, found))
+    }
+
+    /// Receive until the regex matches or EOF is reached.
+    ///
+    /// Returns the received data together with the capture groups of the match, where the first
+    /// group is the whole match. The captures are empty if the timeout or EOF is reached before a
+    /// match is found. Note that the regex is matched against the data received so far, so a
+    /// pattern like `[0-9]+` may match before all the digits arrive. Anchor the pattern with a
+    /// trailing delimiter to avoid this.
+    /// ```rust
+    /// use io_tubes::{regex::bytes::Regex, tubes::Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn recv_regex() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send("Gift: 0x7ffff7a05000\n").await?;
+    ///     let regex = Regex::new(r"0x([0-9a-f]+)\n").unwrap();
+    ///     let (data, captures) = p.recv_regex(&regex).await?;
+    ///     assert_eq!(data, b"Gift: 0x7ffff7a05000\n");
+    ///     assert_eq!(captures[1].as_deref(), Some(&b"7ffff7a05000"[..]));
+    ///
+    ///     Ok(())
+    /// }
     ///
-    /// Hence, timeout can only be reliably implemented for async fn (which returns a future under
-    /// the hood) or fn that return a future.
-    pub timeout: Duration,
-
-    read_buf_logged: usize,
-}
-
-const NEW_LINE: u8 = 0xA;
-
-impl<T> Tube<BufReader<T>>
-where
-    T: AsyncRead + AsyncWrite + Unpin,
-{
-    /// Construct a new `Tube<T>`.
-    pub fn new(inner: T) -> Self {
-        Self {
-            inner: BufReader::new(inner),
-            timeout: Duration::MAX,
-            read_buf_logged: 0,
-        }
+    /// recv_regex();
+    /// ```
+    pub async fn recv_regex(
+        &mut self,
+        regex: &Regex,
+    ) -> io::Result<(Vec<u8>, Vec<Option<Vec<u8>>>)> {
+        let mut buf = Vec::new();
+        let recv_timeout = self.recv_timeout();
+        let mut limited = Limited::new(self, self.max_recv_size);
+        let ranges =
+            match timeout(recv_timeout, RecvRegex::new(&mut limited, regex, &mut buf)).await {
+                Ok(Err(_)) if limited.exceeded() => {
+                    return Err(TubeError::TooLarge { partial: buf }.into())
+                }
+                result => result.unwrap_or(Ok(None))?.unwrap_or_default(),
+            };
+        let captures = ranges
+            .into_iter()
+            .map(|range| range.map(|range| buf[range].to_vec()))
+            .collect();
+        Ok((buf, captures))
     }
 
-    /// Construct a new `Tube<T>` with the supplied timeout argument. Note that timeout is only
-    /// implemented for methods directly provided by this struct and not methods from traits.
+    /// Wait until data is available to receive or EOF is reached, without consuming anything.
     ///
+    /// This is useful in `tokio::select!` or custom poll loops that should only continue when the
+    /// tube has something to say.
     /// ```rust
-    /// use io_tubes::tubes::{ProcessTube, Tube};
+    /// use io_tubes::tubes::Tube;
     /// use std::{io, time::Duration};
+    /// use tokio::time;
     ///
     /// #[tokio::main]
-    /// async fn create_with_timeout() -> io::Result<()> {
-    ///     let mut p = Tube::process("/usr/bin/cat")?;
-    ///     p.timeout = Duration::from_millis(50);
-    ///     // Equivalent to
-    ///     let mut p =
-    ///         Tube::with_timeout(ProcessTube::new("/usr/bin/cat")?, Duration::from_millis(50));
+    /// async fn readable() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     tokio::select! {
+    ///         result = p.readable() => panic!("nothing is sent yet: {:?}", result),
+    ///         _ = time::sleep(Duration::from_millis(50)) => {}
+    ///     }
+    ///
+    ///     p.send("data").await?;
+    ///     p.readable().await?;
+    ///     assert_eq!(p.recv(4).await?, b"data");
+    ///
     ///     Ok(())
     /// }
     ///
-    /// create_with_timeout();
+    /// readable();
     /// ```
-    pub fn with_timeout(inner: T, timeout: Duration) -> Self {
-        Self {
-            inner: BufReader::new(inner),
-            timeout,
-            read_buf_logged: 0,
+    pub async fn readable(&mut self) -> io::Result<()> {
+        future::poll_fn(|cx| Pin::new(&mut *self).poll_fill_buf(cx).map_ok(|_| ())).await
+    }
+
+    fn poll_fill_logged<'b>(
+        inner: &'b mut T,
+        read_buf_logged: &mut usize,
+        traffic: &mut Traffic,
+        cx: &mut Context,
+    ) -> Poll<io::Result<&'b [u8]>> {
+        let buf = match Pin::new(inner).poll_fill_buf(cx)? {
+            Poll::Ready(buf) => buf,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        if buf.len() > *read_buf_logged {
+            traffic.received(&buf[*read_buf_logged..]);
+            *read_buf_logged = buf.len();
+        }
+
+        Poll::Ready(Ok(buf))
+    }
+
+    /// Turn the result of a checked method into the result of the lenient one, according to
+    /// [`Tube::on_timeout`].
+    fn lenient(&mut self, result: Result<Vec<u8>, TubeError>) -> io::Result<Vec<u8>> {
+        match result {
+            Err(TubeError::Timeout { partial }) => match self.on_timeout {
+                TimeoutPolicy::Partial => Ok(partial),
+                TimeoutPolicy::Error => {
+                    self.unrecv(&partial);
+                    Err(TubeError::Timeout {
+                        partial: Vec::new(),
+                    }
+                    .into())
+                }
+                TimeoutPolicy::ErrorWithPartial => Err(TubeError::Timeout { partial }.into()),
+            },
+            result => result.or_else(TubeError::into_partial),
         }
     }
+
+    /// Put the data back so that it is received before everything else.
+    pub(crate) fn unrecv(&mut self, data: &[u8]) {
+        self.unread
+            .splice(self.unread_pos..self.unread_pos, data.iter().copied());
+    }
+
+    fn consume_inner(&mut self, amt: usize) {
+        self.read_buf_logged -= amt;
+        Pin::new(&mut self.inner).consume(amt);
+    }
 }
 
-impl Tube<BufReader<ProcessTube>> {
-    /// Create a process with supplied path to program.
+impl<T> Tube<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Run the future with the send timeout, failing with [`TimedOut`](io::ErrorKind::TimedOut).
+    pub(crate) async fn with_send_timeout<F, R>(&mut self, f: F) -> io::Result<R>
+    where
+        F: AsyncFnOnce(&mut Self) -> io::Result<R>,
+    {
+        let duration = self.send_timeout();
+        timeout(duration, f(self))
+            .await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "send timed out")))
+    }
+
+    /// Send data and flush.
+    pub async fn send(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
+        let data = data.as_ref();
+        self.with_send_timeout(async |tube| {
+            tube.write_all(data).await?;
+            tube.flush().await
+        })
+        .await
+    }
+
+    /// Queue data to be sent without waiting, returning an error of kind
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock) if the queue is full.
+    ///
+    /// The queue is written as much as possible immediately, and the rest is written in the
+    /// background whenever the tube is polled, e.g. while waiting in [`Tube::recv_until`].
+    /// Subsequent writes are always sent after the queued data and flushing the tube waits for
+    /// the queue to be empty. Set [`Tube::send_queue_capacity`] to enable the queue, and use
+    /// [`Tube::spawn_flusher`] to write it even while the tube is not used.
     /// ```rust
     /// use io_tubes::tubes::Tube;
     /// use std::io;
     ///
     /// #[tokio::main]
-    /// async fn create_process() -> io::Result<()> {
-    ///     let mut p = Tube::process("/usr/bin/cat")?;
-    ///     p.send("abcdHi!").await?;
-    ///     let result = p.recv_until("Hi").await?;
-    ///     assert_eq!(result, b"abcdHi");
+    /// async fn send_nowait() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///     p.send_queue_capacity = 8;
+    ///
+    ///     p.send_nowait("AAAA")?;
+    ///     p.send_nowait("BBBB")?;
+    ///     assert_eq!(p.recv_until("BBBB").await?, b"AAAABBBB");
+    ///
     ///     Ok(())
     /// }
     ///
-    /// create_process();
+    /// send_nowait();
     /// ```
-    pub fn process<S: AsRef<OsStr>>(program: S) -> io::Result<Self> {
-        Ok(Self::new(ProcessTube::new(program)?))
+    pub fn send_nowait(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
+        let data = data.as_ref();
+        if self.send_queue_capacity == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the send queue is disabled",
+            ));
+        }
+        if let Some(corked) = &mut self.corked {
+            corked.extend_from_slice(data);
+            return Ok(());
+        }
+        let mut cx = Context::from_waker(Waker::noop());
+        let _ = self.poll_send_queue(&mut cx)?;
+        if self.send_queue.queued() + data.len() > self.send_queue_capacity {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        self.send_queue.push(data);
+        let _ = self.poll_send_queue(&mut cx)?;
+        Ok(())
     }
-}
 
-impl Tube<BufReader<TcpStream>> {
-    /// Create a tube by connecting to the remote address.
+    /// Hold back everything written to the tube, including the sends that would flush, until
+    /// [`Tube::uncork`] sends it all at once. This avoids sending a payload built by many small
+    /// sends in as many segments, which some targets read only partially.
+    ///
+    /// The data held back is not logged until it is sent. Shutting down the tube sends it first.
     /// ```rust
-    /// use io_tubes::tubes::{Listener, Tube};
-    /// use std::{
-    ///     io,
-    ///     net::{IpAddr, Ipv4Addr, SocketAddr},
-    /// };
+    /// use io_tubes::tubes::Tube;
+    /// use std::{io, time::Duration};
     ///
     /// #[tokio::main]
-    /// async fn create_remote() -> io::Result<()> {
-    ///     let l = Listener::listen().await?;
-    ///     let mut p =
-    ///         Tube::remote(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), l.port()?)).await?;
-    ///     let mut server = l.accept().await?;
-    ///     p.send("Client Hello").await?;
-    ///     server.send("Server Hello").await?;
-    ///     assert_eq!(p.recv_until("Hello").await?, b"Server Hello");
-    ///     assert_eq!(server.recv_until("Hello").await?, b"Client Hello");
+    /// async fn cork() -> io::Result<()> {
+    ///     let (mut p, mut server) = Tube::pair();
+    ///     server.timeout = Duration::from_millis(50);
+    ///
+    ///     p.cork();
+    ///     for byte in b"AAAA" {
+    ///         p.send([*byte]).await?;
+    ///     }
+    ///     p.send_line("BBBB").await?;
+    ///     assert!(p.is_corked());
+    ///     assert_eq!(server.recv(1).await?, b"");
+    ///
+    ///     p.uncork().await?;
+    ///     assert_eq!(server.recv_line().await?, b"AAAABBBB\n");
+    ///
     ///     Ok(())
     /// }
     ///
-    /// create_remote();
+    /// cork();
     /// ```
-    pub async fn remote(addr: impl ToSocketAddrs) -> io::Result<Self> {
-        Ok(Self::new(TcpStream::connect(addr).await?))
+    pub fn cork(&mut self) {
+        self.corked.get_or_insert_with(Vec::new);
     }
-}
 
-impl<T> Tube<T>
-where
-    T: AsyncBufRead + AsyncWrite + Unpin,
-{
-    /// Construct a tube from any custom buffered type.
-    pub fn from_buffered(inner: T) -> Self {
-        Self {
-            inner,
-            timeout: Duration::MAX,
-            read_buf_logged: 0,
+    /// Send the data held back since [`Tube::cork`] and flush, then send as usual again.
+    pub async fn uncork(&mut self) -> io::Result<()> {
+        if let Some(corked) = self.corked.take() {
+            self.send_queue.push(&corked);
         }
+        self.with_send_timeout(async |tube| tube.flush().await)
+            .await
     }
 
-    /// Receive up to `len` bytes.
-    pub async fn recv(&mut self, len: usize) -> io::Result<Vec<u8>> {
-        let mut buf = vec![0; len];
-        let len = time::timeout(self.timeout, self.read(&mut buf[..]))
-            .await
-            .unwrap_or(Ok(0))?;
-        buf.truncate(len);
-        Ok(buf)
+    /// Whether the tube is corked by [`Tube::cork`].
+    pub fn is_corked(&self) -> bool {
+        self.corked.is_some()
     }
 
-    /// Receive until new line (0xA byte) is reached or EOF is reached.
-    pub async fn recv_line(&mut self) -> io::Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        time::timeout(self.timeout, self.read_until(NEW_LINE, &mut buf))
-            .await
-            .unwrap_or(Ok(0))?;
-        Ok(buf)
+    /// Send a cyclic pattern of `len` bytes, so that the offset can be found with
+    /// [`cyclic_find`](crate::utils::cyclic_find) after a crash.
+    pub async fn send_cyclic(&mut self, len: usize) -> io::Result<()> {
+        self.send(cyclic(len)).await
     }
 
-    /// Receive until the delims are found or EOF is reached.
+    /// Build the payload with [`fit`](crate::utils::fit) and send it.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
     ///
-    /// A lookup table will be built to enable efficient matching of long patterns.
-    pub async fn recv_until(&mut self, delims: impl AsRef<[u8]>) -> io::Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        time::timeout(
-            self.timeout,
-            RecvUntil::new(self, delims.as_ref(), &mut buf),
-        )
-        .await
-        .unwrap_or(Ok(()))?;
-        Ok(buf)
+    /// #[tokio::main]
+    /// async fn send_flat() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send_flat([(8, 0xdeadbeefu32.into()), (16, "\n".into())]).await?;
+    ///     let line = p.recv_line().await?;
+    ///     assert_eq!(line, b"aaaaaaaa\xef\xbe\xad\xde\0\0\0\0\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// send_flat();
+    /// ```
+    pub async fn send_flat(
+        &mut self,
+        values: impl IntoIterator<Item = (usize, FlatValue)>,
+    ) -> io::Result<()> {
+        self.send(fit(values)).await
     }
 
-    /// Send data and flush.
-    pub async fn send(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
-        self.write_all(data.as_ref()).await?;
-        self.flush().await
+    /// Build the payload with [`fit_with`](crate::utils::fit_with) and send it.
+    pub async fn send_flat_with(
+        &mut self,
+        values: impl IntoIterator<Item = (usize, FlatValue)>,
+        options: &FlatOptions,
+    ) -> io::Result<()> {
+        self.send(fit_with(values, options)).await
     }
 
-    /// Same as send, but add new line (0xA byte).
+    /// Same as send, but add [`Tube::newline`].
     pub async fn send_line(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
-        self.write_all(data.as_ref()).await?;
-        self.write_all(&[NEW_LINE]).await?;
-        self.flush().await
+        let data = data.as_ref();
+        let newline = self.newline.clone();
+        self.with_send_timeout(async |tube| {
+            tube.write_all(data).await?;
+            tube.write_all(&newline).await?;
+            tube.flush().await
+        })
+        .await
     }
 
-    /// Send line after receiving the pattern from read.
+    /// Send the value formatted with [`Display`](fmt::Display), e.g. a number or the result of
+    /// [`format_args!`], without building the string at the call site.
     /// ```rust
     /// use io_tubes::tubes::Tube;
     /// use std::io;
     ///
     /// #[tokio::main]
-    /// async fn send_line_after() -> io::Result<()> {
-    ///     let mut p = Tube::process("/usr/bin/cat")?;
+    /// async fn send_display() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
     ///
-    ///     p.send("Hello, what's your name? ").await?;
-    ///     assert_eq!(
-    ///         p.send_line_after("name", "test").await?,
-    ///         b"Hello, what's your name"
-    ///     );
-    ///     assert_eq!(p.recv_line().await?, b"? test\n");
+    ///     let (idx, addr) = (7, 0x401136);
+    ///     p.send_display(format_args!("%{}$p ", idx)).await?;
+    ///     p.send_line_display(format_args!("{:#x}", addr)).await?;
+    ///     p.send_line_display(1337).await?;
+    ///     assert_eq!(p.recv_line().await?, b"%7$p 0x401136\n");
+    ///     assert_eq!(p.recv_line().await?, b"1337\n");
     ///
     ///     Ok(())
     /// }
     ///
-    /// send_line_after();
+    /// send_display();
     /// ```
-    pub async fn send_line_after(
-        &mut self,
-        pattern: impl AsRef<[u8]>,
-        data: impl AsRef<[u8]>,
-    ) -> io::Result<Vec<u8>> {
-        let result = self.recv_until(pattern).await?;
-        self.send_line(data).await?;
-        Ok(result)
+    pub async fn send_display(&mut self, value: impl fmt::Display) -> io::Result<()> {
+        self.send(value.to_string()).await
     }
 
-    /// Connect the tube to stdin and stdout so you can interact with it directly.
-    pub async fn interactive(&mut self) -> io::Result<()> {
-        Interactive::new(self).await
+    /// Same as [`Tube::send_display`], but add [`Tube::newline`].
+    pub async fn send_line_display(&mut self, value: impl fmt::Display) -> io::Result<()> {
+        self.send_line(value.to_string()).await
     }
 
-    /// Consume the tube to get back the underlying BufReader
-    pub fn into_inner(self) -> T {
-        self.inner
+    /// Wait until the tube is ready to accept more data, i.e. all the data previously written are
+    /// flushed to the underlying transport. The send timeout applies.
+    pub async fn writable(&mut self) -> io::Result<()> {
+        self.with_send_timeout(async |tube| tube.flush().await)
+            .await
+    }
+
+    /// Close the sending direction only, e.g. for filters that read everything before
+    /// responding. The queued data is written first, then a TCP socket is shut down for writing
+    /// and the stdin of a process is closed, so the other side receives EOF while everything
+    /// sent back can still be received. The send timeout applies.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn close_send() -> io::Result<()> {
+    ///     let mut p = Tube::echo();
+    ///
+    ///     p.send_line("b").await?;
+    ///     p.send_line("a").await?;
+    ///     p.close_send().await?;
+    ///     assert_eq!(p.recv_all().await?, b"b\na\n");
+    ///     assert!(p.send("more").await.is_err());
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// close_send();
+    /// ```
+    pub async fn close_send(&mut self) -> io::Result<()> {
+        self.with_send_timeout(async |tube| {
+            tube.flush().await?;
+            tube.shutdown().await
+        })
+        .await
+    }
+
+    fn poll_write_logged(
+        inner: &mut T,
+        traffic: &mut Traffic,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let numb = match Pin::new(inner).poll_write(cx, buf)? {
+            Poll::Ready(numb) => numb,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        traffic.sent(&buf[..numb]);
+
+        Poll::Ready(Ok(numb))
+    }
+
+    /// Write the queued data, returning any error occurred in the background.
+    pub(super) fn poll_send_queue(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        if let Some(err) = self.send_queue.error.take() {
+            return Poll::Ready(Err(err));
+        }
+        while !self.send_queue.is_empty() {
+            let numb = match Self::poll_write_logged(
+                &mut self.inner,
+                &mut self.traffic,
+                cx,
+                self.send_queue.pending(),
+            )? {
+                Poll::Ready(numb) => numb,
+                Poll::Pending => return Poll::Pending,
+            };
+            if numb == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.send_queue.advance(numb);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Make progress on the send queue in the background of other operations.
+    fn poll_send_queue_background(&mut self, cx: &mut Context) {
+        if !self.send_queue.is_empty() {
+            if let Poll::Ready(Err(err)) = self.poll_send_queue(cx) {
+                self.send_queue.error = Some(err);
+            }
+        }
     }
 }
 
 impl<T> AsyncRead for Tube<T>
 where
-    T: AsyncBufRead + AsyncWrite + Unpin,
+    T: AsyncBufRead + Unpin,
 {
     fn poll_read(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context,
         buf: &mut ReadBuf,
     ) -> Poll<io::Result<()>> {
+        if let Some(background_send) = self.background_send {
+            background_send(&mut self, cx);
+        }
+        if self.unread_pos < self.unread.len() {
+            let unread = &self.unread[self.unread_pos..];
+            let len = unread.len().min(buf.remaining());
+            buf.put_slice(&unread[..len]);
+            self.consume(len);
+            return Poll::Ready(Ok(()));
+        }
+
+        if self.read_chunk_size.is_some() {
+            // BufReader reads directly into large buffers, which would exceed the chunk size.
+            let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+            let len = data.len().min(buf.remaining());
+            buf.put_slice(&data[..len]);
+            self.consume(len);
+            return Poll::Ready(Ok(()));
+        }
+
         let olen = buf.filled().len();
+        let this = self.get_mut();
 
-        if Pin::new(&mut self.get_mut().inner)
-            .poll_read(cx, buf)?
-            .is_pending()
-        {
+        if Pin::new(&mut this.inner).poll_read(cx, buf)?.is_pending() {
             return Poll::Pending;
         }
 
-        debug!(target: "Tube::recv", "Received {:?}", buf.filled()[olen..].hex_dump());
+        this.traffic.received(&buf.filled()[olen..]);
 
         Poll::Ready(Ok(()))
     }
@@ -271,25 +2449,37 @@ where
 
 impl<T> AsyncWrite for Tube<T>
 where
-    T: AsyncBufRead + AsyncWrite + Unpin,
+    T: AsyncWrite + Unpin,
 {
     fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
-        let numb = match Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)? {
-            Poll::Ready(numb) => numb,
-            Poll::Pending => return Poll::Pending,
-        };
-
-        debug!(target: "Tube::send", "Sent {:?}", buf[..numb].hex_dump());
-
-        Poll::Ready(Ok(numb))
+        let this = self.get_mut();
+        if let Some(corked) = &mut this.corked {
+            corked.extend_from_slice(buf);
+            return Poll::Ready(Ok(buf.len()));
+        }
+        if this.poll_send_queue(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        Self::poll_write_logged(&mut this.inner, &mut this.traffic, cx, buf)
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+        let this = self.get_mut();
+        if this.poll_send_queue(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
     }
 
     fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+        let this = self.get_mut();
+        if let Some(corked) = this.corked.take() {
+            this.send_queue.push(&corked);
+        }
+        if this.poll_send_queue(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
     }
 
     fn poll_write_vectored(
@@ -297,7 +2487,16 @@ where
         cx: &mut Context,
         bufs: &[io::IoSlice],
     ) -> Poll<io::Result<usize>> {
-        let numb = match Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)? {
+        let this = self.get_mut();
+        if let Some(corked) = &mut this.corked {
+            let len = bufs.iter().map(|buf| buf.len()).sum();
+            corked.extend(bufs.iter().flat_map(|buf| buf.iter()));
+            return Poll::Ready(Ok(len));
+        }
+        if this.poll_send_queue(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        let numb = match Pin::new(&mut this.inner).poll_write_vectored(cx, bufs)? {
             Poll::Ready(numb) => numb,
             Poll::Pending => return Poll::Pending,
         };
@@ -307,7 +2506,8 @@ where
             if to_log == 0 {
                 break;
             }
-            debug!(target: "Tube::send", "Send {:?}", buf[..to_log].hex_dump());
+            let len = to_log.min(buf.len());
+            this.traffic.sent(&buf[..len]);
             to_log = to_log.saturating_sub(buf.len());
         }
 
@@ -321,31 +2521,34 @@ where
 
 impl<T> AsyncBufRead for Tube<T>
 where
-    T: AsyncBufRead + AsyncWrite + Unpin,
+    T: AsyncBufRead + Unpin,
 {
     fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
-        let Self {
-            inner,
-            timeout: _,
-            read_buf_logged,
-        } = self.get_mut();
-
-        let buf = match Pin::new(inner).poll_fill_buf(cx)? {
-            Poll::Ready(buf) => buf,
-            Poll::Pending => return Poll::Pending,
-        };
-
-        if buf.len() > *read_buf_logged {
-            debug!(target: "Tube::recv", "Recevied {:?}", buf[*read_buf_logged..].hex_dump());
-            *read_buf_logged = buf.len();
+        let this = self.get_mut();
+        if let Some(background_send) = this.background_send {
+            background_send(this, cx);
         }
-
-        Poll::Ready(Ok(buf))
+        if this.unread_pos < this.unread.len() {
+            return Poll::Ready(Ok(&this.unread[this.unread_pos..]));
+        }
+        Self::poll_fill_logged(
+            &mut this.inner,
+            &mut this.read_buf_logged,
+            &mut this.traffic,
+            cx,
+        )
     }
 
     fn consume(mut self: Pin<&mut Self>, amt: usize) {
-        self.read_buf_logged -= amt;
-        Pin::new(&mut self.get_mut().inner).consume(amt);
+        if self.unread_pos < self.unread.len() {
+            self.unread_pos += amt;
+            if self.unread_pos >= self.unread.len() {
+                self.unread.clear();
+                self.unread_pos = 0;
+            }
+            return;
+        }
+        self.consume_inner(amt);
     }
 }
 
@@ -363,10 +2566,6 @@ where
     T: AsyncBufRead + AsyncWrite + Unpin,
 {
     fn from(tube_like: T) -> Self {
-        Self {
-            inner: tube_like,
-            timeout: Duration::MAX,
-            read_buf_logged: 0,
-        }
+        Self::from_buffered(tube_like)
     }
 }
@@ -0,0 +1,344 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, BufReader, ReadBuf},
+    net::{lookup_host, ToSocketAddrs, UdpSocket},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use super::{Accept, Listener, Tube};
+
+/// The largest payload of a UDP datagram.
+const MAX_DATAGRAM: usize = 65535;
+
+/// A UDP socket connected to a peer, which reads the received datagrams as a stream and sends
+/// every write as a datagram. Empty datagrams are skipped, since reading nothing means EOF.
+#[derive(Debug)]
+pub struct UdpTube {
+    socket: UdpSocket,
+    /// Holds the last datagram received in `datagram[..len]`, of which `datagram[pos..len]` is
+    /// not read yet. It is allocated once at the full size of a datagram.
+    datagram: Box<[u8]>,
+    len: usize,
+    pos: usize,
+}
+
+impl UdpTube {
+    /// Bind to an ephemeral port and connect to the first address resolved.
+    pub async fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let addr = lookup_host(addr).await?.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        })?;
+        let local: SocketAddr = match addr {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(addr).await?;
+        Ok(socket.into())
+    }
+
+    /// Gets a reference to the socket.
+    pub fn get_ref(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl From<UdpSocket> for UdpTube {
+    /// The socket must be connected.
+    fn from(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            datagram: Box::default(),
+            len: 0,
+            pos: 0,
+        }
+    }
+}
+
+impl AsyncRead for UdpTube {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.pos == this.len {
+            if this.datagram.is_empty() {
+                this.datagram = vec![0; MAX_DATAGRAM].into_boxed_slice();
+            }
+            let mut datagram = ReadBuf::new(&mut this.datagram);
+            let result = this.socket.poll_recv(cx, &mut datagram);
+            this.len = match result {
+                Poll::Ready(Ok(())) => datagram.filled().len(),
+                _ => 0,
+            };
+            this.pos = 0;
+            ready!(result)?;
+        }
+        let len = (this.len - this.pos).min(buf.remaining());
+        buf.put_slice(&this.datagram[this.pos..this.pos + len]);
+        this.pos += len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for UdpTube {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.socket.poll_send(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Tube<BufReader<UdpTube>> {
+    /// Create a tube that exchanges datagrams with the address, see [`UdpTube`].
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    /// use tokio::net::UdpSocket;
+    ///
+    /// #[tokio::main]
+    /// async fn udp() -> io::Result<()> {
+    ///     let server = UdpSocket::bind("127.0.0.1:0").await?;
+    ///     let mut p = Tube::udp(server.local_addr()?).await?;
+    ///
+    ///     p.send("ping").await?;
+    ///     let mut buf = [0; 16];
+    ///     let (len, peer) = server.recv_from(&mut buf).await?;
+    ///     assert_eq!(&buf[..len], b"ping");
+    ///     server.send_to(b"pong\n", peer).await?;
+    ///     assert_eq!(p.recv_line().await?, b"pong\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// udp();
+    /// ```
+    pub async fn udp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let tube = UdpTube::connect(addr).await?;
+        let peer = tube.socket.peer_addr();
+        let local = tube.socket.local_addr();
+        let mut tube = Self::new(tube);
+        tube.traffic.info.peer_addr = peer.as_ref().ok().copied();
+        tube.traffic.info.local_addr = local.ok();
+        if let Ok(peer) = peer {
+            tube.traffic.describe("peer", peer);
+            tube.traffic.set_name(format!("udp:{}", peer));
+        }
+        Ok(tube)
+    }
+}
+
+/// A peer of a [`Listener::bind_udp`], which reads the datagrams from the peer as a stream and
+/// sends every write as a datagram to the peer. Reading reaches EOF once the listener is
+/// dropped.
+#[derive(Debug)]
+pub struct UdpPeer {
+    socket: Arc<UdpSocket>,
+    peer: SocketAddr,
+    datagrams: mpsc::UnboundedReceiver<Vec<u8>>,
+    /// The last datagram received, of which `datagram[pos..]` is not read yet.
+    datagram: Vec<u8>,
+    pos: usize,
+}
+
+impl UdpPeer {
+    /// The address of the peer.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer
+    }
+}
+
+impl AsyncRead for UdpPeer {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.pos == this.datagram.len() {
+            match ready!(this.datagrams.poll_recv(cx)) {
+                Some(datagram) => this.datagram = datagram,
+                None => return Poll::Ready(Ok(())),
+            }
+            this.pos = 0;
+        }
+        let len = (this.datagram.len() - this.pos).min(buf.remaining());
+        buf.put_slice(&this.datagram[this.pos..this.pos + len]);
+        this.pos += len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for UdpPeer {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.socket.poll_send_to(cx, buf, self.peer)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Demultiplexes the datagrams of a UDP socket by their source address for a [`Listener`],
+/// created by [`Listener::bind_udp`]. A datagram from a new address is accepted as a new
+/// [`UdpPeer`], and the later ones from the address are received by it until it is dropped.
+#[derive(Debug)]
+pub struct UdpAcceptor {
+    socket: Arc<UdpSocket>,
+    peers: Mutex<mpsc::UnboundedReceiver<io::Result<UdpPeer>>>,
+    /// Receives the datagrams in the background, so that the peers receive without accepting.
+    task: JoinHandle<()>,
+}
+
+impl UdpAcceptor {
+    fn new(socket: UdpSocket) -> Self {
+        let socket = Arc::new(socket);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(demultiplex(socket.clone(), sender));
+        Self {
+            socket,
+            peers: Mutex::new(receiver),
+            task,
+        }
+    }
+
+    /// Gets a reference to the socket.
+    pub fn get_ref(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl Drop for UdpAcceptor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn demultiplex(socket: Arc<UdpSocket>, peers: mpsc::UnboundedSender<io::Result<UdpPeer>>) {
+    let mut senders: HashMap<SocketAddr, mpsc::UnboundedSender<Vec<u8>>> = HashMap::new();
+    let mut buf = vec![0; MAX_DATAGRAM];
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(err) => {
+                let _ = peers.send(Err(err));
+                return;
+            }
+        };
+        if len == 0 {
+            continue;
+        }
+        let datagram = buf[..len].to_vec();
+        if let Some(sender) = senders.get(&peer) {
+            if sender.send(datagram.clone()).is_ok() {
+                continue;
+            }
+        }
+        // A new peer, or a peer whose tube is dropped and is accepted again.
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let _ = sender.send(datagram);
+        senders.insert(peer, sender);
+        let stream = UdpPeer {
+            socket: socket.clone(),
+            peer,
+            datagrams: receiver,
+            datagram: Vec::new(),
+            pos: 0,
+        };
+        if peers.send(Ok(stream)).is_err() {
+            return;
+        }
+    }
+}
+
+impl Accept for UdpAcceptor {
+    type Stream = UdpPeer;
+
+    fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(UdpPeer, Option<String>)>> {
+        let mut peers = self.peers.lock().unwrap_or_else(|err| err.into_inner());
+        peers.poll_recv(cx).map(|peer| match peer {
+            Some(peer) => peer.map(|peer| {
+                let addr = peer.peer.to_string();
+                (peer, Some(addr))
+            }),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "the socket stopped receiving",
+            )),
+        })
+    }
+
+    fn local_addr(&self, stream: &UdpPeer) -> Option<SocketAddr> {
+        stream.socket.local_addr().ok()
+    }
+}
+
+impl Listener<UdpAcceptor> {
+    /// Bind a UDP socket to the address and accept a tube for every address that sends to it,
+    /// so that a datagram service like DNS or TFTP can be scripted the same way as a TCP one.
+    /// The first datagram from a peer is received by its tube.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn bind_udp() -> io::Result<()> {
+    ///     let l = Listener::bind_udp("127.0.0.1:0").await?;
+    ///     let addr = l.inner.get_ref().local_addr()?;
+    ///     let mut a = Tube::udp(addr).await?;
+    ///     let mut b = Tube::udp(addr).await?;
+    ///
+    ///     a.send("from a\n").await?;
+    ///     let mut server_a = l.accept().await?;
+    ///     b.send("from b\n").await?;
+    ///     let mut server_b = l.accept().await?;
+    ///     a.send("again\n").await?;
+    ///
+    ///     assert_eq!(server_b.recv_line().await?, b"from b\n");
+    ///     assert_eq!(server_a.recv_line().await?, b"from a\n");
+    ///     assert_eq!(server_a.recv_line().await?, b"again\n");
+    ///     server_b.send("reply\n").await?;
+    ///     assert_eq!(b.recv_line().await?, b"reply\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// bind_udp();
+    /// ```
+    pub async fn bind_udp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Listener::from_accept(UdpAcceptor::new(
+            UdpSocket::bind(addr).await?,
+        )))
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    future,
+    io::{self, IoSlice, IoSliceMut},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+    path::Path,
+};
+
+use log::debug;
+use nix::sys::socket::{
+    cmsg_space, recvmsg, sendmsg, ControlMessage, ControlMessageOwned, MsgFlags, UnixAddr,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, BufReader, Interest},
+    net::{unix::UCred, UnixStream},
+};
+
+use crate::utils::timeout;
+
+use super::{Direction, Tube};
+
+impl Tube<BufReader<UnixStream>> {
+    /// Create a tube by connecting to the unix socket at the path.
+    pub async fn unix(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut tube = Self::new(UnixStream::connect(path).await?);
+        tube.traffic.set_name(format!("unix:{}", path.display()));
+        Ok(tube)
+    }
+
+    /// Returns the credentials of the process on the other end (SO_PEERCRED), which are taken
+    /// when the connection is established.
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    /// use tokio::net::UnixStream;
+    ///
+    /// #[tokio::main]
+    /// async fn peer_cred() -> io::Result<()> {
+    ///     let (a, _b) = UnixStream::pair()?;
+    ///     let a = Tube::new(a);
+    ///     assert_eq!(a.peer_cred()?.pid(), Some(std::process::id() as i32));
+    ///     Ok(())
+    /// }
+    ///
+    /// peer_cred();
+    /// ```
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        self.inner.get_ref().peer_cred()
+    }
+
+    /// Send the file descriptor (SCM_RIGHTS) along with `data`, which must not be empty.
+    ///
+    /// Data written before must be flushed first, as with [`Tube::send`].
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{fs::File, io, io::Seek, os::fd::AsFd};
+    /// use tokio::net::UnixStream;
+    ///
+    /// #[tokio::main]
+    /// async fn send_fd() -> io::Result<()> {
+    ///     let (a, b) = UnixStream::pair()?;
+    ///     let (mut a, mut b) = (Tube::new(a), Tube::new(b));
+    ///
+    ///     let file = File::open("/etc/hostname")?;
+    ///     a.send_fd(file.as_fd(), "fd").await?;
+    ///     a.send("rest").await?;
+    ///
+    ///     let mut received = File::from(b.recv_fd().await?);
+    ///     assert_eq!(received.stream_position()?, 0);
+    ///     assert_eq!(b.recv_until("rest").await?, b"fdrest");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// send_fd();
+    /// ```
+    pub async fn send_fd(&mut self, fd: impl AsFd, data: impl AsRef<[u8]>) -> io::Result<()> {
+        self.send_fds(&[fd.as_fd()], data).await
+    }
+
+    /// Send several file descriptors (SCM_RIGHTS) at once along with `data`, which must not be
+    /// empty. They are received in the same order by [`Tube::recv_fds`].
+    ///
+    /// The data queued by [`Tube::send_nowait`] is sent first. Fails with
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) while the tube is corked by [`Tube::cork`].
+    /// ```rust
+    /// use io_tubes::tubes::Tube;
+    /// use std::{fs::File, io, io::Read, os::fd::AsFd};
+    /// use tokio::net::UnixStream;
+    ///
+    /// #[tokio::main]
+    /// async fn send_fds() -> io::Result<()> {
+    ///     let (a, b) = UnixStream::pair()?;
+    ///     let (mut a, mut b) = (Tube::new(a), Tube::new(b));
+    ///
+    ///     let (hostname, null) = (File::open("/etc/hostname")?, File::open("/dev/null")?);
+    ///     a.send_fds(&[hostname.as_fd(), null.as_fd()], "fds").await?;
+    ///
+    ///     let fds = b.recv_fds(4).await?;
+    ///     assert_eq!(fds.len(), 2);
+    ///     let mut data = Vec::new();
+    ///     File::from(fds.into_iter().nth(1).unwrap()).read_to_end(&mut data)?;
+    ///     assert!(data.is_empty());
+    ///     assert_eq!(b.recv(3).await?, b"fds");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// send_fds();
+    /// ```
+    pub async fn send_fds(
+        &mut self,
+        fds: &[BorrowedFd<'_>],
+        data: impl AsRef<[u8]>,
+    ) -> io::Result<()> {
+        let data = data.as_ref();
+        if data.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "data must not be empty when sending file descriptor",
+            ));
+        }
+        if self.corked.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot send file descriptor while the tube is corked",
+            ));
+        }
+        let fds: Vec<RawFd> = fds.iter().map(|fd| fd.as_raw_fd()).collect();
+        let numb = self
+            .with_send_timeout(async |tube| {
+                // The data queued by `send_nowait` must go first.
+                future::poll_fn(|cx| tube.poll_send_queue(cx)).await?;
+                let stream = tube.inner.get_ref();
+                stream
+                    .async_io(Interest::WRITABLE, || {
+                        let iov = [IoSlice::new(data)];
+                        let cmsgs = [ControlMessage::ScmRights(&fds)];
+                        let fd = stream.as_raw_fd();
+                        sendmsg::<UnixAddr>(fd, &iov, &cmsgs, MsgFlags::empty(), None)
+                            .map_err(io::Error::from)
+                    })
+                    .await
+            })
+            .await?;
+        let label = self.traffic.label(Some(Direction::Send));
+        debug!(target: "Tube::send", "[{}] Sent fds {:?}", label, fds);
+        self.traffic.sent(&data[..numb]);
+        // The file descriptors are attached to the first byte, so the rest is sent normally.
+        self.send(&data[numb..]).await
+    }
+
+    /// Receive a file descriptor (SCM_RIGHTS) sent along with some data.
+    ///
+    /// The data received with the file descriptor is kept and can be received normally
+    /// afterwards. The data carrying the file descriptor must not be received by other methods
+    /// first, otherwise the file descriptor is discarded. Fails with
+    /// [`TimedOut`](io::ErrorKind::TimedOut) if nothing is received before the timeout.
+    pub async fn recv_fd(&mut self) -> io::Result<OwnedFd> {
+        self.recv_fds(1).await?.into_iter().next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no file descriptor received with the data",
+            )
+        })
+    }
+
+    /// Same as [`Tube::recv_fd`], but receive up to `max` file descriptors sent together. The
+    /// ones beyond `max` are closed. The result is empty if the data came without
+    /// file descriptors.
+    pub async fn recv_fds(&mut self, max: usize) -> io::Result<Vec<OwnedFd>> {
+        let mut buf = [0; 4096];
+        let stream = self.inner.get_ref();
+        let recv = stream.async_io(Interest::READABLE, || {
+            let mut iov = [IoSliceMut::new(&mut buf)];
+            let mut cmsg_buf = Vec::with_capacity(cmsg_space::<RawFd>() * max.max(1));
+            let msg = recvmsg::<UnixAddr>(
+                stream.as_raw_fd(),
+                &mut iov,
+                Some(&mut cmsg_buf),
+                MsgFlags::MSG_CMSG_CLOEXEC,
+            )
+            .map_err(io::Error::from)?;
+            let mut fds = Vec::new();
+            for cmsg in msg.cmsgs().map_err(io::Error::from)? {
+                if let ControlMessageOwned::ScmRights(raw) = cmsg {
+                    // SAFETY: the kernel just installed the descriptors for us.
+                    fds.extend(
+                        raw.into_iter()
+                            .map(|raw| unsafe { OwnedFd::from_raw_fd(raw) }),
+                    );
+                }
+            }
+            Ok((msg.bytes, fds))
+        });
+        let (numb, mut fds) = timeout(self.recv_timeout(), recv)
+            .await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "recv timed out")))?;
+        self.append_unread(&buf[..numb]);
+        fds.truncate(max);
+        Ok(fds)
+    }
+}
+
+/// A unix socket listener that returns Tube when a connection is accepted.
+#[derive(Debug)]
+pub struct UnixListener {
+    /// The inner UnixListener
+    pub inner: tokio::net::UnixListener,
+}
+
+impl UnixListener {
+    /// Create a listener by binding to the supplied path.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(UnixListener {
+            inner: tokio::net::UnixListener::bind(path)?,
+        })
+    }
+
+    /// Accepts a connection. Use [`Tube::peer_cred`] to check who is connected.
+    /// ```rust
+    /// use io_tubes::tubes::{Tube, UnixListener};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn accept_unix() -> io::Result<()> {
+    ///     let path = std::env::temp_dir().join("io-tubes-accept-unix.sock");
+    ///     let _ = std::fs::remove_file(&path);
+    ///     let l = UnixListener::bind(&path)?;
+    ///     let mut p = Tube::unix(&path).await?;
+    ///     let mut server = l.accept().await?;
+    ///     assert_eq!(server.peer_cred()?.uid(), p.peer_cred()?.uid());
+    ///     p.send("Client Hello").await?;
+    ///     assert_eq!(server.recv_until("Hello").await?, b"Client Hello");
+    ///     Ok(())
+    /// }
+    ///
+    /// accept_unix();
+    /// ```
+    pub async fn accept(&self) -> io::Result<Tube<BufReader<UnixStream>>> {
+        Ok(Tube::new(self.inner.accept().await?.0))
+    }
+}
+
+impl From<tokio::net::UnixListener> for UnixListener {
+    fn from(inner: tokio::net::UnixListener) -> Self {
+        Self { inner }
+    }
+}
+
+impl From<UnixListener> for tokio::net::UnixListener {
+    fn from(listener: UnixListener) -> Self {
+        listener.inner
+    }
+}
+
+impl<T> AsFd for Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + AsFd + Unpin,
+{
+    /// Borrow the file descriptor of the underlying socket, e.g. to set socket options.
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.get_ref().as_fd()
+    }
+}
+
+impl<T> AsRawFd for Tube<BufReader<T>>
+where
+    T: AsyncRead + AsyncWrite + AsRawFd + Unpin,
+{
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.get_ref().as_raw_fd()
+    }
+}
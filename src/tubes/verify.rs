@@ -0,0 +1,110 @@
+use std::{io, path::Path};
+
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use super::{traffic::Direction, transcript::parse_transcript, TranscriptEvent, Tube};
+
+/// The number of bytes received before a divergence that are shown with it.
+const CONTEXT_LEN: usize = 64;
+
+impl<T> Tube<T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Drive the interaction of a golden transcript, e.g. recorded by
+    /// [`Tube::record`](Tube::record), and check that the output is the same, which tests the
+    /// service itself rather than a client of it.
+    ///
+    /// The data of the send events is sent in order, and the data of the receive events must be
+    /// received exactly, no matter how it is split into events. Fails with
+    /// [`InvalidData`](io::ErrorKind::InvalidData) describing the first divergence, including the
+    /// data received right before it. Output after the last event is not checked.
+    /// ```rust
+    /// use io_tubes::tubes::{MemoryTranscript, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn verify_against() -> io::Result<()> {
+    ///     let golden = MemoryTranscript::new();
+    ///     let mut p = Tube::echo();
+    ///     p.record_to(golden.clone())?;
+    ///     p.send("Hello\n").await?;
+    ///     p.recv_line().await?;
+    ///
+    ///     let mut events = golden.events();
+    ///     let mut p = Tube::echo();
+    ///     p.verify_against(&events).await?;
+    ///
+    ///     // the service changed its reply
+    ///     events[1].data = b"Help\n".to_vec();
+    ///     let mut p = Tube::echo();
+    ///     let err = p.verify_against(&events).await.unwrap_err();
+    ///     assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    ///     assert!(err.to_string().starts_with("diverged at offset 3 in event 1"));
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// verify_against();
+    /// ```
+    pub async fn verify_against(&mut self, events: &[TranscriptEvent]) -> io::Result<()> {
+        let mut context = Vec::new();
+        for (index, event) in events.iter().enumerate() {
+            match event.direction {
+                Direction::Send => self.send(&event.data).await?,
+                Direction::Recv => {
+                    let (received, ended) = self.recv_up_to(event.data.len()).await?;
+                    if let Some(offset) = divergence(&event.data, &received) {
+                        let mut message = format!(
+                            "diverged at offset {} in event {}\n  expected: b\"{}\"\n  received: b\"{}\"",
+                            offset,
+                            index,
+                            event.data.escape_ascii(),
+                            received.escape_ascii(),
+                        );
+                        if let Some(ended) = ended {
+                            message.push_str(&format!(" ({})", ended));
+                        }
+                        message.push_str(&format!("\n  after: b\"{}\"", context.escape_ascii()));
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                    }
+                    context.extend_from_slice(&received);
+                    let excess = context.len().saturating_sub(CONTEXT_LEN);
+                    context.drain(..excess);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as [`verify_against`](Tube::verify_against), but reads the golden transcript from a
+    /// file in the format of [`Tube::record`](Tube::record).
+    pub async fn verify_against_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let events = parse_transcript(&std::fs::read_to_string(path)?)?;
+        self.verify_against(&events).await
+    }
+
+    /// Receive `len` bytes, stopping early on timeout or EOF, which is described in that case.
+    async fn recv_up_to(&mut self, len: usize) -> io::Result<(Vec<u8>, Option<&'static str>)> {
+        let mut received = Vec::with_capacity(len);
+        while received.len() < len {
+            match self.recv_checked(len - received.len()).await {
+                Ok(data) => received.extend_from_slice(&data),
+                Err(err) if err.is_timeout() => return Ok((received, Some("timed out"))),
+                Err(err) if err.is_eof() => return Ok((received, Some("EOF"))),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok((received, None))
+    }
+}
+
+/// The offset of the first byte that differs, if any.
+fn divergence(expected: &[u8], received: &[u8]) -> Option<usize> {
+    let same = expected
+        .iter()
+        .zip(received)
+        .take_while(|(a, b)| a == b)
+        .count();
+    (same < expected.len().max(received.len())).then_some(same)
+}
@@ -0,0 +1,226 @@
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+};
+
+use tokio::{
+    io::{AsyncReadExt, BufReader},
+    net::{lookup_host, TcpStream},
+};
+
+use crate::utils::base64_encode;
+
+use super::Tube;
+
+/// A proxy to reach the target through, see [`Tube::remote_via`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyConfig {
+    /// A SOCKS5 proxy. Host names of the target are resolved by the proxy.
+    Socks5 {
+        /// The address of the proxy, e.g. `127.0.0.1:1080`.
+        addr: String,
+        /// The username and password, if the proxy requires them.
+        auth: Option<(String, String)>,
+    },
+    /// An HTTP proxy that supports the `CONNECT` method.
+    HttpConnect {
+        /// The address of the proxy, e.g. `127.0.0.1:8080`.
+        addr: String,
+        /// The username and password for basic authentication, if the proxy requires them.
+        auth: Option<(String, String)>,
+    },
+}
+
+impl Tube<BufReader<TcpStream>> {
+    /// Create a tube by connecting to the target through the proxy, e.g. a jump host of the
+    /// competition network. The handshake with the proxy is done before the tube is returned,
+    /// and a refusal of the proxy is returned as an error.
+    /// ```rust
+    /// use io_tubes::tubes::{Listener, ProxyConfig, Tube};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn remote_via() -> io::Result<()> {
+    ///     // A minimal HTTP proxy.
+    ///     let proxy = Listener::bind("127.0.0.1:0").await?.serve(|mut client| async move {
+    ///         let request = client.recv_until("\r\n\r\n").await?;
+    ///         let request = String::from_utf8_lossy(&request);
+    ///         let target = request.split(' ').nth(1).unwrap_or_default();
+    ///         let mut server = Tube::remote(target).await?;
+    ///         client.send("HTTP/1.1 200 Connection established\r\n\r\n").await?;
+    ///         client.join(&mut server).await?;
+    ///         Ok(())
+    ///     })?;
+    ///
+    ///     let target = Listener::bind("127.0.0.1:0").await?;
+    ///     let config = ProxyConfig::HttpConnect {
+    ///         addr: format!("127.0.0.1:{}", proxy.port()),
+    ///         auth: None,
+    ///     };
+    ///     let mut p = Tube::remote_via(&config, ("127.0.0.1", target.port()?)).await?;
+    ///     let mut server = target.accept().await?;
+    ///     p.send_line("Hello").await?;
+    ///     assert_eq!(server.recv_line().await?, b"Hello\n");
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// remote_via();
+    /// ```
+    pub async fn remote_via(proxy: &ProxyConfig, target: (&str, u16)) -> io::Result<Self> {
+        let (ProxyConfig::Socks5 { addr, .. } | ProxyConfig::HttpConnect { addr, .. }) = proxy;
+        let addrs: Vec<SocketAddr> = lookup_host(addr.as_str()).await?.collect();
+        let mut tube = Self::remote(&addrs[..]).await?;
+        match proxy {
+            ProxyConfig::Socks5 { auth, .. } => tube.socks5_connect(auth.as_ref(), target).await?,
+            ProxyConfig::HttpConnect { auth, .. } => {
+                tube.http_connect(auth.as_ref(), target).await?
+            }
+        }
+        tube.traffic.describe(
+            "peer",
+            format_args!("{}:{} via {}", target.0, target.1, addr),
+        );
+        tube.traffic
+            .set_name(format!("remote:{}:{}", target.0, target.1));
+        Ok(tube)
+    }
+
+    async fn socks5_connect(
+        &mut self,
+        auth: Option<&(String, String)>,
+        (host, port): (&str, u16),
+    ) -> io::Result<()> {
+        let methods: &[u8] = match auth {
+            Some(_) => &[2, 0, 2],
+            None => &[1, 0],
+        };
+        self.send([&[5], methods].concat()).await?;
+        let reply = self.recv_exact_or_eof(2).await?;
+        match (reply[1], auth) {
+            (0, _) => {}
+            (2, Some((username, password))) => {
+                let mut request = vec![1];
+                for field in [username, password] {
+                    let len = u8::try_from(field.len()).map_err(|_| {
+                        proxy_error(io::ErrorKind::InvalidInput, "SOCKS5 credential too long")
+                    })?;
+                    request.push(len);
+                    request.extend_from_slice(field.as_bytes());
+                }
+                self.send(request).await?;
+                if self.recv_exact_or_eof(2).await?[1] != 0 {
+                    return Err(proxy_error(
+                        io::ErrorKind::PermissionDenied,
+                        "SOCKS5 authentication failed",
+                    ));
+                }
+            }
+            _ => {
+                return Err(proxy_error(
+                    io::ErrorKind::PermissionDenied,
+                    "no acceptable SOCKS5 authentication method",
+                ))
+            }
+        }
+
+        let mut request = vec![5, 1, 0];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(1);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                let len = u8::try_from(host.len()).map_err(|_| {
+                    proxy_error(io::ErrorKind::InvalidInput, "host name too long for SOCKS5")
+                })?;
+                request.push(3);
+                request.push(len);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        self.send(request).await?;
+
+        let reply = self.recv_exact_or_eof(4).await?;
+        let (kind, reason) = match reply[1] {
+            0 => (None, ""),
+            2 => (
+                Some(io::ErrorKind::PermissionDenied),
+                "not allowed by ruleset",
+            ),
+            5 => (Some(io::ErrorKind::ConnectionRefused), "connection refused"),
+            3 => (Some(io::ErrorKind::Other), "network unreachable"),
+            4 => (Some(io::ErrorKind::Other), "host unreachable"),
+            6 => (Some(io::ErrorKind::TimedOut), "TTL expired"),
+            _ => (Some(io::ErrorKind::Other), "general failure"),
+        };
+        if let Some(kind) = kind {
+            return Err(proxy_error(kind, &format!("SOCKS5 proxy: {}", reason)));
+        }
+        // Skip the address bound by the proxy.
+        let len = match reply[3] {
+            1 => 4,
+            4 => 16,
+            _ => usize::from(self.recv_exact_or_eof(1).await?[0]),
+        };
+        self.recv_exact_or_eof(len + 2).await?;
+        Ok(())
+    }
+
+    async fn http_connect(
+        &mut self,
+        auth: Option<&(String, String)>,
+        (host, port): (&str, u16),
+    ) -> io::Result<()> {
+        let authority = match host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(_)) => format!("[{}]:{}", host, port),
+            _ => format!("{}:{}", host, port),
+        };
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+        if let Some((username, password)) = auth {
+            let credentials = base64_encode(format!("{}:{}", username, password).as_bytes());
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+        }
+        request.push_str("\r\n");
+        self.send(request).await?;
+
+        let response = self.recv_until("\r\n\r\n").await?;
+        if !response.ends_with(b"\r\n\r\n") {
+            return Err(proxy_error(
+                io::ErrorKind::UnexpectedEof,
+                "HTTP proxy closed the connection",
+            ));
+        }
+        let status_line = String::from_utf8_lossy(&response);
+        let status_line = status_line.lines().next().unwrap_or_default();
+        match status_line.split(' ').nth(1) {
+            Some(status) if status.starts_with('2') => Ok(()),
+            Some("407") => Err(proxy_error(io::ErrorKind::PermissionDenied, status_line)),
+            _ => Err(proxy_error(io::ErrorKind::ConnectionRefused, status_line)),
+        }
+    }
+
+    /// Receive exactly `len` bytes, failing with
+    /// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) if the proxy closes the connection.
+    async fn recv_exact_or_eof(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; len];
+        self.read_exact(&mut data)
+            .await
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::UnexpectedEof => {
+                    proxy_error(io::ErrorKind::UnexpectedEof, "proxy closed the connection")
+                }
+                _ => err,
+            })?;
+        Ok(data)
+    }
+}
+
+fn proxy_error(kind: io::ErrorKind, message: &str) -> io::Error {
+    io::Error::new(kind, message.to_string())
+}
@@ -0,0 +1,123 @@
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    task::{ready, Context, Poll},
+};
+
+use nix::{
+    errno::Errno,
+    sys::socket::{
+        accept4, bind, connect, getpeername, getsockopt, listen, socket, sockopt, AddressFamily,
+        Backlog, SockFlag, SockType, VsockAddr,
+    },
+};
+use tokio::io::{unix::AsyncFd, BufReader};
+
+use super::{Accept, FdStream, Listener, Tube};
+
+/// The CID of the host, for connecting from a guest to a service on the host.
+pub const VMADDR_CID_HOST: u32 = 2;
+/// The CID that binds on all CIDs, for listening on the host for connections from guests.
+pub const VMADDR_CID_ANY: u32 = u32::MAX;
+
+fn vsock_socket() -> io::Result<OwnedFd> {
+    Ok(socket(
+        AddressFamily::Vsock,
+        SockType::Stream,
+        SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC,
+        None,
+    )?)
+}
+
+impl Tube<BufReader<FdStream>> {
+    /// Connect to the port of a VM over AF_VSOCK, e.g. a QEMU guest started with
+    /// `-device vhost-vsock-pci,guest-cid=3` or a Firecracker guest. Linux only.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::Tube;
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn vsock() -> io::Result<()> {
+    ///     let mut p = Tube::vsock(3, 1337).await?;
+    ///     p.send_line("id").await?;
+    ///     p.recv_line().await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// vsock();
+    /// ```
+    pub async fn vsock(cid: u32, port: u32) -> io::Result<Self> {
+        let fd = vsock_socket()?;
+        match connect(fd.as_raw_fd(), &VsockAddr::new(cid, port)) {
+            Ok(()) | Err(Errno::EINPROGRESS) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let fd = AsyncFd::new(fd)?;
+        fd.writable().await?.retain_ready();
+        let fd = fd.into_inner();
+        match getsockopt(&fd, sockopt::SocketError)? {
+            0 => {}
+            err => return Err(io::Error::from_raw_os_error(err)),
+        }
+
+        let mut tube = Self::from_fd(fd)?;
+        tube.traffic.describe("peer", format!("{}:{}", cid, port));
+        tube.traffic.set_name(format!("vsock:{}:{}", cid, port));
+        Ok(tube)
+    }
+}
+
+/// Accepts AF_VSOCK connections for a [`Listener`], created by [`Listener::bind_vsock`].
+#[derive(Debug)]
+pub struct VsockAcceptor {
+    inner: AsyncFd<OwnedFd>,
+}
+
+impl Accept for VsockAcceptor {
+    type Stream = FdStream;
+
+    fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(FdStream, Option<String>)>> {
+        loop {
+            let mut guard = ready!(self.inner.poll_read_ready(cx))?;
+            let flags = SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC;
+            if let Ok(result) = guard.try_io(|fd| Ok(accept4(fd.as_raw_fd(), flags)?)) {
+                // SAFETY: accept4 returned a new descriptor that nothing else owns.
+                let fd = unsafe { OwnedFd::from_raw_fd(result?) };
+                let peer = getpeername::<VsockAddr>(fd.as_raw_fd())
+                    .ok()
+                    .map(|addr| format!("{}:{}", addr.cid(), addr.port()));
+                return Poll::Ready(FdStream::new(fd).map(|stream| (stream, peer)));
+            }
+        }
+    }
+}
+
+impl Listener<VsockAcceptor> {
+    /// Listen on the port over AF_VSOCK, e.g. on the host for a guest connecting back with
+    /// [`VMADDR_CID_ANY`], or inside a guest for the host. Linux only.
+    /// ```rust,no_run
+    /// use io_tubes::tubes::{Listener, VMADDR_CID_ANY};
+    /// use std::io;
+    ///
+    /// #[tokio::main]
+    /// async fn bind_vsock() -> io::Result<()> {
+    ///     let l = Listener::bind_vsock(VMADDR_CID_ANY, 1337)?;
+    ///     let mut guest = l.accept().await?;
+    ///     guest.send_line("Hello").await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// bind_vsock();
+    /// ```
+    pub fn bind_vsock(cid: u32, port: u32) -> io::Result<Self> {
+        let fd = vsock_socket()?;
+        bind(fd.as_raw_fd(), &VsockAddr::new(cid, port))?;
+        listen(&fd, Backlog::MAXCONN)?;
+        Ok(Listener::from_accept(VsockAcceptor {
+            inner: AsyncFd::new(fd)?,
+        }))
+    }
+}
@@ -0,0 +1,489 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+    time::Duration,
+};
+
+use super::{
+    transcript::{format_event, parse_transcript},
+    Direction, TranscriptEvent, TranscriptSink,
+};
+
+const MAGIC: &[u8; 8] = b"IOTZSTD1";
+const INDEX_MAGIC: &[u8; 8] = b"IOTZIDX1";
+/// The index offset, the number of blocks and the magic at the end of the file.
+const TRAILER_LEN: u64 = 8 + 4 + 8;
+
+/// Options for [`ZstdTranscript::create_with`].
+#[derive(Debug, Clone)]
+pub struct ZstdTranscriptOptions {
+    /// The compression level, 3 by default.
+    pub level: i32,
+    /// The number of events compressed together in a block, which is the unit that is
+    /// decompressed when seeking. 1024 by default.
+    pub block_events: usize,
+    /// A dictionary from [`ZstdTranscript::train_dictionary`], which keeps small blocks small
+    /// when the sessions look alike. The same dictionary is needed to read the transcript.
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl Default for ZstdTranscriptOptions {
+    fn default() -> Self {
+        Self {
+            level: 3,
+            block_events: 1024,
+            dictionary: None,
+        }
+    }
+}
+
+/// Where a block is in the file and what it covers.
+#[derive(Debug, Clone, Default)]
+struct BlockIndex {
+    offset: u64,
+    first_seq: u64,
+    first_elapsed: Duration,
+    last_elapsed: Duration,
+    /// The annotations in the block with the sequence number of their event.
+    markers: Vec<(u64, String)>,
+}
+
+/// Writes the events compressed with zstd into a file, e.g. to keep hours of interactive
+/// sessions small. The events are compressed in blocks, and an index of the time and the
+/// annotations of each block is written when the recording stops, so that
+/// [`ZstdTranscriptReader`] only decompresses the blocks it needs.
+///
+/// The file starts with `IOTZSTD1`, followed by the blocks. Each block is the length of the
+/// compressed data as a little endian `u32` followed by a zstd frame of the text format of
+/// [`Tube::record`](super::Tube::record), where the annotations are comment lines of the form
+/// `# <seq> <annotation>`. A transcript without the index, e.g. from a crashed script, can
+/// still be read by scanning the blocks.
+/// ```rust
+/// use io_tubes::tubes::{Tube, ZstdTranscript, ZstdTranscriptReader};
+/// use std::{io, time::Duration};
+///
+/// #[tokio::main]
+/// async fn zstd_transcript() -> io::Result<()> {
+///     let path = std::env::temp_dir().join("io-tubes-record.zst");
+///
+///     let mut p = Tube::echo();
+///     p.record_to(ZstdTranscript::create(&path)?)?;
+///     for i in 0..100 {
+///         p.send(format!("line {}\n", i)).await?;
+///         p.recv_line().await?;
+///     }
+///     p.stop_recording()?;
+///
+///     let mut reader = ZstdTranscriptReader::open(&path)?;
+///     let events = reader.events()?;
+///     assert_eq!(events.len(), 200);
+///     assert_eq!(events[199].data, b"line 99\n");
+///     assert!(reader.events_since(Duration::MAX)?.is_empty());
+///
+///     Ok(())
+/// }
+///
+/// zstd_transcript();
+/// ```
+pub struct ZstdTranscript {
+    writer: BufWriter<File>,
+    compressor: zstd::bulk::Compressor<'static>,
+    block_events: usize,
+    /// The text of the events in the current block and the number of events.
+    block: String,
+    events: usize,
+    current: BlockIndex,
+    index: Vec<BlockIndex>,
+    offset: u64,
+}
+
+impl ZstdTranscript {
+    /// Create the file with the default options, truncating it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::create_with(path, &ZstdTranscriptOptions::default())
+    }
+
+    /// Create the file with the options, truncating it if it exists.
+    pub fn create_with(
+        path: impl AsRef<Path>,
+        options: &ZstdTranscriptOptions,
+    ) -> io::Result<Self> {
+        let compressor = match &options.dictionary {
+            Some(dictionary) => zstd::bulk::Compressor::with_dictionary(options.level, dictionary)?,
+            None => zstd::bulk::Compressor::new(options.level)?,
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        Ok(Self {
+            writer,
+            compressor,
+            block_events: options.block_events.max(1),
+            block: String::new(),
+            events: 0,
+            current: BlockIndex::default(),
+            index: Vec::new(),
+            offset: MAGIC.len() as u64,
+        })
+    }
+
+    /// Train a dictionary of at most `max_size` bytes from previous sessions, see
+    /// [`ZstdTranscriptOptions::dictionary`].
+    pub fn train_dictionary(
+        sessions: &[Vec<TranscriptEvent>],
+        max_size: usize,
+    ) -> io::Result<Vec<u8>> {
+        let samples: Vec<String> = sessions
+            .iter()
+            .flatten()
+            .map(|event| {
+                let mut line = String::new();
+                format_event(
+                    &mut line,
+                    event.seq,
+                    event.elapsed,
+                    event.direction,
+                    &event.data,
+                );
+                line
+            })
+            .collect();
+        zstd::dict::from_samples(&samples, max_size)
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.events == 0 {
+            return Ok(());
+        }
+        let compressed = self.compressor.compress(self.block.as_bytes())?;
+        let len = u32::try_from(compressed.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "block too large"))?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&compressed)?;
+        let mut block = std::mem::take(&mut self.current);
+        block.offset = self.offset;
+        self.index.push(block);
+        self.offset += 4 + compressed.len() as u64;
+        self.block.clear();
+        self.events = 0;
+        Ok(())
+    }
+
+    fn write_index(&mut self) -> io::Result<()> {
+        let index_offset = self.offset;
+        for block in &self.index {
+            self.writer.write_all(&block.offset.to_le_bytes())?;
+            self.writer.write_all(&block.first_seq.to_le_bytes())?;
+            write_micros(&mut self.writer, block.first_elapsed)?;
+            write_micros(&mut self.writer, block.last_elapsed)?;
+            self.writer
+                .write_all(&(block.markers.len() as u32).to_le_bytes())?;
+            for (seq, marker) in &block.markers {
+                self.writer.write_all(&seq.to_le_bytes())?;
+                self.writer
+                    .write_all(&(marker.len() as u32).to_le_bytes())?;
+                self.writer.write_all(marker.as_bytes())?;
+            }
+        }
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer
+            .write_all(&(self.index.len() as u32).to_le_bytes())?;
+        self.writer.write_all(INDEX_MAGIC)
+    }
+}
+
+impl std::fmt::Debug for ZstdTranscript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZstdTranscript")
+            .field("blocks", &self.index.len())
+            .field("events", &self.events)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TranscriptSink for ZstdTranscript {
+    fn write_event(
+        &mut self,
+        seq: u64,
+        elapsed: Duration,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()> {
+        if self.events == self.block_events {
+            self.write_block()?;
+        }
+        if self.events == 0 {
+            self.current.first_seq = seq;
+            self.current.first_elapsed = elapsed;
+        }
+        self.current.last_elapsed = elapsed;
+        format_event(&mut self.block, seq, elapsed, direction, data);
+        self.events += 1;
+        Ok(())
+    }
+
+    fn write_annotation(&mut self, seq: u64, annotation: &str) -> io::Result<()> {
+        for line in annotation.lines() {
+            self.block.push_str(&format!("# {} {}\n", seq, line));
+        }
+        self.current.markers.push((seq, annotation.to_owned()));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.write_index()?;
+        self.writer.flush()
+    }
+}
+
+fn write_micros(writer: &mut impl Write, elapsed: Duration) -> io::Result<()> {
+    let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+    writer.write_all(&micros.to_le_bytes())
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// A cursor over the index at the end of the file.
+struct IndexReader<'a>(&'a [u8]);
+
+impl IndexReader<'_> {
+    fn take(&mut self, len: usize) -> io::Result<&[u8]> {
+        if self.0.len() < len {
+            return Err(invalid("truncated transcript index"));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+/// Reads a transcript written by [`ZstdTranscript`], decompressing only the blocks needed.
+pub struct ZstdTranscriptReader {
+    file: File,
+    decompressor: zstd::bulk::Decompressor<'static>,
+    index: Vec<BlockIndex>,
+    /// Where the blocks end, which is the start of the index or the end of the file.
+    end: u64,
+}
+
+impl ZstdTranscriptReader {
+    /// Open the transcript at the path.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_with(path, zstd::bulk::Decompressor::new()?)
+    }
+
+    /// Open the transcript at the path that is compressed with the dictionary.
+    pub fn open_with_dictionary(path: impl AsRef<Path>, dictionary: &[u8]) -> io::Result<Self> {
+        Self::open_with(path, zstd::bulk::Decompressor::with_dictionary(dictionary)?)
+    }
+
+    fn open_with(
+        path: impl AsRef<Path>,
+        decompressor: zstd::bulk::Decompressor<'static>,
+    ) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a zstd transcript"));
+        }
+        let mut reader = Self {
+            file,
+            decompressor,
+            index: Vec::new(),
+            end: 0,
+        };
+        if !reader.read_index()? {
+            reader.scan_index()?;
+        }
+        Ok(reader)
+    }
+
+    /// Read the index at the end of the file, returning false if there is none.
+    fn read_index(&mut self) -> io::Result<bool> {
+        let len = self.file.seek(SeekFrom::End(0))?;
+        if len < MAGIC.len() as u64 + TRAILER_LEN {
+            return Ok(false);
+        }
+        let mut trailer = [0; TRAILER_LEN as usize];
+        self.file.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+        self.file.read_exact(&mut trailer)?;
+        if &trailer[12..] != INDEX_MAGIC {
+            return Ok(false);
+        }
+        let mut trailer = IndexReader(&trailer);
+        let index_offset = trailer.u64()?;
+        let blocks = trailer.u32()?;
+        if index_offset > len - TRAILER_LEN {
+            return Err(invalid("invalid transcript index offset"));
+        }
+        let mut data = vec![0; (len - TRAILER_LEN - index_offset) as usize];
+        self.file.seek(SeekFrom::Start(index_offset))?;
+        self.file.read_exact(&mut data)?;
+        let mut data = IndexReader(&data);
+        for _ in 0..blocks {
+            let offset = data.u64()?;
+            let first_seq = data.u64()?;
+            let first_elapsed = Duration::from_micros(data.u64()?);
+            let last_elapsed = Duration::from_micros(data.u64()?);
+            let markers = (0..data.u32()?)
+                .map(|_| {
+                    let seq = data.u64()?;
+                    let len = data.u32()? as usize;
+                    let marker = String::from_utf8_lossy(data.take(len)?).into_owned();
+                    Ok((seq, marker))
+                })
+                .collect::<io::Result<_>>()?;
+            self.index.push(BlockIndex {
+                offset,
+                first_seq,
+                first_elapsed,
+                last_elapsed,
+                markers,
+            });
+        }
+        self.end = index_offset;
+        Ok(true)
+    }
+
+    /// Rebuild the index by decompressing every block, for a transcript whose recording didn't
+    /// stop properly. A block cut short at the end is ignored.
+    fn scan_index(&mut self) -> io::Result<()> {
+        let len = self.file.seek(SeekFrom::End(0))?;
+        let mut offset = MAGIC.len() as u64;
+        while offset + 4 <= len {
+            let Ok(text) = self.read_block(offset) else {
+                break;
+            };
+            let events = parse_transcript(&text)?;
+            let (Some(first), Some(last)) = (events.first(), events.last()) else {
+                break;
+            };
+            self.index.push(BlockIndex {
+                offset,
+                first_seq: first.seq,
+                first_elapsed: first.elapsed,
+                last_elapsed: last.elapsed,
+                markers: parse_markers(&text),
+            });
+            let mut header = [0; 4];
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.read_exact(&mut header)?;
+            offset += 4 + u32::from_le_bytes(header) as u64;
+        }
+        self.end = offset;
+        Ok(())
+    }
+
+    /// Decompress the block at the offset into the text format.
+    fn read_block(&mut self, offset: u64) -> io::Result<String> {
+        let mut header = [0; 4];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut header)?;
+        let len = u32::from_le_bytes(header) as u64;
+        if self.end != 0 && offset + 4 + len > self.end {
+            return Err(invalid("truncated transcript block"));
+        }
+        let mut compressed = vec![0; len as usize];
+        self.file.read_exact(&mut compressed)?;
+        let capacity = zstd::zstd_safe::get_frame_content_size(&compressed)
+            .ok()
+            .flatten()
+            .ok_or_else(|| invalid("invalid transcript block"))?;
+        let text = self
+            .decompressor
+            .decompress(&compressed, capacity as usize)?;
+        String::from_utf8(text).map_err(|_| invalid("invalid transcript block"))
+    }
+
+    fn events_from_block(&mut self, first: usize) -> io::Result<Vec<TranscriptEvent>> {
+        let offsets: Vec<u64> = self.index[first..].iter().map(|b| b.offset).collect();
+        let mut events = Vec::new();
+        for offset in offsets {
+            let text = self.read_block(offset)?;
+            events.extend(parse_transcript(&text)?);
+        }
+        Ok(events)
+    }
+
+    /// The number of compressed blocks.
+    pub fn blocks(&self) -> usize {
+        self.index.len()
+    }
+
+    /// The annotations in the transcript with the sequence number of their event, read from the
+    /// index without decompressing anything.
+    pub fn markers(&self) -> Vec<(u64, String)> {
+        self.index
+            .iter()
+            .flat_map(|block| block.markers.iter().cloned())
+            .collect()
+    }
+
+    /// All the events.
+    pub fn events(&mut self) -> io::Result<Vec<TranscriptEvent>> {
+        self.events_from_block(0)
+    }
+
+    /// The events at or after `elapsed` since the recording started.
+    pub fn events_since(&mut self, elapsed: Duration) -> io::Result<Vec<TranscriptEvent>> {
+        let first = self
+            .index
+            .iter()
+            .position(|block| block.last_elapsed >= elapsed)
+            .unwrap_or(self.index.len());
+        let mut events = self.events_from_block(first)?;
+        events.retain(|event| event.elapsed >= elapsed);
+        Ok(events)
+    }
+
+    /// The events from the first event with an annotation containing `marker`, or `None` if no
+    /// annotation contains it.
+    pub fn events_from_marker(&mut self, marker: &str) -> io::Result<Option<Vec<TranscriptEvent>>> {
+        let Some((first, seq)) = self.index.iter().enumerate().find_map(|(i, block)| {
+            block
+                .markers
+                .iter()
+                .find(|(_, annotation)| annotation.contains(marker))
+                .map(|(seq, _)| (i, *seq))
+        }) else {
+            return Ok(None);
+        };
+        let mut events = self.events_from_block(first)?;
+        let start = events
+            .iter()
+            .position(|event| event.seq == seq)
+            .unwrap_or(0);
+        events.drain(..start);
+        Ok(Some(events))
+    }
+}
+
+impl std::fmt::Debug for ZstdTranscriptReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZstdTranscriptReader")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parse the annotation comments of a block, see [`ZstdTranscript`].
+fn parse_markers(text: &str) -> Vec<(u64, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let (seq, marker) = line.strip_prefix("# ")?.split_once(' ')?;
+            Some((seq.parse().ok()?, marker.to_owned()))
+        })
+        .collect()
+}
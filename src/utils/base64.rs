@@ -0,0 +1,75 @@
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode the data with the standard base64 alphabet and padding.
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode data encoded with the standard base64 alphabet, where the padding is optional.
+/// Returns `None` if the data is not valid base64.
+pub fn base64_decode(data: &[u8]) -> Option<Vec<u8>> {
+    let data = match data.iter().position(|&c| c == b'=') {
+        Some(pad) if data.len().is_multiple_of(4) && data[pad..].iter().all(|&c| c == b'=') => {
+            (data.len() - pad <= 2).then_some(&data[..pad])?
+        }
+        Some(_) => return None,
+        None => data,
+    };
+    if data.len() % 4 == 1 {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    for chunk in data.chunks(4) {
+        let mut group = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|&a| a == c)? as u32;
+            group |= value << (18 - 6 * i);
+        }
+        decoded.extend_from_slice(&group.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64_decode, base64_encode};
+
+    #[test]
+    fn can_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(&[0xFF, 0xFE, 0x00]), "//4A");
+    }
+
+    #[test]
+    fn can_base64_decode() {
+        assert_eq!(base64_decode(b"").unwrap(), b"");
+        assert_eq!(base64_decode(b"Zg==").unwrap(), b"f");
+        assert_eq!(base64_decode(b"Zm8=").unwrap(), b"fo");
+        assert_eq!(base64_decode(b"Zm8").unwrap(), b"fo");
+        assert_eq!(base64_decode(b"Zm9vYmFy").unwrap(), b"foobar");
+        assert_eq!(base64_decode(b"//4A").unwrap(), [0xFF, 0xFE, 0x00]);
+        assert_eq!(base64_decode(b"Z"), None);
+        assert_eq!(base64_decode(b"Zg=a"), None);
+        assert_eq!(base64_decode(b"Zm9v!"), None);
+    }
+}
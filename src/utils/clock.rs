@@ -0,0 +1,10 @@
+use std::time::Instant;
+
+/// Whether the target provides a clock. There is none on `wasm32-unknown-unknown`, where
+/// [`Instant::now`] panics, so the timeouts are not applied and no times are recorded.
+pub(crate) const AVAILABLE: bool = cfg!(not(all(target_family = "wasm", target_os = "unknown")));
+
+/// The current time, or `None` if the target has no clock.
+pub(crate) fn now() -> Option<Instant> {
+    AVAILABLE.then(Instant::now)
+}
@@ -0,0 +1,132 @@
+/// Options for the de Bruijn sequence of [`cyclic_with`] and [`cyclic_find_with`].
+#[derive(Debug, Clone)]
+pub struct CyclicOptions {
+    /// The bytes that the pattern is made of.
+    pub alphabet: Vec<u8>,
+    /// The length of the subsequences that are unique in the pattern, usually the word size.
+    pub n: usize,
+}
+
+impl Default for CyclicOptions {
+    /// The lowercase letters with unique 4 bytes subsequences, same as pwntools.
+    fn default() -> Self {
+        Self {
+            alphabet: b"abcdefghijklmnopqrstuvwxyz".to_vec(),
+            n: 4,
+        }
+    }
+}
+
+/// Generate a cyclic pattern of `len` bytes, where every 4 bytes subsequence is unique.
+///
+/// Panics if `len` is longer than the pattern, which is 456976 bytes.
+/// ```rust
+/// use io_tubes::{
+///     packing::p32,
+///     utils::{cyclic, cyclic_find},
+/// };
+///
+/// assert_eq!(cyclic(20), b"aaaabaaacaaadaaaeaaa");
+///
+/// // The crashing instruction pointer is found to be 0x61616166.
+/// assert_eq!(cyclic_find(p32(0x61616166)), Some(20));
+/// ```
+pub fn cyclic(len: usize) -> Vec<u8> {
+    cyclic_with(len, &CyclicOptions::default())
+}
+
+/// Generate a cyclic pattern of `len` bytes with the supplied options.
+///
+/// Panics if `len` is longer than the pattern, which is `alphabet.len().pow(n)` bytes.
+pub fn cyclic_with(len: usize, options: &CyclicOptions) -> Vec<u8> {
+    let pattern = de_bruijn(options, len);
+    assert!(
+        pattern.len() >= len,
+        "cyclic pattern can only be {} bytes long",
+        pattern.len()
+    );
+    pattern
+}
+
+/// Find the offset of the subsequence in the cyclic pattern. Only the first 4 bytes are used.
+pub fn cyclic_find(subseq: impl AsRef<[u8]>) -> Option<usize> {
+    cyclic_find_with(subseq, &CyclicOptions::default())
+}
+
+/// Find the offset of the subsequence in the cyclic pattern with the supplied options. Only the
+/// first `n` bytes are used.
+pub fn cyclic_find_with(subseq: impl AsRef<[u8]>, options: &CyclicOptions) -> Option<usize> {
+    let subseq = subseq.as_ref().get(..options.n)?;
+    de_bruijn(options, usize::MAX)
+        .windows(options.n)
+        .position(|window| window == subseq)
+}
+
+/// Generate the de Bruijn sequence up to `limit` bytes by concatenating the Lyndon words whose
+/// length divides `n` in lexicographic order.
+fn de_bruijn(options: &CyclicOptions, limit: usize) -> Vec<u8> {
+    let k = options.alphabet.len();
+    let n = options.n;
+    let mut sequence = Vec::new();
+    if k == 0 || n == 0 {
+        return sequence;
+    }
+    let mut word = vec![0];
+    loop {
+        if n.is_multiple_of(word.len()) {
+            sequence.extend(word.iter().map(|&i| options.alphabet[i]));
+            if sequence.len() >= limit {
+                sequence.truncate(limit);
+                return sequence;
+            }
+        }
+        let len = word.len();
+        while word.len() < n {
+            word.push(word[word.len() - len]);
+        }
+        while word.last() == Some(&(k - 1)) {
+            word.pop();
+        }
+        match word.last_mut() {
+            Some(last) => *last += 1,
+            None => return sequence,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cyclic, cyclic_find, cyclic_find_with, cyclic_with, CyclicOptions};
+
+    #[test]
+    fn can_generate_cyclic() {
+        assert_eq!(cyclic(0), b"");
+        assert_eq!(cyclic(40), b"aaaabaaacaaadaaaeaaafaaagaaahaaaiaaajaaa");
+
+        let options = CyclicOptions {
+            alphabet: b"01".to_vec(),
+            n: 3,
+        };
+        assert_eq!(cyclic_with(8, &options), b"00010111");
+    }
+
+    #[test]
+    fn can_find_cyclic() {
+        let pattern = cyclic(1000);
+        assert_eq!(cyclic_find(&pattern[123..]), Some(123));
+        assert_eq!(cyclic_find(b"aaa"), None);
+        assert_eq!(cyclic_find(b"AAAA"), None);
+
+        let options = CyclicOptions {
+            alphabet: b"01".to_vec(),
+            n: 3,
+        };
+        assert_eq!(cyclic_find_with(b"011", &options), Some(4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn cyclic_too_long() {
+        cyclic(26usize.pow(4) + 1);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    time::{self, Sleep},
+};
+
+use crate::report;
+
+use super::{Needle, RecvNeedle};
+
+/// The common helpers of [`Tube`](crate::tubes::Tube) for any [`AsyncBufRead`] that is also
+/// [`AsyncWrite`], e.g. a stream wrapped in a [`BufReader`](tokio::io::BufReader), without
+/// wrapping it in a tube. Nothing is logged, lines end with `\n` and there is no timeout unless
+/// the call goes through [`TubeExt::timeout`].
+///
+/// The methods of a tube take precedence over the ones of this trait, so importing it does not
+/// change what the calls on a tube do.
+/// ```rust
+/// use io_tubes::io::TubeExt;
+/// use std::{io, time::Duration};
+/// use tokio::io::{duplex, BufReader};
+///
+/// #[tokio::main]
+/// async fn tube_ext() -> io::Result<()> {
+///     let (client, server) = duplex(64);
+///     let (mut client, mut server) = (BufReader::new(client), BufReader::new(server));
+///
+///     let server = tokio::spawn(async move {
+///         server.send("name? ").await?;
+///         let line = server.recv_line().await?;
+///         io::Result::Ok((line, server))
+///     });
+///     assert_eq!(client.send_line_after("? ", "admin").await?, b"name? ");
+///     let (line, _server) = server.await??;
+///     assert_eq!(line, b"admin\n");
+///
+///     let err = client
+///         .timeout(Duration::from_millis(50))
+///         .recv_line()
+///         .await
+///         .unwrap_err();
+///     assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+///
+///     Ok(())
+/// }
+///
+/// tube_ext();
+/// ```
+pub trait TubeExt: AsyncBufRead + AsyncWrite + Unpin {
+    /// Receive up to `len` bytes, returning an empty vector on EOF.
+    fn recv(&mut self, len: usize) -> impl Future<Output = io::Result<Vec<u8>>> {
+        async move {
+            let mut data = vec![0; len];
+            let len = self.read(&mut data).await?;
+            data.truncate(len);
+            Ok(data)
+        }
+    }
+
+    /// Receive until the pattern is found, including it. The data received so far is returned if
+    /// EOF is reached first.
+    fn recv_until(&mut self, delims: impl Needle) -> impl Future<Output = io::Result<Vec<u8>>> {
+        async move {
+            let mut buf = Vec::new();
+            RecvNeedle::new(self, &delims, &mut buf).await?;
+            Ok(buf)
+        }
+    }
+
+    /// Receive until a newline, including it.
+    fn recv_line(&mut self) -> impl Future<Output = io::Result<Vec<u8>>> {
+        self.recv_until(b'\n')
+    }
+
+    /// Receive until EOF.
+    fn recv_all(&mut self) -> impl Future<Output = io::Result<Vec<u8>>> {
+        async move {
+            let mut buf = Vec::new();
+            self.read_to_end(&mut buf).await?;
+            Ok(buf)
+        }
+    }
+
+    /// Send data and flush.
+    fn send(&mut self, data: impl AsRef<[u8]>) -> impl Future<Output = io::Result<()>> {
+        async move {
+            self.write_all(data.as_ref()).await?;
+            self.flush().await
+        }
+    }
+
+    /// Send data followed by a newline and flush.
+    fn send_line(&mut self, data: impl AsRef<[u8]>) -> impl Future<Output = io::Result<()>> {
+        async move {
+            self.write_all(data.as_ref()).await?;
+            self.write_all(b"\n").await?;
+            self.flush().await
+        }
+    }
+
+    /// Send a line after receiving the pattern, returning the data received.
+    fn send_line_after(
+        &mut self,
+        pattern: impl Needle,
+        data: impl AsRef<[u8]>,
+    ) -> impl Future<Output = io::Result<Vec<u8>>> {
+        async move {
+            let received = self.recv_until(pattern).await?;
+            self.send_line(data).await?;
+            Ok(received)
+        }
+    }
+
+    /// Make the calls through the returned stream fail with
+    /// [`TimedOut`](io::ErrorKind::TimedOut) once `duration` has passed from now.
+    fn timeout(&mut self, duration: Duration) -> WithTimeout<'_, Self> {
+        WithTimeout {
+            inner: self,
+            sleep: Box::pin(time::sleep(duration)),
+        }
+    }
+}
+
+impl<T> TubeExt for T where T: AsyncBufRead + AsyncWrite + Unpin + ?Sized {}
+
+/// A stream that fails with [`TimedOut`](io::ErrorKind::TimedOut) once its time is up, returned
+/// by [`TubeExt::timeout`].
+#[derive(Debug)]
+pub struct WithTimeout<'a, T: ?Sized> {
+    inner: &'a mut T,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl<T: ?Sized> WithTimeout<'_, T> {
+    /// Fail if the time is up, or else register to be woken up when it is.
+    fn poll_elapsed(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+        match self.sleep.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                report::record_timeout();
+                Err(io::Error::new(io::ErrorKind::TimedOut, "timed out"))
+            }
+            Poll::Pending => Ok(()),
+        }
+    }
+}
+
+impl<T> AsyncRead for WithTimeout<'_, T>
+where
+    T: AsyncRead + Unpin + ?Sized,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.poll_elapsed(cx)?;
+        Pin::new(&mut *this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T> AsyncBufRead for WithTimeout<'_, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        this.poll_elapsed(cx)?;
+        Pin::new(&mut *this.inner).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut *self.get_mut().inner).consume(amt);
+    }
+}
+
+impl<T> AsyncWrite for WithTimeout<'_, T>
+where
+    T: AsyncWrite + Unpin + ?Sized,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.poll_elapsed(cx)?;
+        Pin::new(&mut *this.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.poll_elapsed(cx)?;
+        Pin::new(&mut *this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.poll_elapsed(cx)?;
+        Pin::new(&mut *this.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,220 @@
+use crate::packing::{pack, Endian};
+
+/// A value in a payload built by [`flat`] or [`fit`]. Integers are packed with the word size and
+/// bytes are copied as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlatValue {
+    /// An integer packed into [`FlatOptions::word_size`] bytes, negative integers are packed in
+    /// two's complement.
+    Int(u64),
+    /// Bytes inlined into the payload.
+    Bytes(Vec<u8>),
+}
+
+macro_rules! impl_from_int {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for FlatValue {
+                fn from(value: $int) -> Self {
+                    Self::Int(value as u64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl From<&[u8]> for FlatValue {
+    fn from(value: &[u8]) -> Self {
+        Self::Bytes(value.to_vec())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for FlatValue {
+    fn from(value: &[u8; N]) -> Self {
+        Self::Bytes(value.to_vec())
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for FlatValue {
+    fn from(value: [u8; N]) -> Self {
+        Self::Bytes(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for FlatValue {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Bytes(value)
+    }
+}
+
+impl From<&str> for FlatValue {
+    fn from(value: &str) -> Self {
+        Self::Bytes(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for FlatValue {
+    fn from(value: String) -> Self {
+        Self::Bytes(value.into_bytes())
+    }
+}
+
+/// Options of [`flat_with`] and [`fit_with`].
+#[derive(Debug, Clone)]
+pub struct FlatOptions {
+    /// The number of bytes that integers are packed into, which must be at most 8.
+    pub word_size: usize,
+    /// The byte order of the packed integers.
+    pub endian: Endian,
+    /// The byte used to fill the gaps between the values.
+    pub filler: u8,
+    /// Pad the payload with the filler to this length. Panics if the payload is longer.
+    pub length: Option<usize>,
+}
+
+impl Default for FlatOptions {
+    /// 8 bytes little endian words filled with `b'a'`, which is what most 64-bit targets use.
+    fn default() -> Self {
+        Self {
+            word_size: 8,
+            endian: Endian::Little,
+            filler: b'a',
+            length: None,
+        }
+    }
+}
+
+impl FlatOptions {
+    fn bytes(&self, value: FlatValue) -> Vec<u8> {
+        match value {
+            FlatValue::Int(value) => pack(value, self.word_size, self.endian),
+            FlatValue::Bytes(bytes) => bytes,
+        }
+    }
+
+    fn pad(&self, mut payload: Vec<u8>) -> Vec<u8> {
+        if let Some(length) = self.length {
+            assert!(
+                payload.len() <= length,
+                "payload of {} bytes is longer than {} bytes",
+                payload.len(),
+                length
+            );
+            payload.resize(length, self.filler);
+        }
+        payload
+    }
+}
+
+/// Concatenate the values, packing integers into 8 bytes little endian words.
+/// ```rust
+/// use io_tubes::utils::{flat, FlatValue};
+///
+/// let payload = flat([0x401136.into(), FlatValue::from("/bin/sh\0")]);
+/// assert_eq!(payload, b"\x36\x11\x40\x00\x00\x00\x00\x00/bin/sh\0");
+/// ```
+pub fn flat(values: impl IntoIterator<Item = FlatValue>) -> Vec<u8> {
+    flat_with(values, &FlatOptions::default())
+}
+
+/// Concatenate the values with the supplied options.
+pub fn flat_with(values: impl IntoIterator<Item = FlatValue>, options: &FlatOptions) -> Vec<u8> {
+    let payload = values
+        .into_iter()
+        .flat_map(|value| options.bytes(value))
+        .collect();
+    options.pad(payload)
+}
+
+/// Place each value at its offset and fill the gaps with `b'a'`, packing integers into 8 bytes
+/// little endian words. The values can be in any order.
+///
+/// Panics if the values overlap.
+/// ```rust
+/// use io_tubes::utils::fit;
+///
+/// // Overwrite the return address after a 0x28 bytes buffer.
+/// let payload = fit([(0x28, 0x401136.into()), (0, "sh\0".into())]);
+/// assert_eq!(payload.len(), 0x30);
+/// assert_eq!(&payload[..5], b"sh\0aa");
+/// assert_eq!(&payload[0x28..], b"\x36\x11\x40\x00\x00\x00\x00\x00");
+/// ```
+pub fn fit(values: impl IntoIterator<Item = (usize, FlatValue)>) -> Vec<u8> {
+    fit_with(values, &FlatOptions::default())
+}
+
+/// Place each value at its offset with the supplied options.
+///
+/// Panics if the values overlap.
+pub fn fit_with(
+    values: impl IntoIterator<Item = (usize, FlatValue)>,
+    options: &FlatOptions,
+) -> Vec<u8> {
+    let mut values: Vec<_> = values.into_iter().collect();
+    values.sort_by_key(|&(offset, _)| offset);
+    let mut payload = Vec::new();
+    for (offset, value) in values {
+        assert!(
+            offset >= payload.len(),
+            "value at offset {:#x} overlaps the previous value ending at {:#x}",
+            offset,
+            payload.len()
+        );
+        payload.resize(offset, options.filler);
+        payload.extend(options.bytes(value));
+    }
+    options.pad(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{fit, fit_with, flat_with, FlatOptions, FlatValue};
+    use crate::packing::Endian;
+
+    #[test]
+    fn can_flat() {
+        let options = FlatOptions {
+            word_size: 4,
+            endian: Endian::Big,
+            filler: 0,
+            length: Some(12),
+        };
+        let payload = flat_with([(-1i32).into(), b"ab".into(), 0x1337u16.into()], &options);
+        assert_eq!(payload, b"\xff\xff\xff\xffab\x00\x00\x13\x37\x00\x00");
+    }
+
+    #[test]
+    fn can_fit() {
+        let mut values = BTreeMap::new();
+        values.insert(4, FlatValue::from(1u8));
+        values.insert(0, FlatValue::from("AB"));
+        let options = FlatOptions {
+            word_size: 2,
+            ..Default::default()
+        };
+        assert_eq!(fit_with(values, &options), b"ABaa\x01\x00");
+
+        assert_eq!(fit([(2, "x".into()), (0, "yy".into())]), b"yyx");
+        assert_eq!(fit([]), b"");
+    }
+
+    #[test]
+    #[should_panic]
+    fn fit_overlap() {
+        fit([(0, 0.into()), (4, 0.into())]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn flat_too_long() {
+        let options = FlatOptions {
+            length: Some(4),
+            ..Default::default()
+        };
+        flat_with([0.into()], &options);
+    }
+}
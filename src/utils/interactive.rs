@@ -1,72 +1,296 @@
 use std::{
     future::Future,
-    io::{Error, ErrorKind},
     ops::DerefMut,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
-use tokio::io::{self, AsyncBufRead, AsyncWrite, BufReader, Stdin};
+use tokio::{
+    io::{self, AsyncBufRead, AsyncRead, AsyncWrite, BufReader},
+    time::{self, Instant, Sleep},
+};
+
+use crate::tubes::{IdleAction, IdleHook, InteractiveEnd, Tube};
 
-use crate::tubes::Tube;
+use super::AnyMatcher;
 
+/// A future pumping data between the tube and a pair of local streams until either side reaches
+/// EOF, the escape sequence is read from the input or the idle hook ends it. It is the building block of
+/// [`Tube::interactive`](crate::tubes::Tube::interactive) and
+/// [`Tube::interact_with`](crate::tubes::Tube::interact_with).
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 #[derive(Debug)]
-pub struct Interactive<'a, T>
+pub struct Interactive<'a, T, R, W>
 where
     T: AsyncBufRead + AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
 {
     inner: &'a mut Tube<T>,
-    stdin: BufReader<Stdin>,
+    input: BufReader<R>,
+    output: W,
+    /// The sequence from the input that ends the interaction instead of being sent.
+    escape: &'a [u8],
+    /// The number of bytes at the end of the input matching the start of the escape sequence, which
+    /// are held back until they are known not to be the escape sequence.
+    escape_matched: usize,
+    /// Data from the input waiting to be sent.
+    pending: Vec<u8>,
+    /// Whether the escape sequence is found, which ends the interaction once pending is sent.
+    escaped: bool,
+    /// The responses sent when their patterns are received, and the matcher of the patterns
+    /// that are not empty together with the index of the response of each.
+    responses: &'a [(Vec<u8>, Vec<u8>)],
+    matcher: Option<(AnyMatcher, Vec<usize>)>,
+    idle: Option<Idle<'a>>,
+}
+
+/// Calls the hook whenever nothing is typed or received for the timeout.
+#[derive(Debug)]
+struct Idle<'a> {
+    timeout: Duration,
+    hook: Option<&'a IdleHook>,
+    /// When something is last typed or received.
+    since: Instant,
+    sleep: Pin<Box<Sleep>>,
 }
 
-impl<'a, T> Interactive<'a, T>
+impl<'a, T, R, W> Interactive<'a, T, R, W>
 where
     T: AsyncBufRead + AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
 {
-    pub fn new(inner: &'a mut Tube<T>) -> Self {
+    /// Send everything read from `input` to the tube and write everything received to `output`.
+    /// The escape sequence is disabled if it is empty.
+    pub fn new(inner: &'a mut Tube<T>, input: R, output: W, escape: &'a [u8]) -> Self {
         Self {
             inner,
-            stdin: BufReader::new(io::stdin()),
+            input: BufReader::new(input),
+            output,
+            escape,
+            escape_matched: 0,
+            pending: Vec::new(),
+            escaped: false,
+            responses: &[],
+            matcher: None,
+            idle: None,
         }
     }
+
+    /// Call the hook whenever nothing is typed or received for `timeout`, or end the interaction
+    /// with [`InteractiveEnd::Idle`] if there is no hook.
+    pub fn idle(mut self, timeout: Duration, hook: Option<&'a IdleHook>) -> Self {
+        let since = Instant::now();
+        self.idle = Some(Idle {
+            timeout,
+            hook,
+            since,
+            sleep: Box::pin(time::sleep_until(since + timeout)),
+        });
+        self
+    }
+
+    /// Send the response of a pattern whenever the pattern is received. Empty patterns are
+    /// ignored.
+    pub fn responses(mut self, responses: &'a [(Vec<u8>, Vec<u8>)]) -> Self {
+        let (indices, patterns): (Vec<usize>, Vec<&[u8]>) = responses
+            .iter()
+            .enumerate()
+            .filter(|(_, (pattern, _))| !pattern.is_empty())
+            .map(|(idx, (pattern, _))| (idx, &pattern[..]))
+            .unzip();
+        self.responses = responses;
+        self.matcher = (!patterns.is_empty()).then(|| (AnyMatcher::new(&patterns), indices));
+        self
+    }
 }
 
-impl<'a, T> Future for Interactive<'a, T>
+impl<'a, T, R, W> Future for Interactive<'a, T, R, W>
 where
     T: AsyncBufRead + AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
 {
-    type Output = io::Result<()>;
+    type Output = io::Result<InteractiveEnd>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        let Self { inner, stdin } = self.deref_mut();
-        let mut stdin = stdin;
+        let Self {
+            inner,
+            input,
+            output,
+            escape,
+            escape_matched,
+            pending,
+            escaped,
+            responses,
+            matcher,
+            idle,
+        } = self.deref_mut();
+        let mut active = false;
 
-        // stdin -> input
-        while let Poll::Ready(buf) = Pin::new(stdin.deref_mut()).poll_fill_buf(cx)? {
+        // input -> tube
+        loop {
+            if !pending.is_empty() {
+                match Pin::new(inner.deref_mut()).poll_write(cx, pending)? {
+                    Poll::Ready(amt) => {
+                        pending.drain(..amt);
+                        continue;
+                    }
+                    Poll::Pending => break,
+                }
+            }
+            if *escaped {
+                return Poll::Ready(Ok(InteractiveEnd::Escape));
+            }
+            let buf = match Pin::new(&mut *input).poll_fill_buf(cx)? {
+                Poll::Ready(buf) => buf,
+                Poll::Pending => break,
+            };
             if buf.is_empty() {
-                return Poll::Ready(Ok(()));
+                if *escape_matched == 0 {
+                    return Poll::Ready(Ok(InteractiveEnd::Eof));
+                }
+                pending.extend_from_slice(&escape[..*escape_matched]);
+                *escape_matched = 0;
+                continue;
             }
-            let write_res = Pin::new(inner.deref_mut()).poll_write(cx, buf);
-            if let Poll::Ready(amt) = write_res? {
-                Pin::new(stdin.deref_mut()).consume(amt);
-            } else {
-                break;
+            let mut len = buf.len();
+            for (count, &byte) in buf.iter().enumerate() {
+                if escape.get(*escape_matched) == Some(&byte) {
+                    *escape_matched += 1;
+                    if *escape_matched == escape.len() {
+                        *escaped = true;
+                        len = count + 1;
+                        break;
+                    }
+                    continue;
+                }
+                if *escape_matched > 0 {
+                    pending.extend_from_slice(&escape[..*escape_matched]);
+                    *escape_matched = 0;
+                    if escape.first() == Some(&byte) {
+                        *escape_matched = 1;
+                        continue;
+                    }
+                }
+                pending.push(byte);
             }
+            Pin::new(&mut *input).consume(len);
+            active = true;
         }
 
-        // output -> stdout
+        // tube -> output
+        let mut responded = false;
         while let Poll::Ready(buf) = Pin::new(inner.deref_mut()).poll_fill_buf(cx)? {
             if buf.is_empty() {
-                return Poll::Ready(Err(Error::from(ErrorKind::BrokenPipe)));
+                return Pin::new(&mut *output)
+                    .poll_flush(cx)
+                    .map_ok(|()| InteractiveEnd::RemoteEof);
             }
-            let write_res = Pin::new(&mut io::stdout()).poll_write(cx, buf);
+            let write_res = Pin::new(&mut *output).poll_write(cx, buf);
             if let Poll::Ready(amt) = write_res? {
+                if let Some((matcher, indices)) = matcher {
+                    let mut written = &buf[..amt];
+                    while let Some((len, found)) = matcher.push_bytes(written) {
+                        pending.extend_from_slice(&responses[indices[found]].1);
+                        responded = true;
+                        written = &written[len..];
+                    }
+                }
                 Pin::new(inner.deref_mut()).consume(amt);
+                active |= amt > 0;
             } else {
                 break;
             }
         }
+        // The output may be buffered, e.g. when it is another tube.
+        let _ = Pin::new(&mut *output).poll_flush(cx)?;
 
+        if let Some(idle) = idle {
+            if active {
+                idle.since = Instant::now();
+                idle.sleep.as_mut().reset(idle.since + idle.timeout);
+            }
+            while idle.sleep.as_mut().poll(cx).is_ready() {
+                let action = match idle.hook {
+                    Some(hook) => hook.call(idle.since.elapsed()),
+                    None => IdleAction::End,
+                };
+                match action {
+                    IdleAction::Continue => {}
+                    IdleAction::Send(data) => {
+                        pending.extend_from_slice(&data);
+                        responded = true;
+                    }
+                    IdleAction::End => return Poll::Ready(Ok(InteractiveEnd::Idle)),
+                }
+                idle.sleep.as_mut().reset(Instant::now() + idle.timeout);
+            }
+        }
+
+        if responded {
+            // Send the responses, which are only written at the start of the poll.
+            cx.waker().wake_by_ref();
+        }
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::Interactive;
+    use crate::tubes::{IdleAction, IdleHook, InteractiveEnd, Tube};
+
+    #[tokio::test]
+    async fn sends_auto_responses() -> io::Result<()> {
+        let (mut p, mut server) = Tube::pair();
+        let responses = [
+            (b"sure? ".to_vec(), b"y\n".to_vec()),
+            (Vec::new(), b"ignored".to_vec()),
+        ];
+        let mut output = Vec::new();
+        let (input, user) = tokio::io::duplex(64);
+
+        let server = async move {
+            server.send("Delete? Are you sure? ").await?;
+            assert_eq!(server.recv_line().await?, b"y\n");
+            server.send("Really sure? ").await?;
+            assert_eq!(server.recv_line().await?, b"y\n");
+            // The user leaves, which ends the interaction.
+            drop(user);
+            io::Result::Ok(())
+        };
+        let interactive = Interactive::new(&mut p, input, &mut output, &[]).responses(&responses);
+        let (end, server) = tokio::join!(interactive, server);
+        server?;
+        assert_eq!(end?, InteractiveEnd::Eof);
+        assert_eq!(output, b"Delete? Are you sure? Really sure? ");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn calls_the_idle_hook() -> io::Result<()> {
+        let (mut p, mut server) = Tube::pair();
+        let calls = AtomicUsize::new(0);
+        let hook = IdleHook::new(move |_| match calls.fetch_add(1, Ordering::Relaxed) {
+            0 | 1 => IdleAction::Send(b"ping\n".to_vec()),
+            _ => IdleAction::End,
+        });
+        let mut output = Vec::new();
+        let (input, _user) = tokio::io::duplex(64);
+
+        let end = Interactive::new(&mut p, input, &mut output, &[])
+            .idle(Duration::from_millis(20), Some(&hook))
+            .await?;
+        assert_eq!(end, InteractiveEnd::Idle);
+        assert_eq!(server.recv_lines(2).await?, [b"ping\n", b"ping\n"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+// Only `core` and `alloc` are used here, so that the matchers can be reused in `no_std` code.
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+/// Searches a stream for a delimiter with the KMP algorithm, fed one chunk at a time. This is
+/// the matcher behind [`Tube::recv_until`](crate::tubes::Tube::recv_until).
+///
+/// Only the failure links are stored, which take a word per byte of the delimiter, so long
+/// delimiters are cheap to match. Outside of a partial match, the chunk is scanned for the first
+/// byte of the delimiter with `memchr`, which uses SIMD where available.
+///
+/// The matchers only depend on `core` and `alloc`, so that `no_std` code such as an agent running
+/// on the target can match exactly the same way as the host.
+/// ```rust
+/// use io_tubes::utils::Matcher;
+///
+/// let mut matcher = Matcher::new(b"fox");
+/// assert_eq!(matcher.push_bytes(b"The quick brown f"), None);
+/// assert_eq!(matcher.push_bytes(b"ox jumps"), Some(2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    /// The length of the prefix of the delimiter matched so far.
+    state: usize,
+    delims: Vec<u8>,
+    /// The length of the longest proper prefix of `delims[..=i]` that is also its suffix.
+    failure: Vec<usize>,
+}
+
+impl Matcher {
+    /// Build the failure links for the delimiter. An empty delimiter matches immediately.
+    pub fn new(delims: &[u8]) -> Self {
+        let mut failure = vec![0; delims.len()];
+        let mut lps = 0;
+        for i in 1..delims.len() {
+            while lps > 0 && delims[i] != delims[lps] {
+                lps = failure[lps - 1];
+            }
+            if delims[i] == delims[lps] {
+                lps += 1;
+            }
+            failure[i] = lps;
+        }
+        Self {
+            state: 0,
+            delims: delims.to_vec(),
+            failure,
+        }
+    }
+
+    /// Feed the next chunk of the stream. Returns the number of bytes in `data` up to and
+    /// including the end of the delimiter if it is found, after which the search starts over.
+    pub fn push_bytes(&mut self, data: &[u8]) -> Option<usize> {
+        let Some(&first) = self.delims.first() else {
+            return Some(0);
+        };
+        let mut pos = 0;
+        while pos < data.len() {
+            if self.state == 0 {
+                pos += memchr::memchr(first, &data[pos..])?;
+            }
+            let byte = data[pos];
+            while self.state > 0 && self.delims[self.state] != byte {
+                self.state = self.failure[self.state - 1];
+            }
+            if self.delims[self.state] == byte {
+                self.state += 1;
+            }
+            pos += 1;
+            if self.state == self.delims.len() {
+                self.state = 0;
+                return Some(pos);
+            }
+        }
+        None
+    }
+
+    /// Forget the partial match, so that the next chunk starts a new search.
+    pub fn reset(&mut self) {
+        self.state = 0;
+    }
+}
+
+/// Searches a stream for several delimiters at once with an Aho-Corasick automaton, fed one chunk
+/// at a time. The delimiter that ends first is found, and the longest one is reported if several
+/// end at the same byte, or the earlier one if the same delimiter is given twice. This is the
+/// matcher behind [`Tube::recv_until_any`](crate::tubes::Tube::recv_until_any).
+/// ```rust
+/// use io_tubes::utils::AnyMatcher;
+///
+/// let mut matcher = AnyMatcher::new(&[b"> ", b"Invalid"]);
+/// assert_eq!(matcher.push_bytes(b"1. Add\n>"), None);
+/// assert_eq!(matcher.push_bytes(b" Invalid"), Some((1, 0)));
+///
+/// let mut matcher = AnyMatcher::new(&[b"$ ", b"# $ "]);
+/// assert_eq!(matcher.push_bytes(b"# $ "), Some((4, 1)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnyMatcher {
+    state: usize,
+    lookup_table: Vec<[usize; 256]>,
+    /// The index of the delimiter that is found when reaching each state.
+    outputs: Vec<Option<usize>>,
+    /// The first bytes of the delimiters, which are scanned for with `memchr` outside of a
+    /// partial match if there are at most 3 of them.
+    first_bytes: Vec<u8>,
+}
+
+impl AnyMatcher {
+    /// Build the automaton for the delimiters.
+    pub fn new(delims: &[&[u8]]) -> Self {
+        const NONE: usize = usize::MAX;
+
+        // Build the trie, earlier delimiters take priority when duplicated.
+        let mut lookup_table = vec![[NONE; 256]];
+        let mut outputs = vec![None];
+        for (delim_idx, delim) in delims.iter().enumerate() {
+            let mut state = 0;
+            for &byte in delim.iter() {
+                if lookup_table[state][byte as usize] == NONE {
+                    lookup_table[state][byte as usize] = lookup_table.len();
+                    lookup_table.push([NONE; 256]);
+                    outputs.push(None);
+                }
+                state = lookup_table[state][byte as usize];
+            }
+            outputs[state].get_or_insert(delim_idx);
+        }
+
+        let first_bytes = (0..=u8::MAX)
+            .filter(|&byte| lookup_table[0][byte as usize] != NONE)
+            .collect();
+
+        // Turn the trie into a DFA by filling in the failure transitions in BFS order.
+        let mut fail = vec![0; lookup_table.len()];
+        let mut queue = VecDeque::new();
+        for next in lookup_table[0].iter_mut() {
+            match *next {
+                NONE => *next = 0,
+                next => queue.push_back(next),
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            if outputs[state].is_none() {
+                outputs[state] = outputs[fail[state]];
+            }
+            let fallback_row = lookup_table[fail[state]];
+            for (next, fallback) in lookup_table[state].iter_mut().zip(fallback_row) {
+                match *next {
+                    NONE => *next = fallback,
+                    child => {
+                        fail[child] = fallback;
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+        Self {
+            state: 0,
+            lookup_table,
+            outputs,
+            first_bytes,
+        }
+    }
+
+    /// Feed the next chunk of the stream. Returns the number of bytes in `data` up to and
+    /// including the end of the delimiter and the index of the delimiter if one is found, after
+    /// which the search starts over. An empty delimiter matches immediately.
+    pub fn push_bytes(&mut self, data: &[u8]) -> Option<(usize, usize)> {
+        if let Some(found) = self.outputs[self.state] {
+            return Some((0, found));
+        }
+        let mut pos = 0;
+        while pos < data.len() {
+            if self.state == 0 {
+                pos += self.skip(&data[pos..])?;
+            }
+            self.state = self.lookup_table[self.state][data[pos] as usize];
+            pos += 1;
+            if let Some(found) = self.outputs[self.state] {
+                self.state = 0;
+                return Some((pos, found));
+            }
+        }
+        None
+    }
+
+    /// The position of the first byte in `data` that may start a delimiter.
+    fn skip(&self, data: &[u8]) -> Option<usize> {
+        match self.first_bytes[..] {
+            [a] => memchr::memchr(a, data),
+            [a, b] => memchr::memchr2(a, b, data),
+            [a, b, c] => memchr::memchr3(a, b, c, data),
+            [] => None,
+            _ => Some(0),
+        }
+    }
+
+    /// Forget the partial match, so that the next chunk starts a new search.
+    pub fn reset(&mut self) {
+        self.state = 0;
+    }
+}
+
+/// Searches a stream for an approximate occurrence of a delimiter, which is at most `max_edits`
+/// insertions, deletions or substitutions of bytes away from it, fed one chunk at a time. This is
+/// the matcher behind [`RecvUntilOptions::max_edits`](crate::tubes::RecvUntilOptions::max_edits).
+///
+/// The match ends at the first byte where the edit distance is small enough and which equals the
+/// last byte of the delimiter, so that it doesn't end before the delimiter is fully received.
+/// ```rust
+/// use io_tubes::utils::FuzzyMatcher;
+///
+/// let mut matcher = FuzzyMatcher::new(b"[pid 1234] ready", 4);
+/// assert_eq!(matcher.push_bytes(b"booting\n[pid 9"), None);
+/// assert_eq!(matcher.push_bytes(b"87] ready\n"), Some((9, 15)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FuzzyMatcher {
+    delims: Vec<u8>,
+    max_edits: usize,
+    /// The edit distance between each prefix of the delimiter and the best match of it ending at
+    /// the current byte, and the number of bytes in that match.
+    distances: Vec<(usize, usize)>,
+}
+
+impl FuzzyMatcher {
+    /// Build the matcher for the delimiter. Panics if `max_edits` is not less than the length of
+    /// the delimiter, since anything would match.
+    pub fn new(delims: &[u8], max_edits: usize) -> Self {
+        assert!(
+            max_edits < delims.len(),
+            "max_edits must be less than the length of the delimiter"
+        );
+        let mut matcher = Self {
+            delims: delims.to_vec(),
+            max_edits,
+            distances: Vec::new(),
+        };
+        matcher.reset();
+        matcher
+    }
+
+    /// Feed the next chunk of the stream. Returns the number of bytes in `data` up to and
+    /// including the end of the match and the total length of the match if one is found, after
+    /// which the search starts over. The match may start in an earlier chunk.
+    pub fn push_bytes(&mut self, data: &[u8]) -> Option<(usize, usize)> {
+        let last = self.delims.len();
+        for (count, &new_byte) in data.iter().enumerate() {
+            // The empty prefix matches nothing right after the byte.
+            let mut diagonal = self.distances[0];
+            for idx in 1..=last {
+                let (above, above_len) = self.distances[idx - 1];
+                let (left, left_len) = self.distances[idx];
+                let substitution = (
+                    diagonal.0 + usize::from(self.delims[idx - 1] != new_byte),
+                    diagonal.1 + 1,
+                );
+                let insertion = (left + 1, left_len + 1);
+                let deletion = (above + 1, above_len);
+                diagonal = self.distances[idx];
+                self.distances[idx] = substitution.min(insertion).min(deletion);
+            }
+            let (distance, len) = self.distances[last];
+            if distance <= self.max_edits && new_byte == self.delims[last - 1] {
+                self.reset();
+                return Some((count + 1, len));
+            }
+        }
+        None
+    }
+
+    /// Forget the partial match, so that the next chunk starts a new search.
+    pub fn reset(&mut self) {
+        self.distances = (0..=self.delims.len()).map(|idx| (idx, 0)).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnyMatcher, FuzzyMatcher, Matcher};
+
+    #[test]
+    fn matches_across_chunks() {
+        let mut matcher = Matcher::new(b"abab");
+        assert_eq!(matcher.push_bytes(b"aba"), None);
+        assert_eq!(matcher.push_bytes(b"cab"), None);
+        assert_eq!(matcher.push_bytes(b"aab"), None);
+        assert_eq!(matcher.push_bytes(b"abx"), Some(2));
+
+        // the search starts over after a match
+        assert_eq!(matcher.push_bytes(b"ab"), None);
+        matcher.reset();
+        assert_eq!(matcher.push_bytes(b"ab"), None);
+        assert_eq!(matcher.push_bytes(b"ab"), Some(2));
+
+        assert_eq!(Matcher::new(b"").push_bytes(b"abc"), Some(0));
+    }
+
+    #[test]
+    fn matches_like_a_naive_search() {
+        // Every delimiter and haystack over a small alphabet, so that partial matches overlap.
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            b'a' + (state % 3) as u8
+        };
+        for _ in 0..1000 {
+            let delims: Vec<u8> = (0..1 + next() as usize % 5).map(|_| next()).collect();
+            let haystack: Vec<u8> = (0..32).map(|_| next()).collect();
+            let expected = haystack
+                .windows(delims.len())
+                .position(|window| window == delims)
+                .map(|pos| pos + delims.len());
+            let mut matcher = Matcher::new(&delims);
+            let mut found = None;
+            for (i, chunk) in haystack.chunks(3).enumerate() {
+                if let Some(len) = matcher.push_bytes(chunk) {
+                    found = Some(i * 3 + len);
+                    break;
+                }
+            }
+            assert_eq!(found, expected, "{:?} in {:?}", delims, haystack);
+        }
+    }
+
+    #[test]
+    fn matches_long_delimiters() {
+        let delims = alloc::vec![b'a'; 64 * 1024];
+        let mut matcher = Matcher::new(&delims);
+        assert_eq!(matcher.push_bytes(&delims[1..]), None);
+        assert_eq!(matcher.push_bytes(b"ab"), Some(1));
+    }
+
+    #[test]
+    fn any_matches_across_chunks() {
+        let mut matcher = AnyMatcher::new(&[b"abcd", b"bc"]);
+        assert_eq!(matcher.push_bytes(b"ab"), None);
+        assert_eq!(matcher.push_bytes(b"cd"), Some((1, 1)));
+        assert_eq!(matcher.push_bytes(b"abcd"), Some((3, 1)));
+
+        let mut matcher = AnyMatcher::new(&[b"x", b""]);
+        assert_eq!(matcher.push_bytes(b"x"), Some((0, 1)));
+    }
+
+    #[test]
+    fn any_reports_the_longest_delimiter() {
+        let mut matcher = AnyMatcher::new(&[b"b", b"ab"]);
+        assert_eq!(matcher.push_bytes(b"ab"), Some((2, 1)));
+        assert_eq!(matcher.push_bytes(b"cb"), Some((2, 0)));
+
+        let mut matcher = AnyMatcher::new(&[b"ab", b"b", b"ab"]);
+        assert_eq!(matcher.push_bytes(b"xab"), Some((3, 0)));
+    }
+
+    #[test]
+    fn any_matches_like_a_naive_search() {
+        // Up to 5 delimiters, so that both the scan for the first bytes and the automaton alone
+        // are used.
+        let mut state = 0x9e3779b97f4a7c15u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            b'a' + (state % 6) as u8
+        };
+        for _ in 0..1000 {
+            let delims: Vec<Vec<u8>> = (0..1 + next() as usize % 5)
+                .map(|_| (0..1 + next() as usize % 3).map(|_| next()).collect())
+                .collect();
+            let haystack: Vec<u8> = (0..32).map(|_| next()).collect();
+            let expected = (1..=haystack.len())
+                .find(|&end| delims.iter().any(|delim| haystack[..end].ends_with(delim)));
+            let delim_refs: Vec<&[u8]> = delims.iter().map(|delim| &delim[..]).collect();
+            let mut matcher = AnyMatcher::new(&delim_refs);
+            let mut found = None;
+            for (i, chunk) in haystack.chunks(3).enumerate() {
+                if let Some((len, idx)) = matcher.push_bytes(chunk) {
+                    assert!(haystack[..i * 3 + len].ends_with(&delims[idx]));
+                    found = Some(i * 3 + len);
+                    break;
+                }
+            }
+            assert_eq!(found, expected, "{:?} in {:?}", delims, haystack);
+        }
+    }
+
+    #[test]
+    fn fuzzy_matches_across_chunks() {
+        let mut matcher = FuzzyMatcher::new(b"abcd", 1);
+        assert_eq!(matcher.push_bytes(b"xxab"), None);
+        // substitution
+        assert_eq!(matcher.push_bytes(b"xdyy"), Some((2, 4)));
+        // insertion
+        assert_eq!(matcher.push_bytes(b"abxcd"), Some((5, 5)));
+        // deletion
+        assert_eq!(matcher.push_bytes(b"acd"), Some((3, 3)));
+        // too many edits
+        assert_eq!(matcher.push_bytes(b"axxd"), None);
+
+        // the last byte must match exactly
+        let mut matcher = FuzzyMatcher::new(b"abc", 1);
+        assert_eq!(matcher.push_bytes(b"ab"), None);
+        assert_eq!(matcher.push_bytes(b"c"), Some((1, 3)));
+    }
+}
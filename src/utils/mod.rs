@@ -1,5 +1,43 @@
-mod recv_until;
-pub use recv_until::*;
+//! Utilities for exploit development that don't need a tube.
 
-mod interactive;
-pub use interactive::*;
+mod matcher;
+pub use matcher::*;
+
+pub(crate) mod recv_until;
+pub(crate) use recv_until::*;
+
+pub(crate) mod recv_regex;
+pub(crate) use recv_regex::*;
+
+pub(crate) mod needle;
+pub use needle::Needle;
+pub(crate) use needle::{tolerant_regex, RecvNeedle};
+
+pub(crate) mod ext;
+
+pub(crate) mod interactive;
+pub(crate) use interactive::*;
+
+#[cfg(not(target_family = "wasm"))]
+mod multiplex;
+#[cfg(not(target_family = "wasm"))]
+pub(crate) use multiplex::*;
+
+#[cfg(unix)]
+mod raw_mode;
+#[cfg(unix)]
+pub(crate) use raw_mode::*;
+
+mod base64;
+pub(crate) use base64::*;
+
+mod cyclic;
+pub use cyclic::*;
+
+mod flat;
+pub use flat::*;
+
+mod timeout;
+pub(crate) use timeout::*;
+
+pub(crate) mod clock;
@@ -0,0 +1,185 @@
+use std::{
+    future::Future,
+    ops::DerefMut,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{self, AsyncBufRead, AsyncWrite, BufReader, Stdin, Stdout};
+
+use crate::tubes::Tube;
+
+/// Attaches stdin and stdout to several tubes at once, switching between them with a prefix key.
+/// This is the future behind [`interactive_multi`](crate::tubes::interactive_multi).
+///
+/// Input goes to the active tube and only its output is shown, while the output of the other
+/// tubes stays in their buffers until they are switched to. The future completes when stdin
+/// reaches EOF, the user detaches or all the tubes are closed.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct Multiplex<'a, T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    tubes: &'a mut [Tube<T>],
+    /// Whether each tube reached EOF.
+    closed: Vec<bool>,
+    /// Whether each tube is written to since it was last flushed.
+    unflushed: Vec<bool>,
+    /// The index of the tube that gets the input and shows its output.
+    active: usize,
+    prefix: u8,
+    /// The prefix key is pressed and the next key is a command.
+    pending_prefix: bool,
+    /// The prefix key at the start of stdin's buffer is pressed twice and is sent as is.
+    literal_prefix: bool,
+    stdin: BufReader<Stdin>,
+    stdout: Stdout,
+}
+
+impl<'a, T> Multiplex<'a, T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    /// Attach to the tubes, starting with the first one. `prefix` is the key that starts a
+    /// command, e.g. `0x01` for Ctrl-A.
+    pub fn new(tubes: &'a mut [Tube<T>], prefix: u8) -> Self {
+        Self {
+            closed: vec![false; tubes.len()],
+            unflushed: vec![false; tubes.len()],
+            tubes,
+            active: 0,
+            prefix,
+            pending_prefix: false,
+            literal_prefix: false,
+            stdin: BufReader::new(io::stdin()),
+            stdout: io::stdout(),
+        }
+    }
+
+    /// Make `target` the active tube if it exists, telling the user on stderr either way.
+    fn switch_to(&mut self, target: usize) {
+        if target < self.tubes.len() {
+            self.active = target;
+            eprintln!("\n[switched to tube {}]", target);
+        } else {
+            eprintln!("\n[no tube {}]", target);
+        }
+    }
+
+    /// Handle the key pressed after the prefix key. Returns `true` if the user detached.
+    fn handle_command(&mut self, key: u8) -> bool {
+        match key {
+            b'0'..=b'9' => self.switch_to((key - b'0') as usize),
+            b'n' => self.switch_to((self.active + 1) % self.tubes.len()),
+            b'd' => return true,
+            _ => {}
+        }
+        false
+    }
+}
+
+impl<'a, T> Future for Multiplex<'a, T>
+where
+    T: AsyncBufRead + AsyncWrite + Unpin,
+{
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.deref_mut();
+        if this.tubes.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+
+        // stdin -> active tube, intercepting the prefix key
+        while let Poll::Ready(buf) = Pin::new(&mut this.stdin).poll_fill_buf(cx)? {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.pending_prefix {
+                this.pending_prefix = false;
+                let key = buf[0];
+                if key == this.prefix {
+                    // Pressing the prefix twice sends it literally, keep it in the buffer.
+                    this.literal_prefix = true;
+                    continue;
+                }
+                Pin::new(&mut this.stdin).consume(1);
+                if this.handle_command(key) {
+                    return Poll::Ready(Ok(()));
+                }
+                continue;
+            }
+
+            let start = usize::from(this.literal_prefix);
+            let end = buf[start..]
+                .iter()
+                .position(|&byte| byte == this.prefix)
+                .map_or(buf.len(), |pos| pos + start);
+            if end == 0 {
+                Pin::new(&mut this.stdin).consume(1);
+                this.pending_prefix = true;
+                continue;
+            }
+
+            if this.closed[this.active] {
+                Pin::new(&mut this.stdin).consume(end);
+                this.literal_prefix = false;
+                continue;
+            }
+            match Pin::new(&mut this.tubes[this.active]).poll_write(cx, &buf[..end])? {
+                Poll::Ready(amt) => {
+                    Pin::new(&mut this.stdin).consume(amt);
+                    if amt > 0 {
+                        this.literal_prefix = false;
+                        this.unflushed[this.active] = true;
+                    }
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        // Flush the input, e.g. for a corked tube or an adapter that buffers its output.
+        for (tube, unflushed) in this.tubes.iter_mut().zip(&mut this.unflushed) {
+            if *unflushed && Pin::new(tube).poll_flush(cx)?.is_ready() {
+                *unflushed = false;
+            }
+        }
+
+        // active tube -> stdout, the other tubes keep their output buffered until switched to
+        while !this.closed[this.active] {
+            let tube = &mut this.tubes[this.active];
+            let Poll::Ready(buf) = Pin::new(&mut *tube).poll_fill_buf(cx)? else {
+                break;
+            };
+            if buf.is_empty() {
+                this.closed[this.active] = true;
+                eprintln!("\n[tube {} closed]", this.active);
+                break;
+            }
+            match Pin::new(&mut this.stdout).poll_write(cx, buf)? {
+                Poll::Ready(amt) => Pin::new(tube).consume(amt),
+                Poll::Pending => break,
+            }
+        }
+        let _ = Pin::new(&mut this.stdout).poll_flush(cx)?;
+
+        // The other tubes are only checked for EOF, their output is kept until switched to.
+        for (index, tube) in this.tubes.iter_mut().enumerate() {
+            if index == this.active || this.closed[index] {
+                continue;
+            }
+            if let Poll::Ready(buf) = Pin::new(tube).poll_fill_buf(cx)? {
+                if buf.is_empty() {
+                    this.closed[index] = true;
+                    eprintln!("\n[tube {} closed]", index);
+                }
+            }
+        }
+        if this.closed.iter().all(|&closed| closed) {
+            return Poll::Ready(Ok(()));
+        }
+
+        Poll::Pending
+    }
+}
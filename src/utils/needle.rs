@@ -0,0 +1,367 @@
+use std::{
+    borrow::Cow,
+    future::Future,
+    io,
+    ops::{DerefMut, Range},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "stream")]
+use bytes::Bytes;
+use regex::bytes::Regex;
+use tokio::io::AsyncBufRead;
+
+use super::Matcher;
+
+/// A pattern to receive until, accepted by [`Tube::recv_until`](crate::tubes::Tube::recv_until)
+/// and the other methods waiting for a single pattern. It is implemented for byte strings,
+/// strings, single bytes and chars, and [`Regex`], so `recv_until(b'\n')`, `recv_until('>')` and
+/// `recv_until(&regex)` all work.
+///
+/// Custom needles only need [`Needle::find`], which is run against everything received so far
+/// each time more data arrives. Needles that are a fixed byte string should also return it from
+/// [`Needle::literal`], so that they are searched for incrementally instead.
+/// ```rust
+/// use io_tubes::{tubes::Tube, utils::Needle};
+/// use std::{io, ops::Range};
+///
+/// /// A run of at least `len` printable bytes.
+/// struct Printable {
+///     len: usize,
+/// }
+///
+/// impl Needle for Printable {
+///     fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+///         let mut start = 0;
+///         for (i, byte) in haystack.iter().enumerate() {
+///             if !byte.is_ascii_graphic() {
+///                 start = i + 1;
+///             } else if i + 1 - start == self.len {
+///                 return Some(start..i + 1);
+///             }
+///         }
+///         None
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn needle() -> io::Result<()> {
+///     let mut p = Tube::echo();
+///
+///     p.send(b"\x00\x01ab\x02flag{abc}").await?;
+///     assert_eq!(p.recv_until(Printable { len: 5 }).await?, b"\x00\x01ab\x02flag{");
+///     assert_eq!(p.recv_until('}').await?, b"abc}");
+///
+///     Ok(())
+/// }
+///
+/// needle();
+/// ```
+pub trait Needle {
+    /// Find the first match in `haystack`, returning where it starts and ends.
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>>;
+
+    /// The bytes matched by the needle if they are always the same. Only literal needles can be
+    /// matched with [`RecvUntilOptions::max_edits`](crate::tubes::RecvUntilOptions::max_edits).
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        None
+    }
+}
+
+impl Needle for [u8] {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        let end = Matcher::new(self).push_bytes(haystack)?;
+        Some(end - self.len()..end)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl<const N: usize> Needle for [u8; N] {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        self.as_slice().find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl Needle for Vec<u8> {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        self.as_slice().find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl Needle for str {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        self.as_bytes().find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(self.as_bytes()))
+    }
+}
+
+impl Needle for String {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        self.as_bytes().find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(self.as_bytes()))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Needle for Bytes {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        self.as_ref().find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl Needle for u8 {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        let start = haystack.iter().position(|byte| byte == self)?;
+        Some(start..start + 1)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Owned(vec![*self]))
+    }
+}
+
+impl Needle for char {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        self.encode_utf8(&mut [0; 4]).as_bytes().find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Owned(
+            self.encode_utf8(&mut [0; 4]).as_bytes().to_vec(),
+        ))
+    }
+}
+
+/// The leftmost-first match of the regex. Note that the regex is matched against the data
+/// received so far, see [`Tube::recv_regex`](crate::tubes::Tube::recv_regex).
+impl Needle for Regex {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        Regex::find(self, haystack).map(|m| m.range())
+    }
+}
+
+impl<B> Needle for Cow<'_, B>
+where
+    B: Needle + ToOwned + ?Sized,
+{
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        (**self).find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        (**self).literal()
+    }
+}
+
+impl<N: Needle + ?Sized> Needle for &N {
+    fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        (**self).find(haystack)
+    }
+
+    fn literal(&self) -> Option<Cow<'_, [u8]>> {
+        (**self).literal()
+    }
+}
+
+/// A regex matching the literal with ASCII letters in any case if `ignore_case`, and any run of
+/// ASCII whitespace in place of each run of whitespace in the literal if `collapse_whitespace`.
+pub(crate) fn tolerant_regex(
+    literal: &[u8],
+    ignore_case: bool,
+    collapse_whitespace: bool,
+) -> Regex {
+    let mut pattern = String::from(if ignore_case { "(?i-u)" } else { "(?-u)" });
+    let mut bytes = literal.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        if collapse_whitespace && byte.is_ascii_whitespace() {
+            while bytes.next_if(|byte| byte.is_ascii_whitespace()).is_some() {}
+            pattern.push_str(r"\s+");
+        } else if byte.is_ascii_alphanumeric() {
+            pattern.push(byte as char);
+        } else {
+            pattern.push_str(&format!(r"\x{:02x}", byte));
+        }
+    }
+    Regex::new(&pattern).expect("escaped literal is a valid regex")
+}
+
+/// A future receiving into `buf` until the needle is found, which resolves to the length of the
+/// match at the end of `buf`, or `None` if EOF is reached first. It is the building block of
+/// [`Tube::recv_until`](crate::tubes::Tube::recv_until).
+///
+/// Literal needles are searched for like [`RecvUntil`](super::RecvUntil). Other needles are run
+/// against everything appended to `buf` by the future so far.
+/// ```rust
+/// use io_tubes::io::RecvNeedle;
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn recv_needle() -> io::Result<()> {
+///     let mut reader: &[u8] = b"name> flag";
+///     let mut buf = Vec::new();
+///
+///     assert_eq!(RecvNeedle::new(&mut reader, &'>', &mut buf).await?, Some(1));
+///     assert_eq!(buf, b"name>");
+///     assert_eq!(reader, b" flag");
+///
+///     Ok(())
+/// }
+///
+/// recv_needle();
+/// ```
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvNeedle<'a, T, N>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+    N: Needle + ?Sized,
+{
+    inner: &'a mut T,
+    needle: &'a N,
+    /// The matcher and the length of the needle if it is literal.
+    literal: Option<(Matcher, usize)>,
+    buf: &'a mut Vec<u8>,
+    /// The length of `buf` before the future, which is not searched.
+    start: usize,
+}
+
+impl<'a, T, N> RecvNeedle<'a, T, N>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+    N: Needle + ?Sized,
+{
+    /// Receive from `inner` until `needle`, appending the data up to the end of the match to
+    /// `buf`. Data is only consumed from `inner` once it is appended, so dropping the future
+    /// loses nothing.
+    pub fn new(inner: &'a mut T, needle: &'a N, buf: &'a mut Vec<u8>) -> Self {
+        let literal = needle
+            .literal()
+            .map(|delims| (Matcher::new(&delims), delims.len()));
+        let start = buf.len();
+        Self {
+            inner,
+            needle,
+            literal,
+            buf,
+            start,
+        }
+    }
+}
+
+impl<'a, T, N> Future for RecvNeedle<'a, T, N>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+    N: Needle + ?Sized,
+{
+    /// The length of the match, or `None` if EOF is reached before the needle.
+    type Output = io::Result<Option<usize>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let Self {
+            inner,
+            needle,
+            literal,
+            buf,
+            start,
+        } = self.deref_mut();
+        let mut inner = Pin::new(inner);
+        loop {
+            let new_buf = match inner.as_mut().poll_fill_buf(cx)? {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+            if let Some((matcher, match_len)) = literal {
+                if let Some(len) = matcher.push_bytes(new_buf) {
+                    buf.extend_from_slice(&new_buf[..len]);
+                    inner.as_mut().consume(len);
+                    return Poll::Ready(Ok(Some(*match_len)));
+                }
+            }
+            if new_buf.is_empty() {
+                return Poll::Ready(Ok(None));
+            }
+
+            let old_len = buf.len();
+            let new_len = new_buf.len();
+            buf.extend_from_slice(new_buf);
+            if literal.is_none() {
+                // A match may span across several chunks, so everything is searched again.
+                if let Some(range) = needle.find(&buf[*start..]) {
+                    let end = (*start + range.end).max(old_len);
+                    buf.truncate(end);
+                    inner.as_mut().consume(end - old_len);
+                    return Poll::Ready(Ok(Some(range.len())));
+                }
+            }
+            inner.as_mut().consume(new_len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    use super::{tolerant_regex, RecvNeedle};
+    use std::io;
+
+    #[test]
+    fn can_match_tolerant_literal() {
+        let regex = tolerant_regex(b"Login:  [y/N]", true, true);
+        assert!(regex.is_match(b"LOGIN:\t[Y/n]"));
+        assert!(regex.is_match(b"login: [y/n]"));
+        assert!(!regex.is_match(b"login:[y/n]"));
+
+        let regex = tolerant_regex(b"a.b \xff", false, false);
+        assert!(regex.is_match(b"a.b \xff"));
+        assert!(!regex.is_match(b"A.b \xff"));
+        assert!(!regex.is_match(b"axb \xff"));
+    }
+
+    #[tokio::test]
+    async fn can_recv_needle_across_chunks() -> io::Result<()> {
+        let first: &[u8] = b"Leak: 0x7fff";
+        let second: &[u8] = b"f7a05000\nNext";
+        let mut reader = BufReader::new(first.chain(second));
+        let regex = Regex::new(r"0x[0-9a-f]+\n").unwrap();
+        let mut buf = b"old".to_vec();
+
+        let found = RecvNeedle::new(&mut reader, &regex, &mut buf).await?;
+        assert_eq!(found, Some(15));
+        assert_eq!(buf, b"oldLeak: 0x7ffff7a05000\n");
+
+        buf.clear();
+        assert_eq!(RecvNeedle::new(&mut reader, "xt", &mut buf).await?, Some(2));
+        assert_eq!(buf, b"Next");
+
+        // EOF without match
+        buf.clear();
+        assert_eq!(RecvNeedle::new(&mut reader, &b'\n', &mut buf).await?, None);
+        assert!(buf.is_empty());
+
+        Ok(())
+    }
+}
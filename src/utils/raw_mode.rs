@@ -0,0 +1,35 @@
+use std::io::{self, IsTerminal};
+
+use nix::sys::termios::{cfmakeraw, tcgetattr, tcsetattr, OutputFlags, SetArg, Termios};
+
+/// Puts stdin into raw mode until dropped, so that the original mode is restored on return and
+/// when unwinding from a panic.
+#[derive(Debug)]
+pub struct RawMode {
+    original: Termios,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        let stdin = io::stdin();
+        if !stdin.is_terminal() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "stdin is not a terminal",
+            ));
+        }
+        let original = tcgetattr(&stdin)?;
+        let mut raw = original.clone();
+        cfmakeraw(&mut raw);
+        // Keep translating "\n" to "\r\n" so that line based targets are still readable.
+        raw.output_flags |= OutputFlags::OPOST | OutputFlags::ONLCR;
+        tcsetattr(&stdin, SetArg::TCSANOW, &raw)?;
+        Ok(Self { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = tcsetattr(io::stdin(), SetArg::TCSANOW, &self.original);
+    }
+}
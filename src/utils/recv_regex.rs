@@ -0,0 +1,101 @@
+use regex::bytes::Regex;
+use std::{
+    future::Future,
+    io,
+    ops::{DerefMut, Range},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::AsyncBufRead;
+
+/// A future receiving into `buf` until the regex matches, which resolves to the ranges of the
+/// capture groups inside `buf`. It is the building block of
+/// [`Tube::recv_regex`](crate::tubes::Tube::recv_regex).
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvRegex<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    inner: &'a mut T,
+    regex: &'a Regex,
+    buf: &'a mut Vec<u8>,
+}
+
+impl<'a, T> RecvRegex<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    /// Receive from `inner` until the regex matches, appending the data up to the end of the match
+    /// to `buf`.
+    pub fn new(inner: &'a mut T, regex: &'a Regex, buf: &'a mut Vec<u8>) -> Self {
+        Self { inner, regex, buf }
+    }
+}
+
+impl<'a, T> Future for RecvRegex<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    /// The ranges of the capture groups inside the buffer, or `None` if EOF is reached before a
+    /// match is found.
+    type Output = io::Result<Option<Vec<Option<Range<usize>>>>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let Self { inner, regex, buf } = self.deref_mut();
+        let mut inner = Pin::new(inner);
+        loop {
+            let new_buf = match inner.as_mut().poll_fill_buf(cx)? {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+            if new_buf.is_empty() {
+                return Poll::Ready(Ok(None));
+            }
+
+            // The regex has to be run against everything received so far since a match may span
+            // across several chunks.
+            let old_len = buf.len();
+            let new_len = new_buf.len();
+            buf.extend_from_slice(new_buf);
+            if let Some(captures) = regex.captures(buf) {
+                let end = captures.get(0).map_or(buf.len(), |m| m.end());
+                let ranges = captures.iter().map(|m| m.map(|m| m.range())).collect();
+                buf.truncate(end);
+                inner.as_mut().consume(end - old_len);
+                return Poll::Ready(Ok(Some(ranges)));
+            }
+            inner.as_mut().consume(new_len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+
+    use super::RecvRegex;
+    use std::io;
+
+    #[tokio::test]
+    async fn can_recv_regex() -> io::Result<()> {
+        let mut fake_reader: &[u8] = b"Leak: 0x7ffff7a05000\nNext";
+        let regex = Regex::new(r"0x([0-9a-f]+)\n").unwrap();
+        let mut buf = Vec::new();
+
+        let captures = RecvRegex::new(&mut fake_reader, &regex, &mut buf)
+            .await?
+            .unwrap();
+        assert_eq!(buf, b"Leak: 0x7ffff7a05000\n");
+        assert_eq!(&buf[captures[1].clone().unwrap()], b"7ffff7a05000");
+        assert_eq!(fake_reader, b"Next");
+
+        // EOF without match
+        let mut buf = Vec::new();
+        let captures = RecvRegex::new(&mut fake_reader, &regex, &mut buf).await?;
+        assert!(captures.is_none());
+        assert_eq!(buf, b"Next");
+
+        Ok(())
+    }
+}
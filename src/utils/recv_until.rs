@@ -7,6 +7,29 @@ use std::{
 };
 use tokio::io::AsyncBufRead;
 
+use super::{AnyMatcher, FuzzyMatcher, Matcher};
+
+/// A future receiving into `buf` until the delimiter is found, which resolves to `false` if EOF
+/// is reached first. It is the building block of [`Tube::recv_until`](crate::tubes::Tube::recv_until)
+/// and works on any [`AsyncBufRead`] without a timeout.
+/// ```rust
+/// use io_tubes::io::RecvUntil;
+/// use std::io;
+///
+/// #[tokio::main]
+/// async fn recv_until() -> io::Result<()> {
+///     let mut reader: &[u8] = b"name? flag";
+///     let mut buf = Vec::new();
+///
+///     assert!(RecvUntil::new(&mut reader, b"? ", &mut buf).await?);
+///     assert_eq!(buf, b"name? ");
+///     assert_eq!(reader, b"flag");
+///
+///     Ok(())
+/// }
+///
+/// recv_until();
+/// ```
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 #[derive(Debug)]
 pub struct RecvUntil<'a, T>
@@ -14,8 +37,7 @@ where
     T: AsyncBufRead + Unpin + ?Sized + 'a,
 {
     inner: &'a mut T,
-    cur_index: usize,
-    lookup_table: Vec<[usize; 256]>,
+    matcher: Matcher,
     buf: &'a mut Vec<u8>,
 }
 
@@ -23,47 +45,152 @@ impl<'a, T> RecvUntil<'a, T>
 where
     T: AsyncBufRead + Unpin + ?Sized + 'a,
 {
-    fn compute_lookup_table(delims: &[u8]) -> Vec<[usize; 256]> {
-        let mut lookup_table = Vec::with_capacity(delims.len());
-        let mut lps = 0;
-        lookup_table.resize(delims.len(), [0; 256]);
-        for (row_idx, &delim_last) in delims.iter().enumerate() {
-            for new_byte in 0..=255 {
-                if new_byte == delim_last {
-                    lookup_table[row_idx][new_byte as usize] = row_idx + 1;
-                } else {
-                    lookup_table[row_idx][new_byte as usize] = lookup_table[lps][new_byte as usize];
-                }
+    /// Receive from `inner` until `delims`, appending the data to `buf`. Data is only consumed
+    /// from `inner` once it is appended, so dropping the future loses nothing.
+    pub fn new(inner: &'a mut T, delims: &[u8], buf: &'a mut Vec<u8>) -> Self {
+        Self {
+            inner,
+            matcher: Matcher::new(delims),
+            buf,
+        }
+    }
+}
+
+impl<'a, T> Future for RecvUntil<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    /// Whether the delims are found before EOF.
+    type Output = io::Result<bool>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // reborrow everything so borrow checker actually understands
+        let Self {
+            inner,
+            matcher,
+            buf,
+        } = self.deref_mut();
+        let mut inner = Pin::new(inner);
+        loop {
+            let new_buf = match inner.as_mut().poll_fill_buf(cx)? {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+            if let Some(len) = matcher.push_bytes(new_buf) {
+                buf.extend_from_slice(&new_buf[..len]);
+                inner.as_mut().consume(len);
+                return Poll::Ready(Ok(true));
             }
-            if row_idx != 0 {
-                lps = lookup_table[lps][delim_last as usize];
+            if new_buf.is_empty() {
+                return Poll::Ready(Ok(false));
             }
+            buf.extend_from_slice(new_buf);
+            let len = new_buf.len();
+            inner.as_mut().consume(len);
         }
-        lookup_table
     }
+}
 
-    pub fn new(inner: &'a mut T, delims: &[u8], buf: &'a mut Vec<u8>) -> Self {
+/// Same as [`RecvUntil`], but searches for several delimiters at once with an Aho-Corasick
+/// automaton and reports the index of the delimiter found.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvUntilAny<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    inner: &'a mut T,
+    matcher: AnyMatcher,
+    buf: &'a mut Vec<u8>,
+}
+
+impl<'a, T> RecvUntilAny<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    /// Receive from `inner` until any of `delims`, appending the data to `buf`.
+    pub fn new(inner: &'a mut T, delims: &[&[u8]], buf: &'a mut Vec<u8>) -> Self {
         Self {
             inner,
-            cur_index: 0,
-            lookup_table: Self::compute_lookup_table(delims),
+            matcher: AnyMatcher::new(delims),
             buf,
         }
     }
 }
 
-impl<'a, T> Future for RecvUntil<'a, T>
+impl<'a, T> Future for RecvUntilAny<'a, T>
 where
     T: AsyncBufRead + Unpin + ?Sized + 'a,
 {
-    type Output = io::Result<()>;
+    /// The index of the delimiter found, or `None` if EOF is reached.
+    type Output = io::Result<Option<usize>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let Self {
+            inner,
+            matcher,
+            buf,
+        } = self.deref_mut();
+        let mut inner = Pin::new(inner);
+        loop {
+            let new_buf = match inner.as_mut().poll_fill_buf(cx)? {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+            if let Some((len, found)) = matcher.push_bytes(new_buf) {
+                buf.extend_from_slice(&new_buf[..len]);
+                inner.as_mut().consume(len);
+                return Poll::Ready(Ok(Some(found)));
+            }
+            if new_buf.is_empty() {
+                return Poll::Ready(Ok(None));
+            }
+            buf.extend_from_slice(new_buf);
+            let len = new_buf.len();
+            inner.as_mut().consume(len);
+        }
+    }
+}
+
+/// Same as [`RecvUntil`], but finds an approximate occurrence of the delimiter with a
+/// [`FuzzyMatcher`] and reports the length of the match.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct RecvUntilFuzzy<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    inner: &'a mut T,
+    matcher: FuzzyMatcher,
+    buf: &'a mut Vec<u8>,
+}
+
+impl<'a, T> RecvUntilFuzzy<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    /// Receive from `inner` until `delims` with at most `max_edits` edits, appending the data to
+    /// `buf`.
+    pub fn new(inner: &'a mut T, delims: &[u8], max_edits: usize, buf: &'a mut Vec<u8>) -> Self {
+        Self {
+            inner,
+            matcher: FuzzyMatcher::new(delims, max_edits),
+            buf,
+        }
+    }
+}
+
+impl<'a, T> Future for RecvUntilFuzzy<'a, T>
+where
+    T: AsyncBufRead + Unpin + ?Sized + 'a,
+{
+    /// The length of the match at the end of the buffer, or `None` if EOF is reached.
+    type Output = io::Result<Option<usize>>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        // reborrow everything so borrow checker actually understands
         let Self {
             inner,
-            cur_index,
-            lookup_table,
+            matcher,
             buf,
         } = self.deref_mut();
         let mut inner = Pin::new(inner);
@@ -72,16 +199,13 @@ where
                 Poll::Ready(result) => result,
                 Poll::Pending => return Poll::Pending,
             };
-            for (count, new_byte) in new_buf.iter().enumerate() {
-                *cur_index = lookup_table[*cur_index][*new_byte as usize];
-                if *cur_index == lookup_table.len() {
-                    buf.extend_from_slice(&new_buf[..=count]);
-                    inner.as_mut().consume(count + 1);
-                    return Poll::Ready(Ok(()));
-                }
+            if let Some((len, match_len)) = matcher.push_bytes(new_buf) {
+                buf.extend_from_slice(&new_buf[..len]);
+                inner.as_mut().consume(len);
+                return Poll::Ready(Ok(Some(match_len)));
             }
             if new_buf.is_empty() {
-                return Poll::Ready(Ok(()));
+                return Poll::Ready(Ok(None));
             }
             buf.extend_from_slice(new_buf);
             let len = new_buf.len();
@@ -94,7 +218,7 @@ where
 mod tests {
     use tokio::io::AsyncBufRead;
 
-    use super::RecvUntil;
+    use super::{RecvUntil, RecvUntilAny, RecvUntilFuzzy};
     use std::io;
 
     async fn recv_until<T: AsyncBufRead + Unpin>(
@@ -124,4 +248,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn can_recv_until_any() -> io::Result<()> {
+        let mut fake_reader: &[u8] = b"1. Add\n2. Delete\n> Invalid choice\n";
+        let delims: [&[u8]; 3] = [b"> ", b"choice: ", b"Invalid"];
+
+        let mut buf = Vec::new();
+        let found = RecvUntilAny::new(&mut fake_reader, &delims, &mut buf).await?;
+        assert_eq!(found, Some(0));
+        assert_eq!(buf, b"1. Add\n2. Delete\n> ");
+
+        let mut buf = Vec::new();
+        let found = RecvUntilAny::new(&mut fake_reader, &delims, &mut buf).await?;
+        assert_eq!(found, Some(2));
+        assert_eq!(buf, b"Invalid");
+
+        // overlapping delimiters report the one that ends first
+        let mut fake_reader: &[u8] = b"abcd";
+        let delims: [&[u8]; 2] = [b"abcd", b"bc"];
+        let mut buf = Vec::new();
+        let found = RecvUntilAny::new(&mut fake_reader, &delims, &mut buf).await?;
+        assert_eq!(found, Some(1));
+        assert_eq!(buf, b"abc");
+
+        // EOF
+        let mut buf = Vec::new();
+        let found = RecvUntilAny::new(&mut fake_reader, &delims, &mut buf).await?;
+        assert_eq!(found, None);
+        assert_eq!(buf, b"d");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn can_recv_until_fuzzy() -> io::Result<()> {
+        let mut fake_reader: &[u8] = b"[12:01:33] Welcome, user 4127!\nmenu";
+
+        let mut buf = Vec::new();
+        let found =
+            RecvUntilFuzzy::new(&mut fake_reader, b"Welcome, user 1000!\n", 4, &mut buf).await?;
+        assert_eq!(found, Some(20));
+        assert_eq!(buf, b"[12:01:33] Welcome, user 4127!\n");
+
+        // EOF
+        let mut buf = Vec::new();
+        let found = RecvUntilFuzzy::new(&mut fake_reader, b"Welcome", 1, &mut buf).await?;
+        assert_eq!(found, None);
+        assert_eq!(buf, b"menu");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,23 @@
+use std::{future::Future, time::Duration};
+
+use tokio::time::{self, error::Elapsed};
+
+use crate::report;
+
+use super::clock;
+
+/// Same as [`tokio::time::timeout`], but the timeouts are counted in the session report. Without
+/// a clock, the future runs without a timeout.
+pub(crate) async fn timeout<F: Future>(
+    duration: Duration,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    if !clock::AVAILABLE {
+        return Ok(future.await);
+    }
+    let result = time::timeout(duration, future).await;
+    if result.is_err() {
+        report::record_timeout();
+    }
+    result
+}